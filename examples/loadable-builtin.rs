@@ -0,0 +1,111 @@
+//! Example of implementing a builtin command that can be loaded into a running
+//! kodegen_bash_shell based shell via `enable -f`.
+//!
+//! Unlike `custom-builtin`, this example is built as a shared library (`cdylib`); the
+//! shell discovers its builtins at runtime through the exported entry point, so no
+//! rebuild of the shell is needed.
+//!
+//! Build and load this example with:
+//! ```bash
+//! cargo build --example loadable-builtin
+//! enable -f target/debug/examples/libloadable_builtin.so hello
+//! hello world
+//! enable -d hello
+//! ```
+
+use std::ffi::{CStr, c_char, c_int};
+
+use kodegen_bash_shell::core::loadable::{
+    ABI_VERSION, BuiltinDescriptor, HostApi, LibraryDescriptor, STDOUT_STREAM,
+};
+
+/// Wrapper that lets us store the descriptor table (which contains raw pointers)
+/// in a static.
+struct Descriptors([BuiltinDescriptor; 1]);
+
+// SAFETY: The descriptors only point to immutable, static data.
+unsafe impl Sync for Descriptors {}
+
+static BUILTINS: Descriptors = Descriptors([BuiltinDescriptor {
+    name: c"hello".as_ptr(),
+    short_doc: c"hello [name ...]".as_ptr(),
+    long_doc:
+        c"Print a greeting.\n\nGreets each given name, or the current user if none are given."
+            .as_ptr(),
+    execute: hello,
+}]);
+
+struct Library(LibraryDescriptor);
+
+// SAFETY: The descriptor only points to immutable, static data.
+unsafe impl Sync for Library {}
+
+static LIBRARY: Library = Library(LibraryDescriptor {
+    abi_version: ABI_VERSION,
+    builtin_count: BUILTINS.0.len(),
+    builtins: BUILTINS.0.as_ptr(),
+});
+
+/// Entry point looked up by the shell when loading this library.
+#[unsafe(no_mangle)]
+pub extern "C" fn kodegen_bash_shell_builtins() -> *const LibraryDescriptor {
+    &raw const LIBRARY.0
+}
+
+/// Implementation of the `hello` builtin.
+unsafe extern "C" fn hello(host: *const HostApi, argc: usize, argv: *const *const c_char) -> c_int {
+    // SAFETY: The shell passes a valid host API and `argc` valid argument strings.
+    let (host, args) = unsafe {
+        let args: Vec<String> = std::slice::from_raw_parts(argv, argc)
+            .iter()
+            .map(|arg| CStr::from_ptr(*arg).to_string_lossy().into_owned())
+            .collect();
+        (&*host, args)
+    };
+
+    let names = if args.len() > 1 {
+        args[1..].to_vec()
+    } else {
+        vec![get_variable(host, c"USER").unwrap_or_else(|| String::from("world"))]
+    };
+
+    for name in names {
+        let greeting = format!("Hello, {name}!\n");
+        // SAFETY: The context was provided by the shell for this call.
+        let written = unsafe {
+            (host.write_output)(
+                host.context,
+                STDOUT_STREAM,
+                greeting.as_ptr(),
+                greeting.len(),
+            )
+        };
+        if written < 0 {
+            return 1;
+        }
+    }
+
+    0
+}
+
+/// Retrieves the value of a shell variable through the host API.
+fn get_variable(host: &HostApi, name: &CStr) -> Option<String> {
+    let mut buf = vec![0u8; 256];
+    loop {
+        // SAFETY: `buf` is valid for writes of `buf.len()` bytes.
+        let len = unsafe {
+            (host.get_variable)(
+                host.context,
+                name.as_ptr(),
+                buf.as_mut_ptr().cast(),
+                buf.len(),
+            )
+        };
+        let len = usize::try_from(len).ok()?;
+        if len < buf.len() {
+            buf.truncate(len);
+            return Some(String::from_utf8_lossy(&buf).into_owned());
+        }
+        buf.resize(len + 1, 0);
+    }
+}
@@ -1,4 +1,5 @@
 use clap::Parser;
+use itertools::Itertools;
 use std::io::Write;
 
 use crate::core::{ExecutionResult, builtins};
@@ -10,9 +11,18 @@ pub(crate) struct AliasCommand {
     #[arg(short = 'p')]
     print: bool,
 
-    /// List of aliases to display or update.
-    #[arg(name = "name[=value]")]
-    aliases: Vec<String>,
+    //
+    // Declarations
+    //
+    // N.B. These are skipped by clap, but filled in by the BuiltinDeclarationCommand trait.
+    #[clap(skip)]
+    declarations: Vec<crate::core::CommandArg>,
+}
+
+impl builtins::DeclarationCommand for AliasCommand {
+    fn set_declarations(&mut self, declarations: Vec<crate::core::CommandArg>) {
+        self.declarations = declarations;
+    }
 }
 
 impl builtins::Command for AliasCommand {
@@ -24,18 +34,25 @@ impl builtins::Command for AliasCommand {
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
         let mut exit_code = ExecutionResult::success();
 
-        if self.print || self.aliases.is_empty() {
-            for (name, value) in &context.shell.aliases {
+        if self.print || self.declarations.is_empty() {
+            for (name, value) in context
+                .shell
+                .aliases
+                .iter()
+                .sorted_by_key(|(name, _)| *name)
+            {
                 writeln!(context.stdout(), "alias {name}='{value}'")?;
             }
         } else {
-            for alias in &self.aliases {
+            for declaration in &self.declarations {
+                // N.B. Declarations that parsed as assignments display as `name=value`.
+                let alias = declaration.to_string();
                 if let Some((name, unexpanded_value)) = alias.split_once('=') {
                     context
                         .shell
                         .aliases
                         .insert(name.to_owned(), unexpanded_value.to_owned());
-                } else if let Some(value) = context.shell.aliases.get(alias) {
+                } else if let Some(value) = context.shell.aliases.get(&alias) {
                     writeln!(context.stdout(), "alias {alias}='{value}'")?;
                 } else {
                     writeln!(
@@ -1,10 +1,11 @@
 use clap::{Parser, ValueEnum};
-use std::{io::Write, str::FromStr as _, sync::Arc};
+use itertools::Itertools;
+use std::{io::Write, path::Path, sync::Arc};
 use strum::IntoEnumIterator;
 use tokio::sync::Mutex;
 
 use crate::core::{
-    ExecutionExitCode, ExecutionResult, builtins, error, interfaces, sys, trace_categories,
+    ExecutionExitCode, ExecutionResult, builtins, inputrc, interfaces, trace_categories,
 };
 
 /// Identifier for a keymap
@@ -85,8 +86,6 @@ pub(crate) struct BindCommand {
     key_sequence: Option<String>,
 }
 
-const BIND_FEATURE_ISSUE_ID: u32 = 380;
-
 impl builtins::Command for BindCommand {
     type Error = crate::core::Error;
 
@@ -114,61 +113,82 @@ impl BindCommand {
         context: &crate::core::ExecutionContext<'_>,
     ) -> Result<ExecutionResult, crate::core::Error> {
         let mut bindings = bindings.lock().await;
+        let mut result = ExecutionResult::success();
 
         if self.list_funcs {
-            for func in interfaces::InputFunction::iter() {
-                writeln!(context.stdout(), "{func}")?;
-            }
-        }
+            let mut func_names: Vec<_> = interfaces::InputFunction::iter()
+                .map(|func| func.to_string())
+                .collect();
+            func_names.sort();
 
-        if self.list_funcs_and_bindings {
-            for (seq, action) in &bindings.get_current() {
-                writeln!(context.stdout(), "{action} can be found on {seq}")?;
+            for func_name in func_names {
+                writeln!(context.stdout(), "{func_name}")?;
             }
-
-            return error::unimp_with_issue("bind -P", BIND_FEATURE_ISSUE_ID);
-        }
-
-        if self.list_funcs_and_bindings_reusable {
-            return error::unimp_with_issue("bind -p", BIND_FEATURE_ISSUE_ID);
         }
 
-        if self.list_key_seqs_that_invoke_macros {
-            return error::unimp_with_issue("bind -S", BIND_FEATURE_ISSUE_ID);
+        if self.list_funcs_and_bindings || self.list_funcs_and_bindings_reusable {
+            display_funcs_and_bindings(
+                &*bindings,
+                self.list_funcs_and_bindings_reusable,
+                context.stdout(),
+            )?;
         }
 
-        if self.list_key_seqs_that_invoke_macros_reusable {
-            return error::unimp_with_issue("bind -s", BIND_FEATURE_ISSUE_ID);
+        if self.list_key_seqs_that_invoke_macros || self.list_key_seqs_that_invoke_macros_reusable {
+            display_macros(
+                &*bindings,
+                self.list_key_seqs_that_invoke_macros_reusable,
+                context.stdout(),
+            )?;
         }
 
-        if self.list_vars {
-            return error::unimp_with_issue("bind -V", BIND_FEATURE_ISSUE_ID);
+        if self.list_vars || self.list_vars_reusable {
+            display_variables(&*bindings, self.list_vars_reusable, context.stdout())?;
         }
 
-        if self.list_vars_reusable {
-            // For now we'll just display a few items and show defaults.
-            writeln!(context.stdout(), "set mark-directories on")?;
-            writeln!(context.stdout(), "set mark-symlinked-directories off")?;
+        if self.list_key_seq_bindings {
+            for (seq, action) in sorted_bindings(&*bindings) {
+                if let interfaces::KeyAction::ShellCommand(command) = action {
+                    writeln!(context.stdout(), "\"{seq}\": \"{command}\"")?;
+                }
+            }
         }
 
-        if self.query_func_bindings.is_some() {
-            return error::unimp_with_issue("bind -q", BIND_FEATURE_ISSUE_ID);
+        if let Some(func_name) = &self.query_func_bindings {
+            let func = inputrc::parse_input_function(func_name)?;
+            let seqs = seqs_bound_to_func(&*bindings, func);
+
+            if seqs.is_empty() {
+                writeln!(context.stdout(), "{func} is not bound to any keys.")?;
+                result = ExecutionResult::general_error();
+            } else {
+                writeln!(
+                    context.stdout(),
+                    "{func} can be invoked via {}.",
+                    format_seq_list(&seqs)
+                )?;
+            }
         }
 
-        if self.remove_func_bindings.is_some() {
-            return error::unimp_with_issue("bind -u", BIND_FEATURE_ISSUE_ID);
+        if let Some(func_name) = &self.remove_func_bindings {
+            let func = inputrc::parse_input_function(func_name)?;
+            for seq in seqs_bound_to_func(&*bindings, func) {
+                bindings.unbind(&seq)?;
+            }
         }
 
-        if self.remove_key_seq_binding.is_some() {
-            return error::unimp_with_issue("bind -r", BIND_FEATURE_ISSUE_ID);
+        if let Some(key_seq) = &self.remove_key_seq_binding {
+            let seq = inputrc::parse_key_sequence(key_seq)?;
+            bindings.unbind(&seq)?;
         }
 
-        if self.bindings_file.is_some() {
-            return error::unimp_with_issue("bind -f", BIND_FEATURE_ISSUE_ID);
-        }
+        if let Some(bindings_file) = &self.bindings_file {
+            let path = context.shell.absolute_path(Path::new(bindings_file));
+            let diagnostics = inputrc::read_file(context.shell, &mut *bindings, &path)?;
 
-        if self.list_key_seq_bindings {
-            return error::unimp_with_issue("bind -X", BIND_FEATURE_ISSUE_ID);
+            for diagnostic in &diagnostics {
+                writeln!(context.stderr(), "{}: {diagnostic}", context.command_name)?;
+            }
         }
 
         if !self.key_seq_bindings.is_empty() {
@@ -189,44 +209,112 @@ impl BindCommand {
                 return Ok(ExecutionResult::success());
             }
 
-            let (key_seq, target) = parse_key_sequence_and_readline_target(key_sequence.as_str())?;
-            bind_key_sequence_to_readline_target(&mut *bindings, key_seq, target)?;
+            // The argument may be anything that can appear in an inputrc file, e.g. a
+            // key binding or a variable assignment.
+            inputrc::apply_line(&mut *bindings, key_sequence.as_str())?;
         }
 
         drop(bindings);
 
-        Ok(ExecutionResult::success())
+        Ok(result)
     }
 }
 
-fn parse_key_sequence_and_shell_command(
-    input: &str,
-) -> Result<(interfaces::KeySequence, String), crate::core::Error> {
-    tracing::debug!(target: trace_categories::INPUT,
-        "parsing key binding entry: '{input}'"
-    );
+/// Returns all current bindings, ordered by key sequence.
+fn sorted_bindings(
+    bindings: &dyn interfaces::KeyBindings,
+) -> Vec<(interfaces::KeySequence, interfaces::KeyAction)> {
+    let mut bindings: Vec<_> = bindings.get_current().into_iter().collect();
+    bindings.sort_by_cached_key(|(seq, _)| seq.to_string());
+    bindings
+}
 
-    // First trim any whitespace.
-    let input = input.trim();
+fn seqs_bound_to_func(
+    bindings: &dyn interfaces::KeyBindings,
+    func: interfaces::InputFunction,
+) -> Vec<interfaces::KeySequence> {
+    sorted_bindings(bindings)
+        .into_iter()
+        .filter(|(_, action)| *action == interfaces::KeyAction::DoInputFunction(func))
+        .map(|(seq, _)| seq)
+        .collect()
+}
 
-    // This should be something of the form:
-    //     "KEY-SEQUENCE": SHELL-COMMAND
-    let binding = crate::parser::readline_binding::parse_key_sequence_shell_cmd_binding(input)?;
-    let strokes = key_sequence_to_abstract_strokes(&binding.seq)?;
+fn format_seq_list(seqs: &[interfaces::KeySequence]) -> String {
+    seqs.iter().map(|seq| std::format!("\"{seq}\"")).join(", ")
+}
 
-    Ok((interfaces::KeySequence { strokes }, binding.shell_cmd))
+fn display_funcs_and_bindings(
+    bindings: &dyn interfaces::KeyBindings,
+    reusable: bool,
+    mut stdout: impl Write,
+) -> Result<(), crate::core::Error> {
+    let mut funcs: Vec<_> = interfaces::InputFunction::iter().collect();
+    funcs.sort_by_cached_key(ToString::to_string);
+
+    for func in funcs {
+        let seqs = seqs_bound_to_func(bindings, func);
+
+        if reusable {
+            if seqs.is_empty() {
+                writeln!(stdout, "# {func} (not bound)")?;
+            }
+            for seq in &seqs {
+                writeln!(stdout, "\"{seq}\": {func}")?;
+            }
+        } else if seqs.is_empty() {
+            writeln!(stdout, "{func} is not bound to any keys")?;
+        } else {
+            writeln!(stdout, "{func} can be found on {}.", format_seq_list(&seqs))?;
+        }
+    }
+
+    Ok(())
 }
 
-#[derive(Debug)]
-#[allow(dead_code, reason = "not all variants implemented yet")]
-enum BindableReadlineTarget {
-    Function(crate::core::interfaces::InputFunction),
-    Command(String),
+fn display_macros(
+    bindings: &dyn interfaces::KeyBindings,
+    reusable: bool,
+    mut stdout: impl Write,
+) -> Result<(), crate::core::Error> {
+    for (seq, action) in sorted_bindings(bindings) {
+        if let interfaces::KeyAction::InsertMacro(text) = action {
+            if reusable {
+                writeln!(stdout, "\"{seq}\": \"{text}\"")?;
+            } else {
+                writeln!(stdout, "{seq} outputs {text}")?;
+            }
+        }
+    }
+
+    Ok(())
 }
 
-fn parse_key_sequence_and_readline_target(
+fn display_variables(
+    bindings: &dyn interfaces::KeyBindings,
+    reusable: bool,
+    mut stdout: impl Write,
+) -> Result<(), crate::core::Error> {
+    // As with readline, boolean variables are listed before the others.
+    let (booleans, others): (Vec<_>, Vec<_>) = bindings
+        .get_variables()
+        .into_iter()
+        .partition(|(name, _)| interfaces::is_boolean_input_variable(name));
+
+    for (name, value) in booleans.into_iter().chain(others) {
+        if reusable {
+            writeln!(stdout, "set {name} {value}")?;
+        } else {
+            writeln!(stdout, "{name} is set to `{value}'")?;
+        }
+    }
+
+    Ok(())
+}
+
+fn parse_key_sequence_and_shell_command(
     input: &str,
-) -> Result<(interfaces::KeySequence, BindableReadlineTarget), crate::core::Error> {
+) -> Result<(interfaces::KeySequence, String), crate::core::Error> {
     tracing::debug!(target: trace_categories::INPUT,
         "parsing key binding entry: '{input}'"
     );
@@ -234,25 +322,12 @@ fn parse_key_sequence_and_readline_target(
     // First trim any whitespace.
     let input = input.trim();
 
-    // This should be of one of these forms:
-    //     "KEY-SEQUENCE":function-name
-    //     "KEY-SEQUENCE":readline-command
-    let binding = crate::parser::readline_binding::parse_key_sequence_readline_binding(input)?;
-    let strokes = key_sequence_to_abstract_strokes(&binding.seq)?;
-
-    match binding.target {
-        crate::parser::readline_binding::ReadlineTarget::Function(func_name) => {
-            let func = parse_readline_function(func_name.as_str())?;
-            Ok((
-                interfaces::KeySequence { strokes },
-                BindableReadlineTarget::Function(func),
-            ))
-        }
-        crate::parser::readline_binding::ReadlineTarget::Command(cmd) => Ok((
-            interfaces::KeySequence { strokes },
-            BindableReadlineTarget::Command(cmd),
-        )),
-    }
+    // This should be something of the form:
+    //     "KEY-SEQUENCE": SHELL-COMMAND
+    let binding = crate::parser::readline_binding::parse_key_sequence_shell_cmd_binding(input)?;
+    let strokes = inputrc::key_sequence_to_strokes(&binding.seq)?;
+
+    Ok((interfaces::KeySequence { strokes }, binding.shell_cmd))
 }
 
 fn bind_key_sequence_to_shell_cmd(
@@ -268,126 +343,3 @@ fn bind_key_sequence_to_shell_cmd(
 
     Ok(())
 }
-
-fn bind_key_sequence_to_readline_target(
-    bindings: &mut dyn interfaces::KeyBindings,
-    key_sequence: interfaces::KeySequence,
-    target: BindableReadlineTarget,
-) -> Result<(), crate::core::Error> {
-    match target {
-        BindableReadlineTarget::Function(func) => {
-            tracing::debug!(target: trace_categories::INPUT,
-                "binding key sequence: '{key_sequence}' => readline function '{func}'"
-            );
-
-            if matches!(func, interfaces::InputFunction::ViEditingMode) {
-                // NOTE(vi): We don't support vi mode; silently ignore.
-                return Ok(());
-            }
-
-            bindings.bind(key_sequence, interfaces::KeyAction::DoInputFunction(func))?;
-            Ok(())
-        }
-        BindableReadlineTarget::Command(cmd_macro) => {
-            tracing::debug!(target: trace_categories::INPUT,
-                "binding key sequence: '{key_sequence}' => readline macro '{cmd_macro}'"
-            );
-
-            error::unimp("binding key sequence to readline macro")
-        }
-    }
-}
-
-fn key_sequence_to_abstract_strokes(
-    seq: &crate::parser::readline_binding::KeySequence,
-) -> Result<Vec<interfaces::KeyStroke>, crate::core::Error> {
-    let phys_strokes = crate::parser::readline_binding::key_sequence_to_strokes(seq)?;
-
-    // Lift from key codes to abstract keys.
-    let mut abstract_strokes = vec![];
-    for mut phys_stroke in phys_strokes {
-        let mut key = sys::input::try_get_key_from_key_code(phys_stroke.key_code.as_slice());
-
-        // If we couldn't interpret it directly but we see it starts with the escape character,
-        // try to see if we can parse it as an Alt+<key> sequence.
-        if key.is_none() && phys_stroke.key_code.len() > 1 && phys_stroke.key_code[0] == b'\x1b' {
-            key = sys::input::try_get_key_from_key_code(&phys_stroke.key_code[1..]);
-            if key.is_some() {
-                phys_stroke.meta = true;
-            }
-        }
-
-        let Some(key) = key else {
-            return Err(error::ErrorKind::UnhandledKeyCode(phys_stroke.key_code).into());
-        };
-
-        abstract_strokes.push(interfaces::KeyStroke {
-            alt: phys_stroke.meta,
-            control: phys_stroke.control,
-            shift: false,
-            key,
-        });
-    }
-
-    Ok(abstract_strokes)
-}
-
-fn parse_readline_function(
-    func_name: &str,
-) -> Result<interfaces::InputFunction, crate::core::Error> {
-    interfaces::InputFunction::from_str(func_name).map_err(|_err| {
-        crate::core::ErrorKind::UnknownKeyBindingFunction(func_name.to_owned()).into()
-    })
-}
-
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use pretty_assertions::{assert_eq, assert_matches};
-
-    #[test]
-    fn parse_example_key_sequence_and_readline_func() {
-        let (key_seq, target) =
-            parse_key_sequence_and_readline_target(r#""\C-a":beginning-of-line"#).unwrap();
-
-        assert_eq!(
-            key_seq,
-            interfaces::KeySequence {
-                strokes: vec![interfaces::KeyStroke {
-                    alt: false,
-                    control: true,
-                    shift: false,
-                    key: interfaces::Key::Character('a'),
-                }],
-            }
-        );
-
-        assert_matches!(
-            target,
-            BindableReadlineTarget::Function(interfaces::InputFunction::BeginningOfLine)
-        );
-    }
-
-    #[test]
-    fn parse_escape_char_key_binding() {
-        let (key_seq, target) =
-            parse_key_sequence_and_readline_target(r#""\er":transpose-chars"#).unwrap();
-
-        assert_eq!(
-            key_seq,
-            interfaces::KeySequence {
-                strokes: vec![interfaces::KeyStroke {
-                    alt: true,
-                    control: false,
-                    shift: false,
-                    key: interfaces::Key::Character('r'),
-                }],
-            }
-        );
-
-        assert_matches!(
-            target,
-            BindableReadlineTarget::Function(interfaces::InputFunction::TransposeChars)
-        );
-    }
-}
@@ -0,0 +1,111 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+
+use clap::Parser;
+
+use crate::core::{ExecutionResult, builtins};
+use crate::parser::lint;
+
+/// Check shell scripts for likely mistakes, without executing them.
+#[derive(Parser)]
+pub(crate) struct BrushLintCommand {
+    /// Check the given command string instead of script files.
+    #[arg(short = 'c', value_name = "COMMAND")]
+    command: Option<String>,
+
+    /// Report syntax and builtins not specified by POSIX.
+    #[arg(short = 'p', long = "posix")]
+    posix: bool,
+
+    /// Paths of scripts to check; standard input is checked if none are given.
+    paths: Vec<PathBuf>,
+}
+
+impl builtins::Command for BrushLintCommand {
+    type Error = crate::core::Error;
+
+    async fn execute(
+        &self,
+        context: crate::core::ExecutionContext<'_>,
+    ) -> Result<crate::core::ExecutionResult, Self::Error> {
+        let mut options = context.shell.parser_options();
+        if self.posix {
+            options.posix_mode = true;
+        }
+
+        let mut inputs = vec![];
+        if let Some(command) = &self.command {
+            inputs.push((None, command.clone()));
+        } else if self.paths.is_empty() {
+            let mut input = String::new();
+            context.stdin().read_to_string(&mut input)?;
+            inputs.push((None, input));
+        } else {
+            for path in &self.paths {
+                match std::fs::read_to_string(context.shell.absolute_path(path)) {
+                    Ok(input) => inputs.push((Some(path), input)),
+                    Err(e) => {
+                        writeln!(context.stderr(), "brushlint: {}: {e}", path.display())?;
+                        return Ok(ExecutionResult::new(2));
+                    }
+                }
+            }
+        }
+
+        let mut result = ExecutionResult::success();
+        for (path, input) in inputs {
+            let prefix = path.map(|path| format!("{}:", path.display()));
+            let prefix = prefix.as_deref().unwrap_or_default();
+
+            match lint::lint_str(&input, &options) {
+                Ok(findings) => {
+                    for finding in &findings {
+                        writeln!(context.stdout(), "{prefix}{finding}")?;
+                    }
+                    if !findings.is_empty() && result.is_success() {
+                        result = ExecutionResult::general_error();
+                    }
+                }
+                Err(e) => {
+                    writeln!(context.stderr(), "brushlint: {prefix}{e}")?;
+                    result = ExecutionResult::new(2);
+                }
+            }
+        }
+
+        Ok(result)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_brushlint_command_string() -> anyhow::Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        let script = r#"clean="$(brushlint -c 'echo "$HOME"')"
+findings="$(brushlint -p -c '[[ -n $x ]]')"
+statuses="$(
+    brushlint -c 'echo "$HOME"'; echo $?
+    brushlint -c '[[ -n $x ]]' >/dev/null; echo $?
+    brushlint -c 'if then' 2>/dev/null; echo $?
+)""#;
+        let result = shell.exec(script, &params).await?;
+        assert!(result.is_success());
+
+        assert_eq!(shell.env_str("clean").as_deref(), Some(""));
+        assert_eq!(
+            shell.env_str("findings").as_deref(),
+            Some(
+                "1:1: warning[bashism]: `[[ ... ]]` is not specified by POSIX\n\
+                 1:7: warning[unassigned-variable]: `x` is referenced but never assigned"
+            )
+        );
+        assert_eq!(shell.env_str("statuses").as_deref(), Some("0\n1\n2"));
+
+        Ok(())
+    }
+}
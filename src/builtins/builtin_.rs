@@ -1,6 +1,6 @@
 use clap::Parser;
 
-use crate::core::{ExecutionResult, builtins};
+use crate::core::{ExecutionResult, builtins, commands};
 
 /// Directly invokes a built-in, without going through typical search order.
 #[derive(Default, Parser)]
@@ -33,10 +33,10 @@ impl builtins::Command for BuiltinCommand {
 
         let builtin_name = args[0].to_string();
 
-        if let Some(builtin) = context.shell.builtins().get(&builtin_name) {
+        if let Some(builtin) = context.shell.builtins().get(&builtin_name).cloned() {
             if !builtin.disabled {
                 context.command_name = builtin_name;
-                (builtin.execute_func)(context, args).await
+                commands::invoke_builtin(&builtin, context, args).await
             } else {
                 Err(crate::core::ErrorKind::BuiltinNotFound(builtin_name).into())
             }
@@ -1,9 +1,9 @@
 use std::io::Write;
-use std::path::PathBuf;
+use std::path::{Component, Path, PathBuf};
 
 use clap::Parser;
 
-use crate::core::{ExecutionResult, builtins, error};
+use crate::core::{ExecutionExitCode, ExecutionResult, Shell, builtins, env, error};
 
 /// Change the current shell working directory.
 #[derive(Parser)]
@@ -37,9 +37,16 @@ impl builtins::Command for CdCommand {
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<ExecutionResult, Self::Error> {
-        // TODO: implement 'cd -@'
+        // Presenting a file's extended attributes as a directory requires O_XATTR-style
+        // attribute directories, which none of the platforms we run on provide. As with
+        // bash on such platforms, the option is rejected as invalid.
         if self.file_with_xattr_as_dir {
-            return error::unimp("cd -@");
+            writeln!(
+                context.stderr(),
+                "{}: -@: invalid option",
+                context.command_name
+            )?;
+            return Ok(ExecutionExitCode::InvalidUsage.into());
         }
 
         let mut should_print = false;
@@ -53,6 +60,9 @@ impl builtins::Command for CdCommand {
                     writeln!(context.stderr(), "OLDPWD not set")?;
                     return Ok(ExecutionResult::general_error());
                 }
+            } else if let Some((found_dir, print)) = search_cdpath(context.shell, target_dir) {
+                should_print = print;
+                found_dir
             } else {
                 // TODO: remove clone, and use temporary lifetime extension after rust 1.75
                 target_dir.clone()
@@ -65,21 +75,36 @@ impl builtins::Command for CdCommand {
             return Ok(ExecutionResult::general_error());
         };
 
-        if self.use_physical_dir
+        let physical = self.use_physical_dir
             || context
                 .shell
                 .options
-                .do_not_resolve_symlinks_when_changing_dir
-        {
-            // -e is only relevant in physical mode.
-            if self.exit_on_failed_cwd_resolution {
-                return error::unimp("cd -e");
-            }
+                .do_not_resolve_symlinks_when_changing_dir;
+
+        let mut result = change_dir(context.shell, &target_dir, physical);
 
-            target_dir = context.shell.absolute_path(target_dir).canonicalize()?;
+        // If the directory couldn't be changed to, then see if there's an alternate
+        // interpretation of the argument we should try (per `cdable_vars` and `cdspell`).
+        if result.is_err()
+            && !should_print
+            && let Some(arg) = &self.target_dir
+            && let Some(alternate_dir) = find_alternate_dir(context.shell, arg)
+        {
+            should_print = true;
+            target_dir = alternate_dir;
+            result = change_dir(context.shell, &target_dir, physical);
         }
 
-        context.shell.set_working_dir(&target_dir)?;
+        result?;
+
+        // -e is only relevant in physical mode: fail if the new working directory
+        // can't be resolved.
+        if physical
+            && self.exit_on_failed_cwd_resolution
+            && context.shell.working_dir().canonicalize().is_err()
+        {
+            return Ok(ExecutionResult::general_error());
+        }
 
         // Bash compatibility
         // https://www.gnu.org/software/bash/manual/bash.html#index-cd
@@ -87,9 +112,201 @@ impl builtins::Command for CdCommand {
         // the directory change is successful, the absolute pathname of the new working
         // directory is written to the standard output.
         if should_print {
-            writeln!(context.stdout(), "{}", target_dir.display())?;
+            writeln!(context.stdout(), "{}", context.shell.working_dir().display())?;
         }
 
         Ok(ExecutionResult::success())
     }
 }
+
+fn change_dir(shell: &mut Shell, target_dir: &Path, physical: bool) -> Result<(), error::Error> {
+    if physical {
+        let target_dir = shell.absolute_path(target_dir).canonicalize()?;
+        shell.set_working_dir(target_dir)
+    } else {
+        shell.set_working_dir(target_dir)
+    }
+}
+
+/// Searches the directories listed in `CDPATH` for the given target. Returns the
+/// directory found, along with whether it should be displayed once changed to (i.e.,
+/// whether it was found via a non-empty `CDPATH` entry).
+fn search_cdpath(shell: &Shell, target_dir: &Path) -> Option<(PathBuf, bool)> {
+    // CDPATH isn't consulted for absolute paths or for paths explicitly relative to the
+    // current directory.
+    if target_dir.is_absolute()
+        || matches!(
+            target_dir.components().next(),
+            Some(Component::CurDir | Component::ParentDir)
+        )
+    {
+        return None;
+    }
+
+    let cdpath = shell.env_str("CDPATH")?;
+    for entry in cdpath.split(':') {
+        let candidate = if entry.is_empty() {
+            target_dir.to_path_buf()
+        } else {
+            Path::new(entry).join(target_dir)
+        };
+
+        if shell.absolute_path(&candidate).is_dir() {
+            return Some((candidate, !entry.is_empty()));
+        }
+    }
+
+    None
+}
+
+/// Finds an alternate directory to change to when the given argument doesn't name
+/// one, either by treating it as a variable name (`cdable_vars`) or by correcting
+/// minor misspellings (`cdspell`).
+fn find_alternate_dir(shell: &Shell, arg: &Path) -> Option<PathBuf> {
+    if shell.options.cdable_vars
+        && let Some(name) = arg.to_str()
+        && env::valid_variable_name(name)
+        && let Some(value) = shell.env_str(name)
+    {
+        let value = PathBuf::from(value.as_ref());
+        if shell.absolute_path(&value).is_dir() {
+            return Some(value);
+        }
+    }
+
+    // N.B. Spelling correction is only performed by interactive shells.
+    if shell.options.cd_autocorrect_spelling && shell.options.interactive {
+        return correct_dir_spelling(&shell.absolute_path(arg));
+    }
+
+    None
+}
+
+/// Tries to correct minor misspellings in each component of the given absolute path,
+/// returning the corrected path if it names a directory.
+fn correct_dir_spelling(path: &Path) -> Option<PathBuf> {
+    let mut corrected = PathBuf::new();
+
+    for component in path.components() {
+        let Component::Normal(name) = component else {
+            corrected.push(component);
+            continue;
+        };
+
+        if corrected.join(name).is_dir() {
+            corrected.push(name);
+            continue;
+        }
+
+        let name = name.to_str()?;
+        let replacement = std::fs::read_dir(&corrected)
+            .ok()?
+            .filter_map(Result::ok)
+            .filter(|entry| entry.path().is_dir())
+            .filter_map(|entry| entry.file_name().into_string().ok())
+            .filter(|candidate| is_minor_misspelling(name, candidate))
+            .min()?;
+
+        corrected.push(replacement);
+    }
+
+    Some(corrected)
+}
+
+/// Checks whether `typed` differs from `candidate` by a single transposition,
+/// insertion, deletion, or substitution of a character.
+fn is_minor_misspelling(typed: &str, candidate: &str) -> bool {
+    let typed: Vec<char> = typed.chars().collect();
+    let candidate: Vec<char> = candidate.chars().collect();
+
+    let prefix_len = typed
+        .iter()
+        .zip(candidate.iter())
+        .take_while(|(x, y)| x == y)
+        .count();
+
+    let typed_rest = &typed[prefix_len..];
+    let candidate_rest = &candidate[prefix_len..];
+
+    match (typed_rest, candidate_rest) {
+        // Identical strings aren't misspellings.
+        ([], []) => false,
+        // Substitution.
+        ([_, t @ ..], [_, c @ ..]) if t == c => true,
+        // Transposition.
+        ([t1, t2, t @ ..], [c1, c2, c @ ..]) => t1 == c2 && t2 == c1 && t == c,
+        // Extra or missing character.
+        ([_, t @ ..], c) | (t, [_, c @ ..]) => t == c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_is_minor_misspelling() {
+        assert!(is_minor_misspelling("tmp", "tnp"));
+        assert!(is_minor_misspelling("tpm", "tmp"));
+        assert!(is_minor_misspelling("tm", "tmp"));
+        assert!(is_minor_misspelling("tmmp", "tmp"));
+        assert!(!is_minor_misspelling("tmp", "tmp"));
+        assert!(!is_minor_misspelling("tmp", "usr"));
+        assert!(!is_minor_misspelling("tmp", "tmpfoo"));
+    }
+
+    #[tokio::test]
+    async fn test_cd_cdpath() -> anyhow::Result<()> {
+        let base = std::env::temp_dir().join(format!("cd-cdpath-test-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("parent").join("child"))?;
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        shell.set_working_dir(&base)?;
+
+        let script = format!("CDPATH=:{}; cd child", base.join("parent").display());
+        let result = shell.exec(script, &params).await?;
+        let cwd = shell.working_dir().to_path_buf();
+        std::fs::remove_dir_all(&base)?;
+
+        assert!(result.is_success());
+        assert_eq!(cwd, base.join("parent").join("child"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cd_cdpath_prints_dir_unless_entry_empty() -> anyhow::Result<()> {
+        let base = std::env::temp_dir().join(format!("cd-cdpath-print-{}", std::process::id()));
+        std::fs::create_dir_all(base.join("child"))?;
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        shell.set_working_dir(&base)?;
+
+        let script = r#"dot="$(CDPATH=.; cd child)"; empty="$(CDPATH=:; cd child)""#;
+        shell.exec(script, &params).await?;
+        let dot = shell.env_str("dot").unwrap_or_default().to_string();
+        let empty = shell.env_str("empty").unwrap_or_default().to_string();
+        std::fs::remove_dir_all(&base)?;
+
+        assert_eq!(dot, base.join("child").to_string_lossy());
+        assert_eq!(empty, "");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_cd_rejects_xattr_option() -> anyhow::Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        let cwd = shell.working_dir().to_path_buf();
+
+        let result = shell.exec("cd -@ /", &params).await?;
+
+        assert_eq!(u8::from(&result.exit_code), 2);
+        assert_eq!(shell.working_dir(), cwd);
+
+        Ok(())
+    }
+}
@@ -3,9 +3,8 @@ use itertools::Itertools;
 use std::{io::Write, sync::LazyLock};
 
 use crate::core::{
-    ErrorKind, ExecutionResult, builtins,
+    ErrorKind, ExecutionResult, arithmetic, builtins,
     env::{self, EnvironmentLookup, EnvironmentScope},
-    error,
     variables::{
         self, ArrayLiteral, ShellValue, ShellValueLiteral, ShellValueUnsetType, ShellVariable,
         ShellVariableUpdateTransform,
@@ -52,11 +51,11 @@ crate::minus_or_plus_flag_arg!(MakeExportedFlag, 'x', "Mark the variable for exp
 #[clap(override_usage = "declare [OPTIONS] [DECLARATIONS]...")]
 pub(crate) struct DeclareCommand {
     /// Constrain to function names or definitions.
-    #[arg(short = 'f')]
+    #[arg(short = 'f', alias = "+f")]
     function_names_or_defs_only: bool,
 
     /// Constrain to function names only.
-    #[arg(short = 'F')]
+    #[arg(short = 'F', alias = "+F")]
     function_names_only: bool,
 
     /// Create global variable, if applicable.
@@ -138,10 +137,6 @@ impl builtins::Command for DeclareCommand {
             return Ok(ExecutionResult::general_error());
         }
 
-        if self.locals_inherit_from_prev_scope {
-            return error::unimp("declare -I");
-        }
-
         let mut result = ExecutionResult::success();
         if !self.declarations.is_empty() {
             for declaration in &self.declarations {
@@ -196,7 +191,11 @@ impl DeclareCommand {
             if let Some(func_registration) = context.shell.funcs().get(name) {
                 if self.function_names_only {
                     if self.print {
-                        writeln!(context.stdout(), "declare -f {name}")?;
+                        writeln!(
+                            context.stdout(),
+                            "declare -{} {name}",
+                            func_registration.attribute_flags()
+                        )?;
                     } else {
                         writeln!(context.stdout(), "{name}")?;
                     }
@@ -208,7 +207,7 @@ impl DeclareCommand {
                 // For some reason, bash does not print an error message in this case.
                 Ok(false)
             }
-        } else if let Some(variable) = context.shell.env.get_using_policy(name, lookup) {
+        } else if let Some(variable) = context.shell.env.get_using_policy_noref(name, lookup) {
             let mut cs = variable.attribute_flags(context.shell);
             if cs.is_empty() {
                 cs.push('-');
@@ -240,10 +239,17 @@ impl DeclareCommand {
         declaration: &crate::core::CommandArg,
         verb: DeclareVerb,
     ) -> Result<bool, crate::core::Error> {
-        let create_var_local = matches!(verb, DeclareVerb::Local)
-            || (context.shell.in_function() && !self.create_global);
+        // `readonly` (like `declare -g`) never creates a local variable.
+        let create_var_local = match verb {
+            DeclareVerb::Local => true,
+            DeclareVerb::Declare => context.shell.in_function() && !self.create_global,
+            DeclareVerb::Readonly => false,
+        };
 
         if self.function_names_or_defs_only || self.function_names_only {
+            if self.make_traced.is_some() || self.make_exported.is_some() {
+                return self.update_function_attributes(context, declaration);
+            }
             return self.try_display_declaration(context, declaration, verb);
         }
 
@@ -253,9 +259,9 @@ impl DeclareCommand {
 
         // Special-case: `local -`
         if name == "-" && matches!(verb, DeclareVerb::Local) {
-            // TODO: `local -` allows shadowing the current `set` options (i.e., $-), with
-            // subsequent updates getting discarded when the current local scope is popped.
-            tracing::warn!("not yet implemented: local -");
+            // `local -` shadows the current `set` options (i.e., $-); any subsequent
+            // updates get discarded when the current function returns.
+            context.shell.save_set_options_for_current_function();
             return Ok(true);
         }
 
@@ -272,15 +278,86 @@ impl DeclareCommand {
         // Figure out where we should look.
         let lookup = if create_var_local {
             EnvironmentLookup::OnlyInCurrentLocal
+        } else if self.create_global {
+            EnvironmentLookup::OnlyInGlobal
         } else {
             EnvironmentLookup::Anywhere
         };
 
+        // Unless we've been asked to update the name reference attribute itself, any
+        // declaration of a (visible) name reference applies to the variable it refers to.
+        let name = if self.make_nameref.is_some()
+            || context
+                .shell
+                .env
+                .get_using_policy_noref(name.as_str(), lookup)
+                .is_none()
+        {
+            name
+        } else {
+            context.shell.env.resolve_nameref(name.as_str())?.unwrap_or(name)
+        };
+
+        // Make sure that any newly established name reference refers to something valid.
+        if matches!(self.make_nameref.to_bool(), Some(true))
+            && let Some(ShellValueLiteral::Scalar(target)) = &initial_value
+            && !Self::validate_nameref_target(context, name.as_str(), target)?
+        {
+            return Ok(false);
+        }
+
+        let existing_var = context
+            .shell
+            .env
+            .get_using_policy_noref(name.as_str(), lookup);
+
+        // A new local variable may shadow a variable from a previous scope; with `-I`
+        // (or `localvar_inherit`), it starts out with that variable's value and
+        // attributes.
+        let shadowed_var = if create_var_local && existing_var.is_none() {
+            context
+                .shell
+                .env
+                .get_using_policy_noref(name.as_str(), EnvironmentLookup::Anywhere)
+        } else {
+            None
+        };
+
+        if shadowed_var.is_some_and(ShellVariable::is_readonly) {
+            writeln!(
+                context.stderr(),
+                "{}: {name}: readonly variable",
+                context.command_name
+            )?;
+            return Ok(false);
+        }
+
+        let inherited_var = shadowed_var
+            .filter(|_| {
+                self.locals_inherit_from_prev_scope
+                    || context.shell.options.local_vars_inherit_value_and_attrs
+            })
+            .cloned();
+
+        // Values assigned to integer variables are evaluated as arithmetic expressions.
+        let will_be_integer = self.make_integer.to_bool().unwrap_or_else(|| {
+            existing_var
+                .or(inherited_var.as_ref())
+                .is_some_and(ShellVariable::is_treated_as_integer)
+        });
+
+        let initial_value = match initial_value {
+            Some(value) if will_be_integer => {
+                Some(arithmetic::eval_integer_assignment(context.shell, value)?)
+            }
+            value => value,
+        };
+
         // Look up the variable.
         if let Some(var) = context
             .shell
             .env
-            .get_mut_using_policy(name.as_str(), lookup)
+            .get_mut_using_policy_noref(name.as_str(), lookup)
         {
             if self.make_associative_array.is_some() {
                 var.convert_to_associative_array()?;
@@ -291,12 +368,30 @@ impl DeclareCommand {
 
             self.apply_attributes_before_update(var)?;
 
-            if let Some(initial_value) = initial_value {
-                // We append if the declaration included an explicit index.
-                var.assign(initial_value, assigned_index.is_some())?;
+            match initial_value {
+                // Scalar assignments to dynamic values are handed to the values themselves.
+                Some(ShellValueLiteral::Scalar(value))
+                    if assigned_index.is_none()
+                        && matches!(var.value(), ShellValue::Dynamic(_)) =>
+                {
+                    context
+                        .shell
+                        .assign_dynamic_variable(name.as_str(), None, value, false)?;
+                }
+                Some(initial_value) => {
+                    // We append if the declaration included an explicit index.
+                    var.assign(initial_value, assigned_index.is_some())?;
+                }
+                None => (),
             }
 
-            self.apply_attributes_after_update(var, verb)?;
+            if let Some(var) = context
+                .shell
+                .env
+                .get_mut_using_policy_noref(name.as_str(), lookup)
+            {
+                self.apply_attributes_after_update(var, verb)?;
+            }
         } else {
             let unset_type = if self.make_indexed_array.is_some() {
                 ShellValueUnsetType::IndexedArray
@@ -308,7 +403,21 @@ impl DeclareCommand {
                 ShellValueUnsetType::Untyped
             };
 
-            let mut var = ShellVariable::new(ShellValue::Unset(unset_type));
+            let mut var = if let Some(mut inherited_var) = inherited_var {
+                // The name reference attribute is never inherited.
+                inherited_var.unset_treat_as_nameref();
+
+                if self.make_associative_array.is_some() {
+                    inherited_var.convert_to_associative_array()?;
+                }
+                if self.make_indexed_array.is_some() {
+                    inherited_var.convert_to_indexed_array()?;
+                }
+
+                inherited_var
+            } else {
+                ShellVariable::new(ShellValue::Unset(unset_type))
+            };
 
             self.apply_attributes_before_update(&mut var)?;
 
@@ -334,6 +443,67 @@ impl DeclareCommand {
         Ok(true)
     }
 
+    fn update_function_attributes(
+        &self,
+        context: &mut crate::core::ExecutionContext<'_>,
+        declaration: &crate::core::CommandArg,
+    ) -> Result<bool, crate::core::Error> {
+        let crate::core::CommandArg::String(name) = declaration else {
+            writeln!(
+                context.stderr(),
+                "{}: {declaration}: not found",
+                context.command_name
+            )?;
+            return Ok(false);
+        };
+
+        // As with display, bash does not print an error message for unknown functions.
+        let Some(registration) = context.shell.func_mut(name) else {
+            return Ok(false);
+        };
+
+        if let Some(value) = self.make_traced.to_bool() {
+            if value {
+                registration.enable_trace();
+            } else {
+                registration.disable_trace();
+            }
+        }
+        if let Some(value) = self.make_exported.to_bool() {
+            if value {
+                registration.export();
+            } else {
+                registration.unexport();
+            }
+        }
+
+        Ok(true)
+    }
+
+    fn validate_nameref_target(
+        context: &crate::core::ExecutionContext<'_>,
+        name: &str,
+        target: &str,
+    ) -> Result<bool, crate::core::Error> {
+        if target == name {
+            return Err(ErrorKind::SelfReferencingNameReference(name.to_owned()).into());
+        }
+
+        if !target.is_empty()
+            && !env::valid_variable_name(target)
+            && env::split_array_element_ref(target).is_none()
+        {
+            writeln!(
+                context.stderr(),
+                "{}: `{target}': invalid variable name for name reference",
+                context.command_name
+            )?;
+            return Ok(false);
+        }
+
+        Ok(true)
+    }
+
     #[allow(clippy::unwrap_in_result)]
     fn declaration_to_name_and_value(
         declaration: &crate::core::CommandArg,
@@ -465,7 +635,7 @@ impl DeclareCommand {
         if let Some(value) = self.make_readonly.to_bool() {
             filters.push(Box::new(move |(_, v)| v.is_readonly() == value));
         }
-        if let Some(value) = self.make_readonly.to_bool() {
+        if let Some(value) = self.make_traced.to_bool() {
             filters.push(Box::new(move |(_, v)| v.is_trace_enabled() == value));
         }
         if let Some(value) = self.uppercase_value_on_assignment.to_bool() {
@@ -533,10 +703,17 @@ impl DeclareCommand {
         context: &crate::core::ExecutionContext<'_>,
     ) -> Result<(), crate::core::Error> {
         for (name, registration) in context.shell.funcs().iter().sorted_by_key(|v| v.0) {
+            let flags = registration.attribute_flags();
             if self.function_names_only {
-                writeln!(context.stdout(), "declare -f {name}")?;
+                writeln!(context.stdout(), "declare -{flags} {name}")?;
             } else {
                 writeln!(context.stdout(), "{}", registration.definition())?;
+
+                // Attributes beyond being a function are listed after the definition,
+                // in a form that restores them when the output is re-evaluated.
+                if flags != "f" {
+                    writeln!(context.stdout(), "declare -{flags} {name}")?;
+                }
             }
         }
 
@@ -607,6 +784,17 @@ impl DeclareCommand {
             }
         }
 
+        // As in bash, asking for both lowercase and uppercase conversion cancels both.
+        if matches!(
+            (
+                self.lowercase_value_on_assignment.to_bool(),
+                self.uppercase_value_on_assignment.to_bool()
+            ),
+            (Some(true), Some(true))
+        ) {
+            var.set_update_transform(ShellVariableUpdateTransform::None);
+        }
+
         Ok(())
     }
 
@@ -628,3 +816,83 @@ impl DeclareCommand {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    async fn exec_and_get(script: &str, var_names: &[&str]) -> anyhow::Result<Vec<String>> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        let result = shell.exec(script, &params).await?;
+        assert!(result.is_success());
+
+        Ok(var_names
+            .iter()
+            .map(|name| shell.env_str(name).unwrap_or_default().into_owned())
+            .collect())
+    }
+
+    #[tokio::test]
+    async fn test_local_inherits_from_previous_scope() -> anyhow::Result<()> {
+        let script = r#"
+declare -u upper=abc; target=t; declare -n ref=target
+f() { local -I upper ref; upper=xyz; inherited="$upper/$ref"; }
+g() { local upper; shadowed="${upper-unset}"; }
+h() { shopt -s localvar_inherit; local upper; via_option="$upper"; }
+f; g; h
+"#;
+        let values = exec_and_get(script, &["inherited", "shadowed", "via_option"]).await?;
+        assert_eq!(values, ["XYZ/target", "unset", "ABC"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_scoping_of_declarations() -> anyhow::Result<()> {
+        let script = r#"
+x=global
+f() { local x=local; g; in_f="$x"; }
+g() { declare -g x=updated; readonly r=1; }
+outer() { local v=outer; inner; after_unset="${v-unset}"; }
+inner() { unset v; }
+f
+shopt -s localvar_unset; outer
+"#;
+        let values = exec_and_get(script, &["x", "in_f", "r", "after_unset"]).await?;
+        assert_eq!(values, ["updated", "local", "1", "unset"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_attributes_applied_on_assignment() -> anyhow::Result<()> {
+        let script = r#"
+declare -i i=2+3; i+=2*2
+declare -c c=hELLO; c+=" WORLD"
+declare -ul neither=MiXeD
+f() { local -i n=i*2; doubled=$n; }; f
+"#;
+        let values = exec_and_get(script, &["i", "c", "neither", "doubled"]).await?;
+        assert_eq!(values, ["9", "Hello world", "MiXeD", "18"]);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_function_trace_attribute() -> anyhow::Result<()> {
+        let script = r#"
+returns=""
+trap 'returns+="$FUNCNAME "' RETURN
+plain() { :; }; traced() { :; }
+declare -ft traced
+plain; traced
+listing="$(declare -F)"
+"#;
+        let values = exec_and_get(script, &["returns", "listing"]).await?;
+        assert_eq!(values, ["traced ", "declare -f plain\ndeclare -ft traced"]);
+
+        Ok(())
+    }
+}
@@ -9,6 +9,18 @@ pub(crate) enum DirError {
     #[error("directory stack is empty")]
     DirStackEmpty,
 
+    /// The directory stack has no entry other than the current directory.
+    #[error("no other directory")]
+    NoOtherDirectory,
+
+    /// The given argument isn't a valid directory stack position.
+    #[error("{0}: invalid number")]
+    InvalidPosition(String),
+
+    /// The given directory stack position is out of range.
+    #[error("{0}: directory stack index out of range")]
+    IndexOutOfRange(String),
+
     /// A shell error occurred.
     #[error(transparent)]
     ShellError(#[from] crate::core::Error),
@@ -17,7 +29,10 @@ pub(crate) enum DirError {
 impl From<&DirError> for crate::core::ExecutionExitCode {
     fn from(value: &DirError) -> Self {
         match value {
-            DirError::DirStackEmpty => Self::GeneralError,
+            DirError::DirStackEmpty | DirError::NoOtherDirectory | DirError::IndexOutOfRange(_) => {
+                Self::GeneralError
+            }
+            DirError::InvalidPosition(_) => Self::InvalidUsage,
             DirError::ShellError(e) => e.into(),
         }
     }
@@ -43,12 +58,15 @@ pub(crate) struct DirsCommand {
     /// Print one directory per line with its index.
     #[arg(short = 'v')]
     print_one_per_line_with_index: bool,
-    //
-    // TODO: implement +N and -N
+
+    /// Display only the entry at the given position (+N counts from the left of the
+    /// list, -N from the right, starting with zero).
+    #[arg(allow_negative_numbers = true)]
+    position: Option<String>,
 }
 
 impl builtins::Command for DirsCommand {
-    type Error = crate::core::Error;
+    type Error = DirError;
 
     async fn execute(
         &self,
@@ -56,46 +74,128 @@ impl builtins::Command for DirsCommand {
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
         if self.clear {
             context.shell.directory_stack.clear();
-        } else {
-            let dirs = vec![context.shell.working_dir()]
-                .into_iter()
-                .chain(
-                    context
-                        .shell
-                        .directory_stack
-                        .iter()
-                        .rev()
-                        .map(|p| p.as_path()),
-                )
-                .collect::<Vec<_>>();
-
-            let one_per_line = self.print_one_per_line || self.print_one_per_line_with_index;
-
-            for (i, dir) in dirs.iter().enumerate() {
-                if !one_per_line && i > 0 {
-                    write!(context.stdout(), " ")?;
-                }
-
-                if self.print_one_per_line_with_index {
-                    write!(context.stdout(), "{i:2}  ")?;
-                }
-
-                let mut dir_str = dir.to_string_lossy().to_string();
-
-                if !self.tilde_long {
-                    dir_str = context.shell.tilde_shorten(dir_str);
-                }
-
-                write!(context.stdout(), "{dir_str}")?;
-
-                if one_per_line || i == dirs.len() - 1 {
-                    writeln!(context.stdout())?;
-                }
+            return Ok(ExecutionResult::success());
+        }
+
+        let dirs = context.shell.directory_stack_entries();
+
+        if let Some(position) = &self.position {
+            if !is_stack_position(position) {
+                return Err(DirError::InvalidPosition(position.clone()));
+            }
+
+            let index = context
+                .shell
+                .resolve_directory_stack_position(position)
+                .ok_or_else(|| {
+                    if dirs.len() == 1 {
+                        DirError::DirStackEmpty
+                    } else {
+                        DirError::IndexOutOfRange(position.clone())
+                    }
+                })?;
+
+            let dir_str = self.format_dir(context.shell, dirs[index]);
+            if self.print_one_per_line_with_index {
+                writeln!(context.stdout(), "{index:2}  {dir_str}")
+                    .map_err(crate::core::Error::from)?;
+            } else {
+                writeln!(context.stdout(), "{dir_str}").map_err(crate::core::Error::from)?;
             }
 
             return Ok(ExecutionResult::success());
         }
 
+        let one_per_line = self.print_one_per_line || self.print_one_per_line_with_index;
+        let mut output = String::new();
+
+        for (i, dir) in dirs.iter().enumerate() {
+            if !one_per_line && i > 0 {
+                output.push(' ');
+            }
+
+            if self.print_one_per_line_with_index {
+                output.push_str(std::format!("{i:2}  ").as_str());
+            }
+
+            output.push_str(self.format_dir(context.shell, dir).as_str());
+
+            if one_per_line || i == dirs.len() - 1 {
+                output.push('\n');
+            }
+        }
+
+        write!(context.stdout(), "{output}").map_err(crate::core::Error::from)?;
+
         Ok(ExecutionResult::success())
     }
 }
+
+impl DirsCommand {
+    fn format_dir(&self, shell: &crate::core::Shell, dir: &std::path::Path) -> String {
+        let dir_str = dir.to_string_lossy().to_string();
+        if self.tilde_long {
+            dir_str
+        } else {
+            shell.tilde_shorten(dir_str)
+        }
+    }
+}
+
+/// Returns whether the given argument has the form of a directory stack position
+/// (i.e., `+N` or `-N`).
+pub(crate) fn is_stack_position(arg: &str) -> bool {
+    arg.strip_prefix(['+', '-'])
+        .is_some_and(|digits| !digits.is_empty() && digits.chars().all(|c| c.is_ascii_digit()))
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    #[tokio::test]
+    async fn test_directory_stack_positions() -> anyhow::Result<()> {
+        let base = std::env::temp_dir().join(format!("dirs-test-{}", std::process::id()));
+        for name in ["a", "b", "c"] {
+            std::fs::create_dir_all(base.join(name))?;
+        }
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        shell.set_working_dir(base.join("a"))?;
+
+        let script = format!(
+            r#"base={}
+pushd $base/b >/dev/null; pushd $base/c >/dev/null
+pushd +2 >/dev/null; rotated="${{DIRSTACK[*]}}"
+popd -1 >/dev/null; popped="${{DIRSTACK[*]}}"
+DIRSTACK[1]=$base/x; assigned="$(dirs -l +1)"
+tilde=~-0"#,
+            base.display()
+        );
+        let result = shell.exec(script, &params).await?;
+        std::fs::remove_dir_all(&base)?;
+
+        assert!(result.is_success());
+
+        let base = base.display();
+        assert_eq!(
+            shell.env_str("rotated").as_deref(),
+            Some(format!("{base}/a {base}/c {base}/b").as_str())
+        );
+        assert_eq!(
+            shell.env_str("popped").as_deref(),
+            Some(format!("{base}/a {base}/b").as_str())
+        );
+        assert_eq!(
+            shell.env_str("assigned").as_deref(),
+            Some(format!("{base}/x").as_str())
+        );
+        assert_eq!(
+            shell.env_str("tilde").as_deref(),
+            Some(format!("{base}/x").as_str())
+        );
+
+        Ok(())
+    }
+}
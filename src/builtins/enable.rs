@@ -4,7 +4,7 @@ use itertools::Itertools;
 use std::io::Write;
 
 use crate::core::builtins;
-use crate::core::error;
+use crate::core::loadable;
 
 /// Enable, disable, or display built-in commands.
 #[derive(Parser)]
@@ -25,7 +25,8 @@ pub(crate) struct EnableCommand {
     #[arg(short = 's')]
     special_only: bool,
 
-    /// Path to a shared object from which built-in commands will be loaded.
+    /// Path to a shared object from which built-in commands will be loaded. If no
+    /// names are given, all built-in commands implemented by the object are loaded.
     #[arg(short = 'f', value_name = "PATH")]
     shared_object_path: Option<String>,
 
@@ -46,14 +47,19 @@ impl builtins::Command for EnableCommand {
     ) -> Result<ExecutionResult, Self::Error> {
         let mut result = ExecutionResult::success();
 
-        if self.shared_object_path.is_some() {
-            return error::unimp("enable -f");
-        }
-        if self.remove_loaded_builtin {
-            return error::unimp("enable -d");
-        }
-
-        if !self.names.is_empty() {
+        if let Some(shared_object_path) = &self.shared_object_path {
+            let path = loadable::resolve_library_path(context.shell, shared_object_path);
+            for builtin in loadable::load_library(&path, &self.names)? {
+                context.shell.register_loaded_builtin(builtin);
+            }
+        } else if self.remove_loaded_builtin {
+            for name in &self.names {
+                if let Err(e) = context.shell.unregister_loaded_builtin(name) {
+                    writeln!(context.stderr(), "{}: {e}", context.command_name)?;
+                    result = ExecutionResult::general_error();
+                }
+            }
+        } else if !self.names.is_empty() {
             for name in &self.names {
                 if let Some(builtin) = context.shell.builtin_mut(name) {
                     builtin.disabled = self.disable;
@@ -116,6 +116,22 @@ impl ExportCommand {
                     }
                 };
 
+                // Scalar assignments to dynamic values are handed to the values themselves.
+                if let variables::ShellValueLiteral::Scalar(s) = &value
+                    && context
+                        .shell
+                        .assign_dynamic_variable(name, None, s.clone(), false)?
+                {
+                    if let Some((_, var)) = context.shell.env.get_mut(name) {
+                        if self.unexport {
+                            var.unexport();
+                        } else {
+                            var.export();
+                        }
+                    }
+                    return Ok(ExecutionResult::success());
+                }
+
                 // Update the variable with the provided value and then mark it exported.
                 context.shell.env.update_or_add(
                     name,
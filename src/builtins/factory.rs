@@ -83,7 +83,7 @@ pub fn default_builtins(set: BuiltinSet) -> HashMap<String, builtins::Registrati
     //
 
     #[cfg(feature = "builtin.alias")]
-    m.insert("alias".into(), builtin::<alias::AliasCommand>()); // TODO: should be exec_declaration_builtin
+    m.insert("alias".into(), decl_builtin::<alias::AliasCommand>());
     #[cfg(feature = "builtin.bg")]
     m.insert("bg".into(), builtin::<bg::BgCommand>());
     #[cfg(feature = "builtin.cd")]
@@ -198,6 +198,10 @@ pub fn default_builtins(set: BuiltinSet) -> HashMap<String, builtins::Registrati
     //
     #[cfg(feature = "builtin.brushinfo")]
     m.insert("brushinfo".into(), builtin::<brushinfo::BrushInfoCommand>());
+    #[cfg(feature = "builtin.brushlint")]
+    m.insert("brushlint".into(), builtin::<brushlint::BrushLintCommand>());
 
-    m
+    m.into_iter()
+        .map(|(name, registration)| (name, registration.stock()))
+        .collect()
 }
@@ -1,4 +1,4 @@
-use crate::core::{ExecutionResult, builtins, error, history};
+use crate::core::{ExecutionResult, builtins, error, escape, history, sys};
 use clap::Parser;
 use std::io::Write;
 
@@ -41,7 +41,8 @@ impl builtins::Command for FcCommand {
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<ExecutionResult, Self::Error> {
-        if self.substitute {
+        // `fc -e -` is the POSIX spelling of `fc -s`.
+        if self.substitute || self.editor.as_deref() == Some("-") {
             return self.do_execute(context).await;
         }
 
@@ -49,7 +50,7 @@ impl builtins::Command for FcCommand {
             return self.do_list(&context);
         }
 
-        error::unimp("fc editor mode is not yet implemented")
+        self.do_edit(context).await
     }
 }
 
@@ -154,6 +155,72 @@ impl FcCommand {
         Ok(result)
     }
 
+    async fn do_edit(
+        &self,
+        context: crate::core::ExecutionContext<'_>,
+    ) -> Result<ExecutionResult, crate::core::Error> {
+        let history = context
+            .shell
+            .history()
+            .ok_or_else(|| crate::core::Error::from(crate::core::ErrorKind::HistoryNotEnabled))?;
+
+        let (first_idx, last_idx, reverse) = self.resolve_range(history)?;
+
+        let mut commands: Vec<String> = (first_idx..=last_idx)
+            .filter_map(|idx| history.get(idx).map(|item| item.command_line.clone()))
+            .collect();
+        if reverse {
+            commands.reverse();
+        }
+
+        if commands.is_empty() {
+            return Err(error::ErrorKind::HistoryItemNotFound.into());
+        }
+
+        // Select the editor: -e, then $FCEDIT, then $EDITOR, falling back to vi.
+        let editor = self
+            .editor
+            .clone()
+            .or_else(|| context.shell.env_str("FCEDIT").map(|s| s.into_owned()))
+            .or_else(|| context.shell.env_str("EDITOR").map(|s| s.into_owned()))
+            .filter(|editor| !editor.is_empty())
+            .unwrap_or_else(|| String::from("vi"));
+
+        let (temp_file_path, temp_file) = create_temp_file()?;
+        let edit_result = edit_commands(
+            context.shell,
+            &context.params,
+            &editor,
+            &temp_file_path,
+            temp_file,
+            &commands,
+        )
+        .await;
+        let _ = std::fs::remove_file(&temp_file_path);
+
+        // If the editor failed, then the commands are not executed.
+        let (editor_result, edited) = edit_result?;
+        if !editor_result.is_success() {
+            return Ok(editor_result);
+        }
+
+        // Echo the edited commands, as bash does.
+        write!(context.stderr(), "{edited}")?;
+
+        // Replace the fc command in history with the commands being executed.
+        let history_mut = context
+            .shell
+            .history_mut()
+            .ok_or_else(|| crate::core::Error::from(crate::core::ErrorKind::HistoryNotEnabled))?;
+        history_mut.remove_nth_item(history_mut.count().saturating_sub(1));
+
+        let result = context.shell.exec(edited.clone(), &context.params).await?;
+
+        context.shell.add_to_history(&edited)?;
+
+        Ok(result)
+    }
+
     fn resolve_range(
         &self,
         history: &history::History,
@@ -292,7 +359,85 @@ impl FcCommand {
     }
 }
 
+/// Writes the given commands to the temporary file, runs the editor on it, and reads
+/// back the edited contents. Returns the editor's result along with the contents.
+async fn edit_commands(
+    shell: &mut crate::core::Shell,
+    params: &crate::core::ExecutionParameters,
+    editor: &str,
+    temp_file_path: &std::path::Path,
+    mut temp_file: std::fs::File,
+    commands: &[String],
+) -> Result<(ExecutionResult, String), crate::core::Error> {
+    for command in commands {
+        writeln!(temp_file, "{command}")?;
+    }
+    temp_file.flush()?;
+    drop(temp_file);
+
+    // Run the editor through the shell, since the editor variables may contain
+    // arguments as well as a command name.
+    let editor_cmd = std::format!(
+        "{editor} {}",
+        escape::quote_if_needed(
+            temp_file_path.to_string_lossy().as_ref(),
+            escape::QuoteMode::SingleQuote
+        )
+    );
+    let editor_result = shell.exec(editor_cmd, params).await?;
+
+    let edited = std::fs::read_to_string(temp_file_path)?;
+
+    Ok((editor_result, edited))
+}
+
+/// Creates a new, unpredictably named temporary file to hold commands being edited.
+/// The file is only accessible to the current user.
+fn create_temp_file() -> Result<(std::path::PathBuf, std::fs::File), crate::core::Error> {
+    let temp_dir = std::env::temp_dir();
+
+    loop {
+        let path = temp_dir.join(std::format!("fc-{:016x}.sh", rand::random::<u64>()));
+        match sys::fs::create_private_file(&path) {
+            Ok(file) => return Ok((path, file)),
+            Err(err) if err.kind() == std::io::ErrorKind::AlreadyExists => (),
+            Err(err) => return Err(err.into()),
+        }
+    }
+}
+
 /// Returns the effective history count (excluding the fc command itself).
 fn effective_history_count(history: &history::History) -> usize {
     history.count().saturating_sub(1)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_temp_files_are_unique() -> anyhow::Result<()> {
+        let (first_path, _first) = create_temp_file()?;
+        let (second_path, _second) = create_temp_file()?;
+        let _ = std::fs::remove_file(&first_path);
+        let _ = std::fs::remove_file(&second_path);
+
+        assert_ne!(first_path, second_path);
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_temp_file_is_private() -> anyhow::Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let (path, file) = create_temp_file()?;
+        let mode = file.metadata()?.permissions().mode();
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(mode & 0o777, 0o600);
+
+        Ok(())
+    }
+}
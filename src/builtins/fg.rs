@@ -32,6 +32,7 @@ impl builtins::Command for FgCommand {
                 if matches!(job.state, jobs::JobState::Stopped) {
                     // N.B. We use the '\r' to overwrite any ^Z output.
                     let formatted = job.to_string();
+                    job.mark_reported();
                     writeln!(context.stderr(), "\r{formatted}")?;
                 }
 
@@ -56,6 +57,7 @@ impl builtins::Command for FgCommand {
             if matches!(job.state, jobs::JobState::Stopped) {
                 // N.B. We use the '\r' to overwrite any ^Z output.
                 let formatted = job.to_string();
+                job.mark_reported();
                 writeln!(context.stderr(), "\r{formatted}")?;
             }
 
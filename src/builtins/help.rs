@@ -117,7 +117,12 @@ impl HelpCommand {
             builtins::ContentType::DetailedHelp
         };
 
-        let content = (registration.content_func)(name, content_type)?;
+        // Builtins loaded from shared libraries carry their own documentation.
+        let content = if let Some(loaded) = context.shell.loaded_builtin(name) {
+            loaded.content(content_type)
+        } else {
+            (registration.content_func)(name, content_type)?
+        };
 
         write!(context.stdout(), "{content}")?;
         context.stdout().flush()?;
@@ -1,4 +1,4 @@
-use crate::core::{ExecutionExitCode, ExecutionResult, builtins, error, history};
+use crate::core::{ExecutionExitCode, ExecutionResult, builtins, history};
 use clap::Parser;
 use std::{io::Write, path::PathBuf};
 
@@ -48,6 +48,8 @@ pub(crate) struct HistoryCommand {
 struct HistoryConfig {
     default_history_file_path: Option<PathBuf>,
     time_format: Option<String>,
+    /// Whether the command line invoking this builtin was itself added to the history.
+    includes_current_command: bool,
 }
 
 impl builtins::Command for HistoryCommand {
@@ -61,6 +63,7 @@ impl builtins::Command for HistoryCommand {
         let config = HistoryConfig {
             default_history_file_path: context.shell.history_file_path(),
             time_format: context.shell.history_time_format(),
+            includes_current_command: context.shell.options.interactive,
         };
 
         let stdout = context.stdout();
@@ -132,12 +135,22 @@ impl HistoryCommand {
             return Ok(ExecutionResult::success());
         }
 
-        if self.append_rest_of_file_to_session.is_some() {
-            return error::unimp("history -n is not yet implemented");
-        }
+        if let Some(read_option) = self
+            .append_rest_of_file_to_session
+            .as_ref()
+            .or(self.append_file_to_session.as_ref())
+        {
+            if let Some(file_path) = get_effective_history_file_path(
+                config.default_history_file_path,
+                read_option.as_ref(),
+            ) {
+                history.read_file(
+                    file_path,
+                    self.append_rest_of_file_to_session.is_some(), /*new items only?*/
+                )?;
+            }
 
-        if self.append_file_to_session.is_some() {
-            return error::unimp("history -r is not yet implemented");
+            return Ok(ExecutionResult::success());
         }
 
         if let Some(write_option) = &self.write_session_to_file {
@@ -156,8 +169,14 @@ impl HistoryCommand {
             return Ok(ExecutionResult::success());
         }
 
-        if self.expand_args.is_some() {
-            return error::unimp("history -p is not yet implemented");
+        if let Some(args) = &self.expand_args {
+            // As with bash, the `history -p` command line itself is dropped from the
+            // history so it isn't referenced by the expansions (and isn't stored).
+            if config.includes_current_command {
+                history.remove_nth_item(history.count().saturating_sub(1));
+            }
+
+            return expand_args(history, args.iter().chain(self.args.iter()), stdout, stderr);
         }
 
         if let Some(args) = &self.append_args_to_session {
@@ -177,6 +196,25 @@ impl HistoryCommand {
     }
 }
 
+fn expand_args<'a>(
+    history: &history::History,
+    args: impl Iterator<Item = &'a String>,
+    mut stdout: impl Write,
+    mut stderr: impl Write,
+) -> Result<ExecutionResult, crate::core::Error> {
+    for arg in args {
+        match history.expand(arg) {
+            Ok(expanded) => writeln!(stdout, "{expanded}")?,
+            Err(err) => {
+                writeln!(stderr, "history: {err}")?;
+                return Ok(ExecutionResult::general_error());
+            }
+        }
+    }
+
+    Ok(ExecutionResult::success())
+}
+
 fn display_history(
     history: &history::History,
     config: &HistoryConfig,
@@ -243,4 +281,78 @@ mod tests {
 
         Ok(())
     }
+
+    fn run_history_command(
+        history: &mut history::History,
+        args: &[&str],
+    ) -> Result<(ExecutionResult, String)> {
+        let cmd =
+            HistoryCommand::try_parse_from(std::iter::once("history").chain(args.iter().copied()))?;
+        let config = HistoryConfig {
+            default_history_file_path: None,
+            time_format: None,
+            includes_current_command: false,
+        };
+
+        let mut stdout = vec![];
+        let result = cmd.execute_with_history(history, config, &mut stdout, std::io::sink())?;
+
+        Ok((result, String::from_utf8(stdout)?))
+    }
+
+    #[test]
+    fn test_read_history_file() -> Result<()> {
+        let path = std::env::temp_dir().join(format!("history-read-test-{}", std::process::id()));
+        let path_str = path.to_string_lossy().to_string();
+        std::fs::write(&path, "echo one\necho two\n")?;
+
+        let mut history = history::History::default();
+        run_history_command(&mut history, &["-r", &path_str])?;
+        assert_eq!(history.count(), 2);
+
+        // -n only picks up lines appended since the file was last read.
+        std::fs::write(&path, "echo one\necho two\necho three\n")?;
+        run_history_command(&mut history, &["-n", &path_str])?;
+        run_history_command(&mut history, &["-n", &path_str])?;
+
+        // -r always reads the whole file.
+        run_history_command(&mut history, &["-r", &path_str])?;
+        std::fs::remove_file(&path)?;
+
+        let lines: Vec<_> = history
+            .iter()
+            .map(|item| item.command_line.as_str())
+            .collect();
+        assert_eq!(
+            lines,
+            [
+                "echo one",
+                "echo two",
+                "echo three",
+                "echo one",
+                "echo two",
+                "echo three"
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_expand_args() -> Result<()> {
+        let mut history = history::History::default();
+        history.add(history::Item::new("echo hello world"))?;
+
+        let (result, output) = run_history_command(&mut history, &["-p", "!!:$", "x!$"])?;
+        assert!(result.is_success());
+        assert_eq!(output, "world\nxworld\n");
+
+        // Expansions aren't stored in the history.
+        assert_eq!(history.count(), 1);
+
+        let (result, _) = run_history_command(&mut history, &["-p", "!missing"])?;
+        assert!(!result.is_success());
+
+        Ok(())
+    }
 }
@@ -1,7 +1,7 @@
 use clap::Parser;
 use std::io::Write;
 
-use crate::core::{ExecutionResult, builtins, error, jobs};
+use crate::core::{ExecutionResult, ExecutionSpawnResult, builtins, commands, jobs};
 
 /// Manage jobs.
 #[derive(Parser)]
@@ -26,8 +26,13 @@ pub(crate) struct JobsCommand {
     #[arg(short = 's')]
     stopped_jobs_only: bool,
 
-    /// Job specs to list.
-    // TODO: Add -x option
+    /// Replace job specs in the given command and arguments with the process group
+    /// IDs of the jobs they identify, and execute the command.
+    #[arg(short = 'x')]
+    execute_command: bool,
+
+    /// Job specs to list (or, with -x, the command and arguments to execute).
+    #[arg(trailing_var_arg = true, allow_hyphen_values = true)]
     job_specs: Vec<String>,
 }
 
@@ -38,30 +43,42 @@ impl builtins::Command for JobsCommand {
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
-        if self.also_show_pids {
-            return error::unimp("jobs -l");
-        }
-        if self.list_changed_only {
-            return error::unimp("jobs -n");
+        if self.execute_command {
+            return self.execute_with_replacements(context).await;
         }
 
+        let mut result = ExecutionResult::success();
+        let mut stdout = context.stdout();
+
         if self.job_specs.is_empty() {
-            for job in &context.shell.jobs.jobs {
-                self.display_job(&context, job)?;
+            for job in &mut context.shell.jobs.jobs {
+                self.display_job(&mut stdout, job)?;
             }
         } else {
-            return error::unimp("jobs with job specs");
+            for job_spec in &self.job_specs {
+                if let Some(job) = context.shell.jobs.resolve_job_spec(job_spec) {
+                    self.display_job(&mut stdout, job)?;
+                } else {
+                    writeln!(
+                        context.stderr(),
+                        "{}: {}: no such job",
+                        context.command_name,
+                        job_spec
+                    )?;
+                    result = ExecutionResult::general_error();
+                }
+            }
         }
 
-        Ok(ExecutionResult::success())
+        Ok(result)
     }
 }
 
 impl JobsCommand {
     fn display_job(
         &self,
-        context: &crate::core::ExecutionContext<'_>,
-        job: &jobs::Job,
+        mut stdout: impl Write,
+        job: &mut jobs::Job,
     ) -> Result<(), crate::core::Error> {
         if self.running_jobs_only && !matches!(job.state, jobs::JobState::Running) {
             return Ok(());
@@ -69,15 +86,76 @@ impl JobsCommand {
         if self.stopped_jobs_only && !matches!(job.state, jobs::JobState::Stopped) {
             return Ok(());
         }
+        if self.list_changed_only && !job.has_unreported_change() {
+            return Ok(());
+        }
 
         if self.show_pids_only {
             if let Some(pid) = job.representative_pid() {
-                writeln!(context.stdout(), "{pid}")?;
+                writeln!(stdout, "{pid}")?;
             }
+        } else if self.also_show_pids {
+            writeln!(stdout, "{}", job.to_long_string())?;
         } else {
-            writeln!(context.stdout(), "{job}")?;
+            writeln!(stdout, "{job}")?;
         }
 
+        job.mark_reported();
+
         Ok(())
     }
+
+    async fn execute_with_replacements(
+        &self,
+        mut context: crate::core::ExecutionContext<'_>,
+    ) -> Result<ExecutionResult, crate::core::Error> {
+        let Some(command_name) = self.job_specs.first() else {
+            writeln!(
+                context.stderr(),
+                "{}: -x: option requires an argument",
+                context.command_name
+            )?;
+            return Ok(ExecutionResult::new(2));
+        };
+
+        // Job specs that don't identify a job are passed through unmodified.
+        let command_and_args = self
+            .job_specs
+            .iter()
+            .map(|arg| {
+                if arg.starts_with('%')
+                    && let Some(pgid) = context
+                        .shell
+                        .jobs
+                        .resolve_job_spec(arg)
+                        .and_then(|job| job.process_group_id())
+                {
+                    pgid.to_string().into()
+                } else {
+                    arg.into()
+                }
+            })
+            .collect();
+
+        command_name.clone_into(&mut context.command_name);
+
+        // We do not have an existing process group to place this into.
+        let mut pgid = None;
+
+        match commands::execute(
+            context,
+            &mut pgid,
+            command_and_args,
+            true, /* use functions? */
+            None,
+        )
+        .await?
+        {
+            ExecutionSpawnResult::StartedProcess(mut child) => {
+                let wait_result = child.wait(None).await?;
+                Ok(ExecutionResult::from(wait_result))
+            }
+            ExecutionSpawnResult::Completed(result) => Ok(result),
+        }
+    }
 }
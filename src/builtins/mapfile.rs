@@ -1,8 +1,8 @@
-use std::io::Read;
+use std::io::{Read, Write};
 
 use clap::Parser;
 
-use crate::core::{ErrorKind, ExecutionResult, builtins, env, error, variables};
+use crate::core::{ErrorKind, ExecutionResult, builtins, env, escape, variables};
 
 /// Inspect and modify key bindings and other input configuration.
 #[derive(Parser)]
@@ -15,9 +15,10 @@ pub(crate) struct MapFileCommand {
     #[arg(short = 'n', default_value_t = 0)]
     max_count: i64,
 
-    /// Index into array at which to start assignment.
-    #[arg(short = 'O', default_value_t = 0)]
-    origin: i64,
+    /// Index into array at which to start assignment; if specified, the array
+    /// is not cleared before assignment.
+    #[arg(short = 'O', value_parser = clap::value_parser!(i64).range(0..))]
+    origin: Option<i64>,
 
     /// Number of initial entries to skip.
     #[arg(short = 's', default_value_t = 0, value_parser = clap::value_parser!(i64).range(0..))]
@@ -36,7 +37,7 @@ pub(crate) struct MapFileCommand {
     callback: Option<String>,
 
     /// Number of lines to pass the callback for each group.
-    #[arg(short = 'c', default_value_t = 5000, value_parser = clap::value_parser!(i64).range(1..))]
+    #[arg(short = 'c', default_value_t = 5000)]
     callback_group_size: i64,
 
     /// Name of array to read into.
@@ -51,90 +52,124 @@ impl builtins::Command for MapFileCommand {
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
-        if self.origin != 0 {
-            // This will require merging into a potentially already-existing array.
-            return error::unimp("mapfile -O is not yet implemented");
+        if self.callback_group_size < 1 {
+            writeln!(
+                context.stderr(),
+                "{}: {}: invalid callback quantum",
+                context.command_name,
+                self.callback_group_size
+            )?;
+            return Ok(ExecutionResult::general_error());
         }
 
-        if self.callback_group_size != 5000 || self.callback.is_some() {
-            return error::unimp("mapfile -C/-c is not yet implemented");
-        }
-
-        let input_file = context
+        let mut input_file = context
             .try_fd(self.fd)
             .ok_or_else(|| ErrorKind::BadFileDescriptor(self.fd))?;
 
-        // Read!
-        let results = self.read_entries(input_file)?;
+        // Unless an origin was specified, the array is cleared before anything is
+        // assigned to it; otherwise, any existing elements are preserved.
+        if self.origin.is_some()
+            && let Some((_, var)) = context.shell.env.get_mut(&self.array_var_name)
+        {
+            var.convert_to_indexed_array()?;
+        } else {
+            context.shell.env.update_or_add(
+                &self.array_var_name,
+                variables::ShellValueLiteral::Array(variables::ArrayLiteral(vec![])),
+                |_| Ok(()),
+                env::EnvironmentLookup::Anywhere,
+                env::EnvironmentScope::Global,
+            )?;
+        }
+
+        let _term_mode = setup_terminal_settings(&input_file)?;
+
+        for _ in 0..self.skip_count {
+            if self.read_entry(&mut input_file)?.is_none() {
+                return Ok(ExecutionResult::success());
+            }
+        }
+
+        let max_count = self.max_count.try_into()?;
+        let mut index = self.origin.unwrap_or(0);
+        let mut line_count: usize = 0;
+
+        while max_count == 0 || line_count < max_count {
+            let Some(entry) = self.read_entry(&mut input_file)? else {
+                break;
+            };
+
+            line_count += 1;
+
+            // The callback is evaluated after each group of lines is read, but before
+            // the last line of the group is assigned; it receives the index of the
+            // element to be assigned and the line to assign there.
+            if let Some(callback) = &self.callback
+                && line_count.is_multiple_of(usize::try_from(self.callback_group_size)?)
+            {
+                let command = std::format!(
+                    "{callback} {index} {}",
+                    escape::force_quote(entry.as_str(), escape::QuoteMode::SingleQuote)
+                );
+
+                context.shell.exec(command, &context.params).await?;
+            }
+
+            context.shell.env.update_or_add_array_element(
+                &self.array_var_name,
+                index.to_string(),
+                entry,
+                |_| Ok(()),
+                env::EnvironmentLookup::Anywhere,
+                env::EnvironmentScope::Global,
+            )?;
 
-        // Assign!
-        context.shell.env.update_or_add(
-            &self.array_var_name,
-            variables::ShellValueLiteral::Array(results),
-            |_| Ok(()),
-            env::EnvironmentLookup::Anywhere,
-            env::EnvironmentScope::Global,
-        )?;
+            index += 1;
+        }
 
         Ok(ExecutionResult::success())
     }
 }
 
 impl MapFileCommand {
-    fn read_entries(
+    /// Reads the next entry from the input file; returns `None` at the end of input.
+    fn read_entry(
         &self,
-        mut input_file: crate::core::openfiles::OpenFile,
-    ) -> Result<variables::ArrayLiteral, crate::core::Error> {
-        let _term_mode = setup_terminal_settings(&input_file)?;
-
-        let mut entries = vec![];
-        let mut read_count = 0;
-        let max_count = self.max_count.try_into()?;
+        input_file: &mut crate::core::openfiles::OpenFile,
+    ) -> Result<Option<String>, crate::core::Error> {
         let delimiter = self.delimiter.chars().next().unwrap_or('\n') as u8;
 
         let mut buf = [0u8; 1];
-
-        while max_count == 0 || entries.len() < max_count {
-            let mut line = vec![];
-            let mut saw_delimiter = false;
-
-            loop {
-                match input_file.read(&mut buf) {
-                    Ok(0) => break,                                         // End of input
-                    Ok(1) if buf[0] == b'\x03' => break,                    // Ctrl+C
-                    Ok(1) if buf[0] == b'\x04' && line.is_empty() => break, // Ctrl+D
-                    Ok(1) => {
-                        let byte = buf[0];
-                        line.push(byte);
-                        if byte == delimiter {
-                            saw_delimiter = true;
-                            break;
-                        }
+        let mut line = vec![];
+        let mut saw_delimiter = false;
+
+        loop {
+            match input_file.read(&mut buf) {
+                Ok(0) => break,                                         // End of input
+                Ok(1) if buf[0] == b'\x03' => break,                    // Ctrl+C
+                Ok(1) if buf[0] == b'\x04' && line.is_empty() => break, // Ctrl+D
+                Ok(1) => {
+                    let byte = buf[0];
+                    line.push(byte);
+                    if byte == delimiter {
+                        saw_delimiter = true;
+                        break;
                     }
-                    Ok(_) => unreachable!("input can only be 0, 1, or error"),
-                    Err(e) => return Err(e.into()),
                 }
+                Ok(_) => unreachable!("input can only be 0, 1, or error"),
+                Err(e) => return Err(e.into()),
             }
+        }
 
-            if line.is_empty() && !saw_delimiter {
-                break;
-            }
-
-            if read_count < self.skip_count {
-                read_count += 1;
-                continue;
-            }
-
-            if self.remove_delimiter && line.ends_with(&[delimiter]) {
-                line.pop();
-            }
-
-            let line_str = String::from_utf8_lossy(&line).to_string();
+        if line.is_empty() && !saw_delimiter {
+            return Ok(None);
+        }
 
-            entries.push((None, line_str));
+        if self.remove_delimiter && line.ends_with(&[delimiter]) {
+            line.pop();
         }
 
-        Ok(variables::ArrayLiteral(entries))
+        Ok(Some(String::from_utf8_lossy(&line).to_string()))
     }
 }
 
@@ -153,3 +188,66 @@ fn setup_terminal_settings(
 
     Ok(mode)
 }
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    async fn run_mapfile_with_stdin(
+        command: &str,
+        input: &[u8],
+    ) -> anyhow::Result<crate::core::Shell> {
+        let (reader, mut writer) = std::io::pipe()?;
+        std::io::Write::write_all(&mut writer, input)?;
+        drop(writer);
+
+        let mut shell = crate::core::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+        params.set_fd(
+            crate::core::openfiles::OpenFiles::STDIN_FD,
+            crate::core::openfiles::OpenFile::PipeReader(reader),
+        );
+
+        let result = shell.exec(command, &params).await?;
+        assert!(result.is_success());
+
+        Ok(shell)
+    }
+
+    #[tokio::test]
+    async fn test_mapfile_origin_preserves_elements() -> anyhow::Result<()> {
+        let shell = run_mapfile_with_stdin(
+            "arr=(a b c d); mapfile -t -O 2 arr; s=${arr[*]}",
+            b"x\ny\nz\n",
+        )
+        .await?;
+
+        assert_eq!(shell.env_str("s").as_deref(), Some("a b x y z"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mapfile_callback() -> anyhow::Result<()> {
+        let shell = run_mapfile_with_stdin(
+            "cb() { calls+=\"$1:$2:${#arr[@]} \"; }; mapfile -t -C cb -c 2 arr",
+            b"one\ntwo\nthree\nfour\nfive\n",
+        )
+        .await?;
+
+        assert_eq!(shell.env_str("calls").as_deref(), Some("1:two:1 3:four:3 "));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_mapfile_rejects_invalid_callback_quantum() -> anyhow::Result<()> {
+        let mut shell = crate::core::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        let result = shell.exec("mapfile -c 0 arr 2>/dev/null", &params).await?;
+        assert_eq!(u8::from(&result.exit_code), 1);
+
+        Ok(())
+    }
+}
@@ -10,6 +10,8 @@ mod bind;
 mod break_;
 #[cfg(feature = "builtin.brushinfo")]
 mod brushinfo;
+#[cfg(feature = "builtin.brushlint")]
+mod brushlint;
 #[cfg(feature = "builtin.builtin")]
 mod builtin_;
 #[cfg(feature = "builtin.cd")]
@@ -2,35 +2,59 @@ use clap::Parser;
 
 use crate::core::{ExecutionResult, builtins};
 
+use super::dirs::DirError;
+
 /// Pop a path from the current directory stack.
 #[derive(Parser)]
 pub(crate) struct PopdCommand {
     /// Pop the path without changing the current working directory.
     #[clap(short = 'n')]
     no_directory_change: bool,
-    //
-    // TODO: implement +N and -N
+
+    /// Position of the entry to remove (+N counting from the left of the list shown
+    /// by `dirs`, -N from the right); if not specified, the top of the stack is removed.
+    #[arg(allow_negative_numbers = true)]
+    position: Option<String>,
 }
 
 impl builtins::Command for PopdCommand {
-    type Error = super::dirs::DirError;
+    type Error = DirError;
 
     async fn execute(
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
-        if let Some(popped) = context.shell.directory_stack.pop() {
-            if !self.no_directory_change {
-                context.shell.set_working_dir(&popped)?;
-            }
+        if context.shell.directory_stack.is_empty() {
+            return Err(DirError::DirStackEmpty);
+        }
 
-            // Display dirs.
-            let dirs_cmd = super::dirs::DirsCommand::default();
-            dirs_cmd.execute(context).await?;
+        let index = match &self.position {
+            Some(position) if super::dirs::is_stack_position(position) => context
+                .shell
+                .resolve_directory_stack_position(position)
+                .ok_or_else(|| DirError::IndexOutOfRange(position.clone()))?,
+            Some(position) => return Err(DirError::InvalidPosition(position.clone())),
+            None => 0,
+        };
 
-            Ok(ExecutionResult::success())
+        if index == 0 {
+            // Removing the current directory means changing to the top of the stack.
+            if !self.no_directory_change
+                && let Some(top) = context.shell.directory_stack.last()
+            {
+                let top = top.clone();
+                context.shell.set_working_dir(&top)?;
+            }
+            context.shell.directory_stack.pop();
         } else {
-            Err(super::dirs::DirError::DirStackEmpty)
+            let stack_index = context.shell.directory_stack.len() - index;
+            context.shell.directory_stack.remove(stack_index);
         }
+
+        // Display dirs.
+        let dirs_cmd = super::dirs::DirsCommand::default();
+        dirs_cmd.execute(context).await?;
+
+        Ok(ExecutionResult::success())
     }
 }
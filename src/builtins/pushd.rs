@@ -2,6 +2,8 @@ use clap::Parser;
 
 use crate::core::{ExecutionResult, builtins};
 
+use super::dirs::DirError;
+
 /// Push a path onto the current directory stack.
 #[derive(Parser)]
 pub(crate) struct PushdCommand {
@@ -9,31 +11,56 @@ pub(crate) struct PushdCommand {
     #[clap(short = 'n')]
     no_directory_change: bool,
 
-    /// Directory to push on the directory stack.
-    dir: String,
-    //
-    // TODO: implement +N and -N
+    /// Directory to push on the directory stack; or, a position (+N counting from the
+    /// left of the list shown by `dirs`, -N from the right) to rotate to the top of the
+    /// stack. If not specified, the top two directories are exchanged.
+    #[arg(allow_negative_numbers = true)]
+    dir: Option<String>,
 }
 
 impl builtins::Command for PushdCommand {
-    type Error = crate::core::Error;
+    type Error = DirError;
 
     async fn execute(
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
-        if self.no_directory_change {
-            context
-                .shell
-                .directory_stack
-                .push(std::path::PathBuf::from(&self.dir));
-        } else {
-            let prev_working_dir = context.shell.working_dir().to_path_buf();
-
-            let dir = std::path::Path::new(&self.dir);
-            context.shell.set_working_dir(dir)?;
-
-            context.shell.directory_stack.push(prev_working_dir);
+        match &self.dir {
+            Some(position) if super::dirs::is_stack_position(position) => {
+                if context.shell.directory_stack.is_empty() {
+                    return Err(DirError::DirStackEmpty);
+                }
+
+                let index = context
+                    .shell
+                    .resolve_directory_stack_position(position)
+                    .ok_or_else(|| DirError::IndexOutOfRange(position.clone()))?;
+
+                self.rotate(context.shell, index)?;
+            }
+            Some(dir) => {
+                if self.no_directory_change {
+                    context
+                        .shell
+                        .directory_stack
+                        .push(std::path::PathBuf::from(dir));
+                } else {
+                    let prev_working_dir = context.shell.working_dir().to_path_buf();
+
+                    let dir = std::path::Path::new(dir);
+                    context.shell.set_working_dir(dir)?;
+
+                    context.shell.directory_stack.push(prev_working_dir);
+                }
+            }
+            None => {
+                if context.shell.directory_stack.is_empty() {
+                    return Err(DirError::NoOtherDirectory);
+                }
+
+                // Exchange the top two directories.
+                self.rotate(context.shell, 1)?;
+            }
         }
 
         // Display dirs.
@@ -43,3 +70,32 @@ impl builtins::Command for PushdCommand {
         Ok(ExecutionResult::success())
     }
 }
+
+impl PushdCommand {
+    /// Rotates the directory stack so that the entry at the given index (into the list
+    /// shown by `dirs`) becomes the current directory.
+    fn rotate(&self, shell: &mut crate::core::Shell, index: usize) -> Result<(), DirError> {
+        if index == 0 {
+            return Ok(());
+        }
+
+        let mut entries: Vec<_> = shell
+            .directory_stack_entries()
+            .into_iter()
+            .map(|p| p.to_path_buf())
+            .collect();
+        entries.rotate_left(index);
+
+        // As with bash, when the directory isn't changed, the entry that would have
+        // become the current directory is dropped from the stack.
+        let new_working_dir = entries.remove(0);
+        if !self.no_directory_change {
+            shell.set_working_dir(&new_working_dir)?;
+        }
+
+        entries.reverse();
+        shell.directory_stack = entries;
+
+        Ok(())
+    }
+}
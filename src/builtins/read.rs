@@ -1,11 +1,18 @@
 use clap::Parser;
 use itertools::Itertools;
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
-use crate::core::{ErrorKind, builtins, env, error, variables};
+use crate::core::{ErrorKind, builtins, env, sys, variables};
 
 use std::io::Read;
 
+/// Exit status returned when a read times out (128 + SIGALRM, as bash reports).
+const TIMEOUT_EXIT_STATUS: u8 = 142;
+
+/// Interval at which a blocked read checks for cancellation.
+const CANCELLATION_POLL_INTERVAL: Duration = Duration::from_millis(50);
+
 /// Parse standard input.
 #[derive(Parser)]
 pub(crate) struct ReadCommand {
@@ -48,9 +55,9 @@ pub(crate) struct ReadCommand {
     silent: bool,
 
     /// Specify timeout in seconds; fail if the timeout elapses before
-    /// input is completed.
-    #[clap(short = 't', value_name = "SECONDS")]
-    timeout_in_seconds: Option<usize>,
+    /// input is completed. A timeout of 0 only checks whether input is available.
+    #[clap(short = 't', value_name = "SECONDS", value_parser = parse_timeout)]
+    timeout: Option<Duration>,
 
     /// File descriptor to read from instead of stdin.
     #[clap(short = 'u', name = "FD")]
@@ -68,18 +75,9 @@ impl builtins::Command for ReadCommand {
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
-        if self.use_readline {
-            return error::unimp("read -e");
-        }
-        if self.initial_text.is_some() {
-            return error::unimp("read -i");
-        }
         if self.raw_mode {
             tracing::debug!("read -r is not implemented");
         }
-        if self.timeout_in_seconds.is_some() {
-            return error::unimp_with_issue("read -t", 227);
-        }
 
         // Find the input stream to use.
         let input_stream = if let Some(fd_num) = self.fd_num_to_read {
@@ -93,11 +91,54 @@ impl builtins::Command for ReadCommand {
                 .unwrap()
         };
 
+        // If no timeout was given, then TMOUT provides the default.
+        let timeout = self.timeout.or_else(|| {
+            context
+                .shell
+                .env_str("TMOUT")
+                .and_then(|s| s.parse::<f64>().ok())
+                .filter(|secs| *secs > 0.0)
+                .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        });
+
+        // A zero timeout just checks for available input without reading any.
+        if timeout.is_some_and(|timeout| timeout.is_zero()) {
+            let available = sys::fd::poll_for_input(&input_stream, Duration::ZERO)?;
+            return Ok(if available {
+                crate::core::ExecutionResult::success()
+            } else {
+                crate::core::ExecutionResult::general_error()
+            });
+        }
+
         // Retrieve effective value of IFS for splitting.
         let ifs = context.shell.ifs();
 
-        let input_line = self.read_line(input_stream, context.stdout())?;
-        let result = if input_line.is_some() {
+        // The line editor has no notion of a deadline, so input is read directly
+        // whenever a timeout applies.
+        let line_editor = if self.use_readline && timeout.is_none() && input_stream.is_term() {
+            context.shell.line_editor().clone()
+        } else {
+            None
+        };
+
+        let (input_line, termination) = if let Some(line_editor) = line_editor {
+            let prompt = self.prompt.as_deref().unwrap_or_default();
+            let initial_text = self.initial_text.as_deref().unwrap_or_default();
+            let input_line = line_editor.lock().await.read_line(prompt, initial_text)?;
+            (input_line, ReadTermination::Delimiter)
+        } else {
+            self.read_line(
+                input_stream,
+                context.stdout(),
+                timeout.map(|timeout| Instant::now() + timeout),
+                context.params.cancellation_token(),
+            )?
+        };
+
+        let result = if matches!(termination, ReadTermination::Timeout) {
+            crate::core::ExecutionResult::new(TIMEOUT_EXIT_STATUS)
+        } else if input_line.is_some() {
             crate::core::ExecutionResult::success()
         } else {
             crate::core::ExecutionResult::general_error()
@@ -138,35 +179,19 @@ impl builtins::Command for ReadCommand {
             for (i, name) in self.variable_names.iter().enumerate() {
                 if fields.is_empty() {
                     // Ensure the var is empty.
-                    context.shell.env.update_or_add(
-                        name,
-                        variables::ShellValueLiteral::Scalar(String::new()),
-                        |_| Ok(()),
-                        env::EnvironmentLookup::Anywhere,
-                        env::EnvironmentScope::Global,
-                    )?;
+                    context.shell.assign_scalar_variable(name, String::new())?;
                     continue;
                 }
 
                 let last = i == self.variable_names.len() - 1;
                 if !last {
                     let next_field = fields.pop_front().unwrap();
-                    context.shell.env.update_or_add(
-                        name,
-                        variables::ShellValueLiteral::Scalar(next_field),
-                        |_| Ok(()),
-                        env::EnvironmentLookup::Anywhere,
-                        env::EnvironmentScope::Global,
-                    )?;
+                    context.shell.assign_scalar_variable(name, next_field)?;
                 } else {
                     let remaining_fields = fields.into_iter().join(" ");
-                    context.shell.env.update_or_add(
-                        name,
-                        variables::ShellValueLiteral::Scalar(remaining_fields),
-                        |_| Ok(()),
-                        env::EnvironmentLookup::Anywhere,
-                        env::EnvironmentScope::Global,
-                    )?;
+                    context
+                        .shell
+                        .assign_scalar_variable(name, remaining_fields)?;
                     break;
                 }
             }
@@ -175,13 +200,7 @@ impl builtins::Command for ReadCommand {
 
             // If no variable names were specified, then place everything into the
             // REPLY variable.
-            context.shell.env.update_or_add(
-                "REPLY",
-                variables::ShellValueLiteral::Scalar(input_line),
-                |_| Ok(()),
-                env::EnvironmentLookup::Anywhere,
-                env::EnvironmentScope::Global,
-            )?;
+            context.shell.assign_scalar_variable("REPLY", input_line)?;
         }
 
         Ok(result)
@@ -193,6 +212,7 @@ enum ReadTermination {
     EndOfInput,
     CtrlC,
     Limit,
+    Timeout,
 }
 
 impl ReadCommand {
@@ -200,7 +220,9 @@ impl ReadCommand {
         &self,
         mut input_file: crate::core::openfiles::OpenFile,
         mut output_file: impl std::io::Write,
-    ) -> Result<Option<String>, crate::core::Error> {
+        deadline: Option<Instant>,
+        cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+    ) -> Result<(Option<String>, ReadTermination), crate::core::Error> {
         let _term_mode = self.setup_terminal_settings(&input_file)?;
 
         let delimiter = if self.return_after_n_chars_no_delimiter.is_some() {
@@ -231,6 +253,15 @@ impl ReadCommand {
         let mut buffer = [0; 1]; // 1-byte buffer
 
         let reason = loop {
+            // Only block in the read once we know input is available, so that we can
+            // honor any timeout or cancellation request. Where input can't be polled,
+            // cancellation is only observed between reads.
+            if (deadline.is_some() || (cancellation_token.is_some() && cfg!(unix)))
+                && !wait_for_input(&input_file, deadline, cancellation_token)?
+            {
+                break ReadTermination::Timeout;
+            }
+
             // TODO: Figure out how to restore terminal settings on error?
             let n = input_file.read(&mut buffer)?;
             if n == 0 {
@@ -267,20 +298,25 @@ impl ReadCommand {
                 }
         };
 
-        match reason {
+        let line = match reason {
             ReadTermination::EndOfInput => {
                 if line.is_empty() {
-                    Ok(None)
+                    None
                 } else {
-                    Ok(Some(line))
+                    Some(line)
                 }
             }
             ReadTermination::CtrlC => {
                 // Discard the input and return.
-                Ok(None)
+                None
             }
-            ReadTermination::Delimiter | ReadTermination::Limit => Ok(Some(line)),
-        }
+            // N.B. On timeout, any partial input is still assigned.
+            ReadTermination::Delimiter | ReadTermination::Limit | ReadTermination::Timeout => {
+                Some(line)
+            }
+        };
+
+        Ok((line, reason))
     }
 
     fn setup_terminal_settings(
@@ -302,6 +338,46 @@ impl ReadCommand {
     }
 }
 
+/// Waits for input to become available on the given file. Returns `false` if the
+/// deadline passed first; returns an error if cancellation was requested.
+fn wait_for_input(
+    file: &crate::core::openfiles::OpenFile,
+    deadline: Option<Instant>,
+    cancellation_token: Option<&tokio_util::sync::CancellationToken>,
+) -> Result<bool, crate::core::Error> {
+    loop {
+        if cancellation_token.is_some_and(|token| token.is_cancelled()) {
+            return Err(ErrorKind::Interrupted.into());
+        }
+
+        let wait = if let Some(deadline) = deadline {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            if remaining.is_zero() {
+                return Ok(false);
+            }
+
+            if cancellation_token.is_some() {
+                remaining.min(CANCELLATION_POLL_INTERVAL)
+            } else {
+                remaining
+            }
+        } else {
+            CANCELLATION_POLL_INTERVAL
+        };
+
+        if sys::fd::poll_for_input(file, wait)? {
+            return Ok(true);
+        }
+    }
+}
+
+fn parse_timeout(s: &str) -> Result<Duration, String> {
+    s.parse::<f64>()
+        .ok()
+        .and_then(|secs| Duration::try_from_secs_f64(secs).ok())
+        .ok_or_else(|| format!("{s}: invalid timeout specification"))
+}
+
 fn split_line_by_ifs(ifs: &str, line: &str, max_fields: Option<usize>) -> VecDeque<String> {
     // Separate out the chars to split by.
     let ifs_chars = ifs.chars().collect::<Vec<_>>();
@@ -360,6 +436,74 @@ mod tests {
 
     use super::*;
 
+    async fn run_read_with_stdin(
+        command: &str,
+        reader: std::io::PipeReader,
+    ) -> anyhow::Result<(crate::core::ExecutionResult, crate::core::Shell)> {
+        let mut shell = crate::core::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+        params.set_fd(
+            crate::core::openfiles::OpenFiles::STDIN_FD,
+            crate::core::openfiles::OpenFile::PipeReader(reader),
+        );
+
+        let result = shell.exec(command, &params).await?;
+        Ok((result, shell))
+    }
+
+    #[tokio::test]
+    async fn test_read_timeout_keeps_partial_input() -> anyhow::Result<()> {
+        let (reader, mut writer) = std::io::pipe()?;
+        std::io::Write::write_all(&mut writer, b"partial")?;
+
+        let (result, shell) = run_read_with_stdin("read -t 0.1 value", reader).await?;
+        assert_eq!(u8::from(&result.exit_code), TIMEOUT_EXIT_STATUS);
+        assert_eq!(shell.env_str("value").as_deref(), Some("partial"));
+
+        drop(writer);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_with_tmout() -> anyhow::Result<()> {
+        let (reader, writer) = std::io::pipe()?;
+
+        let (result, _) = run_read_with_stdin("TMOUT=0.1; read value", reader).await?;
+        assert_eq!(u8::from(&result.exit_code), TIMEOUT_EXIT_STATUS);
+
+        drop(writer);
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_zero_timeout_checks_availability() -> anyhow::Result<()> {
+        let (reader, writer) = std::io::pipe()?;
+        let (result, _) = run_read_with_stdin("read -t 0", reader).await?;
+        assert!(!result.is_success());
+        drop(writer);
+
+        let (reader, mut writer) = std::io::pipe()?;
+        std::io::Write::write_all(&mut writer, b"data\n")?;
+        let (result, shell) = run_read_with_stdin("read -t 0 value", reader).await?;
+        assert!(result.is_success());
+        assert!(shell.env_str("value").is_none());
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_with_timeout_completes_before_deadline() -> anyhow::Result<()> {
+        let (reader, mut writer) = std::io::pipe()?;
+        std::io::Write::write_all(&mut writer, b"a b\n")?;
+
+        let (result, shell) = run_read_with_stdin("read -e -i x -t 5 first second", reader).await?;
+        assert!(result.is_success());
+        assert_eq!(shell.env_str("first").as_deref(), Some("a"));
+        assert_eq!(shell.env_str("second").as_deref(), Some("b"));
+
+        Ok(())
+    }
+
     #[test]
     fn test_split_line_by_ifs() {
         let result = split_line_by_ifs(",", "a,b,c", None);
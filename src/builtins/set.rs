@@ -411,7 +411,7 @@ fn display_all(context: &crate::core::ExecutionContext<'_>) -> Result<(), crate:
         // of bash is not quite clear. We've empirically found that some
         // special variables don't get displayed until they're observed
         // at least once.
-        if matches!(var.value(), variables::ShellValue::Dynamic { .. }) {
+        if matches!(var.value(), variables::ShellValue::Dynamic(_)) {
             continue;
         }
 
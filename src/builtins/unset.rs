@@ -43,17 +43,17 @@ impl builtins::Command for UnsetCommand {
         &self,
         context: crate::core::ExecutionContext<'_>,
     ) -> Result<crate::core::ExecutionResult, Self::Error> {
-        //
-        // TODO: implement nameref
-        //
-        if self.name_interpretation.name_references {
-            return crate::core::error::unimp("unset: name references are not yet implemented");
-        }
-
         let unspecified = self.name_interpretation.unspecified();
 
         #[expect(clippy::needless_continue)]
         for name in &self.names {
+            // With -n, we unset the name reference itself rather than the variable it
+            // refers to.
+            if self.name_interpretation.name_references {
+                context.shell.env.unset_noref(name)?;
+                continue;
+            }
+
             if unspecified || self.name_interpretation.shell_variables {
                 let parameter =
                     crate::parser::word::parse_parameter(name, &context.shell.parser_options())?;
@@ -62,7 +62,15 @@ impl builtins::Command for UnsetCommand {
                     crate::parser::word::Parameter::Positional(_) => continue,
                     crate::parser::word::Parameter::Special(_) => continue,
                     crate::parser::word::Parameter::Named(name) => {
-                        context.shell.env.unset(name.as_str())?.is_some()
+                        if context.shell.options.localvar_unset {
+                            context
+                                .shell
+                                .env
+                                .unset_keeping_local_placeholders(name.as_str())?
+                                .is_some()
+                        } else {
+                            context.shell.env.unset(name.as_str())?.is_some()
+                        }
                     }
                     crate::parser::word::Parameter::NamedWithIndex { name, index } => {
                         unset_array_index(context.shell, name.as_str(), index.as_str())?
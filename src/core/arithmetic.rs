@@ -106,6 +106,43 @@ pub(crate) async fn expand_and_eval(
     expr.eval(shell)
 }
 
+/// Evaluates each of the (already expanded) values in the given literal as an
+/// arithmetic expression, as is done when assigning to a variable with the integer
+/// attribute. Array indices are left untouched.
+///
+/// # Arguments
+///
+/// * `shell` - The shell to use for evaluation.
+/// * `literal` - The values being assigned.
+pub(crate) fn eval_integer_assignment(
+    shell: &mut Shell,
+    literal: variables::ShellValueLiteral,
+) -> Result<variables::ShellValueLiteral, EvalError> {
+    let mut eval_value = |value: String| -> Result<String, EvalError> {
+        if value.trim().is_empty() {
+            return Ok(String::from("0"));
+        }
+
+        let expr = crate::parser::arithmetic::parse(&value)
+            .map_err(|_e| EvalError::ParseError(value.clone()))?;
+        Ok(expr.eval(shell)?.to_string())
+    };
+
+    Ok(match literal {
+        variables::ShellValueLiteral::Scalar(value) => {
+            variables::ShellValueLiteral::Scalar(eval_value(value)?)
+        }
+        variables::ShellValueLiteral::Array(variables::ArrayLiteral(elements)) => {
+            variables::ShellValueLiteral::Array(variables::ArrayLiteral(
+                elements
+                    .into_iter()
+                    .map(|(key, value)| Ok((key, eval_value(value)?)))
+                    .collect::<Result<_, EvalError>>()?,
+            ))
+        }
+    })
+}
+
 /// Trait implemented by evaluatable arithmetic expressions.
 pub trait Evaluatable {
     /// Evaluate the given arithmetic expression, returning the resulting numeric value.
@@ -119,9 +156,9 @@ pub trait Evaluatable {
 impl Evaluatable for ast::ArithmeticExpr {
     fn eval(&self, shell: &mut Shell) -> Result<i64, EvalError> {
         let value = match self {
-            Self::Literal(l) => *l,
+            Self::Literal(l, _) => *l,
             Self::Reference(lvalue) => deref_lvalue(shell, lvalue)?,
-            Self::UnaryOp(op, operand) => apply_unary_op(shell, *op, operand)?,
+            Self::UnaryOp(op, operand, _) => apply_unary_op(shell, *op, operand)?,
             Self::BinaryOp(op, left, right) => apply_binary_op(shell, *op, left, right)?,
             Self::Conditional(condition, then_expr, else_expr) => {
                 let conditional_eval = condition.eval(shell)?;
@@ -137,7 +174,7 @@ impl Evaluatable for ast::ArithmeticExpr {
                 let expr_eval = expr.eval(shell)?;
                 assign(shell, lvalue, expr_eval)?
             }
-            Self::UnaryAssignment(op, lvalue) => apply_unary_assignment_op(shell, lvalue, *op)?,
+            Self::UnaryAssignment(op, lvalue, _) => apply_unary_assignment_op(shell, lvalue, *op)?,
             Self::BinaryAssignment(op, lvalue, operand) => {
                 let value = apply_binary_op(shell, *op, &Self::Reference(lvalue.clone()), operand)?;
                 assign(shell, lvalue, value)?
@@ -165,8 +202,8 @@ fn get_var_value<'a>(shell: &'a Shell, name: &str) -> Result<Cow<'a, str>, EvalE
 
 fn deref_lvalue(shell: &mut Shell, lvalue: &ast::ArithmeticTarget) -> Result<i64, EvalError> {
     let value_str: Cow<'_, str> = match lvalue {
-        ast::ArithmeticTarget::Variable(name) => get_var_value(shell, name.as_str())?,
-        ast::ArithmeticTarget::ArrayElement(name, index_expr) => {
+        ast::ArithmeticTarget::Variable(name, _) => get_var_value(shell, name.as_str())?,
+        ast::ArithmeticTarget::ArrayElement(name, index_expr, _) => {
             let index_str = index_expr.eval(shell)?.to_string();
 
             shell
@@ -315,19 +352,12 @@ fn apply_unary_assignment_op(
 
 fn assign(shell: &mut Shell, lvalue: &ast::ArithmeticTarget, value: i64) -> Result<i64, EvalError> {
     match lvalue {
-        ast::ArithmeticTarget::Variable(name) => {
+        ast::ArithmeticTarget::Variable(name, _) => {
             shell
-                .env
-                .update_or_add(
-                    name.as_str(),
-                    variables::ShellValueLiteral::Scalar(value.to_string()),
-                    |_| Ok(()),
-                    env::EnvironmentLookup::Anywhere,
-                    env::EnvironmentScope::Global,
-                )
+                .assign_scalar_variable(name.as_str(), value.to_string())
                 .map_err(|_err| EvalError::FailedToUpdateEnvironment)?;
         }
-        ast::ArithmeticTarget::ArrayElement(name, index_expr) => {
+        ast::ArithmeticTarget::ArrayElement(name, index_expr, _) => {
             let index_str = index_expr.eval(shell)?.to_string();
 
             shell
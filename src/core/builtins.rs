@@ -139,6 +139,10 @@ pub struct Registration {
 
     /// Is this builtin one that takes specially handled declarations?
     pub declaration_builtin: bool,
+
+    /// Is this one of the shell's own builtins, as opposed to one provided by an embedder
+    /// or loaded from a shared library?
+    pub stock: bool,
 }
 
 impl Registration {
@@ -150,6 +154,15 @@ impl Registration {
             ..self
         }
     }
+
+    /// Updates the given registration to mark it for one of the shell's own builtins.
+    #[must_use]
+    pub const fn stock(self) -> Self {
+        Self {
+            stock: true,
+            ..self
+        }
+    }
 }
 
 fn get_builtin_man_page(_name: &str, _command: &clap::Command) -> Result<String, error::Error> {
@@ -354,6 +367,7 @@ pub fn simple_builtin<B: SimpleCommand + Send + Sync>() -> Registration {
         disabled: false,
         special_builtin: false,
         declaration_builtin: false,
+        stock: false,
     }
 }
 
@@ -366,6 +380,7 @@ pub fn builtin<B: Command + Send + Sync>() -> Registration {
         disabled: false,
         special_builtin: false,
         declaration_builtin: false,
+        stock: false,
     }
 }
 
@@ -379,6 +394,7 @@ pub fn decl_builtin<B: DeclarationCommand + Send + Sync>() -> Registration {
         disabled: false,
         special_builtin: false,
         declaration_builtin: true,
+        stock: false,
     }
 }
 
@@ -396,6 +412,7 @@ pub fn raw_arg_builtin<B: DeclarationCommand + Default + Send + Sync>() -> Regis
         disabled: false,
         special_builtin: false,
         declaration_builtin: true,
+        stock: false,
     }
 }
 
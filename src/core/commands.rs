@@ -8,7 +8,7 @@ use sys::commands::{CommandExt, CommandFdInjectionExt, CommandFgControlExt};
 
 use super::{
     ErrorKind, ExecutionControlFlow, ExecutionParameters, ExecutionResult, Shell, ShellFd,
-    builtins, env, error, escape,
+    builtins, dryrun, env, error, escape, interfaces,
     interp::{self, Execute, ProcessGroupPolicy},
     openfiles::{self, OpenFile, OpenFiles},
     pathsearch, processes,
@@ -235,10 +235,8 @@ async fn invoke_debug_trap_handler_if_registered(
     if context.shell.traps.handler_depth == 0 {
         let debug_trap_handler = context
             .shell
-            .traps
-            .handlers
-            .get(&traps::TrapSignal::Debug)
-            .cloned();
+            .applicable_trap_handler(traps::TrapSignal::Debug)
+            .map(str::to_owned);
         if let Some(debug_trap_handler) = debug_trap_handler {
             // TODO: Confirm whether trap handlers should be executed in the same process group.
             let mut handler_params = context.params.clone();
@@ -270,6 +268,34 @@ async fn invoke_debug_trap_handler_if_registered(
     Ok(())
 }
 
+/// Runs the `RETURN` trap handler, if one applies, as a shell function or sourced
+/// script finishes executing.
+///
+/// # Arguments
+///
+/// * `shell` - The shell in which the function or script is executing.
+/// * `params` - Execution parameters for the function or script.
+pub(crate) async fn invoke_return_trap_handler_if_registered(
+    shell: &mut Shell,
+    params: &ExecutionParameters,
+) {
+    if shell.traps.handler_depth == 0
+        && let Some(return_trap_handler) = shell
+            .applicable_trap_handler(traps::TrapSignal::Return)
+            .map(str::to_owned)
+    {
+        let mut handler_params = params.clone();
+        handler_params.process_group_policy = ProcessGroupPolicy::SameProcessGroup;
+
+        shell.traps.handler_depth += 1;
+
+        // N.B. As with other trap handlers, the handler's own result is discarded.
+        let _ = shell.exec(return_trap_handler, &handler_params).await;
+
+        shell.traps.handler_depth -= 1;
+    }
+}
+
 /// Executes a simple command.
 ///
 /// The command may be a builtin, a shell function, or an externally
@@ -318,6 +344,15 @@ pub async fn execute(
             .funcs()
             .get(cmd_context.command_name.as_str())
         {
+            if let Some(dry_run) = cmd_context.params.dry_run() {
+                dry_run.record_command(
+                    cmd_context.shell,
+                    &args,
+                    dryrun::DryRunCommandKind::Function,
+                    false,
+                );
+            }
+
             // Strip the function name off args.
             return invoke_shell_function(func_reg.definition.clone(), cmd_context, &args[1..])
                 .await;
@@ -329,6 +364,26 @@ pub async fn execute(
             return execute_builtin_command(&builtin, cmd_context, args).await;
         }
 
+    // With autocd enabled, interactive shells treat a lone directory name as an
+    // argument to cd.
+    if cmd_context.shell.options.auto_cd
+        && cmd_context.shell.options.interactive
+        && args.len() == 1
+        && cmd_context
+            .shell
+            .absolute_path(&cmd_context.command_name)
+            .is_dir()
+        && let Some(cd) = cmd_context.shell.builtins().get("cd").cloned()
+        && !cd.disabled
+    {
+        let cd_args = vec![
+            CommandArg::String("cd".into()),
+            CommandArg::String("--".into()),
+            CommandArg::String(cmd_context.command_name.clone()),
+        ];
+        return execute_builtin_command(&cd, cmd_context, cd_args).await;
+    }
+
     // We still haven't found a command to invoke. We'll need to look for an external command.
     if !cmd_context.command_name.contains(std::path::MAIN_SEPARATOR) {
         // All else failed; if we were given path directories to search, try to look through them
@@ -354,7 +409,19 @@ pub async fn execute(
                 &args[1..],
             )
         } else {
-            Err(ErrorKind::CommandNotFound(cmd_context.command_name).into())
+            if let Some(dry_run) = cmd_context.params.dry_run() {
+                dry_run.record_command(
+                    cmd_context.shell,
+                    &args,
+                    dryrun::DryRunCommandKind::NotFound,
+                    false,
+                );
+
+                // Not-found handlers may have effects of their own, so don't consult them.
+                return Err(ErrorKind::CommandNotFound(cmd_context.command_name).into());
+            }
+
+            handle_command_not_found(cmd_context, process_group_id, &args).await
         }
     } else {
         let resolved_path = cmd_context.command_name.clone();
@@ -369,12 +436,93 @@ pub async fn execute(
     }
 }
 
+/// Name of the shell function invoked when a command can't be found.
+const COMMAND_NOT_FOUND_HANDLER_FUNC: &str = "command_not_found_handle";
+
+/// Handles a command that couldn't be found in the search path, consulting the
+/// embedder's handler (if any) and then the `command_not_found_handle` function (if
+/// defined) before reporting the failure.
+async fn handle_command_not_found(
+    mut cmd_context: ExecutionContext<'_>,
+    process_group_id: &mut Option<i32>,
+    args: &[CommandArg],
+) -> Result<ExecutionSpawnResult, error::Error> {
+    let argv: Vec<String> = args.iter().map(|arg| arg.to_string()).collect();
+
+    if let Some(handler) = cmd_context.shell.command_not_found_handler().clone() {
+        match handler
+            .handle_command_not_found(&mut cmd_context, argv.as_slice())
+            .await?
+        {
+            interfaces::CommandNotFoundAction::NotHandled => (),
+            interfaces::CommandNotFoundAction::Handled(result) => return Ok(result.into()),
+            interfaces::CommandNotFoundAction::Execute(path) => {
+                return execute_external_command(
+                    cmd_context,
+                    path.to_string_lossy().as_ref(),
+                    process_group_id,
+                    &args[1..],
+                );
+            }
+        }
+    }
+
+    // Invoke the handler function in a subshell, unless we're already running in it.
+    let handler_func = cmd_context
+        .shell
+        .funcs()
+        .get(COMMAND_NOT_FOUND_HANDLER_FUNC)
+        .map(|registration| registration.definition.clone());
+    let in_handler_func = cmd_context
+        .shell
+        .function_call_stack()
+        .iter()
+        .any(|frame| frame.function_name == COMMAND_NOT_FOUND_HANDLER_FUNC);
+
+    if let Some(handler_func) = handler_func
+        && !in_handler_func
+    {
+        let mut subshell = cmd_context.shell.clone();
+        let handler_context = ExecutionContext {
+            shell: &mut subshell,
+            command_name: COMMAND_NOT_FOUND_HANDLER_FUNC.to_owned(),
+            params: cmd_context.params.clone(),
+        };
+
+        let handler_args: Vec<_> = argv.into_iter().map(CommandArg::String).collect();
+        return match invoke_shell_function(handler_func, handler_context, &handler_args).await? {
+            ExecutionSpawnResult::Completed(mut result) => {
+                // Control flow (e.g., `exit`) doesn't escape the subshell.
+                result.next_control_flow = ExecutionControlFlow::Normal;
+                Ok(result.into())
+            }
+            started @ ExecutionSpawnResult::StartedProcess(_) => Ok(started),
+        };
+    }
+
+    Err(ErrorKind::CommandNotFound(cmd_context.command_name).into())
+}
+
 pub(crate) fn execute_external_command(
     context: ExecutionContext<'_>,
     executable_path: &str,
     process_group_id: &mut Option<i32>,
     args: &[CommandArg],
 ) -> Result<ExecutionSpawnResult, error::Error> {
+    // In dry-run mode, record the command instead of spawning it.
+    if let Some(dry_run) = context.params.dry_run() {
+        let argv: Vec<_> = std::iter::once(CommandArg::String(context.command_name.clone()))
+            .chain(args.iter().cloned())
+            .collect();
+        dry_run.record_command(
+            context.shell,
+            &argv,
+            dryrun::DryRunCommandKind::External(executable_path.into()),
+            true,
+        );
+        return Ok(ExecutionResult::success().into());
+    }
+
     // Filter out the args; we only want strings.
     let mut cmd_args = vec![];
     for arg in args {
@@ -401,8 +549,17 @@ pub(crate) fn execute_external_command(
         false, /* empty environment? */
     )?;
 
+    // Processes spawned for a command substitution lead their own process group (unless
+    // they need to read from the terminal), so that the substitution can kill them along
+    // with any processes they start if it's abandoned.
+    let substitution_processes = context.params.substitution_processes();
+    let isolate_pg = !new_pg
+        && process_group_id.is_none()
+        && substitution_processes.is_some()
+        && !child_stdin_is_terminal;
+
     // Set up process group state.
-    if new_pg {
+    if new_pg || isolate_pg {
         // We need to set up a new process group.
         cmd.process_group(0);
     } else {
@@ -435,9 +592,12 @@ pub(crate) fn execute_external_command(
             #[expect(clippy::cast_possible_wrap)]
             let pid = child.id().map(|id| id as i32);
             if let Some(pid) = &pid {
-                if new_pg {
+                if new_pg || isolate_pg {
                     *process_group_id = Some(*pid);
                 }
+                if let Some(substitution_processes) = substitution_processes {
+                    substitution_processes.add(*pid, new_pg || isolate_pg);
+                }
             } else {
                 tracing::warn!("could not retrieve pid for child process");
             }
@@ -475,10 +635,45 @@ async fn execute_builtin_command(
     context: ExecutionContext<'_>,
     args: Vec<CommandArg>,
 ) -> Result<ExecutionSpawnResult, error::Error> {
-    let result = (builtin.execute_func)(context, args).await?;
+    let result = invoke_builtin(builtin, context, args).await?;
     Ok(result.into())
 }
 
+/// Invokes the given builtin, or records it instead if running in dry-run mode and the
+/// builtin may have effects outside the shell.
+///
+/// # Arguments
+///
+/// * `builtin` - The registration of the builtin to invoke.
+/// * `context` - The context in which to invoke the builtin.
+/// * `args` - The arguments to the builtin, including its name.
+pub(crate) async fn invoke_builtin(
+    builtin: &builtins::Registration,
+    context: ExecutionContext<'_>,
+    args: Vec<CommandArg>,
+) -> Result<ExecutionResult, error::Error> {
+    if let Some(dry_run) = context.params.dry_run() {
+        let stubbed = dryrun::is_stubbed_builtin(builtin, &args);
+        dry_run.record_command(
+            context.shell,
+            &args,
+            dryrun::DryRunCommandKind::Builtin,
+            stubbed,
+        );
+
+        if stubbed {
+            // `exec` wouldn't have returned.
+            let mut result = ExecutionResult::success();
+            if args.first().is_some_and(|arg| arg.to_string() == "exec") {
+                result.next_control_flow = ExecutionControlFlow::ExitShell;
+            }
+            return Ok(result);
+        }
+    }
+
+    (builtin.execute_func)(context, args).await
+}
+
 pub(crate) async fn invoke_shell_function(
     function_definition: Arc<ast::FunctionDefinition>,
     mut context: ExecutionContext<'_>,
@@ -509,6 +704,9 @@ pub(crate) async fn invoke_shell_function(
     // Invoke the function.
     let result = body.execute(context.shell, &params).await;
 
+    // Run any applicable RETURN trap while still in the function's context.
+    invoke_return_trap_handler_if_registered(context.shell, &params).await;
+
     // Clean up parameters so any owned files are closed.
     drop(params);
 
@@ -541,40 +739,203 @@ pub(crate) async fn invoke_command_in_subshell_and_get_output(
     params: &ExecutionParameters,
     s: String,
 ) -> Result<String, error::Error> {
+    // In dry-run mode, the substitution is stubbed out and expands to nothing.
+    if let Some(dry_run) = params.dry_run() {
+        dry_run.record_substitution(s);
+        *shell.last_exit_status_mut() = 0;
+        return Ok(String::new());
+    }
+
+    let output_limit = params.command_substitution_output_limit();
+    let timeout = params.command_substitution_timeout();
+
     // Instantiate a subshell to run the command in.
     let subshell = shell.clone();
 
-    // Get our own set of parameters we can customize and use.
+    // Get our own set of parameters we can customize and use. The subshell
+    // inherits the caller's cancellation token, so any processes it spawns
+    // observe cancellation directly.
     let mut params = params.clone();
     params.process_group_policy = ProcessGroupPolicy::SameProcessGroup;
+    let cancellation_token = params.cancellation_token().cloned();
+    let substitution_processes = params.track_substitution_processes();
 
     // Set up pipe so we can read the output.
     let (reader, writer) = std::io::pipe()?;
     params.set_fd(OpenFiles::STDOUT_FD, writer.into());
 
-    // Start the execution of the command, but don't wait for it to
-    // complete. In case the command generates lots of output, we
-    // need to start reading in parallel so the command doesn't block
-    // when the pipe's buffer fills up. We pass ownership of the
-    // subshell and params to run_substitution_command; we must
-    // ensure that they're both dropped by the time this call
-    // returns (so they're not holding onto the write end of the pipe).
-    let cmd_join_handle = tokio::task::spawn_blocking(move || {
-        let rt = tokio::runtime::Handle::current();
-        rt.block_on(run_substitution_command(subshell, params, s))
+    // Drain the pipe on a blocking thread, forwarding output to us in chunks
+    // over a bounded channel. The command may generate lots of output, so we
+    // need to start reading in parallel so it doesn't block when the pipe's
+    // buffer fills up. The reader stops once the output exceeds the limit.
+    let (output_tx, mut output_rx) =
+        tokio::sync::mpsc::channel(COMMAND_SUBSTITUTION_OUTPUT_CHANNEL_CAPACITY);
+    let reader_join_handle = tokio::task::spawn_blocking(move || {
+        forward_pipe_output(reader.into(), &output_tx, output_limit);
     });
 
-    // Extract output.
-    let output_str = std::io::read_to_string(reader)?;
+    // Start the execution of the command as a task on the current runtime. We
+    // pass ownership of the subshell and params to run_substitution_command;
+    // they're dropped when the task completes (or is aborted), closing the
+    // write end of the pipe.
+    let cmd_join_handle = tokio::spawn(run_substitution_command(subshell, params, s));
+    let abort_handle = cmd_join_handle.abort_handle();
+
+    let collect_output_and_result = async move {
+        let mut output = vec![];
+        while let Some(chunk) = output_rx.recv().await {
+            output.extend_from_slice(&chunk?);
+        }
+
+        let cmd_result = cmd_join_handle.await??;
+        Ok::<_, error::Error>((output, cmd_result))
+    };
+
+    let result = tokio::select! {
+        biased;
 
-    // Now observe the command's completion.
-    let run_result = cmd_join_handle.await?;
-    let cmd_result = run_result?;
+        () = async {
+            if let Some(token) = &cancellation_token {
+                token.cancelled().await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        } => Err(ErrorKind::CommandSubstitutionCancelled.into()),
+        () = async {
+            if let Some(timeout) = timeout {
+                tokio::time::sleep(timeout).await;
+            } else {
+                std::future::pending::<()>().await;
+            }
+        } => Err(ErrorKind::CommandSubstitutionTimedOut.into()),
+        result = collect_output_and_result => result,
+    };
+
+    // Make sure the command, and any processes it started, don't outlive a substitution
+    // that failed. Dropping the output channel (along with the rest of the collecting
+    // future) also stops the reader, even if something still holds the pipe open.
+    if result.is_err() {
+        substitution_processes.kill_all();
+        abort_handle.abort();
+    }
+    let _ = reader_join_handle.await;
+    let (output, cmd_result) = result?;
 
     // Store the status.
     *shell.last_exit_status_mut() = cmd_result.exit_code.into();
 
-    Ok(output_str)
+    Ok(String::from_utf8(output)?)
+}
+
+/// Maximum number of output chunks from a command substitution that may be
+/// buffered before the reader stops draining the pipe.
+const COMMAND_SUBSTITUTION_OUTPUT_CHANNEL_CAPACITY: usize = 16;
+
+/// How often the reader of a command substitution's output checks whether the
+/// substitution is still interested in it, while waiting for output.
+const COMMAND_SUBSTITUTION_READER_POLL_INTERVAL: std::time::Duration =
+    std::time::Duration::from_millis(50);
+
+/// Reads everything from the given pipe, forwarding it in chunks to the provided
+/// channel. Stops early if the receiving side of the channel goes away, or after
+/// reporting an error once more than `limit` bytes have been read.
+fn forward_pipe_output(
+    mut reader: OpenFile,
+    output_tx: &tokio::sync::mpsc::Sender<Result<Vec<u8>, error::Error>>,
+    limit: usize,
+) {
+    use std::io::Read as _;
+
+    let mut buf = [0u8; 8192];
+    let mut total_len = 0;
+    loop {
+        // Where the pipe can be polled, only block in the read once we know there's
+        // output (or end of input), so we notice if the receiver goes away while
+        // something keeps the pipe open.
+        if cfg!(unix) {
+            match sys::fd::poll_for_input(&reader, COMMAND_SUBSTITUTION_READER_POLL_INTERVAL) {
+                Ok(true) => (),
+                Ok(false) if output_tx.is_closed() => break,
+                Ok(false) => continue,
+                Err(e) => {
+                    let _ = output_tx.blocking_send(Err(e));
+                    break;
+                }
+            }
+        }
+
+        let chunk = match reader.read(&mut buf) {
+            Ok(0) => break,
+            Ok(n) if total_len + n > limit => {
+                Err(ErrorKind::CommandSubstitutionOutputTooLarge(limit).into())
+            }
+            Ok(n) => {
+                total_len += n;
+                Ok(buf[..n].to_vec())
+            }
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(e) => Err(e.into()),
+        };
+
+        let is_err = chunk.is_err();
+        if output_tx.blocking_send(chunk).is_err() || is_err {
+            break;
+        }
+    }
+}
+
+/// Tracks the external processes spawned while running a command substitution, so
+/// that they can be killed if the substitution is abandoned. Clones share the same
+/// set of processes.
+#[derive(Clone)]
+pub(crate) struct SubstitutionProcesses {
+    /// Spawned processes, with whether each leads its own process group.
+    processes: Arc<std::sync::Mutex<Vec<(sys::process::ProcessId, bool)>>>,
+    /// The processes of the enclosing substitution, if any.
+    enclosing: Option<Box<Self>>,
+}
+
+impl SubstitutionProcesses {
+    pub(crate) fn new(enclosing: Option<Self>) -> Self {
+        Self {
+            processes: Arc::default(),
+            enclosing: enclosing.map(Box::new),
+        }
+    }
+
+    /// Records a spawned process, here and in any enclosing substitutions.
+    ///
+    /// # Arguments
+    ///
+    /// * `pid` - The process ID of the spawned process.
+    /// * `leads_process_group` - Whether the process leads its own process group.
+    pub(crate) fn add(&self, pid: sys::process::ProcessId, leads_process_group: bool) {
+        let mut tracker = Some(self);
+        while let Some(current) = tracker {
+            if let Ok(mut processes) = current.processes.lock() {
+                processes.push((pid, leads_process_group));
+            }
+            tracker = current.enclosing.as_deref();
+        }
+    }
+
+    /// Kills the recorded processes, along with the process groups they lead.
+    fn kill_all(&self) {
+        let processes = self
+            .processes
+            .lock()
+            .map(|mut processes| std::mem::take(&mut *processes))
+            .unwrap_or_default();
+
+        for (pid, leads_process_group) in processes {
+            // A negative process ID identifies the process group it leads.
+            let target = if leads_process_group { -pid } else { pid };
+            let _ = sys::signal::kill_process(
+                target,
+                traps::TrapSignal::Signal(sys::signal::Signal::SIGKILL),
+            );
+        }
+    }
 }
 
 async fn run_substitution_command(
@@ -653,7 +1014,80 @@ fn try_unwrap_bare_input_redir_program(program: &ast::Program) -> Option<&ast::I
             fd,
             ast::IoFileRedirectKind::Read,
             ast::IoFileRedirectTarget::Filename(..),
+            _,
         ) if fd.is_none_or(|fd| fd == openfiles::OpenFiles::STDIN_FD) => Some(redir),
         _ => None,
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    const MISSING_COMMAND: &str = "definitely-not-a-real-command-1234";
+
+    #[tokio::test]
+    async fn command_not_found_handle_runs_in_subshell() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell
+            .exec(
+                r#"command_not_found_handle() { [[ $* == "$1 a b" ]] || return 1; leaked=1; return 42; }"#,
+                &params,
+            )
+            .await?;
+
+        let result = shell.exec(format!("{MISSING_COMMAND} a b"), &params).await?;
+        assert_eq!(u8::from(&result.exit_code), 42);
+        assert!(shell.env_str("leaked").is_none());
+
+        Ok(())
+    }
+
+    struct TestHandler;
+
+    #[async_trait::async_trait]
+    impl interfaces::CommandNotFoundHandler for TestHandler {
+        async fn handle_command_not_found(
+            &self,
+            context: &mut ExecutionContext<'_>,
+            args: &[String],
+        ) -> Result<interfaces::CommandNotFoundAction, error::Error> {
+            if args[0] == MISSING_COMMAND {
+                context.shell.env.set_global(
+                    "HANDLED_ARGS",
+                    variables::ShellVariable::new(args.join(" ")),
+                )?;
+                Ok(interfaces::CommandNotFoundAction::Handled(
+                    ExecutionResult::new(7),
+                ))
+            } else {
+                Ok(interfaces::CommandNotFoundAction::NotHandled)
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn embedder_command_not_found_handler() -> Result<()> {
+        let mut shell = crate::Shell::builder()
+            .command_not_found_handler(Arc::new(TestHandler))
+            .build()
+            .await?;
+        let params = shell.default_exec_params();
+
+        let result = shell.exec(format!("{MISSING_COMMAND} x"), &params).await?;
+        assert_eq!(u8::from(&result.exit_code), 7);
+        assert_eq!(
+            shell.env_str("HANDLED_ARGS").as_deref(),
+            Some(format!("{MISSING_COMMAND} x").as_str())
+        );
+
+        // Commands the handler declines still fail as usual.
+        let result = shell.exec("another-missing-command-5678", &params).await?;
+        assert_eq!(u8::from(&result.exit_code), 127);
+
+        Ok(())
+    }
+}
@@ -0,0 +1,534 @@
+//! Dry-run execution, for explaining what a program would do without doing it.
+//!
+//! In dry-run mode (see [`ExecutionParameters::set_dry_run`]), the interpreter performs
+//! assignments, expansions and control flow as usual, but replaces anything with effects
+//! outside the shell with a recorded stub:
+//!
+//! * external commands aren't spawned, and complete successfully;
+//! * redirections that write to files use a null file instead of opening (or creating)
+//!   the file;
+//! * command and process substitutions aren't run, and expand to nothing;
+//! * builtins that act on the process, the system or state shared with the shell's host
+//!   (e.g. `exec ls`, `kill`, `ulimit -n 1`, `bind`), and builtins that aren't the shell's own
+//!   (registered by an embedder, or loaded with `enable -f`), aren't run, and complete
+//!   successfully;
+//! * commands that can't be found aren't passed to any not-found handler (the embedder's, or
+//!   a `command_not_found_handle` function), and fail as usual.
+//!
+//! Everything else (the shell's own builtins, functions) runs normally, so dry runs should be
+//! performed in a disposable shell; [`Shell::dry_run`] runs a program in a clone of a shell and returns
+//! the resulting [`DryRunTrace`].
+
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use crate::core::commands::CommandArg;
+use crate::core::{ExecutionParameters, Shell, ShellFd, builtins, error};
+
+/// Builtins that are always stubbed in dry-run mode.
+const STUBBED_BUILTINS: &[&str] = &["fc", "kill", "suspend"];
+
+/// Builtins that are stubbed in dry-run mode when given arguments. N.B. Key bindings are
+/// shared with clones of a shell, so `bind` is among them.
+const STUBBED_BUILTINS_WITH_ARGS: &[&str] =
+    &["bind", "enable", "exec", "history", "ulimit", "umask"];
+
+/// The trace of a dry run: what would have run, in order.
+#[derive(Clone, Debug, Default)]
+pub struct DryRunTrace {
+    /// The steps of the run.
+    pub steps: Vec<DryRunStep>,
+}
+
+impl DryRunTrace {
+    /// Returns the commands of the run.
+    pub fn commands(&self) -> impl Iterator<Item = &DryRunCommand> {
+        self.steps.iter().filter_map(|step| match step {
+            DryRunStep::Command(command) => Some(command),
+            DryRunStep::Substitution(_) => None,
+        })
+    }
+}
+
+/// A step of a dry run.
+#[derive(Clone, Debug)]
+pub enum DryRunStep {
+    /// A command was invoked.
+    Command(DryRunCommand),
+    /// A command or process substitution was stubbed; holds the substituted command.
+    Substitution(String),
+}
+
+/// A command invoked during a dry run.
+#[derive(Clone, Debug)]
+pub struct DryRunCommand {
+    /// The command's arguments, including the command name.
+    pub argv: Vec<String>,
+    /// How the command was resolved.
+    pub kind: DryRunCommandKind,
+    /// Whether the command was stubbed rather than run.
+    pub stubbed: bool,
+    /// The working directory the command would have run in.
+    pub cwd: PathBuf,
+    /// The changes to the exported environment since the start of the dry run, as seen by
+    /// the command; variables that are no longer exported map to `None`.
+    pub env: BTreeMap<String, Option<String>>,
+    /// The redirections in effect for the command, in the order they were applied.
+    pub redirects: Vec<DryRunRedirect>,
+}
+
+/// How a command invoked during a dry run was resolved.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DryRunCommandKind {
+    /// A builtin.
+    Builtin,
+    /// A shell function.
+    Function,
+    /// An external command; holds the path of its executable.
+    External(PathBuf),
+    /// A command that couldn't be found.
+    NotFound,
+}
+
+/// A redirection applied during a dry run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct DryRunRedirect {
+    /// The file descriptor being redirected.
+    pub fd: ShellFd,
+    /// What the file descriptor is redirected to.
+    pub target: DryRunRedirectTarget,
+    /// How the target is opened.
+    pub mode: DryRunRedirectMode,
+}
+
+impl DryRunRedirect {
+    /// Returns whether the redirection was stubbed (i.e., it writes to a file).
+    pub const fn is_stubbed(&self) -> bool {
+        matches!(self.target, DryRunRedirectTarget::File(_))
+            && !matches!(self.mode, DryRunRedirectMode::Read)
+    }
+}
+
+/// The target of a redirection applied during a dry run.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum DryRunRedirectTarget {
+    /// A file; holds its absolute path.
+    File(PathBuf),
+    /// A duplicate of another file descriptor.
+    Fd(ShellFd),
+    /// The file descriptor is closed.
+    Closed,
+    /// A here-document or here-string; holds its expanded contents.
+    HereDocument(String),
+    /// A (stubbed) process substitution; holds the substituted command.
+    ProcessSubstitution(String),
+}
+
+/// How the target of a redirection is opened.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DryRunRedirectMode {
+    /// For reading.
+    Read,
+    /// For writing, truncating any existing file.
+    Write,
+    /// For appending.
+    Append,
+    /// For reading and writing.
+    ReadWrite,
+}
+
+/// Records the trace of a dry run. Clones share the same trace.
+#[derive(Clone)]
+pub struct DryRun {
+    state: Arc<Mutex<DryRunState>>,
+    /// The redirections in effect for the execution parameters holding this.
+    redirects: Vec<DryRunRedirect>,
+}
+
+struct DryRunState {
+    /// The exported environment at the start of the dry run.
+    initial_env: HashMap<String, String>,
+    steps: Vec<DryRunStep>,
+}
+
+impl DryRun {
+    /// Returns a new recorder for a dry run in the given shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell the dry run will execute in.
+    pub fn new(shell: &Shell) -> Self {
+        Self {
+            state: Arc::new(Mutex::new(DryRunState {
+                initial_env: exported_env(shell),
+                steps: vec![],
+            })),
+            redirects: vec![],
+        }
+    }
+
+    /// Returns the trace recorded so far.
+    pub fn trace(&self) -> DryRunTrace {
+        DryRunTrace {
+            steps: self.state().steps.clone(),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, DryRunState> {
+        // The state is only modified by appending to it, so a poisoned lock is still usable.
+        self.state
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+    }
+
+    /// Records the invocation of a command.
+    pub(crate) fn record_command(
+        &self,
+        shell: &Shell,
+        args: &[CommandArg],
+        kind: DryRunCommandKind,
+        stubbed: bool,
+    ) {
+        let env = exported_env(shell);
+
+        let mut state = self.state();
+        let mut env_changes: BTreeMap<_, _> = env
+            .iter()
+            .filter(|(name, value)| state.initial_env.get(*name) != Some(*value))
+            .map(|(name, value)| (name.clone(), Some(value.clone())))
+            .collect();
+        for name in state.initial_env.keys() {
+            if !env.contains_key(name) {
+                env_changes.insert(name.clone(), None);
+            }
+        }
+
+        state.steps.push(DryRunStep::Command(DryRunCommand {
+            argv: args.iter().map(ToString::to_string).collect(),
+            kind,
+            stubbed,
+            cwd: shell.working_dir().to_path_buf(),
+            env: env_changes,
+            redirects: self.redirects.clone(),
+        }));
+    }
+
+    /// Records a stubbed command or process substitution.
+    pub(crate) fn record_substitution(&self, command: String) {
+        self.state().steps.push(DryRunStep::Substitution(command));
+    }
+
+    /// Records a redirection applied to the execution parameters holding this.
+    pub(crate) fn record_redirect(&mut self, redirect: DryRunRedirect) {
+        self.redirects.push(redirect);
+    }
+}
+
+/// Returns whether the given builtin invocation should be stubbed in dry-run mode.
+///
+/// # Arguments
+///
+/// * `builtin` - The registration of the builtin.
+/// * `args` - The arguments of the invocation, including the builtin's name.
+pub(crate) fn is_stubbed_builtin(builtin: &builtins::Registration, args: &[CommandArg]) -> bool {
+    // We can't know what builtins that aren't our own do.
+    if !builtin.stock {
+        return true;
+    }
+
+    let Some(name) = args.first().map(ToString::to_string) else {
+        return false;
+    };
+
+    STUBBED_BUILTINS.contains(&name.as_str())
+        || (args.len() > 1 && STUBBED_BUILTINS_WITH_ARGS.contains(&name.as_str()))
+}
+
+fn exported_env(shell: &Shell) -> HashMap<String, String> {
+    shell
+        .env
+        .iter_exported()
+        .filter(|(_, var)| var.value().is_set())
+        .map(|(name, var)| (name.clone(), var.value().to_cow_str(shell).into_owned()))
+        .collect()
+}
+
+impl Shell {
+    /// Performs a dry run of the given string as a shell program, in a clone of this shell,
+    /// returning the trace of what would have run. See the [module
+    /// documentation](crate::core::dryrun) for what is and isn't executed.
+    ///
+    /// # Arguments
+    ///
+    /// * `command` - The command to dry run.
+    /// * `params` - Execution parameters.
+    pub async fn dry_run<S: Into<String>>(
+        &self,
+        command: S,
+        params: &ExecutionParameters,
+    ) -> Result<DryRunTrace, error::Error> {
+        let mut sandbox = self.clone();
+
+        let dry_run = DryRun::new(&sandbox);
+        let mut params = params.clone();
+        params.set_dry_run(dry_run.clone());
+
+        sandbox.exec(command, &params).await?;
+
+        Ok(dry_run.trace())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::openfiles::{self, OpenFiles};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn summarize(trace: &DryRunTrace) -> Vec<String> {
+        trace
+            .steps
+            .iter()
+            .map(|step| match step {
+                DryRunStep::Command(c) => {
+                    let kind = match &c.kind {
+                        DryRunCommandKind::Builtin => "builtin".into(),
+                        DryRunCommandKind::Function => "function".into(),
+                        DryRunCommandKind::External(path) => {
+                            std::format!("external {}", path.display())
+                        }
+                        DryRunCommandKind::NotFound => "not-found".into(),
+                    };
+                    let stubbed = if c.stubbed { " (stubbed)" } else { "" };
+                    let env = c
+                        .env
+                        .iter()
+                        .map(|(name, value)| std::format!(" {name}={value:?}"))
+                        .collect::<String>();
+                    let redirects = c
+                        .redirects
+                        .iter()
+                        .map(|r| std::format!(" {}:{:?}:{:?}", r.fd, r.mode, r.target))
+                        .collect::<String>();
+                    std::format!(
+                        "{} [{kind}{stubbed}] cwd={}{env}{redirects}",
+                        c.argv.join(" "),
+                        c.cwd.display()
+                    )
+                }
+                DryRunStep::Substitution(command) => std::format!("substitution: {command}"),
+            })
+            .collect()
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn dry_run_records_trace() -> Result<()> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(std::format!("brush-dry-run-{}", std::process::id()));
+        let bin = dir.join("bin");
+        std::fs::create_dir_all(&bin)?;
+        for name in ["rm", "tee"] {
+            let path = bin.join(name);
+            std::fs::write(&path, "")?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        let d = dir.display();
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+        params.set_fd(OpenFiles::STDOUT_FD, openfiles::null()?);
+        params.set_fd(OpenFiles::STDERR_FD, openfiles::null()?);
+        shell
+            .exec(
+                std::format!("PATH={d}/bin; export KEEP=1 DROP=1; cd {d}"),
+                &params,
+            )
+            .await?;
+
+        let trace = shell
+            .dry_run(
+                r#"f() { echo "in f $1"; }
+export GREETING=hi; unset DROP
+for n in 1 2; do f $n >"out$n"; done
+FOO=bar rm -rf "$PWD" 2>&1 <<<"$(date)"
+cd bin && tee -a log <(uptime) && missing
+exec rm log
+echo never
+"#,
+                &params,
+            )
+            .await;
+        let outputs_exist = dir.join("out1").exists() || dir.join("bin/log").exists();
+        std::fs::remove_dir_all(&dir)?;
+
+        let cd_env = std::format!(
+            "DROP=None GREETING=Some(\"hi\") OLDPWD=Some(\"{d}\") PWD=Some(\"{d}/bin\")"
+        );
+        assert_eq!(
+            summarize(&trace?),
+            [
+                std::format!("export GREETING=hi [builtin] cwd={d}"),
+                std::format!("unset DROP [builtin] cwd={d} GREETING=Some(\"hi\")"),
+                std::format!(
+                    "f 1 [function] cwd={d} DROP=None GREETING=Some(\"hi\") \
+                     1:Write:File(\"{d}/out1\")"
+                ),
+                std::format!(
+                    "echo in f 1 [builtin] cwd={d} DROP=None GREETING=Some(\"hi\") \
+                     1:Write:File(\"{d}/out1\")"
+                ),
+                std::format!(
+                    "f 2 [function] cwd={d} DROP=None GREETING=Some(\"hi\") \
+                     1:Write:File(\"{d}/out2\")"
+                ),
+                std::format!(
+                    "echo in f 2 [builtin] cwd={d} DROP=None GREETING=Some(\"hi\") \
+                     1:Write:File(\"{d}/out2\")"
+                ),
+                "substitution: date".into(),
+                std::format!(
+                    "rm -rf {d} [external {d}/bin/rm (stubbed)] cwd={d} DROP=None FOO=Some(\"bar\") \
+                     GREETING=Some(\"hi\") 2:Write:Fd(1) 0:Read:HereDocument(\"\\n\")"
+                ),
+                std::format!("cd bin [builtin] cwd={d} DROP=None GREETING=Some(\"hi\")"),
+                "substitution: uptime".into(),
+                std::format!(
+                    "tee -a log /dev/fd/63 [external {d}/bin/tee (stubbed)] cwd={d}/bin {cd_env}"
+                ),
+                std::format!("missing [not-found] cwd={d}/bin {cd_env}"),
+                std::format!("exec rm log [builtin (stubbed)] cwd={d}/bin {cd_env}"),
+            ]
+        );
+
+        // Nothing ran outside of the sandbox.
+        assert!(!outputs_exist);
+        assert_eq!(shell.env_str("GREETING"), None);
+        assert_eq!(shell.working_dir(), dir.as_path());
+
+        Ok(())
+    }
+
+    /// Set when anything with effects outside of a dry run is invoked.
+    static INVOKED: std::sync::atomic::AtomicBool = std::sync::atomic::AtomicBool::new(false);
+
+    struct CustomBuiltin;
+
+    impl builtins::SimpleCommand for CustomBuiltin {
+        fn get_content(
+            _name: &str,
+            _content_type: builtins::ContentType,
+        ) -> Result<String, error::Error> {
+            Ok(String::new())
+        }
+
+        fn execute<I: Iterator<Item = S>, S: AsRef<str>>(
+            _context: crate::core::ExecutionContext<'_>,
+            _args: I,
+        ) -> Result<crate::core::ExecutionResult, error::Error> {
+            INVOKED.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(crate::core::ExecutionResult::success())
+        }
+    }
+
+    struct NotFoundHandler;
+
+    #[async_trait::async_trait]
+    impl crate::core::interfaces::CommandNotFoundHandler for NotFoundHandler {
+        async fn handle_command_not_found(
+            &self,
+            _context: &mut crate::core::ExecutionContext<'_>,
+            _args: &[String],
+        ) -> Result<crate::core::interfaces::CommandNotFoundAction, error::Error> {
+            INVOKED.store(true, std::sync::atomic::Ordering::SeqCst);
+            Ok(crate::core::interfaces::CommandNotFoundAction::NotHandled)
+        }
+    }
+
+    #[derive(Default)]
+    struct Bindings {
+        bindings: std::collections::HashMap<
+            crate::core::interfaces::KeySequence,
+            crate::core::interfaces::KeyAction,
+        >,
+    }
+
+    impl crate::core::interfaces::KeyBindings for Bindings {
+        fn get_current(
+            &self,
+        ) -> std::collections::HashMap<
+            crate::core::interfaces::KeySequence,
+            crate::core::interfaces::KeyAction,
+        > {
+            self.bindings.clone()
+        }
+
+        fn bind(
+            &mut self,
+            seq: crate::core::interfaces::KeySequence,
+            action: crate::core::interfaces::KeyAction,
+        ) -> Result<(), std::io::Error> {
+            self.bindings.insert(seq, action);
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn dry_run_does_not_affect_host() -> Result<()> {
+        let bindings = Arc::new(tokio::sync::Mutex::new(Bindings::default()));
+        let mut shell = crate::Shell::builder()
+            .command_not_found_handler(Arc::new(NotFoundHandler))
+            .key_bindings(bindings.clone())
+            .interactive(true)
+            .no_profile(true)
+            .no_rc(true)
+            .build()
+            .await?;
+        shell.register_builtin("custom", builtins::simple_builtin::<CustomBuiltin>());
+
+        let mut params = shell.default_exec_params();
+        params.set_fd(OpenFiles::STDOUT_FD, openfiles::null()?);
+        params.set_fd(OpenFiles::STDERR_FD, openfiles::null()?);
+        shell
+            .exec("command_not_found_handle() { echo handled; }", &params)
+            .await?;
+        let history_len = shell.history().map(|history| history.count());
+        assert!(history_len.is_some());
+
+        let trace = shell
+            .dry_run(
+                r#"custom a; builtin custom b; builtin kill -9 1
+missing-command-for-dry-run
+bind '"\C-xa": beginning-of-line'
+history -c; history -s added
+"#,
+                &params,
+            )
+            .await?;
+
+        let d = shell.working_dir().display();
+        assert_eq!(
+            summarize(&trace),
+            [
+                std::format!("custom a [builtin (stubbed)] cwd={d}"),
+                std::format!("builtin custom b [builtin] cwd={d}"),
+                std::format!("custom b [builtin (stubbed)] cwd={d}"),
+                std::format!("builtin kill -9 1 [builtin] cwd={d}"),
+                std::format!("kill -9 1 [builtin (stubbed)] cwd={d}"),
+                std::format!("missing-command-for-dry-run [not-found] cwd={d}"),
+                std::format!("bind \"\\C-xa\": beginning-of-line [builtin (stubbed)] cwd={d}"),
+                std::format!("history -c [builtin (stubbed)] cwd={d}"),
+                std::format!("history -s added [builtin (stubbed)] cwd={d}"),
+            ]
+        );
+
+        assert!(!INVOKED.load(std::sync::atomic::Ordering::SeqCst));
+        assert!(bindings.lock().await.bindings.is_empty());
+        assert_eq!(shell.history().map(|history| history.count()), history_len);
+
+        Ok(())
+    }
+}
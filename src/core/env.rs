@@ -167,12 +167,34 @@ impl ShellEnvironment {
     }
 
     /// Tries to retrieve an immutable reference to the variable with the given name
-    /// in the environment.
+    /// in the environment. If the variable is a name reference, the variable it
+    /// (ultimately) refers to is returned instead.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the variable to retrieve.
     pub fn get<S: AsRef<str>>(&self, name: S) -> Option<(EnvironmentScope, &ShellVariable)> {
+        let name = name.as_ref();
+
+        let found = self.get_noref(name)?;
+        if !found.1.is_treated_as_nameref() {
+            return Some(found);
+        }
+
+        match self.resolve_nameref(name) {
+            Ok(Some(target)) => self.get_noref(target),
+            Ok(None) => Some(found),
+            Err(_) => None,
+        }
+    }
+
+    /// Tries to retrieve an immutable reference to the variable with the given name
+    /// in the environment, without following name references.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    pub fn get_noref<S: AsRef<str>>(&self, name: S) -> Option<(EnvironmentScope, &ShellVariable)> {
         // Look through scopes, from the top of the stack on down.
         for (scope_type, map) in self.scopes.iter().rev() {
             if let Some(var) = map.get(name.as_ref()) {
@@ -184,7 +206,8 @@ impl ShellEnvironment {
     }
 
     /// Tries to retrieve a mutable reference to the variable with the given name
-    /// in the environment.
+    /// in the environment. If the variable is a name reference, the variable it
+    /// (ultimately) refers to is returned instead.
     ///
     /// # Arguments
     ///
@@ -192,6 +215,20 @@ impl ShellEnvironment {
     pub fn get_mut<S: AsRef<str>>(
         &mut self,
         name: S,
+    ) -> Option<(EnvironmentScope, &mut ShellVariable)> {
+        let name = self.resolve_name(name.as_ref()).ok()?;
+        self.get_mut_noref(name)
+    }
+
+    /// Tries to retrieve a mutable reference to the variable with the given name
+    /// in the environment, without following name references.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    pub fn get_mut_noref<S: AsRef<str>>(
+        &mut self,
+        name: S,
     ) -> Option<(EnvironmentScope, &mut ShellVariable)> {
         // Look through scopes, from the top of the stack on down.
         for (scope_type, map) in self.scopes.iter_mut().rev() {
@@ -203,6 +240,60 @@ impl ShellEnvironment {
         None
     }
 
+    /// Resolves the given variable name through any chain of name references (as
+    /// created by `declare -n`). Returns the name of the variable ultimately referenced,
+    /// or `None` if `name` is not a name reference with a target. The returned name
+    /// may include an array subscript (e.g., `arr[1]`) when the reference targets an
+    /// array element.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to resolve.
+    pub fn resolve_nameref(&self, name: &str) -> Result<Option<String>, error::Error> {
+        let mut visited = vec![name];
+        let mut current = name;
+
+        while let Some((_, var)) = self.get_noref(current) {
+            if !var.is_treated_as_nameref() {
+                break;
+            }
+
+            let ShellValue::String(target) = var.value() else {
+                break;
+            };
+
+            if target.is_empty() {
+                break;
+            }
+
+            if visited.contains(&target.as_str()) {
+                return Err(error::ErrorKind::CircularNameReference(name.to_owned()).into());
+            }
+
+            current = target.as_str();
+            visited.push(current);
+
+            // A reference to an array element can't be followed any further.
+            if split_array_element_ref(current).is_some() {
+                break;
+            }
+        }
+
+        if visited.len() > 1 {
+            Ok(Some(current.to_owned()))
+        } else {
+            Ok(None)
+        }
+    }
+
+    /// Returns the name that lookups and updates of the given variable name should
+    /// actually target, following name references.
+    fn resolve_name<'a>(&self, name: &'a str) -> Result<Cow<'a, str>, error::Error> {
+        Ok(self
+            .resolve_nameref(name)?
+            .map_or(Cow::Borrowed(name), Cow::Owned))
+    }
+
     /// Tries to retrieve the string value of the variable with the given name in the
     /// environment.
     ///
@@ -233,12 +324,60 @@ impl ShellEnvironment {
     //
 
     /// Tries to unset the variable with the given name in the environment, returning
-    /// whether or not such a variable existed.
+    /// whether or not such a variable existed. If the variable is a name reference, the
+    /// variable it refers to is unset instead.
     ///
     /// # Arguments
     ///
     /// * `name` - The name of the variable to unset.
     pub fn unset(&mut self, name: &str) -> Result<Option<ShellVariable>, error::Error> {
+        self.unset_impl(name, false)
+    }
+
+    /// Tries to unset the variable with the given name in the environment, without
+    /// following name references. Returns the removed variable, if any.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to unset.
+    pub fn unset_noref(&mut self, name: &str) -> Result<Option<ShellVariable>, error::Error> {
+        self.unset_noref_impl(name, false)
+    }
+
+    /// Tries to unset the variable with the given name in the environment, as with
+    /// [`Self::unset`], but leaving a placeholder for any local variable unset in any
+    /// function scope (not just the current one), so that lookups continue to find it
+    /// unset until that function returns. This implements the `localvar_unset` option.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to unset.
+    pub fn unset_keeping_local_placeholders(
+        &mut self,
+        name: &str,
+    ) -> Result<Option<ShellVariable>, error::Error> {
+        self.unset_impl(name, true)
+    }
+
+    fn unset_impl(
+        &mut self,
+        name: &str,
+        placeholder_in_any_local_scope: bool,
+    ) -> Result<Option<ShellVariable>, error::Error> {
+        let name = self.resolve_name(name)?;
+        if let Some((array_name, index)) = split_array_element_ref(name.as_ref()) {
+            self.unset_index(array_name, index)?;
+            return Ok(None);
+        }
+
+        self.unset_noref_impl(name.as_ref(), placeholder_in_any_local_scope)
+    }
+
+    fn unset_noref_impl(
+        &mut self,
+        name: &str,
+        placeholder_in_any_local_scope: bool,
+    ) -> Result<Option<ShellVariable>, error::Error> {
         let mut local_count = 0;
         for (scope_type, map) in self.scopes.iter_mut().rev() {
             if matches!(scope_type, EnvironmentScope::Local) {
@@ -250,7 +389,9 @@ impl ShellEnvironment {
             if unset_result.is_some() {
                 // If we end up finding a local in the top-most local frame, then we replace
                 // it with a placeholder.
-                if matches!(scope_type, EnvironmentScope::Local) && local_count == 1 {
+                if matches!(scope_type, EnvironmentScope::Local)
+                    && (local_count == 1 || placeholder_in_any_local_scope)
+                {
                     map.set(
                         name,
                         ShellVariable::new(ShellValue::Unset(ShellValueUnsetType::Untyped)),
@@ -286,9 +427,11 @@ impl ShellEnvironment {
         map: &mut ShellVariableMap,
         name: &str,
     ) -> Result<Option<ShellVariable>, error::Error> {
-        match map.get(name).map(|v| v.is_readonly()) {
-            Some(true) => Err(error::ErrorKind::ReadonlyVariable.into()),
-            Some(false) => Ok(map.unset(name)),
+        match map.get_mut(name) {
+            Some(var) => {
+                var.prepare_for_unset()?;
+                Ok(map.unset(name))
+            }
             None => Ok(None),
         }
     }
@@ -304,6 +447,22 @@ impl ShellEnvironment {
         &self,
         name: N,
         lookup_policy: EnvironmentLookup,
+    ) -> Option<&ShellVariable> {
+        let name = self.resolve_name(name.as_ref()).ok()?;
+        self.get_using_policy_noref(name, lookup_policy)
+    }
+
+    /// Tries to retrieve an immutable reference to a variable from the environment,
+    /// using the given name and lookup policy, without following name references.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    /// * `lookup_policy` - The policy to use when looking up the variable.
+    pub fn get_using_policy_noref<N: AsRef<str>>(
+        &self,
+        name: N,
+        lookup_policy: EnvironmentLookup,
     ) -> Option<&ShellVariable> {
         let mut local_count = 0;
         for (scope_type, var_map) in self.scopes.iter().rev() {
@@ -355,6 +514,22 @@ impl ShellEnvironment {
         &mut self,
         name: N,
         lookup_policy: EnvironmentLookup,
+    ) -> Option<&mut ShellVariable> {
+        let name = self.resolve_name(name.as_ref()).ok()?;
+        self.get_mut_using_policy_noref(name, lookup_policy)
+    }
+
+    /// Tries to retrieve a mutable reference to a variable from the environment,
+    /// using the given name and lookup policy, without following name references.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to retrieve.
+    /// * `lookup_policy` - The policy to use when looking up the variable.
+    pub fn get_mut_using_policy_noref<N: AsRef<str>>(
+        &mut self,
+        name: N,
+        lookup_policy: EnvironmentLookup,
     ) -> Option<&mut ShellVariable> {
         let mut local_count = 0;
         for (scope_type, var_map) in self.scopes.iter_mut().rev() {
@@ -412,10 +587,27 @@ impl ShellEnvironment {
         lookup_policy: EnvironmentLookup,
         scope_if_creating: EnvironmentScope,
     ) -> Result<(), error::Error> {
-        let name = name.into();
+        let mut name = name.into();
+
+        if let Some(target) = self.resolve_nameref(&name)? {
+            if let (Some((array_name, index)), variables::ShellValueLiteral::Scalar(value)) =
+                (split_array_element_ref(&target), &value)
+            {
+                return self.update_or_add_array_element(
+                    array_name,
+                    index.to_owned(),
+                    value.clone(),
+                    updater,
+                    lookup_policy,
+                    scope_if_creating,
+                );
+            }
+
+            name = target;
+        }
 
         let auto_export = self.export_variables_on_modification;
-        if let Some(var) = self.get_mut_using_policy(&name, lookup_policy) {
+        if let Some(var) = self.get_mut_using_policy_noref(&name, lookup_policy) {
             var.assign(value, false)?;
             if auto_export {
                 var.export();
@@ -452,9 +644,12 @@ impl ShellEnvironment {
         lookup_policy: EnvironmentLookup,
         scope_if_creating: EnvironmentScope,
     ) -> Result<(), error::Error> {
-        let name = name.into();
+        let mut name = name.into();
+        if let Some(target) = self.resolve_nameref(&name)? {
+            name = target;
+        }
 
-        if let Some(var) = self.get_mut_using_policy(&name, lookup_policy) {
+        if let Some(var) = self.get_mut_using_policy_noref(&name, lookup_policy) {
             var.assign_at_index(index, value, false)?;
             updater(var)
         } else {
@@ -577,6 +772,19 @@ impl ShellVariableMap {
     }
 }
 
+/// Splits a reference to an array element (e.g., `arr[1]`) into the array's name
+/// and the element's index. Returns `None` if the string isn't of that form.
+pub(crate) fn split_array_element_ref(s: &str) -> Option<(&str, &str)> {
+    let (name, rest) = s.split_once('[')?;
+    let index = rest.strip_suffix(']')?;
+
+    if valid_variable_name(name) {
+        Some((name, index))
+    } else {
+        None
+    }
+}
+
 /// Checks if the given name is a valid variable name.
 pub fn valid_variable_name(s: &str) -> bool {
     let mut cs = s.chars();
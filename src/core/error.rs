@@ -77,6 +77,14 @@ pub enum ErrorKind {
     #[error("history item not found")]
     HistoryItemNotFound,
 
+    /// A history expansion referenced an event that could not be found.
+    #[error("{0}: event not found")]
+    HistoryEventNotFound(String),
+
+    /// A history expansion contained an invalid word designator or modifier.
+    #[error("{0}: bad word specifier")]
+    BadHistoryWordSpecifier(String),
+
     /// The requested functionality has not yet been implemented in this shell.
     #[error("not yet implemented: {0}")]
     Unimplemented(&'static str),
@@ -226,6 +234,18 @@ pub enum ErrorKind {
     #[error("interrupted")]
     Interrupted,
 
+    /// A command substitution was cancelled before it completed.
+    #[error("command substitution cancelled")]
+    CommandSubstitutionCancelled,
+
+    /// A command substitution produced more output than allowed.
+    #[error("command substitution output exceeded {0} bytes")]
+    CommandSubstitutionOutputTooLarge(usize),
+
+    /// A command substitution ran for longer than allowed.
+    #[error("command substitution timed out")]
+    CommandSubstitutionTimedOut,
+
     /// Maximum function call depth was exceeded.
     #[error("maximum function call depth exceeded")]
     MaxFunctionCallDepthExceeded,
@@ -258,6 +278,38 @@ pub enum ErrorKind {
     #[error("unknown key binding function: {0}")]
     UnknownKeyBindingFunction(String),
 
+    /// Unknown input variable.
+    #[error("{0}: unknown variable name")]
+    UnknownInputVariable(String),
+
+    /// A shared object containing loadable builtins could not be opened.
+    #[error("cannot open shared object {0}: {1}")]
+    CannotOpenSharedObject(String, String),
+
+    /// A requested builtin is not implemented by a shared object.
+    #[error("cannot find {0} in shared object {1}")]
+    BuiltinNotInSharedObject(String, String),
+
+    /// A shared object implements an unsupported version of the loadable builtin ABI.
+    #[error("{0}: unsupported loadable builtin ABI version {1}")]
+    IncompatibleBuiltinAbi(String, u32),
+
+    /// The builtin was not loaded from a shared object.
+    #[error("{0}: not dynamically loaded")]
+    NotDynamicallyLoaded(String),
+
+    /// Invalid line in input configuration (e.g., an inputrc file).
+    #[error("invalid input configuration line: {0}")]
+    InvalidInputConfigLine(String),
+
+    /// A chain of name references refers back to itself.
+    #[error("{0}: circular name reference")]
+    CircularNameReference(String),
+
+    /// A name reference was made to refer to itself.
+    #[error("{0}: nameref variable self references not allowed")]
+    SelfReferencingNameReference(String),
+
     /// Expanding an unset variable.
     #[error("expanding unset variable: {0}")]
     ExpandingUnsetVariable(String),
@@ -294,6 +346,7 @@ impl From<&ErrorKind> for results::ExecutionExitCode {
             ErrorKind::FunctionParseError(..) => Self::InvalidUsage,
             ErrorKind::FailedToExecuteCommand(..) => Self::CannotExecute,
             ErrorKind::BuiltinError(inner, ..) => inner.as_exit_code(),
+            ErrorKind::Interrupted | ErrorKind::CommandSubstitutionCancelled => Self::Interrupted,
             _ => Self::GeneralError,
         }
     }
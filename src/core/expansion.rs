@@ -750,7 +750,27 @@ impl<'a> WordExpander<'a> {
     }
 
     fn expand_tilde_expression(&self, prefix: &str) -> Result<String, error::Error> {
-        if !prefix.is_empty() {
+        if let Some(var_name) = match prefix {
+            "+" => Some("PWD"),
+            "-" => Some("OLDPWD"),
+            _ => None,
+        } {
+            Ok(self
+                .shell
+                .env_str(var_name)
+                .map_or_else(|| std::format!("~{prefix}"), |value| value.to_string()))
+        } else if prefix.starts_with(['+', '-']) || prefix.starts_with(|c: char| c.is_ascii_digit())
+        {
+            // ~N, ~+N and ~-N refer to entries on the directory stack, as listed by `dirs`.
+            let entries = self.shell.directory_stack_entries();
+            Ok(self
+                .shell
+                .resolve_directory_stack_position(prefix)
+                .map_or_else(
+                    || std::format!("~{prefix}"),
+                    |index| entries[index].to_string_lossy().to_string(),
+                ))
+        } else if !prefix.is_empty() {
             Ok(sys::users::get_user_home_dir(prefix).map_or_else(
                 || std::format!("~{prefix}"),
                 |p| p.to_string_lossy().to_string(),
@@ -835,12 +855,14 @@ impl<'a> WordExpander<'a> {
             crate::parser::word::ParameterExpr::Parameter {
                 parameter,
                 indirect,
+                ..
             } => self.expand_parameter(&parameter, indirect).await,
             crate::parser::word::ParameterExpr::UseDefaultValues {
                 parameter,
                 indirect,
                 test_type,
                 default_value,
+                ..
             } => {
                 let expanded_parameter = self
                     .expand_parameter_allowing_unset(&parameter, indirect)
@@ -861,6 +883,7 @@ impl<'a> WordExpander<'a> {
                 indirect,
                 test_type,
                 default_value,
+                ..
             } => {
                 let expanded_parameter = self
                     .expand_parameter_allowing_unset(&parameter, indirect)
@@ -887,6 +910,7 @@ impl<'a> WordExpander<'a> {
                 indirect,
                 test_type,
                 error_message,
+                ..
             } => {
                 let expanded_parameter = self
                     .expand_parameter_allowing_unset(&parameter, indirect)
@@ -914,6 +938,7 @@ impl<'a> WordExpander<'a> {
                 indirect,
                 test_type,
                 alternative_value,
+                ..
             } => {
                 let expanded_parameter = self
                     .expand_parameter_allowing_unset(&parameter, indirect)
@@ -934,6 +959,7 @@ impl<'a> WordExpander<'a> {
             crate::parser::word::ParameterExpr::ParameterLength {
                 parameter,
                 indirect,
+                ..
             } => {
                 let expansion = self.expand_parameter(&parameter, indirect).await?;
                 Ok(Expansion::from(expansion.polymorphic_len().to_string()))
@@ -942,6 +968,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -955,6 +982,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -968,6 +996,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -982,6 +1011,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -997,6 +1027,7 @@ impl<'a> WordExpander<'a> {
                 indirect,
                 offset,
                 length,
+                ..
             } => {
                 let mut expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
 
@@ -1068,6 +1099,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 op: ParameterTransformOp::ToAttributeFlags,
+                ..
             } => {
                 if let (_, _, Some(var)) = self
                     .try_resolve_parameter_to_variable(&parameter, indirect)
@@ -1082,6 +1114,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 op: ParameterTransformOp::ToAssignmentLogic,
+                ..
             } => {
                 if let (Some(name), index, Some(var)) = self
                     .try_resolve_parameter_to_variable(&parameter, indirect)
@@ -1099,7 +1132,7 @@ impl<'a> WordExpander<'a> {
                         ShellValue::IndexedArray(_)
                         | ShellValue::AssociativeArray(_)
                         // TODO(dynamic): confirm this
-                        | ShellValue::Dynamic { .. } => {
+                        | ShellValue::Dynamic(_) => {
                             let equals_or_nothing = if assignable_value_str.is_empty() {
                                 ""
                             } else {
@@ -1126,6 +1159,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 op,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let came_from_undefined = expanded_parameter.undefined;
@@ -1153,6 +1187,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -1166,6 +1201,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -1179,6 +1215,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -1192,6 +1229,7 @@ impl<'a> WordExpander<'a> {
                 parameter,
                 indirect,
                 pattern,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self.basic_expand_opt_pattern(&pattern).await?;
@@ -1207,6 +1245,7 @@ impl<'a> WordExpander<'a> {
                 pattern,
                 replacement,
                 match_kind,
+                ..
             } => {
                 let expanded_parameter = self.expand_parameter(&parameter, indirect).await?;
                 let expanded_pattern = self
@@ -1237,6 +1276,7 @@ impl<'a> WordExpander<'a> {
             crate::parser::word::ParameterExpr::VariableNames {
                 prefix,
                 concatenate,
+                ..
             } => {
                 if prefix.is_empty() {
                     Ok(Expansion::from(String::new()))
@@ -1268,6 +1308,7 @@ impl<'a> WordExpander<'a> {
             crate::parser::word::ParameterExpr::MemberKeys {
                 variable_name,
                 concatenate,
+                ..
             } => {
                 let keys = if let Some((_, var)) = self.shell.env.get(variable_name) {
                     var.value().element_keys(self.shell)
@@ -1293,7 +1334,8 @@ impl<'a> WordExpander<'a> {
         parameter: &crate::parser::word::Parameter,
         value: String,
     ) -> Result<(), error::Error> {
-        let (variable_name, index) = match parameter {
+        let parameter = self.resolve_nameref_parameter(parameter)?;
+        let (variable_name, index) = match parameter.as_ref() {
             crate::parser::word::Parameter::Named(name) => (name, None),
             crate::parser::word::Parameter::NamedWithIndex { name, index } => {
                 let is_set_assoc_array = if let Some((_, var)) = self.shell.env.get(name) {
@@ -1349,8 +1391,11 @@ impl<'a> WordExpander<'a> {
         if !indirect {
             Ok(self.try_resolve_parameter_to_variable_without_indirect(parameter))
         } else {
-            let expansion = self.expand_parameter(parameter, false).await?;
-            let parameter_str: String = expansion.into();
+            let parameter_str: String = if let Some(target) = self.nameref_target(parameter) {
+                target
+            } else {
+                self.expand_parameter(parameter, false).await?.into()
+            };
             let inner_parameter =
                 crate::parser::word::parse_parameter(parameter_str.as_str(), &self.parser_options)?;
             Ok(self.try_resolve_parameter_to_variable_without_indirect(&inner_parameter))
@@ -1361,7 +1406,11 @@ impl<'a> WordExpander<'a> {
         &self,
         parameter: &crate::parser::word::Parameter,
     ) -> (Option<String>, Option<String>, Option<ShellVariable>) {
-        let (name, index) = match parameter {
+        let parameter = self
+            .resolve_nameref_parameter(parameter)
+            .unwrap_or(Cow::Borrowed(parameter));
+
+        let (name, index) = match parameter.as_ref() {
             crate::parser::word::Parameter::Positional(_)
             | crate::parser::word::Parameter::Special(_) => (None, None),
             crate::parser::word::Parameter::Named(name) => (Some(name.to_owned()), Some("0".into())),
@@ -1419,6 +1468,12 @@ impl<'a> WordExpander<'a> {
         indirect: bool,
         allow_unset_vars: bool,
     ) -> Result<Expansion, error::Error> {
+        // For name references, indirect expansion yields the name of the referenced
+        // variable.
+        if indirect && let Some(target) = self.nameref_target(parameter) {
+            return Ok(Expansion::from(target));
+        }
+
         let expansion = self
             .expand_parameter_without_indirect(parameter, allow_unset_vars)
             .await?;
@@ -1434,11 +1489,73 @@ impl<'a> WordExpander<'a> {
         }
     }
 
+    /// If the given parameter refers to a variable that is a name reference, returns
+    /// the (unexpanded) name it refers to.
+    fn nameref_target(&self, parameter: &crate::parser::word::Parameter) -> Option<String> {
+        let crate::parser::word::Parameter::Named(name) = parameter else {
+            return None;
+        };
+
+        let (_, var) = self.shell.env.get_noref(name)?;
+        if var.is_treated_as_nameref() {
+            var.value().try_get_cow_str(self.shell).map(|s| s.into_owned())
+        } else {
+            None
+        }
+    }
+
+    /// Rewrites the given parameter to refer to the target of any name reference
+    /// it uses; parameters that don't involve name references are returned as-is.
+    fn resolve_nameref_parameter<'p>(
+        &self,
+        parameter: &'p crate::parser::word::Parameter,
+    ) -> Result<Cow<'p, crate::parser::word::Parameter>, error::Error> {
+        let name = match parameter {
+            crate::parser::word::Parameter::Named(name)
+            | crate::parser::word::Parameter::NamedWithIndex { name, .. }
+            | crate::parser::word::Parameter::NamedWithAllIndices { name, .. } => name,
+            crate::parser::word::Parameter::Positional(_)
+            | crate::parser::word::Parameter::Special(_) => return Ok(Cow::Borrowed(parameter)),
+        };
+
+        let Some(target) = self.shell.env.resolve_nameref(name)? else {
+            return Ok(Cow::Borrowed(parameter));
+        };
+
+        let target_is_element = env::split_array_element_ref(target.as_str()).is_some();
+
+        let resolved = match parameter {
+            crate::parser::word::Parameter::Named(_) => {
+                crate::parser::word::parse_parameter(target.as_str(), &self.parser_options)?
+            }
+            crate::parser::word::Parameter::NamedWithIndex { index, .. } if !target_is_element => {
+                crate::parser::word::Parameter::NamedWithIndex {
+                    name: target,
+                    index: index.clone(),
+                }
+            }
+            crate::parser::word::Parameter::NamedWithAllIndices { concatenate, .. }
+                if !target_is_element =>
+            {
+                crate::parser::word::Parameter::NamedWithAllIndices {
+                    name: target,
+                    concatenate: *concatenate,
+                }
+            }
+            _ => return Ok(Cow::Borrowed(parameter)),
+        };
+
+        Ok(Cow::Owned(resolved))
+    }
+
     async fn expand_parameter_without_indirect(
         &mut self,
         parameter: &crate::parser::word::Parameter,
         allow_unset_vars: bool,
     ) -> Result<Expansion, error::Error> {
+        let resolved_parameter = self.resolve_nameref_parameter(parameter)?;
+        let parameter = resolved_parameter.as_ref();
+
         match parameter {
             crate::parser::word::Parameter::Positional(p) => {
                 if *p == 0 {
@@ -1577,12 +1694,22 @@ impl<'a> WordExpander<'a> {
                     }
                 Expansion::from(String::new())
             }
-            crate::parser::word::SpecialParameter::ShellName => Expansion::from(
-                self.shell
-                    .shell_name
-                    .as_ref()
-                    .map_or_else(String::new, |name| name.clone()),
-            ),
+            crate::parser::word::SpecialParameter::ShellName => {
+                // Assignments to BASH_ARGV0 are reflected in $0, for as long as it retains
+                // its special behavior.
+                if let Some((_, var)) = self.shell.env.get("BASH_ARGV0")
+                    && matches!(var.value(), ShellValue::Dynamic(_))
+                {
+                    return Expansion::from(var.value().to_cow_str(self.shell).to_string());
+                }
+
+                Expansion::from(
+                    self.shell
+                        .shell_name
+                        .as_ref()
+                        .map_or_else(String::new, |name| name.clone()),
+                )
+            }
         }
     }
 
@@ -1946,6 +2073,165 @@ mod tests {
         Ok(())
     }
 
+    #[tokio::test]
+    async fn test_command_substitution() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        assert_eq!(
+            full_expand_and_split_str(&mut shell, &params, "$(echo a; echo b)").await?,
+            vec!["a", "b"]
+        );
+        assert_eq!(
+            full_expand_and_split_str(&mut shell, &params, "\"`echo a b`\"").await?,
+            vec!["a b"]
+        );
+
+        // Make sure output larger than a pipe's buffer is fully collected.
+        let output = basic_expand_str(
+            &mut shell,
+            &params,
+            "$(for i in {1..8000}; do echo 0123456789; done)",
+        )
+        .await?;
+        assert_eq!(output.len(), 8000 * 11 - 1);
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_substitution_cancellation() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+
+        let token = tokio_util::sync::CancellationToken::new();
+        params.set_cancellation_token(token.clone());
+
+        std::thread::spawn(move || {
+            std::thread::sleep(std::time::Duration::from_millis(100));
+            token.cancel();
+        });
+
+        let err = basic_expand_str(&mut shell, &params, "$(while true; do :; done)")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            error::ErrorKind::CommandSubstitutionCancelled
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_substitution_output_limit() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+        params.set_command_substitution_output_limit(1024);
+
+        let output = basic_expand_str(&mut shell, &params, "$(printf '%1024s' x)").await?;
+        assert_eq!(output.len(), 1024);
+
+        let err = basic_expand_str(&mut shell, &params, "$(printf '%1025s' x)")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            error::ErrorKind::CommandSubstitutionOutputTooLarge(1024)
+        ));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_command_substitution_timeout() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+        params.set_command_substitution_timeout(std::time::Duration::from_millis(100));
+
+        let output = basic_expand_str(&mut shell, &params, "$(echo small)").await?;
+        assert_eq!(output, "small");
+
+        let err = basic_expand_str(&mut shell, &params, "$(while true; do :; done)")
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            error::ErrorKind::CommandSubstitutionTimedOut
+        ));
+
+        Ok(())
+    }
+
+    #[cfg(target_os = "linux")]
+    #[tokio::test]
+    async fn test_command_substitution_timeout_kills_processes() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let mut params = shell.default_exec_params();
+        params.set_command_substitution_timeout(std::time::Duration::from_millis(100));
+        params.set_fd(
+            super::super::openfiles::OpenFiles::STDIN_FD,
+            super::super::openfiles::null()?,
+        );
+
+        // The grandchild keeps the output pipe open after the timeout, unless it's killed.
+        let pid_file =
+            std::env::temp_dir().join(format!("kodegen-subst-{:016x}.pid", rand::random::<u64>()));
+        let command = format!(
+            "$(sh -c 'sleep 5 & echo $! > {}; wait')",
+            pid_file.display()
+        );
+
+        let start = std::time::Instant::now();
+        let err = basic_expand_str(&mut shell, &params, &command)
+            .await
+            .unwrap_err();
+        assert!(matches!(
+            err.kind(),
+            error::ErrorKind::CommandSubstitutionTimedOut
+        ));
+        assert!(start.elapsed() < std::time::Duration::from_secs(4));
+
+        let pid = std::fs::read_to_string(&pid_file)?;
+        std::fs::remove_file(&pid_file)?;
+
+        // Once killed, the process is either gone or a zombie awaiting its parent.
+        let stat = std::fs::read_to_string(format!("/proc/{}/stat", pid.trim()));
+        if let Ok(stat) = stat {
+            let state = stat.rsplit(')').next().unwrap_or_default().trim_start();
+            assert!(state.starts_with('Z'), "process still running: {stat}");
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_nameref_expansion() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell
+            .exec(
+                "target=value; declare -n ref=target; arr=(a b c); declare -n elem='arr[1]'",
+                &params,
+            )
+            .await?;
+
+        assert_eq!(basic_expand_str(&mut shell, &params, "$ref").await?, "value");
+        assert_eq!(basic_expand_str(&mut shell, &params, "${!ref}").await?, "target");
+        assert_eq!(basic_expand_str(&mut shell, &params, "$elem").await?, "b");
+
+        shell.exec("ref=updated; elem=x", &params).await?;
+        assert_eq!(basic_expand_str(&mut shell, &params, "$target").await?, "updated");
+        assert_eq!(basic_expand_str(&mut shell, &params, "${arr[*]}").await?, "a x c");
+
+        shell.exec("unset -n ref", &params).await?;
+        assert_eq!(basic_expand_str(&mut shell, &params, "$target").await?, "updated");
+        assert_eq!(basic_expand_str(&mut shell, &params, "$ref").await?, "");
+
+        Ok(())
+    }
+
     #[test]
     fn test_to_initial_capitals() {
         assert_eq!(to_initial_capitals("ab bc cd"), String::from("Ab Bc Cd"));
@@ -18,7 +18,7 @@ pub(crate) async fn eval_extended_test_expr(
     params: &ExecutionParameters,
 ) -> Result<bool, error::Error> {
     match expr {
-        ast::ExtendedTestExpr::UnaryTest(op, operand) => {
+        ast::ExtendedTestExpr::UnaryTest(op, operand, _) => {
             apply_unary_predicate(op, operand, shell, params).await
         }
         ast::ExtendedTestExpr::BinaryTest(op, left, right) => {
@@ -34,11 +34,11 @@ pub(crate) async fn eval_extended_test_expr(
                 || eval_extended_test_expr(right, shell, params).await?;
             Ok(result)
         }
-        ast::ExtendedTestExpr::Not(expr) => {
+        ast::ExtendedTestExpr::Not(expr, _) => {
             let result = !eval_extended_test_expr(expr, shell, params).await?;
             Ok(result)
         }
-        ast::ExtendedTestExpr::Parenthesized(expr) => {
+        ast::ExtendedTestExpr::Parenthesized(expr, _) => {
             eval_extended_test_expr(expr, shell, params).await
         }
     }
@@ -184,7 +184,7 @@ pub(crate) fn apply_unary_predicate_to_str(
             }
         }
         ast::UnaryPredicate::ShellVariableIsSetAndAssigned => Ok(shell.env.is_set(operand)),
-        ast::UnaryPredicate::ShellVariableIsSetAndNameRef => match shell.env.get(operand) {
+        ast::UnaryPredicate::ShellVariableIsSetAndNameRef => match shell.env.get_noref(operand) {
             Some((_, reffed)) => Ok(reffed.value().is_set() && reffed.is_treated_as_nameref()),
             None => Ok(false),
         },
@@ -5,6 +5,7 @@ use std::{
     sync::Arc,
 };
 
+use super::namedoptions::SetOptionsSnapshot;
 use crate::parser::ast;
 
 /// An environment for defined, named functions.
@@ -70,6 +71,9 @@ pub struct Registration {
     pub(crate) definition: Arc<crate::parser::ast::FunctionDefinition>,
     /// Whether or not this function definition should be exported to children.
     exported: bool,
+    /// Whether or not the function has the trace attribute, causing it to inherit
+    /// the `DEBUG` and `RETURN` traps.
+    traced: bool,
 }
 
 impl From<crate::parser::ast::FunctionDefinition> for Registration {
@@ -77,6 +81,7 @@ impl From<crate::parser::ast::FunctionDefinition> for Registration {
         Self {
             definition: Arc::new(definition),
             exported: false,
+            traced: false,
         }
     }
 }
@@ -101,6 +106,34 @@ impl Registration {
     pub const fn is_exported(&self) -> bool {
         self.exported
     }
+
+    /// Gives the function the trace attribute.
+    pub const fn enable_trace(&mut self) {
+        self.traced = true;
+    }
+
+    /// Removes the trace attribute from the function.
+    pub const fn disable_trace(&mut self) {
+        self.traced = false;
+    }
+
+    /// Returns whether this function has the trace attribute.
+    pub const fn is_traced(&self) -> bool {
+        self.traced
+    }
+
+    /// Returns the attribute flags for the function, in the form used by
+    /// `declare -F` (e.g., `ft` for a traced function).
+    pub fn attribute_flags(&self) -> String {
+        let mut flags = String::from("f");
+        if self.traced {
+            flags.push('t');
+        }
+        if self.exported {
+            flags.push('x');
+        }
+        flags
+    }
 }
 
 /// Represents an active shell function call.
@@ -110,6 +143,15 @@ pub struct FunctionCall {
     pub function_name: String,
     /// The definition of the invoked function.
     pub function_definition: Arc<crate::parser::ast::FunctionDefinition>,
+    /// The `set` options to restore when the call returns, if `local -` was used.
+    pub saved_set_options: Option<SetOptionsSnapshot>,
+    /// Whether the call inherits the caller's `DEBUG` and `RETURN` traps (i.e., the
+    /// function has the trace attribute, or `functrace` is enabled).
+    pub inherits_debug_and_return_traps: bool,
+    /// The `DEBUG` trap handler registered when the call began.
+    pub(crate) debug_trap_on_entry: Option<String>,
+    /// The `RETURN` trap handler registered when the call began.
+    pub(crate) return_trap_on_entry: Option<String>,
 }
 
 /// Encapsulates a function call stack.
@@ -156,9 +198,18 @@ impl CallStack {
         self.frames.push_front(FunctionCall {
             function_name: name.into(),
             function_definition: function_def.clone(),
+            saved_set_options: None,
+            inherits_debug_and_return_traps: false,
+            debug_trap_on_entry: None,
+            return_trap_on_entry: None,
         });
     }
 
+    /// Returns a mutable reference to the most recent call frame, if there is one.
+    pub fn current_frame_mut(&mut self) -> Option<&mut FunctionCall> {
+        self.frames.front_mut()
+    }
+
     /// Returns the current depth of the function call stack.
     pub fn depth(&self) -> usize {
         self.frames.len()
@@ -175,3 +226,32 @@ impl CallStack {
         self.frames.iter()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use anyhow::Result;
+
+    #[tokio::test]
+    async fn local_dash_restores_set_options() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        let script = r"
+inner() { local -; set -f; set +u; }
+outer() { local -; set -u; inner; [[ $- == *u* && $- != *f* ]] || return 1; set -o pipefail; }
+outer
+";
+        let result = shell.exec(script, &params).await?;
+        assert!(result.is_success());
+
+        assert!(!shell.options.treat_unset_variables_as_error);
+        assert!(!shell.options.disable_filename_globbing);
+        assert!(!shell.options.return_first_failure_from_pipeline);
+
+        // Without `local -`, option changes leak into the caller.
+        shell.exec("leaky() { set -f; }; leaky", &params).await?;
+        assert!(shell.options.disable_filename_globbing);
+
+        Ok(())
+    }
+}
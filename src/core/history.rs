@@ -2,8 +2,8 @@
 
 use chrono::Utc;
 use std::{
-    io::{BufRead, Read, Write},
-    path::Path,
+    io::{BufRead, Read, Seek, Write},
+    path::{Path, PathBuf},
 };
 
 use super::error;
@@ -18,6 +18,9 @@ pub struct History {
     items: rpds::VectorSync<ItemId>,
     id_map: rpds::HashTrieMapSync<ItemId, Item>,
     next_id: ItemId,
+    /// Byte offsets into history files up to which their contents are known to this
+    /// history (i.e., have been read from or written to them).
+    file_offsets: rpds::HashTrieMapSync<PathBuf, u64>,
 }
 
 impl History {
@@ -31,7 +34,17 @@ impl History {
     /// * `reader` - The readable stream to import history from.
     pub fn import(reader: impl Read) -> Result<Self, error::Error> {
         let mut history = Self::default();
+        history.append_from(reader)?;
+        Ok(history)
+    }
 
+    /// Appends the history items read from the given readable stream. Unreadable lines are
+    /// skipped with a warning logged, as with [`History::import`].
+    ///
+    /// # Arguments
+    ///
+    /// * `reader` - The readable stream to read history from.
+    pub fn append_from(&mut self, reader: impl Read) -> Result<(), error::Error> {
         let buf_reader = std::io::BufReader::new(reader);
 
         let mut next_timestamp = None;
@@ -59,16 +72,65 @@ impl History {
             }
 
             let item = Item {
-                id: history.next_id,
+                id: self.next_id,
                 command_line: line,
                 timestamp: next_timestamp.take(),
                 dirty: false,
             };
 
-            history.add(item)?;
+            self.add(item)?;
         }
 
-        Ok(history)
+        Ok(())
+    }
+
+    /// Appends the contents of the given history file to this history.
+    ///
+    /// # Arguments
+    ///
+    /// * `history_file_path` - The path to the history file.
+    /// * `new_items_only` - Whether to only read items added to the file since it was
+    ///   last read from or written to by this history.
+    pub fn read_file(
+        &mut self,
+        history_file_path: impl AsRef<Path>,
+        new_items_only: bool,
+    ) -> Result<(), error::Error> {
+        let history_file_path = history_file_path.as_ref();
+
+        let mut file = std::fs::File::open(history_file_path)?;
+        let file_len = file.metadata()?.len();
+
+        // If the file has shrunk since we last saw it, then it's been rewritten; start over.
+        let start_offset = if new_items_only {
+            self.file_offsets
+                .get(history_file_path)
+                .copied()
+                .filter(|offset| *offset <= file_len)
+                .unwrap_or(0)
+        } else {
+            0
+        };
+
+        file.seek(std::io::SeekFrom::Start(start_offset))?;
+        self.append_from(file.take(file_len - start_offset))?;
+
+        self.mark_file_read(history_file_path, file_len);
+
+        Ok(())
+    }
+
+    /// Records that the given history file's contents up to the given byte offset are
+    /// known to this history, so that a subsequent call to [`History::read_file`]
+    /// requesting only new items will skip them.
+    ///
+    /// # Arguments
+    ///
+    /// * `history_file_path` - The path to the history file.
+    /// * `offset` - The byte offset up to which the file has been read.
+    pub fn mark_file_read(&mut self, history_file_path: impl AsRef<Path>, offset: u64) {
+        self.file_offsets
+            .insert_mut(history_file_path.as_ref().to_path_buf(), offset);
     }
 
     /// Tries to retrieve a history item by its unique identifier. Returns `None` if no item is
@@ -183,7 +245,8 @@ impl History {
             file_options.write(true).truncate(true);
         }
 
-        let mut file = file_options.create(true).open(history_file_path.as_ref())?;
+        let history_file_path = history_file_path.as_ref();
+        let mut file = file_options.create(true).open(history_file_path)?;
 
         for item_id in &self.items {
             if let Some(item) = self.id_map.get_mut(item_id) {
@@ -206,6 +269,9 @@ impl History {
 
         file.flush()?;
 
+        // Everything now in the file is known to us; don't read it back in as new items.
+        self.mark_file_read(history_file_path, file.metadata()?.len());
+
         Ok(())
     }
 
@@ -241,6 +307,17 @@ impl History {
     pub fn count(&self) -> usize {
         self.items.len()
     }
+
+    /// Performs history expansion (e.g., `!!`, `!-2:$`, `^old^new`) on the given line,
+    /// resolving event designators against this history. Returns an error if a
+    /// referenced event can't be found or a word designator is invalid.
+    ///
+    /// # Arguments
+    ///
+    /// * `line` - The line to expand.
+    pub fn expand(&self, line: &str) -> Result<String, error::Error> {
+        super::historyexpansion::expand(self, line)
+    }
 }
 
 /// Represents an item in the history.
@@ -450,37 +527,35 @@ impl<'a> Iterator for Search<'a> {
 
     fn next(&mut self) -> Option<Self::Item> {
         loop {
-            if let Some(index) = self.next_index {
-                // Make sure we haven't hit the end of the history.
-                if index >= self.history.items.len() {
+            let index = self.next_index?;
+
+            // Make sure we haven't hit the end of the history.
+            if index >= self.history.items.len() {
+                return None;
+            }
+
+            let id = self.history.items[index];
+            self.increment_next_index();
+
+            if let Some(item) = self.history.id_map.get(&id) {
+                // Filter based on max_items. Once we hit the limit,
+                // we stop searching.
+                #[expect(clippy::cast_possible_truncation)]
+                #[expect(clippy::cast_sign_loss)]
+                if self
+                    .query
+                    .max_items
+                    .is_some_and(|max_items| self.count >= max_items as usize)
+                {
                     return None;
                 }
 
-                let id = self.history.items[index];
-                self.increment_next_index();
-
-                if let Some(item) = self.history.id_map.get(&id) {
-                    // Filter based on max_items. Once we hit the limit,
-                    // we stop searching.
-                    #[expect(clippy::cast_possible_truncation)]
-                    #[expect(clippy::cast_sign_loss)]
-                    if self
-                        .query
-                        .max_items
-                        .is_some_and(|max_items| self.count >= max_items as usize)
-                    {
-                        return None;
-                    }
-
-                    // Check other filters. If they don't match, then we
-                    // skip but keep searching.
-                    if self.query.includes(item) {
-                        self.count += 1;
-                        return Some(item);
-                    }
+                // Check other filters. If they don't match, then we
+                // skip but keep searching.
+                if self.query.includes(item) {
+                    self.count += 1;
+                    return Some(item);
                 }
-            } else {
-                return None;
             }
         }
     }
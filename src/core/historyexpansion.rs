@@ -0,0 +1,492 @@
+//! Implements bash-style history expansion (e.g., `!!`, `!-2:$`, `^old^new`).
+
+use super::{error, history};
+
+/// Performs history expansion on the given input line, resolving event designators
+/// against the given history.
+///
+/// # Arguments
+///
+/// * `history` - The history to resolve events against.
+/// * `input` - The line to expand.
+pub(crate) fn expand(history: &history::History, input: &str) -> Result<String, error::Error> {
+    // Quick substitution: `^old^new^` is shorthand for `!!:s^old^new^`.
+    if let Some(rest) = input.strip_prefix('^') {
+        let event = last_event(history, "^")?;
+        let mut chars = rest.chars().peekable();
+        let (old, new) = parse_substitution(&mut chars, '^');
+        let remainder: String = chars.collect();
+
+        let substituted = substitute(&event, &old, &new, false)
+            .ok_or_else(|| error::ErrorKind::BadHistoryWordSpecifier(input.to_owned()))?;
+        return Ok(substituted + remainder.as_str());
+    }
+
+    let chars: Vec<char> = input.chars().collect();
+    let mut result = String::new();
+    let mut in_single_quotes = false;
+    let mut i = 0;
+
+    while i < chars.len() {
+        let c = chars[i];
+
+        if c == '\'' {
+            in_single_quotes = !in_single_quotes;
+        }
+
+        if in_single_quotes || c != '!' {
+            // Backslashes quote the next character, including the history character.
+            if c == '\\' && !in_single_quotes && i + 1 < chars.len() {
+                result.push(c);
+                i += 1;
+                result.push(chars[i]);
+            } else {
+                result.push(c);
+            }
+
+            i += 1;
+            continue;
+        }
+
+        // A `!` followed by whitespace, `=`, `(`, or the end of the line is left alone.
+        let next = chars.get(i + 1).copied();
+        if next.is_none_or(|n| n.is_whitespace() || n == '=' || n == '(') {
+            result.push(c);
+            i += 1;
+            continue;
+        }
+
+        let start = i;
+        i += 1;
+
+        let (event, word_default_all) = parse_event(history, &chars, &mut i, &result)?;
+        let spec: String = chars[start..i].iter().collect();
+
+        let words = split_words(&event);
+        let mut selection =
+            parse_word_designator(&chars, &mut i, &words, &spec)?.unwrap_or_else(|| {
+                if word_default_all {
+                    event.clone()
+                } else {
+                    String::new()
+                }
+            });
+
+        apply_modifiers(&chars, &mut i, &mut selection, &spec)?;
+
+        result.push_str(&selection);
+    }
+
+    Ok(result)
+}
+
+/// Parses the event designator following a `!`, returning the selected event line and
+/// whether the whole line should be used in the absence of a word designator.
+fn parse_event(
+    history: &history::History,
+    chars: &[char],
+    i: &mut usize,
+    line_so_far: &str,
+) -> Result<(String, bool), error::Error> {
+    let start = *i;
+
+    match chars[*i] {
+        '!' => {
+            *i += 1;
+            Ok((last_event(history, "!!")?, true))
+        }
+        '#' => {
+            *i += 1;
+            Ok((line_so_far.to_owned(), true))
+        }
+        // Word designators may directly follow the `!` as a shorthand for the previous
+        // command (e.g., `!$`).
+        '$' | '^' | '*' | '%' | ':' => Ok((last_event(history, "!")?, false)),
+        '?' => {
+            *i += 1;
+            let search_start = *i;
+            while *i < chars.len() && chars[*i] != '?' && chars[*i] != '\n' {
+                *i += 1;
+            }
+
+            let needle: String = chars[search_start..*i].iter().collect();
+            if *i < chars.len() && chars[*i] == '?' {
+                *i += 1;
+            }
+
+            let spec: String = chars[start - 1..*i].iter().collect();
+            find_event(history, |line| line.contains(needle.as_str()), &spec)
+                .map(|event| (event, true))
+        }
+        c if c.is_ascii_digit() || c == '-' => {
+            let negative = c == '-';
+            if negative {
+                *i += 1;
+            }
+
+            let digits_start = *i;
+            while *i < chars.len() && chars[*i].is_ascii_digit() {
+                *i += 1;
+            }
+
+            let digits: String = chars[digits_start..*i].iter().collect();
+            let spec: String = chars[start - 1..*i].iter().collect();
+            let n: usize = digits
+                .parse()
+                .map_err(|_| error::ErrorKind::HistoryEventNotFound(spec.clone()))?;
+
+            let index = if negative {
+                history.count().checked_sub(n)
+            } else {
+                n.checked_sub(1)
+            };
+
+            index
+                .and_then(|index| history.get(index))
+                .map(|item| (item.command_line.clone(), true))
+                .ok_or_else(|| error::ErrorKind::HistoryEventNotFound(spec).into())
+        }
+        _ => {
+            while *i < chars.len() && !chars[*i].is_whitespace() && chars[*i] != ':' {
+                *i += 1;
+            }
+
+            let prefix: String = chars[start..*i].iter().collect();
+            let spec: String = chars[start - 1..*i].iter().collect();
+            find_event(history, |line| line.starts_with(prefix.as_str()), &spec)
+                .map(|event| (event, true))
+        }
+    }
+}
+
+/// Parses an optional word designator, returning the selected words (if one was present).
+fn parse_word_designator(
+    chars: &[char],
+    i: &mut usize,
+    words: &[String],
+    spec: &str,
+) -> Result<Option<String>, error::Error> {
+    let bad_spec =
+        || error::Error::from(error::ErrorKind::BadHistoryWordSpecifier(spec.to_owned()));
+
+    // Determine whether a word designator follows; `^`, `$`, `*`, and `%` may omit
+    // the leading `:`.
+    match chars.get(*i) {
+        Some('^' | '$' | '*' | '%') => (),
+        Some(':')
+            if chars
+                .get(*i + 1)
+                .is_some_and(|c| is_word_designator_start(*c)) =>
+        {
+            *i += 1;
+        }
+        _ => return Ok(None),
+    }
+
+    let last = words.len().saturating_sub(1);
+
+    let parse_index = |i: &mut usize| -> Option<usize> {
+        match chars.get(*i) {
+            Some('^') => {
+                *i += 1;
+                Some(1)
+            }
+            Some('$') => {
+                *i += 1;
+                Some(last)
+            }
+            Some(c) if c.is_ascii_digit() => {
+                let start = *i;
+                while *i < chars.len() && chars[*i].is_ascii_digit() {
+                    *i += 1;
+                }
+                chars[start..*i].iter().collect::<String>().parse().ok()
+            }
+            _ => None,
+        }
+    };
+
+    let (first, last) = match chars[*i] {
+        '*' => {
+            *i += 1;
+            if words.len() <= 1 {
+                return Ok(Some(String::new()));
+            }
+            (1, last)
+        }
+        '%' => return Err(bad_spec()),
+        '-' => {
+            *i += 1;
+            (0, parse_index(i).unwrap_or(last.saturating_sub(1)))
+        }
+        _ => {
+            let first = parse_index(i).ok_or_else(bad_spec)?;
+            match chars.get(*i) {
+                Some('*') => {
+                    *i += 1;
+                    (first, last)
+                }
+                Some('-') => {
+                    *i += 1;
+                    let end = parse_index(i).unwrap_or(last.saturating_sub(1));
+                    (first, end)
+                }
+                _ => (first, first),
+            }
+        }
+    };
+
+    if first > last || last >= words.len() {
+        return Err(bad_spec());
+    }
+
+    Ok(Some(words[first..=last].join(" ")))
+}
+
+const fn is_word_designator_start(c: char) -> bool {
+    matches!(c, '^' | '$' | '*' | '%' | '-') || c.is_ascii_digit()
+}
+
+/// Applies any `:`-prefixed modifiers (e.g., `:h`, `:s/old/new/`) to the selection.
+fn apply_modifiers(
+    chars: &[char],
+    i: &mut usize,
+    selection: &mut String,
+    spec: &str,
+) -> Result<(), error::Error> {
+    let mut last_substitution: Option<(String, String)> = None;
+
+    while chars.get(*i) == Some(&':') {
+        let Some(&modifier) = chars.get(*i + 1) else {
+            break;
+        };
+
+        *i += 2;
+
+        match modifier {
+            'h' => {
+                if let Some(pos) = selection.rfind('/') {
+                    selection.truncate(pos);
+                }
+            }
+            't' => {
+                if let Some(pos) = selection.rfind('/') {
+                    *selection = selection[pos + 1..].to_owned();
+                }
+            }
+            'r' => {
+                if let Some(pos) = selection.rfind('.')
+                    && !selection[pos..].contains('/')
+                {
+                    selection.truncate(pos);
+                }
+            }
+            'e' => {
+                *selection = selection
+                    .rfind('.')
+                    .filter(|pos| !selection[*pos..].contains('/'))
+                    .map(|pos| selection[pos..].to_owned())
+                    .unwrap_or_default();
+            }
+            'q' => {
+                *selection = format!("'{}'", selection.replace('\'', r"'\''"));
+            }
+            // N.B. Printing without executing is the caller's concern.
+            'p' => (),
+            's' | 'g' | '&' => {
+                let global = modifier == 'g';
+                let op = if global {
+                    let op = chars.get(*i).copied();
+                    *i += 1;
+                    op
+                } else {
+                    Some(modifier)
+                };
+
+                let (old, new) = match op {
+                    Some('s') => {
+                        let Some(&delimiter) = chars.get(*i) else {
+                            return Err(
+                                error::ErrorKind::BadHistoryWordSpecifier(spec.to_owned()).into()
+                            );
+                        };
+                        *i += 1;
+
+                        let mut rest = chars[*i..].iter().copied().peekable();
+                        let before = chars.len() - *i;
+                        let (old, new) = parse_substitution(&mut rest, delimiter);
+                        *i += before - rest.count();
+                        (old, new)
+                    }
+                    Some('&') => last_substitution.clone().ok_or_else(|| {
+                        error::ErrorKind::BadHistoryWordSpecifier(spec.to_owned())
+                    })?,
+                    _ => {
+                        return Err(
+                            error::ErrorKind::BadHistoryWordSpecifier(spec.to_owned()).into()
+                        );
+                    }
+                };
+
+                *selection = substitute(selection, &old, &new, global)
+                    .ok_or_else(|| error::ErrorKind::BadHistoryWordSpecifier(spec.to_owned()))?;
+                last_substitution = Some((old, new));
+            }
+            _ => {
+                return Err(error::ErrorKind::BadHistoryWordSpecifier(spec.to_owned()).into());
+            }
+        }
+    }
+
+    Ok(())
+}
+
+/// Parses the `old<delim>new<delim>` portion of a substitution. The final delimiter is
+/// optional at the end of the line. An `&` in the replacement stands for the old text.
+fn parse_substitution(
+    chars: &mut std::iter::Peekable<impl Iterator<Item = char>>,
+    delimiter: char,
+) -> (String, String) {
+    let read_part = |chars: &mut std::iter::Peekable<_>| {
+        let mut part = String::new();
+        while let Some(c) = chars.next() {
+            if c == delimiter {
+                break;
+            } else if c == '\\' && chars.peek() == Some(&delimiter) {
+                part.push(delimiter);
+                chars.next();
+            } else {
+                part.push(c);
+            }
+        }
+        part
+    };
+
+    let old = read_part(chars);
+    let new = read_part(chars).replace('&', &old);
+
+    (old, new)
+}
+
+/// Replaces the first (or every, if `global` is set) occurrence of `old` in `input`.
+/// Returns `None` if `old` does not occur.
+fn substitute(input: &str, old: &str, new: &str, global: bool) -> Option<String> {
+    if old.is_empty() || !input.contains(old) {
+        return None;
+    }
+
+    if global {
+        Some(input.replace(old, new))
+    } else {
+        Some(input.replacen(old, new, 1))
+    }
+}
+
+fn last_event(history: &history::History, spec: &str) -> Result<String, error::Error> {
+    history
+        .count()
+        .checked_sub(1)
+        .and_then(|index| history.get(index))
+        .map(|item| item.command_line.clone())
+        .ok_or_else(|| error::ErrorKind::HistoryEventNotFound(spec.to_owned()).into())
+}
+
+fn find_event(
+    history: &history::History,
+    predicate: impl Fn(&str) -> bool,
+    spec: &str,
+) -> Result<String, error::Error> {
+    (0..history.count())
+        .rev()
+        .filter_map(|index| history.get(index))
+        .find(|item| predicate(item.command_line.as_str()))
+        .map(|item| item.command_line.clone())
+        .ok_or_else(|| error::ErrorKind::HistoryEventNotFound(spec.to_owned()).into())
+}
+
+/// Splits a history line into words, keeping quoted sections together.
+fn split_words(line: &str) -> Vec<String> {
+    let mut words = vec![];
+    let mut current = String::new();
+    let mut quote = None;
+    let mut chars = line.chars();
+
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() => {
+                if !current.is_empty() {
+                    words.push(std::mem::take(&mut current));
+                }
+            }
+            (None, '\'' | '"') => {
+                quote = Some(c);
+                current.push(c);
+            }
+            (Some(q), c) if q == c => {
+                quote = None;
+                current.push(c);
+            }
+            (q, '\\') if q != Some('\'') => {
+                current.push(c);
+                if let Some(next) = chars.next() {
+                    current.push(next);
+                }
+            }
+            _ => current.push(c),
+        }
+    }
+
+    if !current.is_empty() {
+        words.push(current);
+    }
+
+    words
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn test_history() -> Result<history::History> {
+        let mut history = history::History::default();
+        for line in ["echo one two three", "ls -l /tmp/file.txt", "cat 'a b' c"] {
+            history.add(history::Item::new(line))?;
+        }
+        Ok(history)
+    }
+
+    #[test]
+    fn test_event_designators() -> Result<()> {
+        let history = test_history()?;
+
+        assert_eq!(expand(&history, "!!")?, "cat 'a b' c");
+        assert_eq!(expand(&history, "!1")?, "echo one two three");
+        assert_eq!(expand(&history, "!-2")?, "ls -l /tmp/file.txt");
+        assert_eq!(expand(&history, "x !ec y")?, "x echo one two three y");
+        assert_eq!(expand(&history, "!?tmp?")?, "ls -l /tmp/file.txt");
+        assert_eq!(expand(&history, "echo ! '!!'")?, "echo ! '!!'");
+        assert!(expand(&history, "!nope").is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_word_designators_and_modifiers() -> Result<()> {
+        let history = test_history()?;
+
+        assert_eq!(expand(&history, "!$")?, "c");
+        assert_eq!(expand(&history, "!!:1")?, "'a b'");
+        assert_eq!(expand(&history, "!1:2-3")?, "two three");
+        assert_eq!(expand(&history, "!1:*")?, "one two three");
+        assert_eq!(expand(&history, "!1:0")?, "echo");
+        assert_eq!(expand(&history, "!-2:$:h")?, "/tmp");
+        assert_eq!(expand(&history, "!-2:$:t:r")?, "file");
+        assert_eq!(expand(&history, "!1:s/one/1/")?, "echo 1 two three");
+        assert_eq!(expand(&history, "^cat^dog^")?, "dog 'a b' c");
+        assert!(expand(&history, "!1:9").is_err());
+
+        Ok(())
+    }
+}
@@ -0,0 +1,764 @@
+//! Support for readline-style input configuration, as found in inputrc files and
+//! as accepted by the `bind` builtin.
+
+use std::fmt::Display;
+use std::path::{Path, PathBuf};
+use std::str::FromStr as _;
+
+use crate::parser::readline_binding;
+
+use super::{Shell, error, interfaces, sys};
+
+/// Version of readline whose configuration syntax we emulate; consulted by
+/// `$if version` conditionals.
+const EMULATED_READLINE_VERSION: (u32, u32) = (8, 2);
+
+/// Name of the application, as tested by `$if` conditionals.
+const APPLICATION_NAME: &str = "Bash";
+
+/// Maximum nesting depth of `$include` directives.
+const MAX_INCLUDE_DEPTH: usize = 10;
+
+/// A problem encountered on a specific line of an input configuration file. Such
+/// problems don't prevent the rest of the file from being applied.
+#[derive(Debug)]
+pub struct Diagnostic {
+    /// Path to the file containing the problematic line.
+    pub path: PathBuf,
+    /// 1-based line number of the problematic line.
+    pub line_number: usize,
+    /// The problem encountered.
+    pub error: error::Error,
+}
+
+impl Display for Diagnostic {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{}: line {}: {}",
+            self.path.display(),
+            self.line_number,
+            self.error
+        )
+    }
+}
+
+/// Loads the user's input configuration file (from `INPUTRC`, `~/.inputrc`, or
+/// `/etc/inputrc`, in that order of preference) into the shell's key bindings, if
+/// the shell has key bindings and such a file exists. Returns any problems
+/// encountered with individual lines.
+///
+/// # Arguments
+///
+/// * `shell` - The shell whose key bindings should be updated.
+pub async fn load_user_config(shell: &Shell) -> Result<Vec<Diagnostic>, error::Error> {
+    let Some(bindings) = shell.key_bindings() else {
+        return Ok(vec![]);
+    };
+
+    let Some(path) = user_config_path(shell) else {
+        return Ok(vec![]);
+    };
+
+    let mut bindings = bindings.lock().await;
+    read_file(shell, &mut *bindings, &path)
+}
+
+fn user_config_path(shell: &Shell) -> Option<PathBuf> {
+    if let Some(inputrc) = shell.env_str("INPUTRC")
+        && !inputrc.is_empty()
+    {
+        return Some(shell.absolute_path(inputrc.as_ref()));
+    }
+
+    shell
+        .home_dir()
+        .map(|home| home.join(".inputrc"))
+        .into_iter()
+        .chain(std::iter::once(PathBuf::from("/etc/inputrc")))
+        .find(|path| path.is_file())
+}
+
+/// Reads the given input configuration file, applying its variable settings and key
+/// bindings. Returns an error if the file can't be read; returns any problems
+/// encountered with individual lines.
+///
+/// # Arguments
+///
+/// * `shell` - The shell on whose behalf the file is being read; used to evaluate
+///   conditionals and resolve paths.
+/// * `bindings` - The key bindings to update.
+/// * `path` - The path to the file.
+pub fn read_file(
+    shell: &Shell,
+    bindings: &mut dyn interfaces::KeyBindings,
+    path: &Path,
+) -> Result<Vec<Diagnostic>, error::Error> {
+    let mut reader = Reader {
+        shell,
+        bindings,
+        conditionals: vec![],
+        diagnostics: vec![],
+    };
+
+    reader.read_file(path, 0)?;
+
+    Ok(reader.diagnostics)
+}
+
+/// Applies a single line of input configuration: either a variable assignment
+/// (`set name value`) or a key binding (e.g., `"\C-a": beginning-of-line`).
+///
+/// # Arguments
+///
+/// * `bindings` - The key bindings to update.
+/// * `line` - The line to apply.
+pub fn apply_line(
+    bindings: &mut dyn interfaces::KeyBindings,
+    line: &str,
+) -> Result<(), error::Error> {
+    let line = line.trim();
+
+    if let Some(assignment) = line.strip_prefix("set")
+        && assignment.starts_with(char::is_whitespace)
+    {
+        let assignment = assignment.trim_start();
+        let (name, value) = assignment
+            .split_once(char::is_whitespace)
+            .unwrap_or((assignment, ""));
+
+        return set_variable(bindings, name, value.trim());
+    }
+
+    // Bindings in vi keymaps are quietly ignored, since vi mode isn't supported.
+    // NOTE(vi): Remove this when vi mode is supported.
+    if bindings
+        .get_variables()
+        .get("keymap")
+        .is_some_and(|keymap| keymap.starts_with("vi"))
+    {
+        return Ok(());
+    }
+
+    let (seq, action) = parse_binding(line)?;
+
+    if matches!(
+        action,
+        interfaces::KeyAction::DoInputFunction(interfaces::InputFunction::ViEditingMode)
+    ) {
+        // NOTE(vi): We don't support vi mode; silently ignore.
+        return Ok(());
+    }
+
+    bindings.bind(seq, action)?;
+
+    Ok(())
+}
+
+fn set_variable(
+    bindings: &mut dyn interfaces::KeyBindings,
+    name: &str,
+    value: &str,
+) -> Result<(), error::Error> {
+    if !interfaces::INPUT_VARIABLE_DEFAULTS
+        .iter()
+        .any(|(known, _)| *known == name)
+    {
+        return Err(error::ErrorKind::UnknownInputVariable(name.to_owned()).into());
+    }
+
+    // As with readline, boolean variables are on if set to "on" or "1" (or nothing at
+    // all); anything else turns them off.
+    let value = if interfaces::is_boolean_input_variable(name) {
+        let value = value.split_whitespace().next().unwrap_or_default();
+        if value.is_empty() || value.eq_ignore_ascii_case("on") || value == "1" {
+            "on"
+        } else {
+            "off"
+        }
+    } else {
+        value
+    };
+
+    bindings.set_variable(name, value)?;
+
+    Ok(())
+}
+
+/// Parses a key binding, in either of the forms accepted in input configuration files:
+/// `"KEY-SEQUENCE": function-name-or-"macro"` or `key-name: function-name-or-"macro"`.
+///
+/// # Arguments
+///
+/// * `input` - The binding to parse.
+pub fn parse_binding(
+    input: &str,
+) -> Result<(interfaces::KeySequence, interfaces::KeyAction), error::Error> {
+    let binding = readline_binding::parse_key_sequence_readline_binding(input.trim())?;
+    let strokes = key_sequence_to_strokes(&binding.seq)?;
+
+    let action = match binding.target {
+        readline_binding::ReadlineTarget::Function(func_name) => {
+            // The function name ends at the first whitespace; anything beyond is ignored.
+            let func_name = func_name.split_whitespace().next().unwrap_or_default();
+            interfaces::KeyAction::DoInputFunction(parse_input_function(func_name)?)
+        }
+        readline_binding::ReadlineTarget::Command(text) => interfaces::KeyAction::InsertMacro(text),
+    };
+
+    Ok((interfaces::KeySequence { strokes }, action))
+}
+
+/// Parses a key sequence on its own (e.g., `"\C-xa"`), optionally enclosed in double
+/// quotes.
+///
+/// # Arguments
+///
+/// * `input` - The key sequence to parse.
+pub fn parse_key_sequence(input: &str) -> Result<interfaces::KeySequence, error::Error> {
+    let seq = readline_binding::parse_key_sequence(input)?;
+    let strokes = key_sequence_to_strokes(&seq)?;
+
+    Ok(interfaces::KeySequence { strokes })
+}
+
+/// Parses the name of an input function (e.g., `beginning-of-line`).
+///
+/// # Arguments
+///
+/// * `func_name` - The name of the function.
+pub fn parse_input_function(func_name: &str) -> Result<interfaces::InputFunction, error::Error> {
+    interfaces::InputFunction::from_str(func_name)
+        .map_err(|_err| error::ErrorKind::UnknownKeyBindingFunction(func_name.to_owned()).into())
+}
+
+/// Lifts a parsed key sequence into abstract key strokes. A sequence may contain
+/// multiple strokes (e.g., `"\C-xa"` is `Ctrl+x` followed by `a`); modifiers apply
+/// only to the key that immediately follows them.
+///
+/// # Arguments
+///
+/// * `seq` - The key sequence to translate.
+pub fn key_sequence_to_strokes(
+    seq: &readline_binding::KeySequence,
+) -> Result<Vec<interfaces::KeyStroke>, error::Error> {
+    let phys_strokes = readline_binding::key_sequence_to_strokes(seq)?;
+
+    let mut abstract_strokes = vec![];
+    for phys_stroke in phys_strokes {
+        let mut remaining = phys_stroke.key_code.as_slice();
+        let mut control = phys_stroke.control;
+        let mut meta = phys_stroke.meta;
+
+        while !remaining.is_empty() {
+            let Some((mut stroke, len)) = next_stroke(remaining) else {
+                return Err(error::ErrorKind::UnhandledKeyCode(remaining.to_vec()).into());
+            };
+
+            stroke.control |= control;
+            stroke.alt |= meta;
+            abstract_strokes.push(normalize_control_key(stroke));
+
+            control = false;
+            meta = false;
+            remaining = &remaining[len..];
+        }
+    }
+
+    Ok(abstract_strokes)
+}
+
+/// Terminals report some control characters as dedicated keys (e.g., `Ctrl+m` as
+/// `Enter`); translates such strokes to the keys that will actually be seen.
+fn normalize_control_key(stroke: interfaces::KeyStroke) -> interfaces::KeyStroke {
+    let interfaces::Key::Character(c) = stroke.key else {
+        return stroke;
+    };
+
+    let key = match c.to_ascii_lowercase() {
+        'm' | 'j' if stroke.control => interfaces::Key::Enter,
+        'i' if stroke.control => interfaces::Key::Tab,
+        '[' if stroke.control => interfaces::Key::Escape,
+        _ => return stroke,
+    };
+
+    interfaces::KeyStroke {
+        control: false,
+        key,
+        ..stroke
+    }
+}
+
+/// Translates the key at the start of the given key codes into a stroke, returning it
+/// along with the number of bytes it consumed.
+fn next_stroke(key_codes: &[u8]) -> Option<(interfaces::KeyStroke, usize)> {
+    // Prefer the longest sequence known to the terminal (e.g., `\e[A` for the up arrow).
+    for len in (1..=key_codes.len()).rev() {
+        if let Some(key) = sys::input::try_get_key_from_key_code(&key_codes[..len]) {
+            return Some((interfaces::KeyStroke::from(key), len));
+        }
+    }
+
+    let first = key_codes[0];
+
+    // An escape followed by another key is treated as Alt+<key>.
+    if first == b'\x1b'
+        && key_codes.len() > 1
+        && let Some((mut stroke, len)) = next_stroke(&key_codes[1..])
+    {
+        stroke.alt = true;
+        return Some((stroke, len + 1));
+    }
+
+    let stroke = match first {
+        b'\t' => interfaces::KeyStroke::from(interfaces::Key::Tab),
+        b'\r' | b'\n' => interfaces::KeyStroke::from(interfaces::Key::Enter),
+        b'\x7f' => interfaces::KeyStroke::from(interfaces::Key::Backspace),
+        b'\x1b' => interfaces::KeyStroke::from(interfaces::Key::Escape),
+        c if c < b' ' => interfaces::KeyStroke {
+            control: true,
+            ..interfaces::KeyStroke::from(interfaces::Key::Character(
+                char::from(c + b'`').to_ascii_lowercase(),
+            ))
+        },
+        c if c.is_ascii() => interfaces::KeyStroke::from(interfaces::Key::Character(char::from(c))),
+        _ => return None,
+    };
+
+    Some((stroke, 1))
+}
+
+struct Conditional {
+    /// Whether lines in the enclosing context are being applied.
+    parent_active: bool,
+    /// Whether the `$if` condition held.
+    condition: bool,
+    /// Whether we're in the `$else` branch.
+    in_else: bool,
+}
+
+impl Conditional {
+    const fn is_active(&self) -> bool {
+        self.parent_active && (self.condition != self.in_else)
+    }
+}
+
+struct Reader<'a> {
+    shell: &'a Shell,
+    bindings: &'a mut dyn interfaces::KeyBindings,
+    conditionals: Vec<Conditional>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Reader<'_> {
+    fn read_file(&mut self, path: &Path, depth: usize) -> Result<(), error::Error> {
+        let contents = std::fs::read_to_string(path)?;
+
+        for (index, line) in contents.lines().enumerate() {
+            if let Err(error) = self.process_line(path, line, depth) {
+                self.diagnostics.push(Diagnostic {
+                    path: path.to_owned(),
+                    line_number: index + 1,
+                    error,
+                });
+            }
+        }
+
+        Ok(())
+    }
+
+    fn is_active(&self) -> bool {
+        self.conditionals.last().is_none_or(Conditional::is_active)
+    }
+
+    fn process_line(&mut self, path: &Path, line: &str, depth: usize) -> Result<(), error::Error> {
+        let line = line.trim_start();
+
+        if line.is_empty() || line.starts_with('#') {
+            return Ok(());
+        }
+
+        if let Some(directive) = line.strip_prefix('$') {
+            return self.process_directive(path, directive.trim_end(), depth);
+        }
+
+        if self.is_active() {
+            apply_line(self.bindings, line)?;
+        }
+
+        Ok(())
+    }
+
+    fn process_directive(
+        &mut self,
+        path: &Path,
+        directive: &str,
+        depth: usize,
+    ) -> Result<(), error::Error> {
+        let (name, arg) = directive
+            .split_once(char::is_whitespace)
+            .map_or((directive, ""), |(name, arg)| (name, arg.trim()));
+
+        match name {
+            "if" => {
+                let parent_active = self.is_active();
+                let condition = parent_active && self.evaluate_condition(arg);
+                self.conditionals.push(Conditional {
+                    parent_active,
+                    condition,
+                    in_else: false,
+                });
+            }
+            "else" => match self.conditionals.last_mut() {
+                Some(conditional) if !conditional.in_else => conditional.in_else = true,
+                _ => {
+                    return Err(
+                        error::ErrorKind::InvalidInputConfigLine(format!("${directive}")).into(),
+                    );
+                }
+            },
+            "endif" => {
+                if self.conditionals.pop().is_none() {
+                    return Err(
+                        error::ErrorKind::InvalidInputConfigLine(format!("${directive}")).into(),
+                    );
+                }
+            }
+            "include" => {
+                if self.is_active() {
+                    if depth >= MAX_INCLUDE_DEPTH {
+                        return Err(error::ErrorKind::InvalidInputConfigLine(format!(
+                            "${directive}"
+                        ))
+                        .into());
+                    }
+
+                    let included_path = self.resolve_include_path(path, arg);
+                    self.read_file(&included_path, depth + 1)?;
+                }
+            }
+            _ => {
+                return Err(
+                    error::ErrorKind::InvalidInputConfigLine(format!("${directive}")).into(),
+                );
+            }
+        }
+
+        Ok(())
+    }
+
+    fn resolve_include_path(&self, including_path: &Path, arg: &str) -> PathBuf {
+        if let Some(rest) = arg.strip_prefix("~/")
+            && let Some(home) = self.shell.home_dir()
+        {
+            return home.join(rest);
+        }
+
+        let arg = Path::new(arg);
+        if arg.is_absolute() {
+            arg.to_owned()
+        } else {
+            including_path
+                .parent()
+                .map_or_else(|| arg.to_owned(), |dir| dir.join(arg))
+        }
+    }
+
+    fn evaluate_condition(&self, condition: &str) -> bool {
+        let variables = self.bindings.get_variables();
+
+        if let Some(mode) = condition.strip_prefix("mode=") {
+            return variables
+                .get("editing-mode")
+                .is_some_and(|current| current == mode.trim());
+        }
+
+        if let Some(term) = condition.strip_prefix("term=") {
+            let term = term.trim();
+            return self.shell.env_str("TERM").is_some_and(|current| {
+                current == term || current.split('-').next().is_some_and(|base| base == term)
+            });
+        }
+
+        if let Some(comparison) = condition.strip_prefix("version") {
+            return evaluate_version_comparison(comparison.trim());
+        }
+
+        // Look for a comparison against a variable's value.
+        for op in ["==", "!=", "="] {
+            if let Some((name, value)) = condition.split_once(op)
+                && let Some(current) = variables.get(name.trim())
+            {
+                let equal = current.eq_ignore_ascii_case(value.trim());
+                return if op == "!=" { !equal } else { equal };
+            }
+        }
+
+        // Otherwise, the condition names an application.
+        condition.eq_ignore_ascii_case(APPLICATION_NAME)
+    }
+}
+
+fn evaluate_version_comparison(comparison: &str) -> bool {
+    let Some(op_len) = comparison.find(|c: char| c.is_ascii_digit()) else {
+        return false;
+    };
+
+    let (op, version) = comparison.split_at(op_len);
+    let mut parts = version.trim().splitn(2, '.');
+    let major = parts.next().and_then(|p| p.parse::<u32>().ok());
+    let minor = parts.next().map_or(Some(0), |p| p.parse::<u32>().ok());
+
+    let (Some(major), Some(minor)) = (major, minor) else {
+        return false;
+    };
+
+    let ordering = EMULATED_READLINE_VERSION.cmp(&(major, minor));
+    match op.trim() {
+        "=" | "==" => ordering.is_eq(),
+        "!=" => ordering.is_ne(),
+        "<" => ordering.is_lt(),
+        "<=" => ordering.is_le(),
+        ">" => ordering.is_gt(),
+        ">=" => ordering.is_ge(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+    use std::collections::{BTreeMap, HashMap};
+
+    #[derive(Default)]
+    struct TestBindings {
+        bindings: HashMap<interfaces::KeySequence, interfaces::KeyAction>,
+        variables: BTreeMap<String, String>,
+    }
+
+    impl interfaces::KeyBindings for TestBindings {
+        fn get_current(&self) -> HashMap<interfaces::KeySequence, interfaces::KeyAction> {
+            self.bindings.clone()
+        }
+
+        fn bind(
+            &mut self,
+            seq: interfaces::KeySequence,
+            action: interfaces::KeyAction,
+        ) -> Result<(), std::io::Error> {
+            self.bindings.insert(seq, action);
+            Ok(())
+        }
+
+        fn unbind(&mut self, seq: &interfaces::KeySequence) -> Result<(), std::io::Error> {
+            self.bindings.remove(seq);
+            Ok(())
+        }
+
+        fn get_variables(&self) -> BTreeMap<String, String> {
+            let mut variables: BTreeMap<_, _> = interfaces::INPUT_VARIABLE_DEFAULTS
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+                .collect();
+            variables.extend(self.variables.clone());
+            variables
+        }
+
+        fn set_variable(&mut self, name: &str, value: &str) -> Result<(), std::io::Error> {
+            self.variables.insert(name.to_owned(), value.to_owned());
+            Ok(())
+        }
+    }
+
+    fn ctrl(c: char) -> interfaces::KeyStroke {
+        interfaces::KeyStroke {
+            control: true,
+            ..interfaces::KeyStroke::from(interfaces::Key::Character(c))
+        }
+    }
+
+    #[test]
+    fn parse_example_key_sequence_and_readline_func() -> Result<()> {
+        let (key_seq, action) = parse_binding(r#""\C-a":beginning-of-line"#)?;
+
+        assert_eq!(
+            key_seq,
+            interfaces::KeySequence {
+                strokes: vec![interfaces::KeyStroke {
+                    alt: false,
+                    control: true,
+                    shift: false,
+                    key: interfaces::Key::Character('a'),
+                }],
+            }
+        );
+
+        assert_eq!(
+            action,
+            interfaces::KeyAction::DoInputFunction(interfaces::InputFunction::BeginningOfLine)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_escape_char_key_binding() -> Result<()> {
+        let (key_seq, action) = parse_binding(r#""\er":transpose-chars"#)?;
+
+        assert_eq!(
+            key_seq,
+            interfaces::KeySequence {
+                strokes: vec![interfaces::KeyStroke {
+                    alt: true,
+                    control: false,
+                    shift: false,
+                    key: interfaces::Key::Character('r'),
+                }],
+            }
+        );
+
+        assert_eq!(
+            action,
+            interfaces::KeyAction::DoInputFunction(interfaces::InputFunction::TransposeChars)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_multi_stroke_sequence() -> Result<()> {
+        let seq = parse_key_sequence(r#""\C-xa""#)?;
+        assert_eq!(
+            seq.strokes,
+            [
+                ctrl('x'),
+                interfaces::KeyStroke::from(interfaces::Key::Character('a'))
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_key_name_binding() -> Result<()> {
+        let (seq, action) = parse_binding("Control-u: universal-argument")?;
+        assert_eq!(seq.strokes, [ctrl('u')]);
+        assert_eq!(
+            action,
+            interfaces::KeyAction::DoInputFunction(interfaces::InputFunction::UniversalArgument)
+        );
+
+        let (seq, action) = parse_binding(r#"Meta-Rubout: "xyz""#)?;
+        assert_eq!(
+            seq.strokes,
+            [interfaces::KeyStroke {
+                alt: true,
+                ..interfaces::KeyStroke::from(interfaces::Key::Backspace)
+            }]
+        );
+        assert_eq!(action, interfaces::KeyAction::InsertMacro("xyz".into()));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_read_file_with_conditionals() -> Result<()> {
+        let shell = crate::Shell::builder().build().await?;
+
+        let path = std::env::temp_dir().join(format!("inputrc-test-{}", std::process::id()));
+        std::fs::write(
+            &path,
+            r#"
+# A comment
+set completion-ignore-case on
+set bell-style none
+
+$if mode=emacs
+"\C-xa": beginning-of-line
+$if version < 8
+"\C-xb": end-of-line
+$else
+"\C-xc": end-of-line
+$endif
+$else
+"\C-xd": kill-line
+$endif
+
+$if Bash
+"\C-xe": "echo hi\C-m"
+$endif
+
+"\C-xf": not-a-function
+"#,
+        )?;
+
+        let mut bindings = TestBindings::default();
+        let diagnostics = read_file(&shell, &mut bindings, &path)?;
+        std::fs::remove_file(&path)?;
+
+        assert_eq!(diagnostics.len(), 1);
+        assert_eq!(diagnostics[0].line_number, 21);
+
+        let variables = interfaces::KeyBindings::get_variables(&bindings);
+        assert_eq!(variables["completion-ignore-case"], "on");
+        assert_eq!(variables["bell-style"], "none");
+
+        let bound: Vec<_> = ['a', 'c', 'e']
+            .into_iter()
+            .map(|c| interfaces::KeySequence {
+                strokes: vec![
+                    ctrl('x'),
+                    interfaces::KeyStroke::from(interfaces::Key::Character(c)),
+                ],
+            })
+            .collect();
+
+        assert_eq!(bindings.bindings.len(), bound.len());
+        for seq in &bound {
+            assert!(
+                bindings.bindings.contains_key(seq),
+                "missing binding: {seq}"
+            );
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_bindings_without_variable_support() -> Result<()> {
+        // Implements only the methods that have no default.
+        #[derive(Default)]
+        struct MinimalBindings {
+            bindings: HashMap<interfaces::KeySequence, interfaces::KeyAction>,
+        }
+
+        impl interfaces::KeyBindings for MinimalBindings {
+            fn get_current(&self) -> HashMap<interfaces::KeySequence, interfaces::KeyAction> {
+                self.bindings.clone()
+            }
+
+            fn bind(
+                &mut self,
+                seq: interfaces::KeySequence,
+                action: interfaces::KeyAction,
+            ) -> Result<(), std::io::Error> {
+                self.bindings.insert(seq, action);
+                Ok(())
+            }
+        }
+
+        let mut bindings = MinimalBindings::default();
+        apply_line(&mut bindings, r#""\C-xa": beginning-of-line"#)?;
+        assert_eq!(bindings.bindings.len(), 1);
+
+        assert!(apply_line(&mut bindings, "set bell-style none").is_err());
+        assert!(interfaces::KeyBindings::get_variables(&bindings).is_empty());
+
+        let seq = parse_key_sequence(r#""\C-xa""#)?;
+        assert!(interfaces::KeyBindings::unbind(&mut bindings, &seq).is_err());
+
+        Ok(())
+    }
+}
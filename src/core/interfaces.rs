@@ -1,5 +1,12 @@
 //! Exports traits for shell interfaces implemented by callers.
 
+mod commandnotfound;
 mod keybindings;
+mod lineeditor;
 
-pub use keybindings::{InputFunction, Key, KeyAction, KeyBindings, KeySequence, KeyStroke};
+pub use commandnotfound::{CommandNotFoundAction, CommandNotFoundHandler};
+pub use keybindings::{
+    INPUT_VARIABLE_DEFAULTS, InputFunction, Key, KeyAction, KeyBindings, KeySequence, KeyStroke,
+    is_boolean_input_variable,
+};
+pub use lineeditor::LineEditor;
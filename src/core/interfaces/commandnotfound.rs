@@ -0,0 +1,32 @@
+use std::path::PathBuf;
+
+use crate::core::{ExecutionContext, ExecutionResult, error};
+
+/// Outcome of a `CommandNotFoundHandler` being consulted about a missing command.
+pub enum CommandNotFoundAction {
+    /// The handler did not handle the command; the shell falls back to invoking any
+    /// `command_not_found_handle` function, or else reporting the error.
+    NotHandled,
+    /// The handler fully handled the command, yielding the given result.
+    Handled(ExecutionResult),
+    /// The command should be executed using the given executable (e.g., after the
+    /// handler installed it).
+    Execute(PathBuf),
+}
+
+/// Encapsulates an embedder-provided fallback for commands that could not be found
+/// in the shell's search path.
+#[async_trait::async_trait]
+pub trait CommandNotFoundHandler: Send + Sync {
+    /// Handles a command that could not be found.
+    ///
+    /// # Arguments
+    ///
+    /// * `context` - The context in which the command was to be executed.
+    /// * `args` - The command's arguments, starting with the command name.
+    async fn handle_command_not_found(
+        &self,
+        context: &mut ExecutionContext<'_>,
+        args: &[String],
+    ) -> Result<CommandNotFoundAction, error::Error>;
+}
@@ -1,15 +1,18 @@
 use std::{
-    collections::HashMap,
+    collections::{BTreeMap, HashMap},
     fmt::{self, Display, Formatter},
 };
 
 /// Represents an action that can be taken in response to a key sequence.
-#[derive(Debug)]
+#[derive(Clone, Debug, Eq, PartialEq)]
 pub enum KeyAction {
     /// Execute a shell command.
     ShellCommand(String),
     /// Execute an input "function".
     DoInputFunction(InputFunction),
+    /// Insert the given macro text, as if typed; the text uses the same escape syntax as
+    /// key sequences (e.g., `\C-m`).
+    InsertMacro(String),
 }
 
 impl Display for KeyAction {
@@ -17,12 +20,22 @@ impl Display for KeyAction {
         match self {
             Self::ShellCommand(command) => write!(f, "shell command: {command}"),
             Self::DoInputFunction(function) => function.fmt(f),
+            Self::InsertMacro(text) => write!(f, "macro: {text}"),
         }
     }
 }
 
 /// Defines all input functions.
-#[derive(Debug, strum_macros::EnumString, strum_macros::Display, strum_macros::EnumIter)]
+#[derive(
+    Clone,
+    Copy,
+    Debug,
+    Eq,
+    PartialEq,
+    strum_macros::EnumString,
+    strum_macros::Display,
+    strum_macros::EnumIter,
+)]
 #[strum(serialize_all = "kebab-case")]
 #[expect(missing_docs)]
 pub enum InputFunction {
@@ -198,7 +211,7 @@ pub enum InputFunction {
 }
 
 /// Represents a sequence of keys.
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct KeySequence {
     /// The strokes in the sequence.
     pub strokes: Vec<KeyStroke>,
@@ -222,7 +235,7 @@ impl From<KeyStroke> for KeySequence {
     }
 }
 
-#[derive(Debug, Eq, Hash, PartialEq)]
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 /// Represents a single key press.
 pub struct KeyStroke {
     /// Alt key was pressed.
@@ -328,10 +341,88 @@ impl Display for Key {
     }
 }
 
+/// Input variables (as set with `set` in an inputrc file or via `bind`) along with
+/// their default values. Variables whose defaults are `on` or `off` are boolean.
+pub const INPUT_VARIABLE_DEFAULTS: &[(&str, &str)] = &[
+    ("bell-style", "audible"),
+    ("bind-tty-special-chars", "on"),
+    ("blink-matching-paren", "off"),
+    ("colored-completion-prefix", "off"),
+    ("colored-stats", "off"),
+    ("comment-begin", "#"),
+    ("completion-display-width", "-1"),
+    ("completion-ignore-case", "off"),
+    ("completion-map-case", "off"),
+    ("completion-prefix-display-length", "0"),
+    ("completion-query-items", "100"),
+    ("convert-meta", "off"),
+    ("disable-completion", "off"),
+    ("echo-control-characters", "on"),
+    ("editing-mode", "emacs"),
+    ("emacs-mode-string", "@"),
+    ("enable-active-region", "on"),
+    ("enable-bracketed-paste", "on"),
+    ("enable-keypad", "off"),
+    ("enable-meta-key", "on"),
+    ("expand-tilde", "off"),
+    ("history-preserve-point", "off"),
+    ("history-size", "0"),
+    ("horizontal-scroll-mode", "off"),
+    ("input-meta", "on"),
+    ("isearch-terminators", ""),
+    ("keymap", "emacs"),
+    ("keyseq-timeout", "500"),
+    ("mark-directories", "on"),
+    ("mark-modified-lines", "off"),
+    ("mark-symlinked-directories", "off"),
+    ("match-hidden-files", "on"),
+    ("menu-complete-display-prefix", "off"),
+    ("output-meta", "on"),
+    ("page-completions", "on"),
+    ("print-completions-horizontally", "off"),
+    ("revert-all-at-newline", "off"),
+    ("search-ignore-case", "off"),
+    ("show-all-if-ambiguous", "off"),
+    ("show-all-if-unmodified", "off"),
+    ("show-mode-in-prompt", "off"),
+    ("skip-completed-text", "off"),
+    ("vi-cmd-mode-string", "(cmd)"),
+    ("vi-ins-mode-string", "(ins)"),
+    ("visible-stats", "off"),
+];
+
+/// Returns whether the named input variable takes a boolean (`on`/`off`) value.
+///
+/// # Arguments
+///
+/// * `name` - The name of the variable.
+pub fn is_boolean_input_variable(name: &str) -> bool {
+    INPUT_VARIABLE_DEFAULTS
+        .iter()
+        .any(|(n, default)| *n == name && matches!(*default, "on" | "off"))
+}
+
 /// Encapsulates the shell's interaction with key bindings for input.
 pub trait KeyBindings: Send {
     /// Retrieves current bindings.
     fn get_current(&self) -> HashMap<KeySequence, KeyAction>;
     /// Updates a binding.
     fn bind(&mut self, seq: KeySequence, action: KeyAction) -> Result<(), std::io::Error>;
+    /// Removes the binding for the given key sequence, if there is one. By default,
+    /// removing bindings is unsupported.
+    fn unbind(&mut self, seq: &KeySequence) -> Result<(), std::io::Error> {
+        let _ = seq;
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
+    /// Retrieves the current values of all input variables, keyed by name. By default,
+    /// no input variables are reported.
+    fn get_variables(&self) -> BTreeMap<String, String> {
+        BTreeMap::new()
+    }
+    /// Updates the value of an input variable. By default, input variables are
+    /// unsupported.
+    fn set_variable(&mut self, name: &str, value: &str) -> Result<(), std::io::Error> {
+        let _ = (name, value);
+        Err(std::io::ErrorKind::Unsupported.into())
+    }
 }
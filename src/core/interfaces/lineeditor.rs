@@ -0,0 +1,16 @@
+/// Encapsulates a line editor that builtins (e.g., `read -e`) may use to read
+/// interactively edited input.
+pub trait LineEditor: Send {
+    /// Reads a line of input, returning `None` if end of input was reached or the
+    /// read was interrupted.
+    ///
+    /// # Arguments
+    ///
+    /// * `prompt` - The prompt to display.
+    /// * `initial_text` - Text to pre-populate the edit buffer with.
+    fn read_line(
+        &mut self,
+        prompt: &str,
+        initial_text: &str,
+    ) -> Result<Option<String>, std::io::Error>;
+}
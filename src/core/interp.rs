@@ -7,7 +7,7 @@ use sys::commands::ExitStatusExt;
 
 use super::arithmetic::{self, ExpandAndEvaluate};
 use super::commands::{self, CommandArg};
-use super::env::{EnvironmentLookup, EnvironmentScope};
+use super::env::EnvironmentScope;
 use super::openfiles::{OpenFile, OpenFiles};
 use super::results::{
     self, ExecutionExitCode, ExecutionResult, ExecutionSpawnResult, ExecutionWaitResult,
@@ -16,7 +16,9 @@ use super::shell::Shell;
 use super::variables::{
     ArrayLiteral, ShellValue, ShellValueLiteral, ShellValueUnsetType, ShellVariable,
 };
-use super::{ShellFd, error, expansion, extendedtests, jobs, openfiles, processes, sys, timing};
+use super::{
+    ShellFd, dryrun, env, error, expansion, extendedtests, jobs, openfiles, processes, sys, timing,
+};
 use tokio_util::sync::CancellationToken;
 
 impl From<processes::ProcessWaitResult> for results::ExecutionResult {
@@ -67,8 +69,19 @@ pub struct ExecutionParameters {
     pub process_group_policy: ProcessGroupPolicy,
     /// Optional cancellation token for interrupting execution.
     cancellation_token: Option<CancellationToken>,
+    /// Optional recorder for dry-run execution.
+    dry_run: Option<dryrun::DryRun>,
+    /// Optional limit on the size of a command substitution's output, in bytes.
+    command_substitution_output_limit: Option<usize>,
+    /// Optional limit on how long a command substitution may run.
+    command_substitution_timeout: Option<std::time::Duration>,
+    /// External processes spawned by the command substitution being run, if any.
+    substitution_processes: Option<commands::SubstitutionProcesses>,
 }
 
+/// Default limit on the size of a command substitution's output, in bytes.
+const DEFAULT_COMMAND_SUBSTITUTION_OUTPUT_LIMIT: usize = 256 * 1024 * 1024;
+
 impl ExecutionParameters {
     /// Returns the standard input file; usable with `write!` et al.
     ///
@@ -183,6 +196,87 @@ impl ExecutionParameters {
     pub fn cancellation_token(&self) -> Option<&CancellationToken> {
         self.cancellation_token.as_ref()
     }
+
+    /// Enables dry-run mode for this execution context, recording what would have run to
+    /// the given recorder. See [`dryrun`] for details.
+    ///
+    /// # Arguments
+    ///
+    /// * `dry_run` - The recorder to use.
+    pub fn set_dry_run(&mut self, dry_run: dryrun::DryRun) {
+        self.dry_run = Some(dry_run);
+    }
+
+    /// Get a reference to the dry-run recorder, if dry-run mode is enabled.
+    pub const fn dry_run(&self) -> Option<&dryrun::DryRun> {
+        self.dry_run.as_ref()
+    }
+
+    /// Sets the maximum number of bytes of output a command substitution may produce
+    /// before it's terminated with an error. Defaults to 256 MiB.
+    ///
+    /// # Arguments
+    ///
+    /// * `limit` - The limit, in bytes.
+    pub const fn set_command_substitution_output_limit(&mut self, limit: usize) {
+        self.command_substitution_output_limit = Some(limit);
+    }
+
+    /// Returns the maximum number of bytes of output a command substitution may produce.
+    pub fn command_substitution_output_limit(&self) -> usize {
+        self.command_substitution_output_limit
+            .unwrap_or(DEFAULT_COMMAND_SUBSTITUTION_OUTPUT_LIMIT)
+    }
+
+    /// Sets how long a command substitution may run before it's terminated with an
+    /// error. By default, there is no limit.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The maximum duration.
+    pub const fn set_command_substitution_timeout(&mut self, timeout: std::time::Duration) {
+        self.command_substitution_timeout = Some(timeout);
+    }
+
+    /// Returns how long a command substitution may run, if limited.
+    pub const fn command_substitution_timeout(&self) -> Option<std::time::Duration> {
+        self.command_substitution_timeout
+    }
+
+    /// Starts tracking the external processes spawned in this context, for a command
+    /// substitution. Processes remain tracked by any enclosing substitutions.
+    pub(crate) fn track_substitution_processes(&mut self) -> commands::SubstitutionProcesses {
+        let processes = commands::SubstitutionProcesses::new(self.substitution_processes.take());
+        self.substitution_processes = Some(processes.clone());
+        processes
+    }
+
+    /// Returns the tracker of the external processes spawned by the command substitution
+    /// being run, if any.
+    pub(crate) const fn substitution_processes(&self) -> Option<&commands::SubstitutionProcesses> {
+        self.substitution_processes.as_ref()
+    }
+
+    /// Returns whether execution in this context has been cancelled.
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation_token
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+    }
+
+    /// Checks for cancellation between iterations of a loop. Loops built only from
+    /// builtins may otherwise never reach an await point that observes the
+    /// cancellation token, so we also give the runtime a chance to schedule
+    /// other tasks here.
+    async fn check_loop_cancellation(&self) -> Result<(), error::Error> {
+        tokio::task::coop::consume_budget().await;
+
+        if self.is_cancelled() {
+            Err(error::ErrorKind::Interrupted.into())
+        } else {
+            Ok(())
+        }
+    }
 }
 
 #[derive(Clone, Debug, Default)]
@@ -263,7 +357,7 @@ impl Execute for ast::CompoundList {
                 // TODO: Reenable launching in child process?
                 // let job = spawn_ao_list_in_child(ao_list, shell, params).await?;
 
-                let job = spawn_ao_list_in_task(ao_list, shell, params);
+                let job = spawn_ao_list_in_background(ao_list, shell, params).await?;
                 let job_formatted = job.to_pid_style_string();
 
                 if shell.options.interactive && !shell.is_subshell() {
@@ -285,6 +379,64 @@ impl Execute for ast::CompoundList {
     }
 }
 
+async fn spawn_ao_list_in_background<'a>(
+    ao_list: &ast::AndOrList,
+    shell: &'a mut Shell,
+    params: &ExecutionParameters,
+) -> Result<&'a jobs::Job, error::Error> {
+    // A lone pipeline of external commands is spawned right away, so the job's processes
+    // (and their IDs) are known from the start, as with bash.
+    if ao_list.additional.is_empty() && spawns_only_external_processes(&ao_list.first, shell) {
+        let mut subshell = shell.clone();
+        subshell.options.interactive = false;
+
+        let spawn_results = spawn_pipeline_processes(&ao_list.first, &mut subshell, params).await?;
+        let tasks = spawn_results
+            .into_iter()
+            .map(|spawn_result| match spawn_result {
+                ExecutionSpawnResult::StartedProcess(child) => jobs::JobTask::External(child),
+                ExecutionSpawnResult::Completed(result) => {
+                    jobs::JobTask::Internal(tokio::spawn(async move { Ok(result) }))
+                }
+            });
+
+        return Ok(shell.jobs.add_as_current(
+            jobs::Job::new(tasks, ao_list.to_string(), jobs::JobState::Running)
+                .with_process_commands(ao_list.first.seq.iter().map(ToString::to_string)),
+        ));
+    }
+
+    Ok(spawn_ao_list_in_task(ao_list, shell, params))
+}
+
+/// Returns whether each command of the given pipeline is a simple command that will be run
+/// as an external process, without any expansions that might block (i.e., command
+/// substitutions); such a pipeline can be spawned without waiting on any of its commands.
+///
+/// # Arguments
+///
+/// * `pipeline` - The pipeline to check.
+/// * `shell` - The shell the pipeline would be run in.
+fn spawns_only_external_processes(pipeline: &ast::Pipeline, shell: &Shell) -> bool {
+    pipeline.timed.is_none()
+        && !pipeline.bang
+        && pipeline.seq.iter().all(|command| {
+            let ast::Command::Simple(simple) = command else {
+                return false;
+            };
+            let Some(name) = &simple.word_or_name else {
+                return false;
+            };
+
+            let text = simple.to_string();
+            !text.contains("$(")
+                && !text.contains('`')
+                && !name.value.contains(['$', '\'', '"', '\\'])
+                && shell.funcs().get(&name.value).is_none()
+                && !shell.builtins().contains_key(&name.value)
+        })
+}
+
 fn spawn_ao_list_in_task<'a>(
     ao_list: &ast::AndOrList,
     shell: &'a mut Shell,
@@ -485,11 +637,19 @@ async fn wait_for_pipeline_processes_and_update_status(
 ) -> Result<ExecutionResult, error::Error> {
     let mut result = ExecutionResult::success();
     let mut stopped_children = vec![];
+    let mut stopped_commands = vec![];
 
     // Clear our the pipeline status so we can start filling it out.
     shell.last_pipeline_statuses.clear();
 
+    let mut stage_commands = pipeline.seq.iter();
+
     while let Some(child) = process_spawn_results.pop_front() {
+        let stage_command = stage_commands
+            .next()
+            .map(|command| command.to_string())
+            .unwrap_or_default();
+
         match child.wait(!stopped_children.is_empty(), params.cancellation_token()).await? {
             ExecutionWaitResult::Completed(current_result) => {
                 result = current_result;
@@ -502,6 +662,7 @@ async fn wait_for_pipeline_processes_and_update_status(
                 shell.last_pipeline_statuses.push(result.exit_code.into());
 
                 stopped_children.push(jobs::JobTask::External(child));
+                stopped_commands.push(stage_command);
             }
             ExecutionWaitResult::Cancelled(child) => {
                 // Exit code 130 = 128 + SIGINT (signal 2)
@@ -510,6 +671,7 @@ async fn wait_for_pipeline_processes_and_update_status(
                 shell.last_pipeline_statuses.push(result.exit_code.into());
 
                 stopped_children.push(jobs::JobTask::External(child));
+                stopped_commands.push(stage_command);
             }
         }
     }
@@ -521,13 +683,17 @@ async fn wait_for_pipeline_processes_and_update_status(
     // If there were stopped jobs, then encapsulate the pipeline as a managed job and hand it
     // off to the job manager.
     if !stopped_children.is_empty() {
-        let job = shell.jobs.add_as_current(jobs::Job::new(
-            stopped_children,
-            pipeline.to_string(),
-            jobs::JobState::Stopped,
-        ));
+        let job = shell.jobs.add_as_current(
+            jobs::Job::new(
+                stopped_children,
+                pipeline.to_string(),
+                jobs::JobState::Stopped,
+            )
+            .with_process_commands(stopped_commands),
+        );
 
         let formatted = job.to_string();
+        job.mark_reported();
 
         // N.B. We use the '\r' to overwrite any ^Z output.
         writeln!(params.stderr(shell), "\r{formatted}")?;
@@ -580,6 +746,14 @@ impl ExecuteInPipeline for ast::Command {
                 };
                 Ok(ExecutionResult::new(result).into())
             }
+            Self::Error(e) => Err(error::ErrorKind::ParseError(
+                crate::parser::ParseError::ParsingWithinSpan {
+                    token: e.tokens.first().cloned(),
+                    span: e.loc.clone(),
+                },
+                crate::parser::SourceInfo::default(),
+            )
+            .into()),
         }
     }
 }
@@ -657,6 +831,8 @@ impl Execute for ast::ForClauseCommand {
         }
 
         for value in expanded_values {
+            params.check_loop_cancellation().await?;
+
             if shell.options.print_commands_and_arguments {
                 if let Some(unexpanded_values) = &self.values {
                     shell
@@ -676,14 +852,15 @@ impl Execute for ast::ForClauseCommand {
                 }
             }
 
-            // Update the variable.
-            shell.env.update_or_add(
-                &self.variable_name,
-                ShellValueLiteral::Scalar(value),
-                |_| Ok(()),
-                EnvironmentLookup::Anywhere,
-                EnvironmentScope::Global,
-            )?;
+            // Update the variable. If it's a name reference, then each value names the
+            // variable it should refer to for this iteration.
+            if let Some((_, var)) = shell.env.get_mut_noref(&self.variable_name)
+                && var.is_treated_as_nameref()
+            {
+                var.assign(ShellValueLiteral::Scalar(value), false)?;
+            } else {
+                shell.assign_scalar_variable(&self.variable_name, value)?;
+            }
 
             result = self.body.list.execute(shell, params).await?;
             if result.is_return_or_exit() {
@@ -715,7 +892,7 @@ impl Execute for ast::CaseClauseCommand {
         // on, but that's not it.
         if shell.options.print_commands_and_arguments {
             shell
-                .trace_command(params, std::format!("case {} in", &self.value))
+                .trace_command(params, std::format!("case {} in", self.value))
                 .await?;
         }
 
@@ -835,6 +1012,8 @@ impl Execute for (WhileOrUntil, &ast::WhileOrUntilClauseCommand) {
         let mut result = ExecutionResult::success();
 
         loop {
+            params.check_loop_cancellation().await?;
+
             let condition_result = test_condition.execute(shell, params).await?;
             if !condition_result.is_normal_flow() {
                 result = condition_result;
@@ -900,6 +1079,8 @@ impl Execute for ast::ArithmeticForClauseCommand {
         }
 
         loop {
+            params.check_loop_cancellation().await?;
+
             if let Some(condition) = &self.condition
                 && condition.eval(shell, params, true).await? == 0 {
                     break;
@@ -1012,36 +1193,17 @@ impl ExecuteInPipeline for ast::SimpleCommand {
                     }
                 }
                 CommandPrefixOrSuffixItem::Word(arg) => {
-                    let mut next_args =
+                    let next_args =
                         expansion::full_expand_and_split_word(context.shell, &params, arg).await?;
 
                     if args.is_empty()
-                        && let Some(cmd_name) = next_args.first() {
-                            if let Some(alias_value) = context.shell.aliases.get(cmd_name.as_str())
-                            {
-                                //
-                                // TODO(#57): This is a total hack; aliases are supposed to be
-                                // handled much earlier in the process.
-                                //
-                                let mut alias_pieces: Vec<_> = alias_value
-                                    .split_ascii_whitespace()
-                                    .map(|i| i.to_owned())
-                                    .collect();
-
-                                next_args.remove(0);
-                                alias_pieces.append(&mut next_args);
-
-                                next_args = alias_pieces;
-                            }
-
-                            let first_arg = next_args[0].as_str();
-
+                        && let Some(first_arg) = next_args.first() {
                             // Check if we're going to be invoking a special declaration builtin.
                             // That will change how we parse and process args.
                             if context
                                 .shell
                                 .builtins()
-                                .get(first_arg)
+                                .get(first_arg.as_str())
                                 .is_some_and(|r| !r.disabled && r.declaration_builtin)
                             {
                                 command_takes_assignments = true;
@@ -1235,20 +1397,31 @@ async fn apply_assignment(
     // Figure out if we are trying to assign to a variable or assign to an element of an existing
     // array.
     let mut array_index;
-    let variable_name = match &assignment.name {
+    let mut variable_name = match &assignment.name {
         ast::AssignmentName::VariableName(name) => {
             array_index = None;
-            name
+            name.clone()
         }
         ast::AssignmentName::ArrayElementName(name, index) => {
             let expanded = expansion::basic_expand_str(shell, params, index).await?;
             array_index = Some(expanded);
-            name
+            name.clone()
         }
     };
 
+    // If the variable is a name reference, then we assign to the variable it refers to.
+    if let Some(target) = shell.env.resolve_nameref(&variable_name)? {
+        match env::split_array_element_ref(target.as_str()) {
+            Some((target_name, target_index)) if array_index.is_none() => {
+                array_index = Some(target_index.to_owned());
+                variable_name = target_name.to_owned();
+            }
+            _ => variable_name = target,
+        }
+    }
+
     // Expand the values.
-    let new_value = match &assignment.value {
+    let mut new_value = match &assignment.value {
         ast::AssignmentValue::Scalar(unexpanded_value) => {
             let value = expansion::basic_expand_word(shell, params, unexpanded_value).await?;
             ShellValueLiteral::Scalar(value)
@@ -1289,12 +1462,17 @@ async fn apply_assignment(
 
     // See if we need to eval an array index.
     if let Some(idx) = &array_index {
-        let will_be_indexed_array = if let Some((_, existing_value)) = shell.env.get(variable_name)
+        let will_be_indexed_array = if let Some((_, existing_value)) = shell.env.get(&variable_name)
         {
-            matches!(
-                existing_value.value(),
-                ShellValue::IndexedArray(_) | ShellValue::Unset(ShellValueUnsetType::IndexedArray)
-            )
+            match existing_value.value() {
+                ShellValue::IndexedArray(_)
+                | ShellValue::Unset(ShellValueUnsetType::IndexedArray) => true,
+                ShellValue::Dynamic(_) => matches!(
+                    existing_value.resolve_value(shell),
+                    ShellValue::IndexedArray(_)
+                ),
+                _ => false,
+            }
         } else {
             true
         };
@@ -1308,6 +1486,36 @@ async fn apply_assignment(
         }
     }
 
+    // Values assigned to integer variables are evaluated as arithmetic expressions.
+    if shell
+        .env
+        .get(&variable_name)
+        .is_some_and(|(_, var)| var.is_treated_as_integer())
+    {
+        new_value = arithmetic::eval_integer_assignment(shell, new_value)?;
+    }
+
+    // Scalar assignments to dynamic values are handed to the values themselves.
+    if let ShellValueLiteral::Scalar(s) = &new_value
+        && shell.env.get(&variable_name).is_some_and(|(scope, var)| {
+            matches!(var.value(), ShellValue::Dynamic(_))
+                && (required_scope.is_none() || Some(scope) == required_scope)
+        })
+    {
+        shell.assign_dynamic_variable(
+            &variable_name,
+            array_index.as_deref(),
+            s.clone(),
+            assignment.append,
+        )?;
+
+        if export && let Some((_, var)) = shell.env.get_mut(&variable_name) {
+            var.export();
+        }
+
+        return Ok(());
+    }
+
     // See if we can find an existing value associated with the variable.
     if let Some((existing_value_scope, existing_value)) = shell.env.get_mut(variable_name.as_str())
         && (required_scope.is_none() || Some(existing_value_scope) == required_scope) {
@@ -1401,7 +1609,7 @@ pub(crate) async fn setup_redirect(
     redirect: &ast::IoRedirect,
 ) -> Result<(), error::Error> {
     match redirect {
-        ast::IoRedirect::OutputAndError(f, append) => {
+        ast::IoRedirect::OutputAndError(f, append, _) => {
             let mut expanded_fields =
                 expansion::full_expand_and_split_word(shell, params, f).await?;
             if expanded_fields.len() != 1 {
@@ -1411,6 +1619,30 @@ pub(crate) async fn setup_redirect(
             let expanded_file_path: PathBuf =
                 shell.absolute_path(Path::new(expanded_fields.remove(0).as_str()));
 
+            if let Some(dry_run) = &mut params.dry_run {
+                let mode = if *append {
+                    dryrun::DryRunRedirectMode::Append
+                } else {
+                    dryrun::DryRunRedirectMode::Write
+                };
+                dry_run.record_redirect(dryrun::DryRunRedirect {
+                    fd: OpenFiles::STDOUT_FD,
+                    target: dryrun::DryRunRedirectTarget::File(expanded_file_path),
+                    mode,
+                });
+                dry_run.record_redirect(dryrun::DryRunRedirect {
+                    fd: OpenFiles::STDERR_FD,
+                    target: dryrun::DryRunRedirectTarget::Fd(OpenFiles::STDOUT_FD),
+                    mode: dryrun::DryRunRedirectMode::Write,
+                });
+
+                let stdout_file = openfiles::null()?;
+                let stderr_file = stdout_file.try_clone()?;
+                params.open_files.set_fd(OpenFiles::STDOUT_FD, stdout_file);
+                params.open_files.set_fd(OpenFiles::STDERR_FD, stderr_file);
+                return Ok(());
+            }
+
             let mut file_options = std::fs::File::options();
             file_options
                 .create(true)
@@ -1433,7 +1665,7 @@ pub(crate) async fn setup_redirect(
             params.open_files.set_fd(OpenFiles::STDERR_FD, stderr_file);
         }
 
-        ast::IoRedirect::File(specified_fd_num, kind, target) => {
+        ast::IoRedirect::File(specified_fd_num, kind, target, _) => {
             match target {
                 ast::IoFileRedirectTarget::Filename(f) => {
                     let mut options = std::fs::File::options();
@@ -1497,6 +1729,21 @@ pub(crate) async fn setup_redirect(
 
                     let fd_num = specified_fd_num.unwrap_or(default_fd_if_unspecified);
 
+                    // In dry-run mode, redirections that write are stubbed out.
+                    if let Some(dry_run) = &mut params.dry_run {
+                        let mode = dry_run_redirect_mode(kind);
+                        dry_run.record_redirect(dryrun::DryRunRedirect {
+                            fd: fd_num,
+                            target: dryrun::DryRunRedirectTarget::File(expanded_file_path.clone()),
+                            mode,
+                        });
+
+                        if mode != dryrun::DryRunRedirectMode::Read {
+                            params.open_files.set_fd(fd_num, openfiles::null()?);
+                            return Ok(());
+                        }
+                    }
+
                     let opened_file = shell
                         .open_file(&options, &expanded_file_path, params)
                         .map_err(|err| {
@@ -1520,6 +1767,14 @@ pub(crate) async fn setup_redirect(
 
                     let fd_num = specified_fd_num.unwrap_or(default_fd_if_unspecified);
 
+                    if let Some(dry_run) = &mut params.dry_run {
+                        dry_run.record_redirect(dryrun::DryRunRedirect {
+                            fd: fd_num,
+                            target: dryrun::DryRunRedirectTarget::Fd(*fd),
+                            mode: dry_run_redirect_mode(kind),
+                        });
+                    }
+
                     if let Some(f) = params.try_fd(shell, *fd) {
                         let target_file = f.try_clone()?;
 
@@ -1563,6 +1818,14 @@ pub(crate) async fn setup_redirect(
                             .parse::<ShellFd>()
                             .map_err(|_| error::ErrorKind::InvalidRedirection)?;
 
+                        if let Some(dry_run) = &mut params.dry_run {
+                            dry_run.record_redirect(dryrun::DryRunRedirect {
+                                fd: fd_num,
+                                target: dryrun::DryRunRedirectTarget::Fd(source_fd_num),
+                                mode: dry_run_redirect_mode(kind),
+                            });
+                        }
+
                         // Duplicate the fd.
                         let target_file = if let Some(f) = params.try_fd(shell, source_fd_num) {
                             f.try_clone()?
@@ -1576,6 +1839,14 @@ pub(crate) async fn setup_redirect(
                     }
 
                     if dash {
+                        if let Some(dry_run) = &mut params.dry_run {
+                            dry_run.record_redirect(dryrun::DryRunRedirect {
+                                fd: fd_num,
+                                target: dryrun::DryRunRedirectTarget::Closed,
+                                mode: dry_run_redirect_mode(kind),
+                            });
+                        }
+
                         // Close the specified fd. Ignore it if it's not valid.
                         params.open_files.remove_fd(fd_num);
                     }
@@ -1601,6 +1872,16 @@ pub(crate) async fn setup_redirect(
                             let fd_num = specified_fd_num
                                 .unwrap_or_else(|| get_default_fd_for_redirect_kind(kind));
 
+                            if let Some(dry_run) = &mut params.dry_run {
+                                dry_run.record_redirect(dryrun::DryRunRedirect {
+                                    fd: fd_num,
+                                    target: dryrun::DryRunRedirectTarget::ProcessSubstitution(
+                                        subshell_cmd.list.to_string(),
+                                    ),
+                                    mode: dry_run_redirect_mode(kind),
+                                });
+                            }
+
                             params.open_files.set_fd(fd_num, target_file);
                         }
                         _ => return error::unimp("invalid process substitution"),
@@ -1609,7 +1890,7 @@ pub(crate) async fn setup_redirect(
             }
         }
 
-        ast::IoRedirect::HereDocument(fd_num, io_here) => {
+        ast::IoRedirect::HereDocument(fd_num, io_here, _) => {
             // If not specified, default to stdin (fd 0).
             let fd_num = fd_num.unwrap_or(0);
 
@@ -1620,18 +1901,34 @@ pub(crate) async fn setup_redirect(
                 io_here.doc.flatten()
             };
 
+            if let Some(dry_run) = &mut params.dry_run {
+                dry_run.record_redirect(dryrun::DryRunRedirect {
+                    fd: fd_num,
+                    target: dryrun::DryRunRedirectTarget::HereDocument(io_here_doc.clone()),
+                    mode: dryrun::DryRunRedirectMode::Read,
+                });
+            }
+
             let f = setup_open_file_with_contents(io_here_doc.as_str())?;
 
             params.open_files.set_fd(fd_num, f);
         }
 
-        ast::IoRedirect::HereString(fd_num, word) => {
+        ast::IoRedirect::HereString(fd_num, word, _) => {
             // If not specified, default to stdin (fd 0).
             let fd_num = fd_num.unwrap_or(0);
 
             let mut expanded_word = expansion::basic_expand_word(shell, params, word).await?;
             expanded_word.push('\n');
 
+            if let Some(dry_run) = &mut params.dry_run {
+                dry_run.record_redirect(dryrun::DryRunRedirect {
+                    fd: fd_num,
+                    target: dryrun::DryRunRedirectTarget::HereDocument(expanded_word.clone()),
+                    mode: dryrun::DryRunRedirectMode::Read,
+                });
+            }
+
             let f = setup_open_file_with_contents(expanded_word.as_str())?;
 
             params.open_files.set_fd(fd_num, f);
@@ -1653,12 +1950,52 @@ const fn get_default_fd_for_redirect_kind(kind: &ast::IoFileRedirectKind) -> She
     }
 }
 
+const fn dry_run_redirect_mode(kind: &ast::IoFileRedirectKind) -> dryrun::DryRunRedirectMode {
+    match kind {
+        ast::IoFileRedirectKind::Read | ast::IoFileRedirectKind::DuplicateInput => {
+            dryrun::DryRunRedirectMode::Read
+        }
+        ast::IoFileRedirectKind::Write
+        | ast::IoFileRedirectKind::Clobber
+        | ast::IoFileRedirectKind::DuplicateOutput => dryrun::DryRunRedirectMode::Write,
+        ast::IoFileRedirectKind::Append => dryrun::DryRunRedirectMode::Append,
+        ast::IoFileRedirectKind::ReadAndWrite => dryrun::DryRunRedirectMode::ReadWrite,
+    }
+}
+
 fn setup_process_substitution(
     shell: &Shell,
     params: &ExecutionParameters,
     kind: &ast::ProcessSubstitutionKind,
     subshell_cmd: &ast::SubshellCommand,
 ) -> Result<(ShellFd, OpenFile), error::Error> {
+    let target_file = if let Some(dry_run) = &params.dry_run {
+        // In dry-run mode, the substitution is stubbed out with a null file.
+        dry_run.record_substitution(subshell_cmd.list.to_string());
+        openfiles::null()?
+    } else {
+        spawn_process_substitution(shell, params, kind, subshell_cmd)?
+    };
+
+    // Starting at 63 (a.k.a. 64-1)--and decrementing--look for an
+    // available fd.
+    let mut candidate_fd_num = 63;
+    while params.open_files.contains_fd(candidate_fd_num) {
+        candidate_fd_num -= 1;
+        if candidate_fd_num == 0 {
+            return error::unimp("no available file descriptors");
+        }
+    }
+
+    Ok((candidate_fd_num, target_file))
+}
+
+fn spawn_process_substitution(
+    shell: &Shell,
+    params: &ExecutionParameters,
+    kind: &ast::ProcessSubstitutionKind,
+    subshell_cmd: &ast::SubshellCommand,
+) -> Result<OpenFile, error::Error> {
     // TODO: Don't execute synchronously!
     // Execute in a subshell.
     let mut subshell = shell.clone();
@@ -1693,34 +2030,78 @@ fn setup_process_substitution(
             .await;
     });
 
-    // Starting at 63 (a.k.a. 64-1)--and decrementing--look for an
-    // available fd.
-    let mut candidate_fd_num = 63;
-    while params.open_files.contains_fd(candidate_fd_num) {
-        candidate_fd_num -= 1;
-        if candidate_fd_num == 0 {
-            return error::unimp("no available file descriptors");
-        }
-    }
-
-    Ok((candidate_fd_num, target_file))
+    Ok(target_file)
 }
 
+/// Size (in bytes) of here-document content that we're comfortable writing
+/// synchronously into a freshly created pipe. This is chosen to fit within the
+/// smallest pipe buffer we expect to encounter on any supported Unix target, so
+/// the write can never block waiting on a reader.
+const MAX_SYNCHRONOUS_PIPE_WRITE_LEN: usize = 4096;
+
+/// Returns the read end of a pipe that will yield the given contents.
+///
+/// Small contents are written directly into the pipe. Larger contents are
+/// streamed from a dedicated writer thread so that arbitrarily large
+/// here-documents don't depend on (or exceed) the kernel's pipe capacity;
+/// the writer exits once all contents are written or the read end has been
+/// closed by all of its holders.
 fn setup_open_file_with_contents(contents: &str) -> Result<OpenFile, error::Error> {
     let (reader, mut writer) = std::io::pipe()?;
 
-    let bytes = contents.as_bytes();
+    if contents.len() <= MAX_SYNCHRONOUS_PIPE_WRITE_LEN {
+        writer.write_all(contents.as_bytes())?;
+        drop(writer);
+    } else {
+        let bytes = contents.as_bytes().to_vec();
+        std::thread::Builder::new()
+            .name(String::from("here-doc-writer"))
+            .spawn(move || {
+                // A failure here means the reader went away before consuming
+                // everything (e.g., `head -1 <<EOF`); that's not an error.
+                let _ = writer.write_all(&bytes);
+            })?;
+    }
 
-    #[cfg(target_os = "linux")]
-    {
-        use std::os::fd::AsFd as _;
+    Ok(reader.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use std::io::Read;
 
-        let len = i32::try_from(bytes.len())?;
-        nix::fcntl::fcntl(reader.as_fd(), nix::fcntl::FcntlArg::F_SETPIPE_SZ(len))?;
+    use super::*;
+
+    fn read_all(file: OpenFile) -> String {
+        let OpenFile::PipeReader(mut reader) = file else {
+            panic!("expected pipe reader");
+        };
+
+        let mut result = String::new();
+        reader.read_to_string(&mut result).unwrap();
+        result
     }
 
-    writer.write_all(bytes)?;
-    drop(writer);
+    #[test]
+    fn small_contents_from_pipe() {
+        let contents = "hello\nworld\n";
+        let file = setup_open_file_with_contents(contents).unwrap();
+        assert_eq!(read_all(file), contents);
+    }
 
-    Ok(reader.into())
+    #[test]
+    fn contents_larger_than_pipe_capacity() {
+        let contents = "0123456789abcdef\n".repeat(256 * 1024);
+        assert!(contents.len() > 4 * 1024 * 1024);
+
+        let file = setup_open_file_with_contents(contents.as_str()).unwrap();
+        assert_eq!(read_all(file), contents);
+    }
+
+    #[test]
+    fn reader_dropped_before_contents_consumed() {
+        let contents = "x".repeat(1024 * 1024);
+        let file = setup_open_file_with_contents(contents.as_str()).unwrap();
+        drop(file);
+    }
 }
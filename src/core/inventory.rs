@@ -0,0 +1,891 @@
+//! Static inventory of the commands a program may invoke.
+//!
+//! The inventory is computed from a parsed [`ast::Program`] without running it: the name of
+//! each simple command (including those in functions, command and process substitutions and
+//! the expansions of aliases) is resolved against the current state of a [`Shell`] the same
+//! way the interpreter would resolve it (alias, function, builtin, then `PATH`). Commands run
+//! indirectly through well-known wrappers (`command`, `builtin`, `exec`, `env`, `sudo`,
+//! `xargs`, `nice`, `nohup`, `timeout` and `find -exec`) are resolved too, as are the commands
+//! in literal code run by `eval`, `trap` and `sh -c` (or `bash -c`, etc.). Command names and
+//! code that can't be known without expanding them (e.g. `$cmd` or `eval "$code"`), and
+//! scripts run by `source`, `sh script` or `curl ... | sh`, are reported as
+//! [`CommandKind::Dynamic`].
+
+use std::collections::HashSet;
+use std::ops::Range;
+use std::path::{Path, PathBuf};
+
+use crate::core::Shell;
+use crate::core::escape;
+use crate::core::sys::fs::PathExt;
+use crate::parser::ast::{self, SourceLocation};
+use crate::parser::visit::{self, Visit};
+use crate::parser::word::{self, WordPiece, WordPieceWithSource};
+use crate::parser::{Parser, ParserOptions, SourceInfo, TokenLocation};
+
+/// How the name of an invoked command resolves.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum CommandKind {
+    /// The name is an alias; holds the alias's value. The commands in the value are
+    /// inventoried separately.
+    Alias(String),
+    /// The name is a function, either already defined in the shell or defined by the program.
+    Function,
+    /// The name is an enabled builtin.
+    Builtin,
+    /// The name is an executable file; holds its path.
+    External(PathBuf),
+    /// The name is literal, but doesn't resolve to anything.
+    NotFound,
+    /// The command depends on expansions (e.g. `$cmd`) or on the contents of a file (e.g.
+    /// `source ./lib.sh`), so can't be resolved statically.
+    Dynamic,
+}
+
+/// A command a program may invoke.
+#[derive(Clone, Debug)]
+pub struct InvokedCommand {
+    /// The name of the command: unquoted for literal names, or as written for dynamic ones.
+    /// For code that can't be resolved (e.g. `eval "$code"`), holds the code or the name of
+    /// the file holding it (`-` for standard input).
+    pub name: String,
+    /// How the name resolves.
+    pub kind: CommandKind,
+    /// The wrappers (e.g. `sudo`, `xargs`) and aliases through which the command is invoked,
+    /// outermost first.
+    pub via: Vec<String>,
+    /// The location of the invoking command in the program's source. Commands in command
+    /// substitutions and alias values are attributed to the command containing them.
+    pub loc: Option<TokenLocation>,
+}
+
+/// The commands a program may invoke, in the order they appear in the program.
+#[derive(Clone, Debug, Default)]
+pub struct CommandInventory {
+    /// Each invocation site of a command.
+    pub commands: Vec<InvokedCommand>,
+}
+
+impl CommandInventory {
+    /// Returns the distinct paths of the external executables that may be invoked.
+    pub fn external_paths(&self) -> Vec<&Path> {
+        let mut paths = indexmap::IndexSet::new();
+        for command in &self.commands {
+            if let CommandKind::External(path) = &command.kind {
+                paths.insert(path.as_path());
+            }
+        }
+        paths.into_iter().collect()
+    }
+
+    /// Returns the invocations of commands whose names couldn't be resolved, either because
+    /// they are dynamic or because nothing by that name was found.
+    pub fn unresolved(&self) -> impl Iterator<Item = &InvokedCommand> {
+        self.commands
+            .iter()
+            .filter(|c| matches!(c.kind, CommandKind::Dynamic | CommandKind::NotFound))
+    }
+
+    /// Returns whether any command name depends on expansions.
+    pub fn has_dynamic(&self) -> bool {
+        self.commands
+            .iter()
+            .any(|c| matches!(c.kind, CommandKind::Dynamic))
+    }
+}
+
+impl Shell {
+    /// Computes the inventory of the commands a program may invoke, resolving their names
+    /// against the current state of the shell.
+    ///
+    /// # Arguments
+    ///
+    /// * `program` - The program to inspect.
+    pub fn command_inventory(&self, program: &ast::Program) -> CommandInventory {
+        let mut functions = FunctionNames::default();
+        functions.visit_program(program);
+
+        let mut collector = InventoryCollector {
+            shell: self,
+            parser_options: self.parser_options(),
+            script_functions: functions.0,
+            expanding_aliases: vec![],
+            enclosing: vec![],
+            nesting: 0,
+            inventory: CommandInventory::default(),
+        };
+        collector.visit_program(program);
+
+        collector.inventory
+    }
+}
+
+/// Collects the names of the functions defined anywhere in a program.
+#[derive(Default)]
+struct FunctionNames(HashSet<String>);
+
+impl Visit for FunctionNames {
+    fn visit_function_definition(&mut self, node: &ast::FunctionDefinition) {
+        self.0.insert(node.fname.value.clone());
+        visit::walk_function_definition(self, node);
+    }
+}
+
+/// The kinds of commands a name may resolve to in a given context.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Lookup {
+    /// Aliases, functions, builtins, then `PATH`.
+    Default,
+    /// Builtins, then `PATH` (as with `command`).
+    NoFunctions,
+    /// Builtins only (as with `builtin`).
+    BuiltinOnly,
+    /// `PATH` only (as with `exec` and external wrappers).
+    PathOnly,
+}
+
+/// An argument of a simple command.
+enum Arg {
+    /// A word without expansions; holds its unquoted value.
+    Literal(String),
+    /// A word with expansions; holds it as written.
+    Dynamic(String),
+}
+
+impl Arg {
+    fn literal(&self) -> Option<&str> {
+        match self {
+            Self::Literal(s) => Some(s.as_str()),
+            Self::Dynamic(_) => None,
+        }
+    }
+
+    fn text(&self) -> &str {
+        match self {
+            Self::Literal(s) | Self::Dynamic(s) => s.as_str(),
+        }
+    }
+}
+
+/// Shell code run by a command.
+enum Payload {
+    /// Code given by a range of arguments, joined with spaces (as with `eval` and `sh -c`).
+    Code(Range<usize>),
+    /// Code read from the file named by an argument (as with `source`).
+    File(usize),
+    /// Code read from standard input (as with `curl ... | sh`).
+    Stdin,
+}
+
+struct InventoryCollector<'a> {
+    shell: &'a Shell,
+    parser_options: ParserOptions,
+    script_functions: HashSet<String>,
+    /// The aliases whose values are being inventoried, to avoid recursing into them.
+    expanding_aliases: Vec<String>,
+    /// The locations of the commands enclosing the one being visited.
+    enclosing: Vec<Option<TokenLocation>>,
+    /// How many command substitutions or alias values deep the visitor is.
+    nesting: usize,
+    inventory: CommandInventory,
+}
+
+impl Visit for InventoryCollector<'_> {
+    fn parser_options(&self) -> &ParserOptions {
+        &self.parser_options
+    }
+
+    fn visit_command(&mut self, node: &ast::Command) {
+        if self.nesting == 0 {
+            self.enclosing.push(node.location());
+            visit::walk_command(self, node);
+            self.enclosing.pop();
+        } else {
+            visit::walk_command(self, node);
+        }
+    }
+
+    fn visit_simple_command(&mut self, node: &ast::SimpleCommand) {
+        if let Some(name) = &node.word_or_name {
+            let loc = if self.nesting == 0 {
+                node.location()
+            } else {
+                self.enclosing.last().cloned().flatten()
+            };
+
+            let mut args = vec![self.arg(name)];
+            if let Some(suffix) = &node.suffix {
+                for item in &suffix.0 {
+                    match item {
+                        ast::CommandPrefixOrSuffixItem::Word(word)
+                        | ast::CommandPrefixOrSuffixItem::AssignmentWord(_, word) => {
+                            args.push(self.arg(word));
+                        }
+                        ast::CommandPrefixOrSuffixItem::IoRedirect(_)
+                        | ast::CommandPrefixOrSuffixItem::ProcessSubstitution(..) => {}
+                    }
+                }
+            }
+
+            self.invocation(&args, Lookup::Default, &[], loc.as_ref());
+        }
+
+        visit::walk_simple_command(self, node);
+    }
+
+    fn visit_command_substitution(&mut self, text: &str) {
+        self.nesting += 1;
+        visit::walk_command_substitution(self, text);
+        self.nesting -= 1;
+    }
+}
+
+impl InventoryCollector<'_> {
+    fn arg(&self, word: &ast::Word) -> Arg {
+        word::parse(&word.value, &self.parser_options)
+            .ok()
+            .and_then(|pieces| literal_value(&pieces, false))
+            .map_or_else(|| Arg::Dynamic(word.value.clone()), Arg::Literal)
+    }
+
+    /// Records the invocation of the command in `args[0]`, along with the commands it runs in
+    /// turn if it's a known wrapper.
+    fn invocation(
+        &mut self,
+        args: &[Arg],
+        lookup: Lookup,
+        via: &[String],
+        loc: Option<&TokenLocation>,
+    ) {
+        let Some(first) = args.first() else {
+            return;
+        };
+
+        let name = match first {
+            Arg::Literal(name) => name.as_str(),
+            Arg::Dynamic(name) => {
+                self.record(name.clone(), CommandKind::Dynamic, via, loc);
+                return;
+            }
+        };
+
+        let kind = self.resolve(name, lookup);
+        self.record(name.to_owned(), kind.clone(), via, loc);
+
+        let mut via = via.to_vec();
+        via.push(name.to_owned());
+
+        match kind {
+            CommandKind::Alias(value) => self.alias_value(name, &value, &via, loc),
+            CommandKind::Builtin | CommandKind::External(_) | CommandKind::NotFound => {
+                for (range, lookup) in wrapped_commands(name, &args[1..]) {
+                    let range = (range.start + 1)..(range.end + 1);
+                    self.invocation(&args[range], lookup, &via, loc);
+                }
+                if let Some(payload) = payload(name, &args[1..]) {
+                    self.payload(payload, &args[1..], &via, loc);
+                }
+            }
+            CommandKind::Function | CommandKind::Dynamic => {}
+        }
+    }
+
+    fn record(
+        &mut self,
+        name: String,
+        kind: CommandKind,
+        via: &[String],
+        loc: Option<&TokenLocation>,
+    ) {
+        self.inventory.commands.push(InvokedCommand {
+            name,
+            kind,
+            via: via.to_vec(),
+            loc: loc.cloned(),
+        });
+    }
+
+    fn alias_value(
+        &mut self,
+        name: &str,
+        value: &str,
+        via: &[String],
+        loc: Option<&TokenLocation>,
+    ) {
+        if self.expanding_aliases.iter().any(|a| a == name) {
+            return;
+        }
+
+        self.expanding_aliases.push(name.to_owned());
+        self.code(value, via, loc);
+        self.expanding_aliases.pop();
+    }
+
+    /// Records the commands in code run by a command, or the code itself if they can't be
+    /// known statically.
+    fn payload(
+        &mut self,
+        payload: Payload,
+        args: &[Arg],
+        via: &[String],
+        loc: Option<&TokenLocation>,
+    ) {
+        match payload {
+            Payload::Code(range) => {
+                let code: Option<Vec<_>> = args[range.clone()].iter().map(Arg::literal).collect();
+                if code.is_none_or(|code| !self.code(&code.join(" "), via, loc)) {
+                    let text = args[range].iter().map(Arg::text).collect::<Vec<_>>();
+                    self.record(text.join(" "), CommandKind::Dynamic, via, loc);
+                }
+            }
+            Payload::File(index) => {
+                self.record(
+                    args[index].text().to_owned(),
+                    CommandKind::Dynamic,
+                    via,
+                    loc,
+                );
+            }
+            Payload::Stdin => self.record("-".into(), CommandKind::Dynamic, via, loc),
+        }
+    }
+
+    /// Records the commands in the given code, attributing them to the command running it.
+    /// Returns whether the code could be parsed.
+    fn code(&mut self, code: &str, via: &[String], loc: Option<&TokenLocation>) -> bool {
+        let mut parser = Parser::new(
+            code.as_bytes(),
+            &self.parser_options,
+            &SourceInfo::default(),
+        );
+        let Ok(program) = parser.parse_program() else {
+            return false;
+        };
+
+        let first = self.inventory.commands.len();
+        self.enclosing.push(loc.cloned());
+        self.nesting += 1;
+        self.visit_program(&program);
+        self.nesting -= 1;
+        self.enclosing.pop();
+
+        for command in &mut self.inventory.commands[first..] {
+            command.via.splice(0..0, via.iter().cloned());
+        }
+
+        true
+    }
+
+    fn resolve(&self, name: &str, lookup: Lookup) -> CommandKind {
+        if lookup == Lookup::Default {
+            if self.shell.options.expand_aliases
+                && let Some(value) = self.shell.aliases.get(name)
+            {
+                return CommandKind::Alias(value.clone());
+            }
+
+            if self.script_functions.contains(name) || self.shell.funcs().get(name).is_some() {
+                return CommandKind::Function;
+            }
+        }
+
+        if lookup != Lookup::PathOnly
+            && self.shell.builtins().get(name).is_some_and(|b| !b.disabled)
+        {
+            return CommandKind::Builtin;
+        }
+
+        if lookup == Lookup::BuiltinOnly {
+            return CommandKind::NotFound;
+        }
+
+        if name.contains(std::path::MAIN_SEPARATOR) {
+            if self.shell.absolute_path(Path::new(name)).executable() {
+                CommandKind::External(PathBuf::from(name))
+            } else {
+                CommandKind::NotFound
+            }
+        } else if let Some(path) = self.shell.program_location_cache.get(name) {
+            CommandKind::External(path)
+        } else if let Some(path) = self.shell.find_first_executable_in_path(name) {
+            CommandKind::External(path)
+        } else {
+            CommandKind::NotFound
+        }
+    }
+}
+
+/// Returns the unquoted value of a word's pieces, or `None` if they contain expansions.
+///
+/// # Arguments
+///
+/// * `pieces` - The pieces of the word.
+/// * `in_double_quotes` - Whether the pieces are enclosed in double quotes.
+fn literal_value(pieces: &[WordPieceWithSource], in_double_quotes: bool) -> Option<String> {
+    let mut value = String::new();
+    for piece in pieces {
+        match &piece.piece {
+            WordPiece::Text(s) | WordPiece::SingleQuotedText(s) => value.push_str(s),
+            WordPiece::AnsiCQuotedText(s) => {
+                let (expanded, _) =
+                    escape::expand_backslash_escapes(s, escape::EscapeExpansionMode::AnsiCQuotes)
+                        .ok()?;
+                value.push_str(&String::from_utf8_lossy(&expanded));
+            }
+            WordPiece::EscapeSequence(s) => {
+                let escaped = s.strip_prefix('\\').unwrap_or(s);
+                if in_double_quotes && !escaped.starts_with(['$', '`', '"', '\\', '\n']) {
+                    value.push_str(s);
+                } else if escaped != "\n" {
+                    value.push_str(escaped);
+                }
+            }
+            WordPiece::DoubleQuotedSequence(pieces)
+            | WordPiece::GettextDoubleQuotedSequence(pieces) => {
+                value.push_str(&literal_value(pieces, true)?);
+            }
+            WordPiece::TildePrefix(_)
+            | WordPiece::ParameterExpansion(_)
+            | WordPiece::CommandSubstitution(_)
+            | WordPiece::BackquotedCommandSubstitution(_)
+            | WordPiece::ArithmeticExpression(_) => return None,
+        }
+    }
+    Some(value)
+}
+
+/// Returns the ranges of a wrapper's arguments that form the commands it runs, along with how
+/// their names are looked up.
+///
+/// # Arguments
+///
+/// * `name` - The name of the (possible) wrapper.
+/// * `args` - The arguments passed to it.
+fn wrapped_commands(name: &str, args: &[Arg]) -> Vec<(Range<usize>, Lookup)> {
+    let whole = |start: Option<usize>, lookup| {
+        start
+            .filter(|start| *start < args.len())
+            .map(|start| (start..args.len(), lookup))
+            .into_iter()
+            .collect()
+    };
+
+    match name {
+        "command" => {
+            // `command -v` and `command -V` only describe the command.
+            if args[..skip_options(args, &[], false).unwrap_or(args.len())]
+                .iter()
+                .any(|a| {
+                    a.literal()
+                        .is_some_and(|a| a.starts_with('-') && a.contains(['v', 'V']))
+                })
+            {
+                vec![]
+            } else {
+                whole(skip_options(args, &[], false), Lookup::NoFunctions)
+            }
+        }
+        "builtin" => whole(skip_options(args, &[], false), Lookup::BuiltinOnly),
+        "exec" => whole(skip_options(args, &["-a"], false), Lookup::PathOnly),
+        "nohup" => whole(skip_options(args, &[], false), Lookup::PathOnly),
+        "nice" => whole(
+            skip_options(args, &["-n", "--adjustment"], false),
+            Lookup::PathOnly,
+        ),
+        "timeout" => {
+            // The duration precedes the command.
+            let duration = skip_options(args, &["-k", "-s", "--kill-after", "--signal"], false);
+            whole(duration.map(|i| i + 1), Lookup::PathOnly)
+        }
+        "env" => whole(
+            skip_options(
+                args,
+                &["-u", "-C", "-S", "--unset", "--chdir", "--split-string"],
+                true,
+            ),
+            Lookup::PathOnly,
+        ),
+        "sudo" | "doas" => {
+            const WITH_VALUES: &[&str] = &[
+                "-u",
+                "-g",
+                "-C",
+                "-D",
+                "-h",
+                "-p",
+                "-r",
+                "-t",
+                "-U",
+                "-T",
+                "-R",
+                "--user",
+                "--group",
+                "--close-from",
+                "--chdir",
+                "--host",
+                "--prompt",
+                "--role",
+                "--type",
+                "--other-user",
+                "--command-timeout",
+                "--chroot",
+            ];
+            whole(skip_options(args, WITH_VALUES, true), Lookup::PathOnly)
+        }
+        "xargs" => {
+            const WITH_VALUES: &[&str] = &[
+                "-a",
+                "-d",
+                "-E",
+                "-I",
+                "-L",
+                "-n",
+                "-P",
+                "-s",
+                "--arg-file",
+                "--delimiter",
+                "--max-lines",
+                "--max-args",
+                "--max-procs",
+                "--max-chars",
+                "--process-slot-var",
+            ];
+            whole(skip_options(args, WITH_VALUES, false), Lookup::PathOnly)
+        }
+        "find" => {
+            let mut commands = vec![];
+            let mut i = 0;
+            while i < args.len() {
+                if matches!(
+                    args[i].literal(),
+                    Some("-exec" | "-execdir" | "-ok" | "-okdir")
+                ) {
+                    let start = i + 1;
+                    let end = args[start..]
+                        .iter()
+                        .position(|a| matches!(a.literal(), Some(";" | "+")))
+                        .map_or(args.len(), |offset| start + offset);
+                    if start < end {
+                        commands.push((start..end, Lookup::PathOnly));
+                    }
+                    i = end;
+                }
+                i += 1;
+            }
+            commands
+        }
+        _ => vec![],
+    }
+}
+
+/// Returns the code a command runs, if it's known to run code given to it.
+///
+/// # Arguments
+///
+/// * `name` - The name of the command.
+/// * `args` - The arguments passed to it.
+fn payload(name: &str, args: &[Arg]) -> Option<Payload> {
+    match name {
+        "eval" => {
+            let start = usize::from(args.first().is_some_and(|a| a.literal() == Some("--")));
+            (start < args.len()).then_some(Payload::Code(start..args.len()))
+        }
+        "source" | "." => skip_options(args, &["-p"], false).map(Payload::File),
+        "trap" => {
+            // An action of `-`, or a lone signal, resets the signals instead.
+            let action = skip_options(args, &[], false)?;
+            (action + 1 < args.len() && args[action].literal() != Some("-"))
+                .then(|| Payload::Code(action..action + 1))
+        }
+        _ if Path::new(name)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .is_some_and(|name| SHELLS.contains(&name)) =>
+        {
+            shell_payload(args)
+        }
+        _ => None,
+    }
+}
+
+/// Shells whose command strings and script operands are inventoried.
+const SHELLS: &[&str] = &["sh", "bash", "dash", "ksh", "zsh"];
+
+/// Returns the code a shell runs given the arguments passed to it: the command string with
+/// `-c`, or else standard input with `-s` or without operands, or else the script named by its
+/// first operand. Dynamic arguments are conservatively treated as the first operand.
+///
+/// # Arguments
+///
+/// * `args` - The arguments passed to the shell.
+fn shell_payload(args: &[Arg]) -> Option<Payload> {
+    let mut command_string = false;
+    let mut read_stdin = false;
+    let mut i = 0;
+    while let Some(arg) = args.get(i).and_then(Arg::literal) {
+        if arg == "--" || arg == "-" {
+            i += 1;
+            break;
+        } else if arg.starts_with("--") {
+            if matches!(arg, "--rcfile" | "--init-file") {
+                i += 1;
+            }
+        } else if let Some(flags) = arg.strip_prefix(['-', '+'])
+            && !flags.is_empty()
+        {
+            command_string |= arg.starts_with('-') && flags.contains('c');
+            read_stdin |= arg.starts_with('-') && flags.contains('s');
+            // `-o` and `-O` take the name of an option.
+            if flags.ends_with(['o', 'O']) {
+                i += 1;
+            }
+        } else {
+            break;
+        }
+
+        i += 1;
+    }
+
+    Some(if command_string && i < args.len() {
+        Payload::Code(i..(i + 1))
+    } else if read_stdin || i >= args.len() {
+        Payload::Stdin
+    } else {
+        Payload::File(i)
+    })
+}
+
+/// Returns the index of the first argument that isn't an option (or, optionally, a variable
+/// assignment), or `None` if there is no such argument. Dynamic arguments are conservatively
+/// treated as the start of the command.
+///
+/// # Arguments
+///
+/// * `args` - The arguments to inspect.
+/// * `with_values` - The options that take a value in the following argument.
+/// * `skip_assignments` - Whether to skip `NAME=VALUE` arguments.
+fn skip_options(args: &[Arg], with_values: &[&str], skip_assignments: bool) -> Option<usize> {
+    let mut i = 0;
+    while i < args.len() {
+        let Some(arg) = args[i].literal() else {
+            return Some(i);
+        };
+
+        if arg == "--" {
+            return Some(i + 1);
+        } else if arg.starts_with("--") {
+            if !arg.contains('=') && with_values.contains(&arg) {
+                i += 1;
+            }
+        } else if let Some(flags) = arg.strip_prefix('-')
+            && !flags.is_empty()
+        {
+            // Only the last of a group of short options can take a separate value.
+            if let Some(pos) = flags
+                .char_indices()
+                .find(|(_, c)| with_values.contains(&std::format!("-{c}").as_str()))
+                .map(|(pos, c)| pos + c.len_utf8())
+                && pos == flags.len()
+            {
+                i += 1;
+            }
+        } else if !(skip_assignments && is_assignment(arg)) {
+            return Some(i);
+        }
+
+        i += 1;
+    }
+
+    None
+}
+
+fn is_assignment(arg: &str) -> bool {
+    arg.split_once('=').is_some_and(|(name, _)| {
+        !name.is_empty()
+            && !name.starts_with(|c: char| c.is_ascii_digit())
+            && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn summarize(shell: &Shell, script: &str) -> Result<Vec<String>> {
+        let mut parser = Parser::new(
+            script.as_bytes(),
+            &shell.parser_options(),
+            &SourceInfo::default(),
+        );
+        let program = parser.parse_program()?;
+
+        Ok(shell
+            .command_inventory(&program)
+            .commands
+            .into_iter()
+            .map(|c| {
+                let kind = match c.kind {
+                    CommandKind::Alias(value) => std::format!("alias({value})"),
+                    CommandKind::Function => "function".into(),
+                    CommandKind::Builtin => "builtin".into(),
+                    CommandKind::External(path) => std::format!("external({})", path.display()),
+                    CommandKind::NotFound => "not-found".into(),
+                    CommandKind::Dynamic => "dynamic".into(),
+                };
+                let loc = c
+                    .loc
+                    .map(|loc| std::format!("{}:{}", loc.start.line, loc.start.column))
+                    .unwrap_or_default();
+                let via = c
+                    .via
+                    .iter()
+                    .map(|v| std::format!("{v} > "))
+                    .collect::<String>();
+                std::format!("{loc} {via}{} {kind}", c.name)
+            })
+            .collect())
+    }
+
+    /// Creates a directory holding empty executables with the given names.
+    #[cfg(unix)]
+    fn bin_dir(test_name: &str, names: &[&str]) -> Result<PathBuf> {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir().join(std::format!(
+            "brush-inventory-{test_name}-{}",
+            std::process::id()
+        ));
+        std::fs::create_dir_all(&dir)?;
+        for name in names {
+            let path = dir.join(name);
+            std::fs::write(&path, "")?;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755))?;
+        }
+        Ok(dir)
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn inventory_resolves_names() -> Result<()> {
+        let bin = bin_dir(
+            "resolve",
+            &["ls", "grep", "rm", "sudo", "env", "find", "xargs"],
+        )?;
+        let b = bin.display();
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        shell
+            .exec(
+                &std::format!("PATH={b}; defined() {{ :; }}; alias ll='ls -l | grep x'"),
+                &params,
+            )
+            .await?;
+        shell.options.expand_aliases = true;
+
+        let script = r#"f() { grep "$1" "$2"; }
+ls $(defined; missing) | f
+"$cmd" --version; ll
+sudo -u root env A=1 rm -rf "$dir"
+find . -name '*.o' -exec rm {} \; -execdir "$tool" {} +
+command -v grep; command ls; builtin echo; xargs -n1
+"#;
+        let summary = summarize(&shell, script);
+        std::fs::remove_dir_all(&bin)?;
+
+        assert_eq!(
+            summary?,
+            [
+                std::format!("1:7 grep external({b}/grep)"),
+                std::format!("2:1 ls external({b}/ls)"),
+                "2:1 defined function".into(),
+                "2:1 missing not-found".into(),
+                "2:26 f function".into(),
+                "3:1 \"$cmd\" dynamic".into(),
+                "3:19 ll alias(ls -l | grep x)".into(),
+                std::format!("3:19 ll > ls external({b}/ls)"),
+                std::format!("3:19 ll > grep external({b}/grep)"),
+                std::format!("4:1 sudo external({b}/sudo)"),
+                std::format!("4:1 sudo > env external({b}/env)"),
+                std::format!("4:1 sudo > env > rm external({b}/rm)"),
+                std::format!("5:1 find external({b}/find)"),
+                std::format!("5:1 find > rm external({b}/rm)"),
+                "5:1 find > \"$tool\" dynamic".into(),
+                "6:1 command builtin".into(),
+                "6:18 command builtin".into(),
+                std::format!("6:18 command > ls external({b}/ls)"),
+                "6:30 builtin builtin".into(),
+                "6:30 builtin > echo builtin".into(),
+                std::format!("6:44 xargs external({b}/xargs)"),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn inventory_resolves_code() -> Result<()> {
+        let bin = bin_dir("code", &["rm", "curl", "sh", "bash", "env", "sudo"])?;
+        let b = bin.display();
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        shell.exec(&std::format!("PATH={b}"), &params).await?;
+
+        let script = r#"eval 'rm -rf /'; eval "$code"
+bash -c 'curl example.com | sh -s x'; sudo sh -ec "$cmd"
+env bash -o pipefail ./install.sh; source ./lib.sh
+trap 'rm -f "$tmp"' EXIT; trap - EXIT; trap -p EXIT
+"#;
+        let summary = summarize(&shell, script);
+        std::fs::remove_dir_all(&bin)?;
+
+        assert_eq!(
+            summary?,
+            [
+                "1:1 eval builtin".into(),
+                std::format!("1:1 eval > rm external({b}/rm)"),
+                "1:18 eval builtin".into(),
+                "1:18 eval > \"$code\" dynamic".into(),
+                std::format!("2:1 bash external({b}/bash)"),
+                std::format!("2:1 bash > curl external({b}/curl)"),
+                std::format!("2:1 bash > sh external({b}/sh)"),
+                "2:1 bash > sh > - dynamic".into(),
+                std::format!("2:39 sudo external({b}/sudo)"),
+                std::format!("2:39 sudo > sh external({b}/sh)"),
+                "2:39 sudo > sh > \"$cmd\" dynamic".into(),
+                std::format!("3:1 env external({b}/env)"),
+                std::format!("3:1 env > bash external({b}/bash)"),
+                "3:1 env > bash > ./install.sh dynamic".into(),
+                "3:36 source builtin".into(),
+                "3:36 source > ./lib.sh dynamic".into(),
+                "4:1 trap builtin".into(),
+                std::format!("4:1 trap > rm external({b}/rm)"),
+                "4:27 trap builtin".into(),
+                "4:40 trap builtin".into(),
+            ]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn literal_values() -> Result<()> {
+        let options = ParserOptions::default();
+        let literal = |s: &str| -> Result<Option<String>> {
+            Ok(literal_value(&word::parse(s, &options)?, false))
+        };
+
+        assert_eq!(literal(r#"'l's"#)?, Some("ls".into()));
+        assert_eq!(literal(r#"\g"r"e\p"#)?, Some("grep".into()));
+        assert_eq!(literal(r#""a\b\$""#)?, Some(r"a\b$".into()));
+        assert_eq!(literal(r"$'\x6cs'")?, Some("ls".into()));
+        assert_eq!(literal(r#""${cmd}""#)?, None);
+        assert_eq!(literal("~/bin/tool")?, None);
+
+        Ok(())
+    }
+}
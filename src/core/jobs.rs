@@ -87,16 +87,17 @@ impl JobManager {
     }
 
     /// Adds a job to the job manager and marks it as the current job;
-    /// returns an immutable reference to the job.
+    /// returns a mutable reference to the job.
     ///
     /// # Arguments
     ///
     /// * `job` - The job to add.
-    pub fn add_as_current(&mut self, mut job: Job) -> &Job {
+    pub fn add_as_current(&mut self, mut job: Job) -> &mut Job {
         for j in &mut self.jobs {
-            if matches!(j.annotation, JobAnnotation::Current) {
-                j.annotation = JobAnnotation::Previous;
-                break;
+            match j.annotation {
+                JobAnnotation::Current => j.annotation = JobAnnotation::Previous,
+                JobAnnotation::Previous => j.annotation = JobAnnotation::None,
+                JobAnnotation::None => (),
             }
         }
 
@@ -104,7 +105,7 @@ impl JobManager {
         job.id = id;
         job.annotation = JobAnnotation::Current;
         self.jobs.push(job);
-        self.jobs.last().unwrap()
+        self.jobs.last_mut().unwrap()
     }
 
     /// Returns the current job, if there is one.
@@ -144,15 +145,30 @@ impl JobManager {
         let remainder = job_spec.strip_prefix('%')?;
 
         match remainder {
-            "%" | "+" => self.current_job_mut(),
+            "" | "%" | "+" => self.current_job_mut(),
             "-" => self.prev_job_mut(),
             s if s.chars().all(char::is_numeric) => {
                 let id = s.parse::<usize>().ok()?;
                 self.jobs.iter_mut().find(|j| j.id == id)
             }
-            _ => {
-                tracing::warn!(target: trace_categories::UNIMPLEMENTED, "unimplemented: job spec naming command: '{job_spec}'");
-                None
+            s => {
+                // %?string refers to a job whose command line contains the string;
+                // %string refers to a job whose command line starts with it. Either
+                // form must identify exactly one job.
+                let mut matching = self.jobs.iter_mut().filter(|j| {
+                    s.strip_prefix('?').map_or_else(
+                        || j.command_line.starts_with(s),
+                        |substr| j.command_line.contains(substr),
+                    )
+                });
+
+                let job = matching.next()?;
+                if matching.next().is_some() {
+                    tracing::debug!(target: trace_categories::JOBS, "ambiguous job spec: '{job_spec}'");
+                    return None;
+                }
+
+                Some(job)
             }
         }
     }
@@ -204,7 +220,7 @@ impl JobManager {
 }
 
 /// Represents the current execution state of a job.
-#[derive(Clone)]
+#[derive(Clone, PartialEq, Eq)]
 pub enum JobState {
     /// Unknown state.
     Unknown,
@@ -248,11 +264,27 @@ impl Display for JobAnnotation {
     }
 }
 
+/// Describes one stage of the pipeline run by a job.
+#[derive(Clone)]
+pub struct JobProcess {
+    /// If available, the process ID of the stage.
+    pub pid: Option<sys::process::ProcessId>,
+
+    /// The command run by the stage.
+    pub command: String,
+}
+
 /// Encapsulates a set of processes managed by the shell as a single unit.
 pub struct Job {
     /// The tasks that make up the job.
     tasks: VecDeque<JobTask>,
 
+    /// The pipeline stages of the job, as they were when the job was created.
+    processes: Vec<JobProcess>,
+
+    /// The state of the job when it was last reported to the user, if ever.
+    last_notified_state: Option<JobState>,
+
     /// If available, the process group ID of the job's processes.
     pgid: Option<sys::process::ProcessId>,
 
@@ -269,15 +301,19 @@ pub struct Job {
     pub state: JobState,
 }
 
+/// Width of the column in which job states are displayed.
+const STATE_COLUMN_WIDTH: usize = 24;
+
 impl Display for Job {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         write!(
             f,
-            "[{}]{:3}{}\t{}",
+            "[{}]{:1}  {:<STATE_COLUMN_WIDTH$}{}{}",
             self.id,
             self.annotation.to_string(),
-            self.state,
-            self.command_line
+            self.state.to_string(),
+            self.command_line,
+            self.background_suffix()
         )
     }
 }
@@ -294,9 +330,23 @@ impl Job {
     where
         I: IntoIterator<Item = JobTask>,
     {
+        let tasks: VecDeque<_> = tasks.into_iter().collect();
+        let processes = tasks
+            .iter()
+            .map(|task| JobProcess {
+                pid: match task {
+                    JobTask::External(p) => p.pid(),
+                    JobTask::Internal(_) => None,
+                },
+                command: command_line.clone(),
+            })
+            .collect();
+
         Self {
             id: 0,
-            tasks: tasks.into_iter().collect(),
+            tasks,
+            processes,
+            last_notified_state: None,
             pgid: None,
             annotation: JobAnnotation::None,
             command_line,
@@ -304,6 +354,79 @@ impl Job {
         }
     }
 
+    /// Updates the commands recorded for the job's pipeline stages; returns the
+    /// updated job.
+    ///
+    /// # Arguments
+    ///
+    /// * `commands` - The commands run by each of the job's stages, in order.
+    pub(crate) fn with_process_commands<I>(mut self, commands: I) -> Self
+    where
+        I: IntoIterator<Item = String>,
+    {
+        for (process, command) in self.processes.iter_mut().zip(commands) {
+            process.command = command;
+        }
+        self
+    }
+
+    /// Returns a multi-line string describing each of the job's pipeline stages,
+    /// including their process IDs.
+    pub fn to_long_string(&self) -> String {
+        let mut result = String::new();
+
+        for (i, process) in self.processes.iter().enumerate() {
+            let pid = process.pid.map(|pid| pid.to_string()).unwrap_or_default();
+
+            if i == 0 {
+                result.push_str(
+                    std::format!(
+                        "[{}]{:1} {pid:>5} {:<STATE_COLUMN_WIDTH$}{}",
+                        self.id,
+                        self.annotation.to_string(),
+                        self.state.to_string(),
+                        process.command
+                    )
+                    .as_str(),
+                );
+            } else {
+                // All stages share the job's state, so it's not repeated.
+                result.push_str(
+                    std::format!(
+                        "\n     {pid:>5} {:<width$}| {}",
+                        "",
+                        process.command,
+                        width = STATE_COLUMN_WIDTH - 2
+                    )
+                    .as_str(),
+                );
+            }
+        }
+
+        result.push_str(self.background_suffix());
+        result
+    }
+
+    /// Returns whether the job's state has changed since it was last reported
+    /// to the user.
+    pub fn has_unreported_change(&self) -> bool {
+        self.last_notified_state.as_ref() != Some(&self.state)
+    }
+
+    /// Records that the job's current state has been reported to the user.
+    pub fn mark_reported(&mut self) {
+        self.last_notified_state = Some(self.state.clone());
+    }
+
+    const fn background_suffix(&self) -> &'static str {
+        // Only running jobs are still executing in the background.
+        if matches!(self.state, JobState::Running) {
+            " &"
+        } else {
+            ""
+        }
+    }
+
     /// Returns a pid-style string for the job.
     pub fn to_pid_style_string(&self) -> String {
         let display_pid = self
@@ -365,13 +488,14 @@ impl Job {
 
     /// Waits for the job to complete.
     pub async fn wait(&mut self) -> Result<ExecutionResult, error::Error> {
-        let mut result = ExecutionResult::success();
+        // The job's result is that of its last task (i.e., its pipeline's last stage).
+        let mut result = None;
 
         while let Some(task) = self.tasks.back_mut() {
             // Jobs don't have access to execution context, pass None for token
             match task.wait(None).await? {
                 JobTaskWaitResult::Completed(execution_result) => {
-                    result = execution_result;
+                    result.get_or_insert(execution_result);
                     self.tasks.pop_back();
                 }
                 JobTaskWaitResult::Stopped => {
@@ -383,7 +507,7 @@ impl Job {
 
         self.state = JobState::Done;
 
-        Ok(result)
+        Ok(result.unwrap_or_else(ExecutionResult::success))
     }
 
     /// Moves the job to execute in the background.
@@ -453,3 +577,114 @@ impl Job {
         self.pgid.or_else(|| self.representative_pid())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn new_job(command_line: &str, stage_count: usize) -> Job {
+        let tasks = (0..stage_count)
+            .map(|_| JobTask::Internal(tokio::spawn(async { Ok(ExecutionResult::success()) })));
+
+        Job::new(tasks, command_line.to_owned(), JobState::Running)
+    }
+
+    #[tokio::test]
+    async fn test_job_display() {
+        let mut manager = JobManager::new();
+        manager.add_as_current(new_job("sleep 10", 1));
+        manager.add_as_current(new_job("sleep 20", 1)).state = JobState::Stopped;
+
+        assert_eq!(
+            manager.jobs[0].to_string(),
+            "[1]-  Running                 sleep 10 &"
+        );
+        assert_eq!(
+            manager.jobs[1].to_string(),
+            "[2]+  Stopped                 sleep 20"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_job_long_display() {
+        let mut manager = JobManager::new();
+        let job = manager.add_as_current(
+            new_job("sleep 10 | cat", 2)
+                .with_process_commands(["sleep 10".to_owned(), "cat".to_owned()]),
+        );
+        job.processes[0].pid = Some(28805);
+        job.processes[1].pid = Some(28807);
+
+        assert_eq!(
+            job.to_long_string(),
+            "[1]+ 28805 Running                 sleep 10\n     28807                       | cat &"
+        );
+    }
+
+    #[tokio::test]
+    async fn background_pipeline_records_processes() -> anyhow::Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell.exec("sleep 0.1 | cat &", &params).await?;
+
+        let job = &shell.jobs.jobs[0];
+        let long = job.to_long_string();
+        let lines: Vec<_> = long.lines().collect();
+        assert_eq!(lines.len(), 2);
+
+        for (process, line) in job.processes.iter().zip(&lines) {
+            let pid = process.pid.expect("process ID").to_string();
+            assert!(line.contains(&std::format!(" {pid} ")), "{long}");
+        }
+        assert!(
+            lines[0].ends_with("Running                 sleep 0.1"),
+            "{long}"
+        );
+        assert!(lines[1].ends_with("| cat &"), "{long}");
+
+        // Waiting on the job yields the status of its last stage.
+        for (pipeline, expected) in [
+            ("sh -c 'exit 3' | cat &", 0),
+            ("cat </dev/null | sh -c 'exit 3' &", 3),
+        ] {
+            shell.exec(pipeline, &params).await?;
+            let result = shell.jobs.jobs.last_mut().unwrap().wait().await?;
+            assert_eq!(u8::from(&result.exit_code), expected);
+        }
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_job_spec() {
+        let mut manager = JobManager::new();
+        manager.add_as_current(new_job("sleep 10", 1));
+        manager.add_as_current(new_job("cat file", 1));
+        manager.add_as_current(new_job("sleep 30", 1));
+
+        let resolve =
+            |manager: &mut JobManager, spec: &str| manager.resolve_job_spec(spec).map(|job| job.id);
+
+        assert_eq!(resolve(&mut manager, "%2"), Some(2));
+        assert_eq!(resolve(&mut manager, "%%"), Some(3));
+        assert_eq!(resolve(&mut manager, "%-"), Some(2));
+        assert_eq!(resolve(&mut manager, "%cat"), Some(2));
+        assert_eq!(resolve(&mut manager, "%?30"), Some(3));
+        assert_eq!(resolve(&mut manager, "%sleep"), None);
+        assert_eq!(resolve(&mut manager, "%4"), None);
+    }
+
+    #[tokio::test]
+    async fn test_job_change_reporting() {
+        let mut job = new_job("sleep 10", 1);
+        assert!(job.has_unreported_change());
+
+        job.mark_reported();
+        assert!(!job.has_unreported_change());
+
+        job.state = JobState::Stopped;
+        assert!(job.has_unreported_change());
+    }
+}
@@ -0,0 +1,549 @@
+//! Support for built-in commands loaded at runtime from shared libraries (see
+//! `enable -f`).
+//!
+//! A loadable builtin library is a shared object (e.g., a Rust `cdylib`) that exports
+//! a C-ABI function named [`ENTRY_POINT_SYMBOL`] of type [`EntryPointFunc`]. That
+//! function returns a [`LibraryDescriptor`] listing the builtins implemented by the
+//! library. All types exchanged with the library are `#[repr(C)]`, so libraries need
+//! not be built with the same compiler (or language) as the shell.
+//!
+//! When invoked, a loadable builtin receives its arguments (including the command
+//! name) as NUL-terminated strings, along with a [`HostApi`] through which it can
+//! access the shell's standard input/output streams and variables. Its return value
+//! is used as the command's exit status.
+
+use std::ffi::{CStr, CString, c_char, c_int, c_void};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use futures::future::BoxFuture;
+
+use crate::core::{ExecutionResult, Shell, builtins, commands, env, error, openfiles, variables};
+
+/// Version of the loadable builtin ABI implemented by this shell. Libraries must
+/// report this version in their [`LibraryDescriptor`].
+pub const ABI_VERSION: u32 = 1;
+
+/// Name of the function that loadable builtin libraries must export.
+pub const ENTRY_POINT_SYMBOL: &str = "kodegen_bash_shell_builtins";
+
+/// Identifies the standard output stream in calls to [`HostApi::write_output`].
+pub const STDOUT_STREAM: c_int = 1;
+
+/// Identifies the standard error stream in calls to [`HostApi::write_output`].
+pub const STDERR_STREAM: c_int = 2;
+
+/// Type of the entry point exported by loadable builtin libraries. The returned
+/// descriptor, and all data it refers to, must remain valid for as long as the
+/// library is loaded.
+pub type EntryPointFunc = unsafe extern "C" fn() -> *const LibraryDescriptor;
+
+/// Type of the function invoked to execute a loadable builtin. `argv` points to
+/// `argc` NUL-terminated strings, followed by a null pointer; the first is the name
+/// the builtin was invoked by. Returns the exit status of the command.
+pub type BuiltinExecuteFunc =
+    unsafe extern "C" fn(host: *const HostApi, argc: usize, argv: *const *const c_char) -> c_int;
+
+/// Describes the builtins implemented by a loadable builtin library.
+#[repr(C)]
+pub struct LibraryDescriptor {
+    /// The version of the ABI implemented by the library; must be [`ABI_VERSION`].
+    pub abi_version: u32,
+    /// The number of entries in `builtins`.
+    pub builtin_count: usize,
+    /// Pointer to an array of `builtin_count` builtin descriptors.
+    pub builtins: *const BuiltinDescriptor,
+}
+
+/// Describes a single loadable builtin.
+#[repr(C)]
+pub struct BuiltinDescriptor {
+    /// Name of the builtin, as a NUL-terminated UTF-8 string.
+    pub name: *const c_char,
+    /// Optional one-line usage synopsis (e.g., `hello [-n count] [name]`); may be null.
+    pub short_doc: *const c_char,
+    /// Optional detailed help text; may be null.
+    pub long_doc: *const c_char,
+    /// Function invoked to execute the builtin.
+    pub execute: BuiltinExecuteFunc,
+}
+
+/// Services provided by the shell to an executing loadable builtin. The pointer
+/// is only valid for the duration of the call to the builtin.
+#[repr(C)]
+pub struct HostApi {
+    /// Opaque context that must be passed back to each of the functions below.
+    pub context: *mut c_void,
+
+    /// Reads up to `len` bytes from standard input into `buf`. Returns the number of
+    /// bytes read (0 at end of input), or -1 on error.
+    pub read_input: unsafe extern "C" fn(context: *mut c_void, buf: *mut u8, len: usize) -> isize,
+
+    /// Writes `len` bytes from `data` to the given stream ([`STDOUT_STREAM`] or
+    /// [`STDERR_STREAM`]). Returns the number of bytes written, or -1 on error.
+    pub write_output: unsafe extern "C" fn(
+        context: *mut c_void,
+        stream: c_int,
+        data: *const u8,
+        len: usize,
+    ) -> isize,
+
+    /// Copies the value of the named shell variable, as a NUL-terminated string,
+    /// into `buf` (which holds `len` bytes), truncating it if needed. Returns the
+    /// length of the full value (excluding the terminator), or -1 if the variable
+    /// is not set.
+    pub get_variable: unsafe extern "C" fn(
+        context: *mut c_void,
+        name: *const c_char,
+        buf: *mut c_char,
+        len: usize,
+    ) -> isize,
+
+    /// Sets the named shell variable to the given value. Returns 0 on success, or
+    /// -1 on error.
+    pub set_variable: unsafe extern "C" fn(
+        context: *mut c_void,
+        name: *const c_char,
+        value: *const c_char,
+    ) -> c_int,
+}
+
+/// A builtin loaded from a shared library.
+pub struct LoadedBuiltin {
+    /// The name of the builtin.
+    pub name: String,
+    /// One-line usage synopsis, if provided by the library.
+    pub short_doc: Option<String>,
+    /// Detailed help text, if provided by the library.
+    pub long_doc: Option<String>,
+    /// Path of the library the builtin was loaded from.
+    pub library_path: PathBuf,
+    execute: BuiltinExecuteFunc,
+    // N.B. Keeps the library loaded for as long as any of its builtins are registered.
+    _library: Arc<Library>,
+}
+
+impl LoadedBuiltin {
+    /// Returns help content for the builtin.
+    ///
+    /// # Arguments
+    ///
+    /// * `content_type` - The type of content to retrieve.
+    pub fn content(&self, content_type: builtins::ContentType) -> String {
+        let short_doc = self.short_doc.as_deref().unwrap_or(self.name.as_str());
+
+        match content_type {
+            builtins::ContentType::ShortUsage => std::format!("{}: {short_doc}\n", self.name),
+            builtins::ContentType::ShortDescription => {
+                let summary = self
+                    .long_doc
+                    .as_deref()
+                    .and_then(|doc| doc.lines().next())
+                    .unwrap_or("dynamically loaded builtin");
+                std::format!("{} - {summary}\n", self.name)
+            }
+            builtins::ContentType::DetailedHelp | builtins::ContentType::ManPage => {
+                let mut content = std::format!("{}: {short_doc}\n", self.name);
+                if let Some(long_doc) = &self.long_doc {
+                    for line in long_doc.lines() {
+                        content.push_str("    ");
+                        content.push_str(line);
+                        content.push('\n');
+                    }
+                }
+                content
+            }
+        }
+    }
+}
+
+#[cfg(any(unix, windows))]
+type Library = libloading::Library;
+
+#[cfg(not(any(unix, windows)))]
+type Library = ();
+
+/// Loads the builtins implemented by the given shared library. If `names` is
+/// non-empty, only the named builtins are loaded, and it is an error for the
+/// library not to implement any of them.
+///
+/// # Arguments
+///
+/// * `path` - The path to the library.
+/// * `names` - The names of the builtins to load; if empty, all are loaded.
+#[cfg(any(unix, windows))]
+pub fn load_library(path: &Path, names: &[String]) -> Result<Vec<LoadedBuiltin>, error::Error> {
+    let display_path = path.to_string_lossy().to_string();
+    let open_error = |e: libloading::Error| {
+        error::ErrorKind::CannotOpenSharedObject(display_path.clone(), e.to_string())
+    };
+
+    // SAFETY: Loading a library runs its initialization routines; we trust the
+    // library the user explicitly asked us to load.
+    let library = Arc::new(unsafe { libloading::Library::new(path) }.map_err(open_error)?);
+
+    // SAFETY: The symbol is required to have the type of `EntryPointFunc`.
+    let entry_point = unsafe { library.get::<EntryPointFunc>(ENTRY_POINT_SYMBOL.as_bytes()) }
+        .map_err(open_error)?;
+
+    // SAFETY: The entry point is required to return a pointer to a descriptor
+    // that remains valid while the library is loaded.
+    let descriptor = unsafe { entry_point().as_ref() }.ok_or_else(|| {
+        error::ErrorKind::CannotOpenSharedObject(
+            display_path.clone(),
+            String::from("no builtins descriptor"),
+        )
+    })?;
+
+    if descriptor.abi_version != ABI_VERSION {
+        return Err(
+            error::ErrorKind::IncompatibleBuiltinAbi(display_path, descriptor.abi_version).into(),
+        );
+    }
+
+    let builtin_descriptors = if descriptor.builtin_count == 0 || descriptor.builtins.is_null() {
+        &[]
+    } else {
+        // SAFETY: The descriptor is required to point to `builtin_count` entries.
+        unsafe { std::slice::from_raw_parts(descriptor.builtins, descriptor.builtin_count) }
+    };
+
+    let mut loaded = vec![];
+    for builtin_descriptor in builtin_descriptors {
+        // SAFETY: The descriptor's strings are required to be NUL-terminated or null.
+        let (name, short_doc, long_doc) = unsafe {
+            (
+                c_str_to_string(builtin_descriptor.name),
+                c_str_to_string(builtin_descriptor.short_doc),
+                c_str_to_string(builtin_descriptor.long_doc),
+            )
+        };
+
+        let Some(name) = name else {
+            continue;
+        };
+
+        if names.is_empty() || names.contains(&name) {
+            loaded.push(LoadedBuiltin {
+                name,
+                short_doc,
+                long_doc,
+                library_path: path.to_path_buf(),
+                execute: builtin_descriptor.execute,
+                _library: library.clone(),
+            });
+        }
+    }
+
+    for name in names {
+        if !loaded.iter().any(|builtin| &builtin.name == name) {
+            return Err(error::ErrorKind::BuiltinNotInSharedObject(
+                name.clone(),
+                path.to_string_lossy().to_string(),
+            )
+            .into());
+        }
+    }
+
+    Ok(loaded)
+}
+
+/// Loads the builtins implemented by the given shared library. Not supported on
+/// this platform.
+///
+/// # Arguments
+///
+/// * `path` - The path to the library.
+/// * `names` - The names of the builtins to load; if empty, all are loaded.
+#[cfg(not(any(unix, windows)))]
+pub fn load_library(path: &Path, names: &[String]) -> Result<Vec<LoadedBuiltin>, error::Error> {
+    let _ = (path, names);
+    error::unimp("loadable builtins on this platform")
+}
+
+/// Locates the shared library to load for `enable -f`. As with bash, a path without
+/// a slash is looked up in the directories listed in `BASH_LOADABLES_PATH`.
+///
+/// # Arguments
+///
+/// * `shell` - The shell in which the library is being loaded.
+/// * `path` - The path given by the user.
+pub fn resolve_library_path(shell: &Shell, path: &str) -> PathBuf {
+    if !path.contains('/')
+        && let Some(search_path) = shell.env_str("BASH_LOADABLES_PATH")
+    {
+        for dir in search_path.split(':').filter(|dir| !dir.is_empty()) {
+            let candidate = shell.absolute_path(Path::new(dir)).join(path);
+            if candidate.is_file() {
+                return candidate;
+            }
+        }
+    }
+
+    if path.contains('/') {
+        shell.absolute_path(Path::new(path))
+    } else {
+        // Defer to the platform's library search.
+        PathBuf::from(path)
+    }
+}
+
+/// Returns a builtin registration that dispatches to the loaded builtin registered
+/// in the shell under the name the command is invoked by.
+pub(crate) fn registration() -> builtins::Registration {
+    builtins::Registration {
+        execute_func: execute_loaded_builtin,
+        content_func: get_loaded_builtin_content,
+        disabled: false,
+        special_builtin: false,
+        declaration_builtin: false,
+        stock: false,
+    }
+}
+
+fn get_loaded_builtin_content(
+    name: &str,
+    content_type: builtins::ContentType,
+) -> Result<String, error::Error> {
+    // N.B. The library's documentation is only reachable through the shell; the
+    // help builtin consults it directly. This is the fallback.
+    Ok(match content_type {
+        builtins::ContentType::ShortDescription => {
+            std::format!("{name} - dynamically loaded builtin\n")
+        }
+        _ => std::format!("{name}: {name}\n"),
+    })
+}
+
+fn execute_loaded_builtin(
+    context: commands::ExecutionContext<'_>,
+    args: Vec<commands::CommandArg>,
+) -> BoxFuture<'_, Result<ExecutionResult, error::Error>> {
+    Box::pin(async move { execute_loaded_builtin_impl(context, args) })
+}
+
+fn execute_loaded_builtin_impl(
+    context: commands::ExecutionContext<'_>,
+    args: Vec<commands::CommandArg>,
+) -> Result<ExecutionResult, error::Error> {
+    let Some(builtin) = context.shell.loaded_builtin(&context.command_name) else {
+        return Err(error::ErrorKind::NotDynamicallyLoaded(context.command_name.clone()).into());
+    };
+    let execute = builtin.execute;
+
+    // As with any C string, arguments end at their first NUL byte (if any).
+    let args: Vec<_> = args
+        .iter()
+        .map(|arg| {
+            let arg = arg.to_string();
+            let arg = arg.split('\0').next().unwrap_or_default();
+            CString::new(arg).unwrap_or_default()
+        })
+        .collect();
+
+    let mut argv: Vec<*const c_char> = args.iter().map(|arg| arg.as_ptr()).collect();
+    argv.push(std::ptr::null());
+
+    let mut host_context = HostContext {
+        stdin: context.try_fd(openfiles::OpenFiles::STDIN_FD),
+        stdout: Box::new(context.stdout()),
+        stderr: Box::new(context.stderr()),
+        shell: context.shell,
+    };
+
+    let host = HostApi {
+        context: (&raw mut host_context).cast(),
+        read_input: host_read_input,
+        write_output: host_write_output,
+        get_variable: host_get_variable,
+        set_variable: host_set_variable,
+    };
+
+    // SAFETY: The builtin is required to conform to `BuiltinExecuteFunc`; its library
+    // is kept loaded by the registration we looked it up from.
+    let exit_code = unsafe { execute(&raw const host, args.len(), argv.as_ptr()) };
+
+    Ok(ExecutionResult::new((exit_code & 0xff) as u8))
+}
+
+struct HostContext<'a> {
+    shell: &'a mut Shell,
+    stdin: Option<openfiles::OpenFile>,
+    stdout: Box<dyn Write>,
+    stderr: Box<dyn Write>,
+}
+
+/// Recovers the host context from the opaque pointer handed to a builtin.
+///
+/// # Safety
+///
+/// The pointer must be the `context` of a `HostApi` whose builtin call is in progress.
+unsafe fn host_context<'a>(context: *mut c_void) -> &'a mut HostContext<'a> {
+    // SAFETY: Guaranteed by the caller.
+    unsafe { &mut *context.cast::<HostContext<'a>>() }
+}
+
+/// Converts a possibly-null C string into an owned string.
+///
+/// # Safety
+///
+/// The pointer must be null or point to a NUL-terminated string.
+unsafe fn c_str_to_string(s: *const c_char) -> Option<String> {
+    if s.is_null() {
+        None
+    } else {
+        // SAFETY: Guaranteed by the caller.
+        Some(unsafe { CStr::from_ptr(s) }.to_string_lossy().to_string())
+    }
+}
+
+unsafe extern "C" fn host_read_input(context: *mut c_void, buf: *mut u8, len: usize) -> isize {
+    // SAFETY: The builtin is required to pass back the context it was given.
+    let host = unsafe { host_context(context) };
+    let Some(stdin) = &mut host.stdin else {
+        return -1;
+    };
+
+    if len == 0 {
+        return 0;
+    }
+
+    // SAFETY: The builtin is required to provide a buffer of at least `len` bytes.
+    let buf = unsafe { std::slice::from_raw_parts_mut(buf, len) };
+    stdin
+        .read(buf)
+        .map_or(-1, |count| isize::try_from(count).unwrap_or(-1))
+}
+
+unsafe extern "C" fn host_write_output(
+    context: *mut c_void,
+    stream: c_int,
+    data: *const u8,
+    len: usize,
+) -> isize {
+    // SAFETY: The builtin is required to pass back the context it was given.
+    let host = unsafe { host_context(context) };
+    let writer = match stream {
+        STDOUT_STREAM => &mut host.stdout,
+        STDERR_STREAM => &mut host.stderr,
+        _ => return -1,
+    };
+
+    if len == 0 {
+        return 0;
+    }
+
+    // SAFETY: The builtin is required to provide `len` readable bytes.
+    let data = unsafe { std::slice::from_raw_parts(data, len) };
+    match writer.write_all(data).and_then(|()| writer.flush()) {
+        Ok(()) => isize::try_from(len).unwrap_or(-1),
+        Err(_) => -1,
+    }
+}
+
+unsafe extern "C" fn host_get_variable(
+    context: *mut c_void,
+    name: *const c_char,
+    buf: *mut c_char,
+    len: usize,
+) -> isize {
+    // SAFETY: The builtin is required to pass back the context it was given, along
+    // with a NUL-terminated name.
+    let (host, name) = unsafe { (host_context(context), c_str_to_string(name)) };
+    let Some(value) = name.and_then(|name| host.shell.env_str(&name).map(|v| v.to_string())) else {
+        return -1;
+    };
+
+    if len > 0 && !buf.is_null() {
+        let copy_len = value.len().min(len - 1);
+        // SAFETY: The builtin is required to provide a buffer of at least `len` bytes.
+        unsafe {
+            std::ptr::copy_nonoverlapping(value.as_ptr().cast::<c_char>(), buf, copy_len);
+            *buf.add(copy_len) = 0;
+        }
+    }
+
+    isize::try_from(value.len()).unwrap_or(-1)
+}
+
+unsafe extern "C" fn host_set_variable(
+    context: *mut c_void,
+    name: *const c_char,
+    value: *const c_char,
+) -> c_int {
+    // SAFETY: The builtin is required to pass back the context it was given, along
+    // with NUL-terminated strings.
+    let (host, name, value) = unsafe {
+        (
+            host_context(context),
+            c_str_to_string(name),
+            c_str_to_string(value),
+        )
+    };
+    let (Some(name), Some(value)) = (name, value) else {
+        return -1;
+    };
+
+    if !env::valid_variable_name(name.as_str()) {
+        return -1;
+    }
+
+    let result = host.shell.env.update_or_add(
+        name,
+        variables::ShellValueLiteral::Scalar(value),
+        |_| Ok(()),
+        env::EnvironmentLookup::Anywhere,
+        env::EnvironmentScope::Global,
+    );
+
+    if result.is_ok() { 0 } else { -1 }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_enable_missing_library() -> anyhow::Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        let result = shell
+            .exec("enable -f /nonexistent/libmissing.so missing", &params)
+            .await?;
+        assert!(!result.is_success());
+        assert!(shell.loaded_builtin("missing").is_none());
+
+        let result = shell.exec("enable -d echo", &params).await?;
+        assert!(!result.is_success());
+        assert!(shell.builtins().contains_key("echo"));
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn test_resolve_library_path() -> anyhow::Result<()> {
+        let dir = std::env::temp_dir().join(format!("loadables-test-{}", std::process::id()));
+        std::fs::create_dir_all(&dir)?;
+        std::fs::write(dir.join("libfake.so"), b"")?;
+
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        shell
+            .exec(
+                format!("BASH_LOADABLES_PATH=/nonexistent:{}", dir.display()),
+                &params,
+            )
+            .await?;
+
+        let resolved = resolve_library_path(&shell, "libfake.so");
+        let unresolved = resolve_library_path(&shell, "libother.so");
+        std::fs::remove_dir_all(&dir)?;
+
+        assert_eq!(resolved, dir.join("libfake.so"));
+        assert_eq!(unresolved, PathBuf::from("libother.so"));
+
+        Ok(())
+    }
+}
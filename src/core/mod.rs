@@ -6,6 +6,7 @@ mod braceexpansion;
 pub mod builtins;
 pub mod commands;
 pub mod completion;
+pub mod dryrun;
 pub mod env;
 pub mod error;
 pub mod escape;
@@ -13,10 +14,14 @@ pub mod expansion;
 mod extendedtests;
 pub mod functions;
 pub mod history;
+mod historyexpansion;
+pub mod inputrc;
 pub mod interfaces;
+pub mod inventory;
 mod interp;
 pub mod jobs;
 mod keywords;
+pub mod loadable;
 pub mod namedoptions;
 pub mod openfiles;
 pub mod options;
@@ -43,4 +48,4 @@ pub use error::{BuiltinError, Error, ErrorKind};
 pub use interp::{ExecutionParameters, ProcessGroupPolicy};
 pub use results::{ExecutionControlFlow, ExecutionExitCode, ExecutionResult, ExecutionSpawnResult, OutputStreamType, StreamingOutput};
 pub use shell::{CreateOptions, Shell, ShellBuilder, ShellBuilderState, ShellFd};
-pub use variables::{ShellValue, ShellVariable};
+pub use variables::{DynamicValue, ShellValue, ShellVariable};
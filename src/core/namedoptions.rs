@@ -61,7 +61,7 @@ pub struct ShellOptionSet {
 }
 
 /// Kind of shell option.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug)]
 pub enum ShellOptionKind {
     /// `set` option.
     Set,
@@ -108,6 +108,46 @@ impl ShellOptionSet {
     }
 }
 
+/// A snapshot of the values of all `set` options (i.e., the state reflected in `$-`
+/// and `set -o`), as captured by `local -`.
+#[derive(Clone, Debug)]
+pub struct SetOptionsSnapshot {
+    values: Vec<(ShellOptionKind, &'static str, bool)>,
+}
+
+impl SetOptionsSnapshot {
+    /// Captures the current values of all `set` options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The runtime options to capture values from.
+    pub fn capture(options: &RuntimeOptions) -> Self {
+        let mut values = vec![];
+        for kind in [ShellOptionKind::Set, ShellOptionKind::SetO] {
+            values.extend(
+                self::options(kind)
+                    .iter()
+                    .map(|option| (kind, option.name, option.definition.get(options))),
+            );
+        }
+
+        Self { values }
+    }
+
+    /// Restores the captured option values into the given runtime options.
+    ///
+    /// # Arguments
+    ///
+    /// * `options` - The runtime options to update.
+    pub fn restore(&self, options: &mut RuntimeOptions) {
+        for (kind, name, value) in &self.values {
+            if let Some(definition) = self::options(*kind).get(name) {
+                definition.set(options, *value);
+            }
+        }
+    }
+}
+
 static SET_OPTIONS: LazyLock<HashMap<&'static str, ShellOptionDef>> = LazyLock::new(|| {
     HashMap::from([
         (
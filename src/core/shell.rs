@@ -19,13 +19,19 @@ use super::{
     pathsearch, scripts, trace_categories, wellknownvars,
 };
 use super::{
-    builtins, commands, completion, env, error, expansion, functions, jobs, keywords, openfiles,
-    prompt, sys::users, traps,
+    builtins, commands, completion, env, error, expansion, functions, jobs, keywords, loadable,
+    namedoptions, openfiles, prompt, sys::users, traps,
 };
 
 /// Type for storing a key bindings helper.
 pub type KeyBindingsHelper = Arc<Mutex<dyn interfaces::KeyBindings>>;
 
+/// Type for storing a line editor helper.
+pub type LineEditorHelper = Arc<Mutex<dyn interfaces::LineEditor>>;
+
+/// Type for storing a handler for commands that could not be found.
+pub type CommandNotFoundHandlerHelper = Arc<dyn interfaces::CommandNotFoundHandler>;
+
 /// Type for storing an error formatter.
 pub type ErrorFormatterHelper = Arc<Mutex<dyn error::ErrorFormatter>>;
 
@@ -97,6 +103,9 @@ pub struct Shell {
     /// Shell built-in commands.
     builtins: HashMap<String, builtins::Registration>,
 
+    /// Built-in commands loaded from shared libraries, by name.
+    loaded_builtins: HashMap<String, Arc<loadable::LoadedBuiltin>>,
+
     /// Shell program location cache.
     pub program_location_cache: pathcache::PathCache,
 
@@ -109,6 +118,13 @@ pub struct Shell {
     /// Key bindings for the shell, optionally implemented by an interactive shell.
     key_bindings: Option<KeyBindingsHelper>,
 
+    /// Line editor for builtins to use, optionally implemented by an interactive shell.
+    line_editor: Option<LineEditorHelper>,
+
+    /// Fallback handler for commands that could not be found, optionally provided by
+    /// an embedder.
+    command_not_found_handler: Option<CommandNotFoundHandlerHelper>,
+
     /// History of commands executed in the shell.
     history: Option<history::History>,
 
@@ -139,10 +155,13 @@ impl Clone for Shell {
             current_line_number: self.current_line_number,
             completion_config: self.completion_config.clone(),
             builtins: self.builtins.clone(),
+            loaded_builtins: self.loaded_builtins.clone(),
             program_location_cache: self.program_location_cache.clone(),
             last_stopwatch_time: self.last_stopwatch_time,
             last_stopwatch_offset: self.last_stopwatch_offset,
             key_bindings: self.key_bindings.clone(),
+            line_editor: self.line_editor.clone(),
+            command_not_found_handler: self.command_not_found_handler.clone(),
             history: self.history.clone(),
             error_formatter: self.error_formatter.clone(),
             depth: self.depth + 1,
@@ -342,6 +361,10 @@ pub struct CreateOptions {
     pub max_function_call_depth: Option<usize>,
     /// Key bindings helper for the shell to use.
     pub key_bindings: Option<KeyBindingsHelper>,
+    /// Line editor helper for builtins to use when reading edited input.
+    pub line_editor: Option<LineEditorHelper>,
+    /// Fallback handler for commands that could not be found.
+    pub command_not_found_handler: Option<CommandNotFoundHandlerHelper>,
     /// Error formatter helper for the shell to use.
     pub error_formatter: Option<ErrorFormatterHelper>,
     /// Brush implementation version.
@@ -392,10 +415,13 @@ impl Shell {
             current_line_number: 0,
             completion_config: completion::Config::default(),
             builtins: options.builtins,
+            loaded_builtins: HashMap::new(),
             program_location_cache: pathcache::PathCache::default(),
             last_stopwatch_time: std::time::SystemTime::now(),
             last_stopwatch_offset: 0,
             key_bindings: options.key_bindings,
+            line_editor: options.line_editor,
+            command_not_found_handler: options.command_not_found_handler,
             history: None,
             error_formatter: options
                 .error_formatter
@@ -422,9 +448,16 @@ impl Shell {
                 options.read(true);
 
                 if let Ok(history_file) =
-                    shell.open_file(&options, history_path, &shell.default_exec_params())
+                    shell.open_file(&options, &history_path, &shell.default_exec_params())
                 {
-                    shell.history = Some(history::History::import(history_file)?);
+                    let mut history = history::History::import(history_file)?;
+
+                    // Remember how much of the file we've seen, for `history -n`.
+                    if let Ok(metadata) = std::fs::metadata(shell.absolute_path(&history_path)) {
+                        history.mark_file_read(&history_path, metadata.len());
+                    }
+
+                    shell.history = Some(history);
                 }
             }
 
@@ -480,6 +513,17 @@ impl Shell {
         &self.key_bindings
     }
 
+    /// Returns the line editor helper for the shell, if one is available.
+    pub const fn line_editor(&self) -> &Option<LineEditorHelper> {
+        &self.line_editor
+    }
+
+    /// Returns the fallback handler for commands that could not be found, if one
+    /// was provided.
+    pub const fn command_not_found_handler(&self) -> &Option<CommandNotFoundHandlerHelper> {
+        &self.command_not_found_handler
+    }
+
     /// Returns the registered builtins for the shell.
     pub const fn builtins(&self) -> &HashMap<String, builtins::Registration> {
         &self.builtins
@@ -564,6 +608,7 @@ impl Shell {
             fname: name.clone().into(),
             body: func_body,
             source: String::new(),
+            uses_function_keyword: false,
         };
 
         self.define_func(name, def);
@@ -777,7 +822,6 @@ impl Shell {
             crate::parser::Parser::new(&mut reader, &self.parser_options(), source_info);
 
         tracing::debug!(target: trace_categories::PARSE, "Parsing sourced file: {}", source_info.source);
-        let parse_result = parser.parse_program();
 
         let mut other_positional_parameters: Vec<_> = args.map(|s| s.as_ref().to_owned()).collect();
         let mut other_shell_name = Some(source_info.source.clone());
@@ -795,13 +839,18 @@ impl Shell {
             );
         }
 
+        let sourced = matches!(call_type, scripts::CallType::Sourced);
         self.script_call_stack
             .push(call_type, source_info.source.clone());
 
         let result = self
-            .run_parsed_result(parse_result, source_info, params)
+            .run_parsed_lines(&mut parser, source_info, params)
             .await;
 
+        if sourced {
+            commands::invoke_return_trap_handler_if_registered(self, params).await;
+        }
+
         self.script_call_stack.pop();
 
         // Restore.
@@ -876,11 +925,52 @@ impl Shell {
         // each string we run could be multiple lines.
         self.current_line_number += 1;
 
-        let parse_result = self.parse_string(command.into());
+        let command = command.into();
         let source_info = crate::parser::SourceInfo {
             source: String::from("main"),
         };
-        self.run_parsed_result(parse_result, &source_info, params)
+
+        // Unless aliases are to be expanded, parse the whole string at once (which may be
+        // cached). Its commands are still executed one at a time, in case one of them starts
+        // alias expansion for those that follow.
+        let mut executed = 0;
+        if self.aliases_to_expand().is_empty()
+            && let Ok(program) = self.parse_string(command.as_str())
+        {
+            let program_len = program.complete_commands.len();
+            let mut result = ExecutionResult::success();
+            for complete_command in program.complete_commands {
+                if !self.aliases_to_expand().is_empty() {
+                    break;
+                }
+
+                let program = crate::parser::ast::Program {
+                    complete_commands: vec![complete_command],
+                };
+                result = self
+                    .run_parsed_result(Ok(program), &source_info, params)
+                    .await?;
+                executed += 1;
+
+                if !result.is_normal_flow() {
+                    break;
+                }
+            }
+
+            if executed == program_len || !result.is_normal_flow() {
+                *self.last_exit_status_mut() = result.exit_code.into();
+                return Ok(result);
+            }
+        }
+
+        // Parse (and execute) the rest of the string line by line, skipping the commands
+        // that were already executed.
+        let mut parser = create_parser(command.as_bytes(), &self.parser_options());
+        for _ in 0..executed {
+            let _ = parser.parse_next_line();
+        }
+
+        self.run_parsed_lines(&mut parser, &source_info, params)
             .await
     }
 
@@ -989,6 +1079,7 @@ impl Shell {
         reader: R,
     ) -> Result<crate::parser::ast::Program, crate::parser::ParseError> {
         let mut parser = create_parser(reader, &self.parser_options());
+        parser.set_aliases(self.aliases_to_expand());
 
         tracing::debug!(target: trace_categories::PARSE, "Parsing reader as program...");
         parser.parse_program()
@@ -1004,7 +1095,29 @@ impl Shell {
         &self,
         s: S,
     ) -> Result<crate::parser::ast::Program, crate::parser::ParseError> {
-        parse_string_impl(s.into(), self.parser_options())
+        let aliases = self.aliases_to_expand();
+        if aliases.is_empty() {
+            return parse_string_impl(s.into(), self.parser_options());
+        }
+
+        // N.B. We don't cache the results of parsing with aliases, since they depend on
+        // the aliases' definitions.
+        let s = s.into();
+        let mut parser = create_parser(s.as_bytes(), &self.parser_options());
+        parser.set_aliases(aliases);
+
+        tracing::debug!(target: trace_categories::PARSE, "Parsing string as program...");
+        parser.parse_program()
+    }
+
+    /// Returns the aliases that should be expanded when parsing shell programs; empty
+    /// unless the `expand_aliases` option is enabled.
+    fn aliases_to_expand(&self) -> HashMap<String, String> {
+        if self.options.expand_aliases {
+            self.aliases.clone()
+        } else {
+            HashMap::new()
+        }
     }
 
     /// Applies basic shell expansion to the provided string.
@@ -1126,6 +1239,47 @@ impl Shell {
         }
     }
 
+    /// Parses and executes the input of the given parser one line at a time, so that each
+    /// line is parsed with the aliases defined by the lines executed before it. Stops at the
+    /// first line that fails to parse, or that doesn't continue to the next line (e.g., by
+    /// exiting the shell).
+    ///
+    /// # Arguments
+    ///
+    /// * `parser` - The parser to read lines from.
+    /// * `source_info` - Information about the source of the input.
+    /// * `params` - Execution parameters.
+    async fn run_parsed_lines<R: std::io::BufRead>(
+        &mut self,
+        parser: &mut crate::parser::Parser<R>,
+        source_info: &crate::parser::SourceInfo,
+        params: &ExecutionParameters,
+    ) -> Result<ExecutionResult, error::Error> {
+        let mut result = ExecutionResult::success();
+
+        loop {
+            parser.set_aliases(self.aliases_to_expand());
+
+            let parse_result = match parser.parse_next_line() {
+                Ok(Some(program)) => Ok(program),
+                Ok(None) => break,
+                Err(err) => Err(err),
+            };
+
+            let parse_failed = parse_result.is_err();
+            result = self
+                .run_parsed_result(parse_result, source_info, params)
+                .await?;
+
+            if parse_failed || !result.is_normal_flow() {
+                break;
+            }
+        }
+
+        *self.last_exit_status_mut() = result.exit_code.into();
+        Ok(result)
+    }
+
     /// Executes the given parsed shell program, returning the resulting exit status.
     ///
     /// # Arguments
@@ -1238,7 +1392,20 @@ impl Shell {
             tracing::debug!(target: trace_categories::FUNCTIONS, "Entering func [depth={depth}]: {prefix}{name}");
         }
 
+        let inherits_traps = self.options.shell_functions_inherit_debug_and_return_traps
+            || self
+                .funcs
+                .get(name)
+                .is_some_and(functions::Registration::is_traced);
+        let debug_trap = self.traps.handlers.get(&traps::TrapSignal::Debug).cloned();
+        let return_trap = self.traps.handlers.get(&traps::TrapSignal::Return).cloned();
+
         self.function_call_stack.push(name, function_def);
+        if let Some(frame) = self.function_call_stack.current_frame_mut() {
+            frame.inherits_debug_and_return_traps = inherits_traps;
+            frame.debug_trap_on_entry = debug_trap;
+            frame.return_trap_on_entry = return_trap;
+        }
         self.env.push_scope(env::EnvironmentScope::Local);
 
         Ok(())
@@ -1249,7 +1416,17 @@ impl Shell {
     pub(crate) fn leave_function(&mut self) -> Result<(), error::Error> {
         self.env.pop_scope(env::EnvironmentScope::Local)?;
 
-        if let Some(exited_call) = self.function_call_stack.pop()
+        let exited_call = self.function_call_stack.pop();
+
+        // Restore any `set` options that were scoped to the function via `local -`.
+        if let Some(saved_options) = exited_call
+            .as_ref()
+            .and_then(|call| call.saved_set_options.as_ref())
+        {
+            saved_options.restore(&mut self.options);
+        }
+
+        if let Some(exited_call) = exited_call
             && tracing::enabled!(target: trace_categories::FUNCTIONS, tracing::Level::DEBUG) {
                 let depth = self.function_call_stack.depth();
                 let prefix = repeated_char_str(' ', depth);
@@ -1259,6 +1436,47 @@ impl Shell {
         Ok(())
     }
 
+    /// Returns the handler registered for the given trap, if it applies in the current
+    /// context. `DEBUG` and `RETURN` handlers are not inherited by shell functions,
+    /// unless the function has the trace attribute or `functrace` is enabled; a
+    /// handler registered by the function itself always applies.
+    ///
+    /// # Arguments
+    ///
+    /// * `signal` - The trap to look up.
+    pub(crate) fn applicable_trap_handler(&self, signal: traps::TrapSignal) -> Option<&str> {
+        let handler = self.traps.handlers.get(&signal)?;
+
+        let Some(frame) = self.function_call_stack.iter().next() else {
+            return Some(handler);
+        };
+
+        let handler_on_entry = match signal {
+            traps::TrapSignal::Debug => &frame.debug_trap_on_entry,
+            traps::TrapSignal::Return => &frame.return_trap_on_entry,
+            _ => return Some(handler),
+        };
+
+        if frame.inherits_debug_and_return_traps || handler_on_entry.as_ref() != Some(handler) {
+            Some(handler)
+        } else {
+            None
+        }
+    }
+
+    /// Snapshots the current `set` options so they are restored when the
+    /// current function returns, implementing `local -`. Subsequent calls within
+    /// the same function invocation retain the original snapshot. Does nothing if
+    /// no function is currently executing.
+    pub(crate) fn save_set_options_for_current_function(&mut self) {
+        if let Some(frame) = self.function_call_stack.current_frame_mut()
+            && frame.saved_set_options.is_none()
+        {
+            frame.saved_set_options =
+                Some(namedoptions::SetOptionsSnapshot::capture(&self.options));
+        }
+    }
+
     /// Returns the path to the history file used by the shell, if one is set.
     pub fn history_file_path(&self) -> Option<PathBuf> {
         self.env_str("HISTFILE")
@@ -1341,6 +1559,76 @@ impl Shell {
         self.env.set_global(name, var)
     }
 
+    /// Tries to assign a scalar value to a variable (or to one of its elements) that
+    /// holds a dynamic value, handing the value to its implementation. Returns whether
+    /// or not the variable was found to be dynamic; if not, nothing is assigned.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to assign.
+    /// * `index` - The index of the element to assign, if any.
+    /// * `value` - The value to assign.
+    /// * `append` - Whether or not to append the value to the preexisting value.
+    pub(crate) fn assign_dynamic_variable(
+        &mut self,
+        name: &str,
+        index: Option<&str>,
+        value: String,
+        append: bool,
+    ) -> Result<bool, error::Error> {
+        let Some((_, var)) = self.env.get(name) else {
+            return Ok(false);
+        };
+        let Some((mut dynamic, value)) =
+            var.prepare_dynamic_assignment(self, index, value, append)?
+        else {
+            return Ok(false);
+        };
+
+        // The dynamic value is detached from the environment while it's updated, so
+        // that it can be given access to the shell.
+        match index {
+            Some(index) => dynamic.set_at_index(self, index, value)?,
+            None => dynamic.set(self, value)?,
+        }
+
+        if let Some((_, var)) = self.env.get_mut(name) {
+            var.replace_dynamic_value(dynamic);
+        }
+
+        Ok(true)
+    }
+
+    /// Assigns a scalar value to a variable, updating it wherever it's visible or else
+    /// adding it as a global. Assignments to dynamic values are handed to them.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the variable to assign.
+    /// * `value` - The value to assign.
+    pub(crate) fn assign_scalar_variable(
+        &mut self,
+        name: &str,
+        value: String,
+    ) -> Result<(), error::Error> {
+        if self
+            .env
+            .get(name)
+            .is_some_and(|(_, var)| matches!(var.value(), variables::ShellValue::Dynamic(_)))
+        {
+            self.assign_dynamic_variable(name, None, value, false)?;
+            return Ok(());
+        }
+
+        self.env.update_or_add(
+            name,
+            variables::ShellValueLiteral::Scalar(value),
+            |_| Ok(()),
+            EnvironmentLookup::Anywhere,
+            EnvironmentScope::Global,
+        )
+    }
+
     /// Register a builtin to the shell's environment.
     ///
     /// # Arguments
@@ -1355,6 +1643,43 @@ impl Shell {
         self.builtins.insert(name.into(), registration);
     }
 
+    /// Registers a builtin loaded from a shared library, replacing any existing builtin
+    /// with the same name.
+    ///
+    /// # Arguments
+    ///
+    /// * `builtin` - The loaded builtin.
+    pub fn register_loaded_builtin(&mut self, builtin: loadable::LoadedBuiltin) {
+        self.builtins
+            .insert(builtin.name.clone(), loadable::registration());
+        self.loaded_builtins
+            .insert(builtin.name.clone(), Arc::new(builtin));
+    }
+
+    /// Removes a builtin that was loaded from a shared library. The library is unloaded
+    /// once none of its builtins remain registered.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the builtin to remove.
+    pub fn unregister_loaded_builtin(&mut self, name: &str) -> Result<(), error::Error> {
+        if self.loaded_builtins.remove(name).is_none() {
+            return Err(error::ErrorKind::NotDynamicallyLoaded(name.to_owned()).into());
+        }
+
+        self.builtins.remove(name);
+        Ok(())
+    }
+
+    /// Returns the builtin with the given name, if it was loaded from a shared library.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the builtin.
+    pub fn loaded_builtin(&self, name: &str) -> Option<&loadable::LoadedBuiltin> {
+        self.loaded_builtins.get(name).map(|builtin| builtin.as_ref())
+    }
+
     /// Tries to retrieve a mutable reference to an existing builtin registration.
     /// Returns `None` if no such registration exists.
     ///
@@ -1569,12 +1894,68 @@ impl Shell {
     /// * `s` - The string to shorten.
     pub fn tilde_shorten(&self, s: String) -> String {
         if let Some(home_dir) = self.home_dir()
-            && let Some(stripped) = s.strip_prefix(home_dir.to_string_lossy().as_ref()) {
-                return format!("~{stripped}");
-            }
+            && let Some(stripped) = s.strip_prefix(home_dir.to_string_lossy().as_ref())
+            && (stripped.is_empty() || stripped.starts_with('/'))
+        {
+            return format!("~{stripped}");
+        }
         s
     }
 
+    /// Returns the directories on the directory stack as they are listed by `dirs`: the
+    /// current working directory first, followed by the stack's entries from the most
+    /// to the least recently pushed.
+    pub fn directory_stack_entries(&self) -> Vec<&Path> {
+        std::iter::once(self.working_dir())
+            .chain(self.directory_stack.iter().rev().map(|p| p.as_path()))
+            .collect()
+    }
+
+    /// Resolves a directory stack position of the form `+N` (counting from the left of
+    /// the list returned by `directory_stack_entries`, starting with zero) or `-N`
+    /// (counting from the right) to an index into that list. A bare `N` is treated as
+    /// `+N`. Returns `None` if the position is malformed or out of range.
+    ///
+    /// # Arguments
+    ///
+    /// * `position` - The position to resolve.
+    pub fn resolve_directory_stack_position(&self, position: &str) -> Option<usize> {
+        let (from_right, digits) = match position.strip_prefix('-') {
+            Some(digits) => (true, digits),
+            None => (false, position.strip_prefix('+').unwrap_or(position)),
+        };
+
+        if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+            return None;
+        }
+
+        let n: usize = digits.parse().ok()?;
+        let len = self.directory_stack.len() + 1;
+        if n >= len {
+            return None;
+        }
+
+        Some(if from_right { len - 1 - n } else { n })
+    }
+
+    /// Replaces the directory stack entry at the given index into the list returned by
+    /// `directory_stack_entries`. The current working directory (index 0) can't be
+    /// replaced this way. Returns whether an entry was replaced.
+    ///
+    /// # Arguments
+    ///
+    /// * `index` - The index of the entry to replace.
+    /// * `dir` - The directory to store in the entry.
+    pub fn set_directory_stack_entry(&mut self, index: usize, dir: PathBuf) -> bool {
+        let len = self.directory_stack.len();
+        if index == 0 || index > len {
+            return false;
+        }
+
+        self.directory_stack[len - index] = dir;
+        true
+    }
+
     /// Returns the shell's current home directory, if available.
     pub(crate) fn home_dir(&self) -> Option<PathBuf> {
         if let Some(home) = self.env.get_str("HOME", self) {
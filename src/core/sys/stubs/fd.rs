@@ -11,3 +11,11 @@ pub fn try_iter_open_fds() -> impl Iterator<Item = (ShellFd, openfiles::OpenFile
 pub fn try_get_file_for_open_fd(_fd: ShellFd) -> Option<openfiles::OpenFile> {
     None
 }
+
+/// Stub implementation for platforms that do not support polling files.
+pub fn poll_for_input(
+    _file: &openfiles::OpenFile,
+    _timeout: std::time::Duration,
+) -> Result<bool, error::Error> {
+    Err(error::ErrorKind::NotSupportedOnThisPlatform("polling for input").into())
+}
@@ -116,3 +116,8 @@ pub fn get_default_standard_utils_paths() -> Vec<String> {
 pub fn open_null_file() -> Result<fs::File, error::Error> {
     fs::File::open("NUL").map_err(|e| error::ErrorKind::IoError(e).into())
 }
+
+/// Creates a new file at the given path. Fails if anything already exists at the path.
+pub(crate) fn create_private_file(path: &std::path::Path) -> Result<fs::File, std::io::Error> {
+    fs::File::options().write(true).create_new(true).open(path)
+}
@@ -72,3 +72,27 @@ unsafe fn open_file_by_fd(fd: RawFd) -> Result<openfiles::OpenFile, error::Error
     let owned_fd = borrowed_fd.try_clone_to_owned()?;
     Ok(std::fs::File::from(owned_fd).into())
 }
+
+/// Waits up to the given timeout for the file to have input available for reading.
+/// Returns whether input is available (or end-of-file has been reached).
+///
+/// # Arguments
+///
+/// * `file` - The file to wait on.
+/// * `timeout` - The maximum amount of time to wait.
+pub fn poll_for_input(
+    file: &openfiles::OpenFile,
+    timeout: std::time::Duration,
+) -> Result<bool, error::Error> {
+    use nix::poll::{PollFd, PollFlags, PollTimeout};
+    use std::os::fd::AsFd as _;
+
+    let mut fds = [PollFd::new(file.as_fd(), PollFlags::POLLIN)];
+    let timeout = PollTimeout::try_from(timeout).unwrap_or(PollTimeout::MAX);
+
+    match nix::poll::poll(&mut fds, timeout) {
+        Ok(ready_count) => Ok(ready_count > 0),
+        Err(nix::errno::Errno::EINTR) => Ok(false),
+        Err(err) => Err(error::ErrorKind::from(err).into()),
+    }
+}
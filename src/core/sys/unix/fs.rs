@@ -188,3 +188,19 @@ pub fn open_null_file() -> Result<std::fs::File, error::Error> {
 
     Ok(f)
 }
+
+/// Creates a new file at the given path that only the current user may read or
+/// write. Fails if anything (including a symbolic link) already exists at the path.
+///
+/// # Arguments
+///
+/// * `path` - The path of the file to create.
+pub(crate) fn create_private_file(path: &Path) -> Result<std::fs::File, std::io::Error> {
+    use std::os::unix::fs::OpenOptionsExt;
+
+    std::fs::File::options()
+        .write(true)
+        .create_new(true)
+        .mode(0o600)
+        .open(path)
+}
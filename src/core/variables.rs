@@ -260,6 +260,10 @@ impl ShellVariable {
                 (ShellValue::String(_), ShellValueLiteral::Array(_)) => {
                     self.convert_to_indexed_array()?;
                 }
+                // Appending an array to a dynamic value replaces it with an indexed array.
+                (ShellValue::Dynamic(_), ShellValueLiteral::Array(_)) => {
+                    return self.assign(value, false);
+                }
                 _ => (),
             }
 
@@ -306,8 +310,18 @@ impl ShellVariable {
                     }
                 },
                 ShellValue::Unset(_) => unreachable!("covered in conversion above"),
-                // TODO(dynamic): implement appending to dynamic vars
-                ShellValue::Dynamic { .. } => Ok(()),
+                ShellValue::Dynamic(dynamic) => match value {
+                    ShellValueLiteral::Scalar(suffix) => {
+                        let mut new_value = dynamic.append_base().unwrap_or_default();
+                        new_value.push_str(suffix.as_str());
+                        self.value = ShellValue::String(new_value);
+                        Ok(())
+                    }
+                    ShellValueLiteral::Array(_) => {
+                        // This case was already handled (see above).
+                        Ok(())
+                    }
+                },
             }
         } else {
             match (&self.value, value) {
@@ -331,7 +345,7 @@ impl ShellVariable {
                         ShellValueUnsetType::IndexedArray | ShellValueUnsetType::Untyped,
                     )
                     | ShellValue::String(_)
-                    | ShellValue::Dynamic { .. },
+                    | ShellValue::Dynamic(_),
                     ShellValueLiteral::Array(literal_values),
                 ) => {
                     self.value = ShellValue::indexed_array_from_literals(literal_values);
@@ -349,12 +363,14 @@ impl ShellVariable {
                     Ok(())
                 }
 
-                // Handle updates to dynamic values; for now we just drop them.
-                // TODO(dynamic): Allow updates to dynamic values
-                (ShellValue::Dynamic { .. }, _) => Ok(()),
-
-                // Assign a scalar value to a scalar or unset (and untyped) variable.
-                (ShellValue::String(_) | ShellValue::Unset(_), ShellValueLiteral::Scalar(s)) => {
+                // Assign a scalar value to a scalar or unset (and untyped) variable. Scalar
+                // updates to dynamic values need the shell and are normally routed through
+                // `Shell::assign_dynamic_variable`; without it, the variable loses its dynamic
+                // behavior, just as it does when assigned an array.
+                (
+                    ShellValue::String(_) | ShellValue::Unset(_) | ShellValue::Dynamic(_),
+                    ShellValueLiteral::Scalar(s),
+                ) => {
                     self.value = ShellValue::String(s);
                     Ok(())
                 }
@@ -484,7 +500,7 @@ impl ShellVariable {
                     // This isn't really title-case; only the first character is capitalized.
                     *s = s.to_lowercase();
                     if let Some(c) = s.chars().next() {
-                        s.replace_range(0..1, &c.to_uppercase().to_string());
+                        s.replace_range(0..c.len_utf8(), &c.to_uppercase().to_string());
                     }
                 }
             }
@@ -511,8 +527,80 @@ impl ShellVariable {
                 let key = index.parse::<u64>().unwrap_or(0);
                 Ok(values.remove(&key).is_some())
             }
-            ShellValue::Dynamic { .. } => Ok(false),
+            ShellValue::Dynamic(_) => Ok(false),
+        }
+    }
+
+    /// If the variable holds a dynamic value, prepares a scalar assignment to it (or
+    /// to one of its elements). Returns a detached copy of the dynamic value along with
+    /// the value it should be handed, accounting for the variable's attributes and, when
+    /// appending, the value being extended. Returns `None` if the value isn't dynamic.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell in which the assignment is taking place.
+    /// * `index` - The index of the element being assigned, if any.
+    /// * `value` - The value being assigned.
+    /// * `append` - Whether or not to append the value to the preexisting value.
+    pub(crate) fn prepare_dynamic_assignment(
+        &self,
+        shell: &Shell,
+        index: Option<&str>,
+        value: String,
+        append: bool,
+    ) -> Result<Option<DynamicAssignment>, error::Error> {
+        let ShellValue::Dynamic(dynamic) = &self.value else {
+            return Ok(None);
+        };
+
+        if self.is_readonly() {
+            return Err(error::ErrorKind::ReadonlyVariable.into());
+        }
+
+        let value = self.convert_value_str_for_assignment(value);
+        if !append {
+            return Ok(Some((dynamic.clone(), value)));
+        }
+
+        let base = match index {
+            Some(index) => self
+                .value
+                .get_at(index, shell)?
+                .map(|existing| existing.to_string()),
+            None => dynamic.append_base(),
+        }
+        .unwrap_or_default();
+
+        let new_value = if self.is_treated_as_integer() {
+            (base.parse::<i64>().unwrap_or(0) + value.parse::<i64>().unwrap_or(0)).to_string()
+        } else {
+            let mut new_value = base;
+            new_value.push_str(value.as_str());
+            self.convert_value_str_for_assignment(new_value)
+        };
+
+        Ok(Some((dynamic.clone(), new_value)))
+    }
+
+    /// Replaces the dynamic value held by the variable, if it still holds one.
+    pub(crate) fn replace_dynamic_value(&mut self, value: Box<dyn DynamicValue>) {
+        if let ShellValue::Dynamic(dynamic) = &mut self.value {
+            *dynamic = value;
+        }
+    }
+
+    /// Prepares the variable for being unset, giving dynamic values a chance to veto
+    /// the operation.
+    pub(crate) fn prepare_for_unset(&mut self) -> Result<(), error::Error> {
+        if self.is_readonly() {
+            return Err(error::ErrorKind::ReadonlyVariable.into());
+        }
+
+        if let ShellValue::Dynamic(dynamic) = &mut self.value {
+            dynamic.unset()?;
         }
+
+        Ok(())
     }
 
     /// Returns the variable's value; for dynamic values, this will resolve the value.
@@ -523,7 +611,7 @@ impl ShellVariable {
     pub fn resolve_value(&self, shell: &Shell) -> ShellValue {
         // N.B. We do *not* specially handle a dynamic value that resolves to a dynamic value.
         match &self.value {
-            ShellValue::Dynamic { getter, .. } => getter(shell),
+            ShellValue::Dynamic(dynamic) => dynamic.get(shell),
             _ => self.value.clone(),
         }
     }
@@ -585,8 +673,94 @@ impl ShellVariable {
     }
 }
 
-type DynamicValueGetter = fn(&Shell) -> ShellValue;
-type DynamicValueSetter = fn(&Shell) -> ();
+/// A dynamic value detached from its variable, along with the value being assigned to it.
+pub(crate) type DynamicAssignment = (Box<dyn DynamicValue>, String);
+
+/// Behavior of a variable whose value is computed on demand (e.g., `RANDOM` or
+/// `SECONDS`). Implementations may hold their own state, which is cloned along with
+/// the rest of the shell's environment (e.g., when a subshell is created).
+pub trait DynamicValue: Send + Sync {
+    /// Computes the current value.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell in which the value is being resolved.
+    fn get(&self, shell: &Shell) -> ShellValue;
+
+    /// Handles a scalar assignment to the variable. By default, assignments are
+    /// silently ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell in which the assignment is taking place.
+    /// * `value` - The value being assigned, after any attribute-driven transformations.
+    ///   For appending assignments (`+=`), this is the complete resulting value.
+    fn set(&mut self, shell: &mut Shell, value: String) -> Result<(), error::Error> {
+        let _ = (shell, value);
+        Ok(())
+    }
+
+    /// Handles an assignment to an element of the variable (e.g., `DIRSTACK[1]=dir`).
+    /// By default, assignments are silently ignored.
+    ///
+    /// # Arguments
+    ///
+    /// * `shell` - The shell in which the assignment is taking place.
+    /// * `index` - The index of the element being assigned.
+    /// * `value` - The value being assigned. For appending assignments (`+=`), this is
+    ///   the complete resulting value of the element.
+    fn set_at_index(
+        &mut self,
+        shell: &mut Shell,
+        index: &str,
+        value: String,
+    ) -> Result<(), error::Error> {
+        let _ = (shell, index, value);
+        Ok(())
+    }
+
+    /// Returns the value that an appending assignment (`+=`) extends. This is not
+    /// necessarily the current value: bash appends to the value retained from the
+    /// last assignment, if any. By default, appends extend the empty string.
+    fn append_base(&self) -> Option<String> {
+        None
+    }
+
+    /// Invoked before the variable is unset; returning an error prevents the variable
+    /// from being removed. Once removed, the variable loses its dynamic behavior.
+    fn unset(&mut self) -> Result<(), error::Error> {
+        Ok(())
+    }
+
+    /// Returns a boxed copy of this value, including any state it holds.
+    fn clone_box(&self) -> Box<dyn DynamicValue>;
+}
+
+impl Clone for Box<dyn DynamicValue> {
+    fn clone(&self) -> Self {
+        self.clone_box()
+    }
+}
+
+impl std::fmt::Debug for dyn DynamicValue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("<dynamic>")
+    }
+}
+
+/// A read-only dynamic value computed by a plain function; assignments are ignored.
+#[derive(Clone, Copy)]
+pub struct ComputedValue(pub fn(&Shell) -> ShellValue);
+
+impl DynamicValue for ComputedValue {
+    fn get(&self, shell: &Shell) -> ShellValue {
+        (self.0)(shell)
+    }
+
+    fn clone_box(&self) -> Box<dyn DynamicValue> {
+        Box::new(*self)
+    }
+}
 
 /// A shell value.
 #[derive(Clone, Debug)]
@@ -600,12 +774,7 @@ pub enum ShellValue {
     /// An indexed array.
     IndexedArray(BTreeMap<u64, String>),
     /// A value that is dynamically computed.
-    Dynamic {
-        /// Function that can query the value.
-        getter: DynamicValueGetter,
-        /// Function that receives value update requests.
-        setter: DynamicValueSetter,
-    },
+    Dynamic(Box<dyn DynamicValue>),
 }
 
 /// The type of an unset shell value.
@@ -696,6 +865,24 @@ pub enum FormatStyle {
 }
 
 impl ShellValue {
+    /// Returns a new dynamic value backed by the given implementation.
+    ///
+    /// # Arguments
+    ///
+    /// * `value` - The implementation of the dynamic value.
+    pub fn dynamic(value: impl DynamicValue + 'static) -> Self {
+        Self::Dynamic(Box::new(value))
+    }
+
+    /// Returns a new read-only dynamic value computed by the given function.
+    ///
+    /// # Arguments
+    ///
+    /// * `getter` - The function that computes the value.
+    pub fn computed(getter: fn(&Shell) -> Self) -> Self {
+        Self::dynamic(ComputedValue(getter))
+    }
+
     /// Returns whether or not the value is an array.
     pub const fn is_array(&self) -> bool {
         matches!(
@@ -867,8 +1054,8 @@ impl ShellValue {
                 result.push(')');
                 Ok(result.into())
             }
-            Self::Dynamic { getter, .. } => {
-                let dynamic_value = getter(shell);
+            Self::Dynamic(dynamic) => {
+                let dynamic_value = dynamic.get(shell);
                 let result = dynamic_value.format(style, shell)?.to_string();
                 Ok(result.into())
             }
@@ -911,8 +1098,8 @@ impl ShellValue {
 
                 Ok(values.get(&index_value).map(|s| Cow::Borrowed(s.as_str())))
             }
-            Self::Dynamic { getter, .. } => {
-                let dynamic_value = getter(shell);
+            Self::Dynamic(dynamic) => {
+                let dynamic_value = dynamic.get(shell);
                 let result = dynamic_value.get_at(index, shell)?;
                 Ok(result.map(|s| s.to_string().into()))
             }
@@ -926,7 +1113,7 @@ impl ShellValue {
             Self::String(_) => vec!["0".to_owned()],
             Self::AssociativeArray(array) => array.keys().map(|k| k.to_owned()).collect(),
             Self::IndexedArray(array) => array.keys().map(|k| k.to_string()).collect(),
-            Self::Dynamic { getter, .. } => getter(shell).element_keys(shell),
+            Self::Dynamic(dynamic) => dynamic.get(shell).element_keys(shell),
         }
     }
 
@@ -937,7 +1124,7 @@ impl ShellValue {
             Self::String(s) => vec![s.to_owned()],
             Self::AssociativeArray(array) => array.values().map(|v| v.to_owned()).collect(),
             Self::IndexedArray(array) => array.values().map(|v| v.to_owned()).collect(),
-            Self::Dynamic { getter, .. } => getter(shell).element_values(shell),
+            Self::Dynamic(dynamic) => dynamic.get(shell).element_values(shell),
         }
     }
 
@@ -955,8 +1142,8 @@ impl ShellValue {
     /// or otherwise doesn't exist.
     pub fn try_get_cow_str(&self, shell: &Shell) -> Option<Cow<'_, str>> {
         match self {
-            Self::Dynamic { getter, .. } => {
-                let dynamic_value = getter(shell);
+            Self::Dynamic(dynamic) => {
+                let dynamic_value = dynamic.get(shell);
                 dynamic_value
                     .try_get_cow_str(shell)
                     .map(|s| s.to_string().into())
@@ -971,7 +1158,7 @@ impl ShellValue {
             Self::String(s) => Some(Cow::Borrowed(s.as_str())),
             Self::AssociativeArray(values) => values.get("0").map(|s| Cow::Borrowed(s.as_str())),
             Self::IndexedArray(values) => values.get(&0).map(|s| Cow::Borrowed(s.as_str())),
-            Self::Dynamic { .. } => None,
+            Self::Dynamic(_) => None,
         }
     }
 
@@ -997,7 +1184,7 @@ impl ShellValue {
                         .into_owned()
                 }
             }
-            Self::Dynamic { getter, .. } => getter(shell).to_assignable_str(index, shell),
+            Self::Dynamic(dynamic) => dynamic.get(shell).to_assignable_str(index, shell),
         }
     }
 }
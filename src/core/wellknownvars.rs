@@ -1,4 +1,4 @@
-use rand::Rng;
+use rand::{Rng, SeedableRng, rngs::StdRng};
 
 use super::{Shell, ShellValue, ShellVariable, error, sys, variables};
 
@@ -51,10 +51,9 @@ pub(crate) fn initialize_vars(
     }
 
     // BASHOPTS
-    let mut bashopts_var = ShellVariable::new(ShellValue::Dynamic {
-        getter: |shell| shell.options.shopt_optstr().into(),
-        setter: |_| (),
-    });
+    let mut bashopts_var = ShellVariable::new(ShellValue::computed(|shell| {
+        shell.options.shopt_optstr().into()
+    }));
     bashopts_var.set_readonly();
     shell.env.set_global("BASHOPTS", bashopts_var)?;
 
@@ -70,20 +69,17 @@ pub(crate) fn initialize_vars(
     // BASH_ALIASES
     shell.env.set_global(
         "BASH_ALIASES",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| {
-                let values = variables::ArrayLiteral(
-                    shell
-                        .aliases
-                        .iter()
-                        .map(|(k, v)| (Some(k.to_owned()), v.to_owned()))
-                        .collect::<Vec<_>>(),
-                );
-
-                ShellValue::associative_array_from_literals(values).unwrap()
-            },
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|shell| {
+            let values = variables::ArrayLiteral(
+                shell
+                    .aliases
+                    .iter()
+                    .map(|(k, v)| (Some(k.to_owned()), v.to_owned()))
+                    .collect::<Vec<_>>(),
+            );
+
+            ShellValue::associative_array_from_literals(values).unwrap()
+        })),
     )?;
 
     // TODO(vars): when extdebug is enabled, BASH_ARGC and BASH_ARGV are set to valid values
@@ -93,23 +89,15 @@ pub(crate) fn initialize_vars(
     // BASH_ARGV0
     shell.env.set_global(
         "BASH_ARGV0",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| {
-                let argv0 = shell.shell_name.as_deref().unwrap_or_default();
-                argv0.to_string().into()
-            },
-            // TODO(vars): implement updating BASH_ARGV0
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::dynamic(ArgV0Value::default())),
     )?;
 
     // TODO(vars): implement mutation of BASH_CMDS
     shell.env.set_global(
         "BASH_CMDS",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| shell.program_location_cache.to_value().unwrap(),
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|shell| {
+            shell.program_location_cache.to_value().unwrap()
+        })),
     )?;
 
     // TODO(vars): implement BASH_COMMAND
@@ -119,19 +107,15 @@ pub(crate) fn initialize_vars(
     // BASH_SOURCE
     shell.env.set_global(
         "BASH_SOURCE",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| get_bash_source_value(shell),
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(get_bash_source_value)),
     )?;
 
     // BASH_SUBSHELL
     shell.env.set_global(
         "BASH_SUBSHELL",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| shell.depth().to_string().into(),
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|shell| {
+            shell.depth().to_string().into()
+        })),
     )?;
 
     // BASH_VERSINFO
@@ -166,47 +150,31 @@ pub(crate) fn initialize_vars(
     // DIRSTACK
     shell.env.set_global(
         "DIRSTACK",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| {
-                shell
-                    .directory_stack
-                    .iter()
-                    .map(|p| p.to_string_lossy().to_string())
-                    .collect::<Vec<_>>()
-                    .into()
-            },
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::dynamic(DirStackValue)),
     )?;
 
     // EPOCHREALTIME
     shell.env.set_global(
         "EPOCHREALTIME",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |_shell| {
-                let now = std::time::SystemTime::now();
-                let since_epoch = now
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default();
-                since_epoch.as_secs_f64().to_string().into()
-            },
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|_shell| {
+            let now = std::time::SystemTime::now();
+            let since_epoch = now
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            since_epoch.as_secs_f64().to_string().into()
+        })),
     )?;
 
     // EPOCHSECONDS
     shell.env.set_global(
         "EPOCHSECONDS",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |_shell| {
-                let now = std::time::SystemTime::now();
-                let since_epoch = now
-                    .duration_since(std::time::UNIX_EPOCH)
-                    .unwrap_or_default();
-                since_epoch.as_secs().to_string().into()
-            },
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|_shell| {
+            let now = std::time::SystemTime::now();
+            let since_epoch = now
+                .duration_since(std::time::UNIX_EPOCH)
+                .unwrap_or_default();
+            since_epoch.as_secs().to_string().into()
+        })),
     )?;
 
     // EUID
@@ -219,10 +187,7 @@ pub(crate) fn initialize_vars(
     // FUNCNAME
     shell.env.set_global(
         "FUNCNAME",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| get_funcname_value(shell),
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(get_funcname_value)),
     )?;
 
     // GROUPS
@@ -230,26 +195,18 @@ pub(crate) fn initialize_vars(
     // don't have to make costly system calls if the user never accesses it.
     shell.env.set_global(
         "GROUPS",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |_shell| {
-                let groups = get_current_user_gids();
-                ShellValue::indexed_array_from_strings(
-                    groups.into_iter().map(|gid| gid.to_string()),
-                )
-            },
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|_shell| {
+            let groups = get_current_user_gids();
+            ShellValue::indexed_array_from_strings(groups.into_iter().map(|gid| gid.to_string()))
+        })),
     )?;
 
     // HISTCMD
-    let mut histcmd_var = ShellVariable::new(ShellValue::Dynamic {
-        getter: |shell| {
-            shell
-                .history()
-                .map_or_else(|| "0".into(), |h| h.count().to_string().into())
-        },
-        setter: |_| (),
-    });
+    let mut histcmd_var = ShellVariable::new(ShellValue::computed(|shell| {
+        shell
+            .history()
+            .map_or_else(|| "0".into(), |h| h.count().to_string().into())
+    }));
     histcmd_var.treat_as_integer();
     shell.env.set_global("HISTCMD", histcmd_var)?;
 
@@ -286,10 +243,7 @@ pub(crate) fn initialize_vars(
     // LINENO
     shell.env.set_global(
         "LINENO",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| shell.current_line_number().to_string().into(),
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::dynamic(LineNumberValue::default())),
     )?;
 
     // MACHTYPE
@@ -336,14 +290,11 @@ pub(crate) fn initialize_vars(
     // TODO: Investigate if this needs to be saved/preserved across prompt display.
     shell.env.set_global(
         "PIPESTATUS",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| {
-                ShellValue::indexed_array_from_strings(
-                    shell.last_pipeline_statuses.iter().map(|s| s.to_string()),
-                )
-            },
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::computed(|shell| {
+            ShellValue::indexed_array_from_strings(
+                shell.last_pipeline_statuses.iter().map(|s| s.to_string()),
+            )
+        })),
     )?;
 
     // PPID
@@ -354,28 +305,14 @@ pub(crate) fn initialize_vars(
     }
 
     // RANDOM
-    let mut random_var = ShellVariable::new(ShellValue::Dynamic {
-        getter: get_random_value,
-        setter: |_| (),
-    });
+    let mut random_var = ShellVariable::new(ShellValue::dynamic(RandomValue::default()));
     random_var.treat_as_integer();
     shell.env.set_global("RANDOM", random_var)?;
 
     // SECONDS
     shell.env.set_global(
         "SECONDS",
-        ShellVariable::new(ShellValue::Dynamic {
-            getter: |shell| {
-                let now = std::time::SystemTime::now();
-                let since_last = now
-                    .duration_since(shell.last_stopwatch_time())
-                    .unwrap_or_default();
-                let total_seconds = since_last.as_secs() + u64::from(shell.last_stopwatch_offset());
-                total_seconds.to_string().into()
-            },
-            // TODO(vars): implement updating SECONDS
-            setter: |_| (),
-        }),
+        ShellVariable::new(ShellValue::dynamic(SecondsValue::default())),
     )?;
 
     // SHELL
@@ -387,10 +324,9 @@ pub(crate) fn initialize_vars(
     }
 
     // SHELLOPTS
-    let mut shellopts_var = ShellVariable::new(ShellValue::Dynamic {
-        getter: |shell| shell.options.seto_optstr().into(),
-        setter: |_| (),
-    });
+    let mut shellopts_var = ShellVariable::new(ShellValue::computed(|shell| {
+        shell.options.seto_optstr().into()
+    }));
     shellopts_var.set_readonly();
     shell.env.set_global("SHELLOPTS", shellopts_var)?;
 
@@ -402,10 +338,7 @@ pub(crate) fn initialize_vars(
     shell.env.set_global("SHLVL", shlvl_var)?;
 
     // SRANDOM
-    let mut random_var = ShellVariable::new(ShellValue::Dynamic {
-        getter: get_srandom_value,
-        setter: |_| (),
-    });
+    let mut random_var = ShellVariable::new(ShellValue::computed(get_srandom_value));
     random_var.treat_as_integer();
     shell.env.set_global("SRANDOM", random_var)?;
 
@@ -466,13 +399,6 @@ fn get_current_user_gids() -> Vec<u32> {
     groups
 }
 
-fn get_random_value(_shell: &Shell) -> ShellValue {
-    let mut rng = rand::rng();
-    let num = rng.random_range(0..32768);
-    let str = num.to_string();
-    str.into()
-}
-
 fn get_srandom_value(_shell: &Shell) -> ShellValue {
     let mut rng = rand::rng();
     let num: u32 = rng.random();
@@ -508,3 +434,280 @@ fn get_bash_source_value(shell: &Shell) -> variables::ShellValue {
             .into()
     }
 }
+
+/// Implements `BASH_ARGV0`; assignments override the shell name reported by it and
+/// by `$0`.
+#[derive(Clone, Default)]
+struct ArgV0Value {
+    name: Option<String>,
+}
+
+impl variables::DynamicValue for ArgV0Value {
+    fn get(&self, shell: &Shell) -> ShellValue {
+        self.name
+            .as_deref()
+            .or(shell.shell_name.as_deref())
+            .unwrap_or_default()
+            .into()
+    }
+
+    fn set(&mut self, _shell: &mut Shell, value: String) -> Result<(), error::Error> {
+        self.name = Some(value);
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn variables::DynamicValue> {
+        Box::new(self.clone())
+    }
+}
+
+/// Implements `DIRSTACK`; assigning to an element of it updates the corresponding
+/// entry of the directory stack.
+#[derive(Clone, Copy)]
+struct DirStackValue;
+
+impl variables::DynamicValue for DirStackValue {
+    fn get(&self, shell: &Shell) -> ShellValue {
+        shell
+            .directory_stack_entries()
+            .into_iter()
+            .map(|p| p.to_string_lossy().to_string())
+            .collect::<Vec<_>>()
+            .into()
+    }
+
+    fn set_at_index(
+        &mut self,
+        shell: &mut Shell,
+        index: &str,
+        value: String,
+    ) -> Result<(), error::Error> {
+        if let Ok(index) = index.parse::<usize>() {
+            shell.set_directory_stack_entry(index, std::path::PathBuf::from(value));
+        }
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn variables::DynamicValue> {
+        Box::new(*self)
+    }
+}
+
+/// Implements `LINENO`. An assignment sets the value reported for the current line;
+/// subsequent lines are numbered relative to it.
+#[derive(Clone, Default)]
+struct LineNumberValue {
+    /// The assigned value, along with the line at which it was assigned.
+    assigned: Option<(i64, u32)>,
+}
+
+impl variables::DynamicValue for LineNumberValue {
+    fn get(&self, shell: &Shell) -> ShellValue {
+        let current = shell.current_line_number();
+        let line = match self.assigned {
+            Some((value, anchor)) => value + i64::from(current) - i64::from(anchor),
+            None => i64::from(current),
+        };
+
+        line.to_string().into()
+    }
+
+    fn set(&mut self, shell: &mut Shell, value: String) -> Result<(), error::Error> {
+        self.assigned = Some((value.parse().unwrap_or(0), shell.current_line_number()));
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn variables::DynamicValue> {
+        Box::new(self.clone())
+    }
+}
+
+/// Implements `RANDOM`; assigning a value seeds the generator so that subsequent
+/// values are reproducible.
+#[derive(Default)]
+struct RandomValue {
+    seeded_rng: std::sync::Mutex<Option<StdRng>>,
+}
+
+impl variables::DynamicValue for RandomValue {
+    fn get(&self, _shell: &Shell) -> ShellValue {
+        let mut seeded_rng = self
+            .seeded_rng
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner);
+
+        let num = match seeded_rng.as_mut() {
+            Some(rng) => rng.random_range(0..32768),
+            None => rand::rng().random_range(0..32768),
+        };
+
+        num.to_string().into()
+    }
+
+    fn set(&mut self, _shell: &mut Shell, value: String) -> Result<(), error::Error> {
+        let seed = value.parse::<i64>().unwrap_or(0).cast_unsigned();
+        *self
+            .seeded_rng
+            .get_mut()
+            .unwrap_or_else(std::sync::PoisonError::into_inner) = Some(StdRng::seed_from_u64(seed));
+        Ok(())
+    }
+
+    fn clone_box(&self) -> Box<dyn variables::DynamicValue> {
+        let seeded_rng = self
+            .seeded_rng
+            .lock()
+            .unwrap_or_else(std::sync::PoisonError::into_inner)
+            .clone();
+
+        Box::new(Self {
+            seeded_rng: std::sync::Mutex::new(seeded_rng),
+        })
+    }
+}
+
+/// Implements `SECONDS`; assigning a value restarts the count from that value.
+#[derive(Clone, Default)]
+struct SecondsValue {
+    /// When the count was last restarted by an assignment, along with the value assigned.
+    restarted: Option<(std::time::SystemTime, String)>,
+}
+
+impl variables::DynamicValue for SecondsValue {
+    fn get(&self, shell: &Shell) -> ShellValue {
+        let (start, offset) = self.restarted.as_ref().map_or_else(
+            || {
+                (
+                    shell.last_stopwatch_time(),
+                    i64::from(shell.last_stopwatch_offset()),
+                )
+            },
+            |(restarted_at, value)| (*restarted_at, value.parse().unwrap_or(0)),
+        );
+
+        let elapsed = std::time::SystemTime::now()
+            .duration_since(start)
+            .unwrap_or_default()
+            .as_secs();
+        let elapsed = i64::try_from(elapsed).unwrap_or(i64::MAX);
+
+        (elapsed + offset).to_string().into()
+    }
+
+    fn set(&mut self, _shell: &mut Shell, value: String) -> Result<(), error::Error> {
+        self.restarted = Some((std::time::SystemTime::now(), value));
+        Ok(())
+    }
+
+    fn append_base(&self) -> Option<String> {
+        self.restarted.as_ref().map(|(_, value)| value.clone())
+    }
+
+    fn clone_box(&self) -> Box<dyn variables::DynamicValue> {
+        Box::new(self.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+
+    async fn expand(shell: &mut Shell, s: &str) -> Result<String> {
+        let params = shell.default_exec_params();
+        Ok(crate::core::expansion::basic_expand_str(shell, &params, s).await?)
+    }
+
+    #[tokio::test]
+    async fn random_is_reproducible_after_seeding() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell.exec("RANDOM=42", &params).await?;
+        let first = expand(&mut shell, "$RANDOM $RANDOM").await?;
+        shell.exec("RANDOM=42", &params).await?;
+        let second = expand(&mut shell, "$RANDOM $RANDOM").await?;
+        assert_eq!(first, second);
+
+        // Unsetting the variable removes its dynamic behavior.
+        shell.exec("unset RANDOM; RANDOM=7", &params).await?;
+        assert_eq!(expand(&mut shell, "$RANDOM $RANDOM").await?, "7 7");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn seconds_can_be_reset_and_appended() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell.exec("SECONDS=100", &params).await?;
+        assert_eq!(expand(&mut shell, "$SECONDS").await?, "100");
+
+        // As in bash, appending extends the last value assigned.
+        shell.exec("SECONDS+=5", &params).await?;
+        assert_eq!(expand(&mut shell, "$SECONDS").await?, "1005");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn assignments_to_read_only_dynamic_values_are_ignored() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell.exec("EPOCHSECONDS=5", &params).await?;
+        assert_ne!(expand(&mut shell, "$EPOCHSECONDS").await?, "5");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn bash_argv0_updates_shell_name() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell.exec("BASH_ARGV0=custom", &params).await?;
+        assert_eq!(expand(&mut shell, "$0 $BASH_ARGV0").await?, "custom custom");
+
+        // As in bash, appending doesn't extend the name previously assigned.
+        shell.exec("BASH_ARGV0+=suffix", &params).await?;
+        assert_eq!(expand(&mut shell, "$0 $BASH_ARGV0").await?, "suffix suffix");
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn dirstack_elements_can_be_assigned() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+        let original_dir = expand(&mut shell, "$PWD").await?;
+
+        shell.exec("pushd / >/dev/null", &params).await?;
+        shell.exec("DIRSTACK[1]+=/suffix", &params).await?;
+        assert_eq!(
+            expand(&mut shell, "${DIRSTACK[1]}").await?,
+            format!("{original_dir}/suffix")
+        );
+
+        Ok(())
+    }
+
+    #[tokio::test]
+    async fn lineno_can_be_assigned() -> Result<()> {
+        let mut shell = crate::Shell::builder().build().await?;
+        let params = shell.default_exec_params();
+
+        shell.exec("LINENO=10; x=$LINENO", &params).await?;
+        shell.exec("y=$LINENO", &params).await?;
+        assert_eq!(expand(&mut shell, "$x $y").await?, "10 11");
+
+        // Lines executed between the assignment and the first read are counted.
+        shell.exec("LINENO=20", &params).await?;
+        shell.exec("true", &params).await?;
+        shell.exec("z=$LINENO", &params).await?;
+        assert_eq!(expand(&mut shell, "$z").await?, "22");
+
+        Ok(())
+    }
+}
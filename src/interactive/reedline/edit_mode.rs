@@ -1,5 +1,9 @@
 use crate::core::interfaces::{self, InputFunction, Key, KeyAction, KeySequence, KeyStroke};
-use std::{collections::HashMap, sync::Arc};
+use crossterm::event::{Event, KeyEvent};
+use std::{
+    collections::{BTreeMap, HashMap},
+    sync::Arc,
+};
 use tokio::sync::Mutex;
 
 #[derive(thiserror::Error, Debug)]
@@ -47,8 +51,24 @@ impl reedline::EditMode for MutableEditMode {
     }
 }
 
+/// A single key press, as reedline sees it.
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+struct KeyCombination {
+    modifier: reedline::KeyModifiers,
+    key_code: reedline::KeyCode,
+}
+
 pub(crate) struct UpdatableBindings {
     bindings: reedline::Keybindings,
+    /// Bindings made via `bind` et al., along with the actions they were made with;
+    /// not all actions can be recovered from the reedline events they translate to.
+    custom_bindings: HashMap<KeySequence, KeyAction>,
+    /// Bindings for multi-stroke key sequences, which reedline doesn't natively support.
+    chords: HashMap<Vec<KeyCombination>, reedline::ReedlineEvent>,
+    /// Key events seen so far that form a prefix of one or more chords.
+    pending: Vec<(KeyCombination, Event)>,
+    /// Current values of input variables.
+    variables: BTreeMap<String, String>,
     edit_mode: Box<dyn reedline::EditMode>,
 }
 
@@ -57,8 +77,17 @@ impl UpdatableBindings {
         // Clone the bindings so we can keep a copy for later updates.
         let edit_mode = Self::rebuild_edit_mode(&bindings);
 
+        let variables = interfaces::INPUT_VARIABLE_DEFAULTS
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect();
+
         Self {
             bindings,
+            custom_bindings: HashMap::new(),
+            chords: HashMap::new(),
+            pending: vec![],
+            variables,
             edit_mode,
         }
     }
@@ -71,11 +100,102 @@ impl UpdatableBindings {
     fn rebuild_edit_mode(bindings: &reedline::Keybindings) -> Box<dyn reedline::EditMode> {
         Box::new(reedline::Emacs::new(bindings.clone()))
     }
+
+    /// Feeds a key event into chord matching. Returns the event to act on, or `None`
+    /// if the key was consumed as (part of) a chord.
+    fn parse_chord_event(&mut self, event: Event) -> Option<reedline::ReedlineEvent> {
+        let Event::Key(KeyEvent {
+            code, modifiers, ..
+        }) = &event
+        else {
+            return Some(self.flush_pending_then(event));
+        };
+
+        self.pending
+            .push((normalize_key_combination(*modifiers, *code), event));
+
+        let keys: Vec<_> = self.pending.iter().map(|(key, _)| key.clone()).collect();
+
+        if let Some(chord_event) = self.chords.get(&keys) {
+            let chord_event = chord_event.clone();
+            self.pending.clear();
+            return Some(chord_event);
+        }
+
+        if self.chords.keys().any(|chord| chord.starts_with(&keys)) {
+            return None;
+        }
+
+        // Not a chord after all; process the pending keys as usual.
+        let pending = std::mem::take(&mut self.pending);
+        let mut events: Vec<_> = pending
+            .into_iter()
+            .filter_map(|(_, event)| self.parse_plain_event(event))
+            .collect();
+
+        if events.len() == 1 {
+            events.pop()
+        } else {
+            Some(reedline::ReedlineEvent::Multiple(events))
+        }
+    }
+
+    fn flush_pending_then(&mut self, event: Event) -> reedline::ReedlineEvent {
+        let pending = std::mem::take(&mut self.pending);
+        let mut events: Vec<_> = pending
+            .into_iter()
+            .map(|(_, event)| event)
+            .chain(std::iter::once(event))
+            .filter_map(|event| self.parse_plain_event(event))
+            .collect();
+
+        if events.len() == 1 {
+            events.pop().unwrap_or(reedline::ReedlineEvent::None)
+        } else {
+            reedline::ReedlineEvent::Multiple(events)
+        }
+    }
+
+    fn parse_plain_event(&mut self, event: Event) -> Option<reedline::ReedlineEvent> {
+        reedline::ReedlineRawEvent::try_from(event)
+            .ok()
+            .map(|event| self.edit_mode.parse_event(event))
+    }
+
+    /// Translates the keys of a macro into the events they would trigger if typed.
+    fn translate_macro(&self, text: &str) -> Option<reedline::ReedlineEvent> {
+        let seq = crate::core::inputrc::parse_key_sequence(&std::format!("\"{text}\"")).ok()?;
+        let keys = translate_key_sequence_to_reedline(&seq)?;
+
+        let events = keys
+            .into_iter()
+            .map(|key| {
+                self.bindings
+                    .find_binding(key.modifier, key.key_code)
+                    .unwrap_or_else(|| match key.key_code {
+                        reedline::KeyCode::Char(c) if key.modifier.is_empty() => {
+                            reedline::ReedlineEvent::Edit(vec![reedline::EditCommand::InsertChar(
+                                c,
+                            )])
+                        }
+                        reedline::KeyCode::Enter => reedline::ReedlineEvent::Enter,
+                        _ => reedline::ReedlineEvent::None,
+                    })
+            })
+            .collect();
+
+        Some(reedline::ReedlineEvent::Multiple(events))
+    }
 }
 
 impl reedline::EditMode for UpdatableBindings {
     fn parse_event(&mut self, event: reedline::ReedlineRawEvent) -> reedline::ReedlineEvent {
-        self.edit_mode.parse_event(event)
+        if self.chords.is_empty() {
+            return self.edit_mode.parse_event(event);
+        }
+
+        self.parse_chord_event(event.into())
+            .unwrap_or(reedline::ReedlineEvent::None)
     }
 
     fn edit_mode(&self) -> reedline::PromptEditMode {
@@ -117,64 +237,143 @@ impl interfaces::KeyBindings for UpdatableBindings {
                 }
         }
 
+        // Bindings made explicitly take precedence over what we could infer.
+        for (seq, action) in &self.custom_bindings {
+            results.insert(seq.clone(), action.clone());
+        }
+
         results
     }
 
     fn bind(&mut self, seq: KeySequence, action: KeyAction) -> Result<(), std::io::Error> {
-        let Some((modifiers, key_code)) = translate_key_sequence_to_reedline(&seq) else {
+        let Some(keys) = translate_key_sequence_to_reedline(&seq) else {
             return Err(std::io::Error::other(KeyError::UnsupportedKeySequence(seq)));
         };
 
-        let Some(event) = translate_action_to_reedline_event(&action) else {
+        let event = if let KeyAction::InsertMacro(text) = &action {
+            self.translate_macro(text)
+        } else {
+            translate_action_to_reedline_event(&action)
+        };
+
+        let Some(event) = event else {
             return Err(std::io::Error::other(KeyError::UnsupportedKeyAction(
                 action,
             )));
         };
 
-        self.update(|bindings| {
-            bindings.add_binding(modifiers, key_code, event.clone());
-        });
+        if let [key] = keys.as_slice() {
+            self.update(|bindings| {
+                bindings.add_binding(key.modifier, key.key_code, event.clone());
+            });
+        } else {
+            self.chords.insert(keys, event);
+        }
+
+        self.custom_bindings.insert(seq, action);
+
+        Ok(())
+    }
+
+    fn unbind(&mut self, seq: &KeySequence) -> Result<(), std::io::Error> {
+        let Some(keys) = translate_key_sequence_to_reedline(seq) else {
+            return Err(std::io::Error::other(KeyError::UnsupportedKeySequence(
+                seq.clone(),
+            )));
+        };
+
+        if let [key] = keys.as_slice() {
+            self.update(|bindings| {
+                bindings.remove_binding(key.modifier, key.key_code);
+            });
+        } else {
+            self.chords.remove(&keys);
+        }
+
+        self.custom_bindings.remove(seq);
+
+        Ok(())
+    }
+
+    fn get_variables(&self) -> BTreeMap<String, String> {
+        self.variables.clone()
+    }
+
+    fn set_variable(&mut self, name: &str, value: &str) -> Result<(), std::io::Error> {
+        // NOTE(vi): We don't support vi mode; quietly stay in emacs mode.
+        if name == "editing-mode" && value != "emacs" {
+            return Ok(());
+        }
+
+        self.variables.insert(name.to_owned(), value.to_owned());
 
         Ok(())
     }
 }
 
-fn translate_key_sequence_to_reedline(
-    seq: &KeySequence,
-) -> Option<(reedline::KeyModifiers, reedline::KeyCode)> {
-    if seq.strokes.len() != 1 {
-        // TODO: handle multiple strokes
+/// Normalizes a key combination the same way reedline's emacs mode does when looking
+/// up bindings: characters typed with modifiers are matched in lowercase.
+fn normalize_key_combination(
+    mut modifier: reedline::KeyModifiers,
+    key_code: reedline::KeyCode,
+) -> KeyCombination {
+    let key_code = match key_code {
+        reedline::KeyCode::Char(c) => {
+            modifier.remove(reedline::KeyModifiers::SHIFT);
+            if modifier.is_empty() {
+                reedline::KeyCode::Char(c)
+            } else {
+                reedline::KeyCode::Char(c.to_ascii_lowercase())
+            }
+        }
+        other => other,
+    };
+
+    KeyCombination { modifier, key_code }
+}
+
+fn translate_key_sequence_to_reedline(seq: &KeySequence) -> Option<Vec<KeyCombination>> {
+    if seq.strokes.is_empty() {
         return None;
     }
 
-    let stroke = &seq.strokes[0];
-
-    let mut modifiers = reedline::KeyModifiers::empty();
-    modifiers.set(reedline::KeyModifiers::ALT, stroke.alt);
-    modifiers.set(reedline::KeyModifiers::CONTROL, stroke.control);
-    modifiers.set(reedline::KeyModifiers::SHIFT, stroke.shift);
-
-    let key_code = match stroke.key {
-        Key::Character(c) => reedline::KeyCode::Char(c),
-        Key::Backspace => reedline::KeyCode::Backspace,
-        Key::Enter => reedline::KeyCode::Enter,
-        Key::Left => reedline::KeyCode::Left,
-        Key::Right => reedline::KeyCode::Right,
-        Key::Up => reedline::KeyCode::Up,
-        Key::Down => reedline::KeyCode::Down,
-        Key::Home => reedline::KeyCode::Home,
-        Key::End => reedline::KeyCode::End,
-        Key::PageUp => reedline::KeyCode::PageUp,
-        Key::PageDown => reedline::KeyCode::PageDown,
-        Key::Tab => reedline::KeyCode::Tab,
-        Key::BackTab => reedline::KeyCode::BackTab,
-        Key::Delete => reedline::KeyCode::Delete,
-        Key::Insert => reedline::KeyCode::Insert,
-        Key::F(n) => reedline::KeyCode::F(n),
-        Key::Escape => reedline::KeyCode::Esc,
-    };
+    let keys = seq
+        .strokes
+        .iter()
+        .map(|stroke| {
+            let mut modifiers = reedline::KeyModifiers::empty();
+            modifiers.set(reedline::KeyModifiers::ALT, stroke.alt);
+            modifiers.set(reedline::KeyModifiers::CONTROL, stroke.control);
+            modifiers.set(reedline::KeyModifiers::SHIFT, stroke.shift);
+
+            let key_code = match stroke.key {
+                Key::Character(c) => reedline::KeyCode::Char(c),
+                Key::Backspace => reedline::KeyCode::Backspace,
+                Key::Enter => reedline::KeyCode::Enter,
+                Key::Left => reedline::KeyCode::Left,
+                Key::Right => reedline::KeyCode::Right,
+                Key::Up => reedline::KeyCode::Up,
+                Key::Down => reedline::KeyCode::Down,
+                Key::Home => reedline::KeyCode::Home,
+                Key::End => reedline::KeyCode::End,
+                Key::PageUp => reedline::KeyCode::PageUp,
+                Key::PageDown => reedline::KeyCode::PageDown,
+                Key::Tab => reedline::KeyCode::Tab,
+                Key::BackTab => reedline::KeyCode::BackTab,
+                Key::Delete => reedline::KeyCode::Delete,
+                Key::Insert => reedline::KeyCode::Insert,
+                Key::F(n) => reedline::KeyCode::F(n),
+                Key::Escape => reedline::KeyCode::Esc,
+            };
+
+            KeyCombination {
+                modifier: modifiers,
+                key_code,
+            }
+        })
+        .collect();
 
-    Some((modifiers, key_code))
+    Some(keys)
 }
 
 fn translate_action_to_reedline_event(action: &KeyAction) -> Option<reedline::ReedlineEvent> {
@@ -183,6 +382,8 @@ fn translate_action_to_reedline_event(action: &KeyAction) -> Option<reedline::Re
             format_reedline_host_command(cmd.as_str()),
         )),
         KeyAction::DoInputFunction(func) => translate_input_function_to_reedline_event(func),
+        // Macros are translated against the current bindings; see `translate_macro`.
+        KeyAction::InsertMacro(_) => None,
     }
 }
 
@@ -0,0 +1,40 @@
+use crate::interactive::interactive_shell::InteractivePrompt;
+
+/// Line editor made available to builtins (e.g., `read -e`). Each read uses a fresh
+/// reedline instance that doesn't consult the shell, since the shell is busy running
+/// the builtin that requested input.
+pub(crate) struct ReedlineLineEditor {
+    disable_color: bool,
+}
+
+impl ReedlineLineEditor {
+    pub(crate) const fn new(disable_color: bool) -> Self {
+        Self { disable_color }
+    }
+}
+
+impl crate::core::interfaces::LineEditor for ReedlineLineEditor {
+    fn read_line(
+        &mut self,
+        prompt: &str,
+        initial_text: &str,
+    ) -> Result<Option<String>, std::io::Error> {
+        let mut reedline = reedline::Reedline::create().with_ansi_colors(!self.disable_color);
+        if !initial_text.is_empty() {
+            reedline.run_edit_commands(&[reedline::EditCommand::InsertString(
+                initial_text.to_owned(),
+            )]);
+        }
+
+        let prompt = InteractivePrompt {
+            prompt: prompt.to_owned(),
+            alt_side_prompt: String::new(),
+            continuation_prompt: String::new(),
+        };
+
+        match reedline.read_line(&prompt)? {
+            reedline::Signal::Success(line) => Ok(Some(line)),
+            _ => Ok(None),
+        }
+    }
+}
@@ -2,6 +2,7 @@ mod completer;
 mod edit_mode;
 mod highlighter;
 mod history;
+mod line_editor;
 mod prompt;
 mod reedline_shell;
 mod refs;
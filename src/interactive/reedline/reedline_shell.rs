@@ -3,7 +3,7 @@ use reedline::MenuBuilder;
 use std::sync::Arc;
 use tokio::sync::Mutex;
 
-use super::{completer, edit_mode, highlighter, history, refs, validator};
+use super::{completer, edit_mode, highlighter, history, line_editor, refs, validator};
 use crate::interactive::{InteractiveShell, ReadResult, ShellError, interactive_shell::InteractivePrompt};
 
 /// Represents an interactive shell capable of taking commands from standard input
@@ -30,10 +30,25 @@ impl ReedlineShell {
         let updatable_bindings = mutable_edit_mode.bindings();
         options.shell.key_bindings = Some(updatable_bindings);
 
+        // Make a line editor available to builtins like `read -e`.
+        options.shell.line_editor = Some(Arc::new(Mutex::new(
+            line_editor::ReedlineLineEditor::new(options.disable_color),
+        )));
+
         // Set up shell first. Its initialization may influence how the
         // editor needs to operate.
         let shell = crate::core::Shell::new(options.shell).await?;
 
+        // Apply the user's readline configuration (e.g., ~/.inputrc), if any.
+        match crate::core::inputrc::load_user_config(&shell).await {
+            Ok(diagnostics) => {
+                for diagnostic in diagnostics {
+                    tracing::warn!("{diagnostic}");
+                }
+            }
+            Err(e) => tracing::warn!("failed to load input configuration: {e}"),
+        }
+
         // Wrap the shell in an Arc<Mutex> so we can share it with the helper
         // objects we'll need to set up for reedline.
         let shell_ref = Arc::new(Mutex::new(shell));
@@ -38,10 +38,10 @@ pub mod shell;
 pub mod prelude {
     // Core shell types
     pub use crate::core::{
-        CommandArg, CreateOptions, Error, ErrorKind, ExecutionContext, ExecutionControlFlow,
-        ExecutionExitCode, ExecutionParameters, ExecutionResult, ExecutionSpawnResult,
-        ProcessGroupPolicy, Shell, ShellBuilder, ShellBuilderState, ShellFd, ShellValue,
-        ShellVariable, BuiltinError, OutputStreamType, StreamingOutput,
+        CommandArg, CreateOptions, DynamicValue, Error, ErrorKind, ExecutionContext,
+        ExecutionControlFlow, ExecutionExitCode, ExecutionParameters, ExecutionResult,
+        ExecutionSpawnResult, ProcessGroupPolicy, Shell, ShellBuilder, ShellBuilderState,
+        ShellFd, ShellValue, ShellVariable, BuiltinError, OutputStreamType, StreamingOutput,
     };
 
     // Builtin command infrastructure
@@ -78,7 +78,7 @@ pub use tokio_util::sync::CancellationToken;
 
 // Re-export commonly used types from core at crate root
 pub use core::{
-    BuiltinError, CommandArg, CreateOptions, Error, ErrorKind, ExecutionContext,
+    BuiltinError, CommandArg, CreateOptions, DynamicValue, Error, ErrorKind, ExecutionContext,
     ExecutionControlFlow, ExecutionExitCode, ExecutionParameters, ExecutionResult,
     ExecutionSpawnResult, ProcessGroupPolicy, Shell, ShellBuilder, ShellBuilderState, ShellFd,
     ShellValue, ShellVariable, OutputStreamType, StreamingOutput,
@@ -0,0 +1,612 @@
+//! Implements alias expansion over tokenized input.
+//!
+//! Aliases are expanded after tokenization but before parsing, following bash's rules: the
+//! first word of each simple command is checked against the defined aliases, and, if it
+//! matches and is unquoted, is replaced with the tokens of the alias's value. Because the
+//! replacement happens before parsing, an alias may expand to reserved words, operators, or
+//! multiple commands. If an alias's value ends in a blank, the word following the alias is
+//! also checked. An alias is never expanded again while its own value is being expanded.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use super::tokenizer::{self, SourcePosition, Token, TokenLocation, TokenizerOptions};
+
+/// Expands aliases within the given tokens, returning the resulting tokens.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to expand aliases within.
+/// * `aliases` - The aliases to expand, mapping names to their (unparsed) values.
+/// * `options` - The options to use when tokenizing the values of aliases.
+pub(crate) fn expand_aliases(
+    tokens: Vec<Token>,
+    aliases: &HashMap<String, String>,
+    options: &TokenizerOptions,
+) -> Vec<Token> {
+    if aliases.is_empty() {
+        return tokens;
+    }
+
+    let mut expander = AliasExpander::new(aliases, options);
+    expander.extend(tokens);
+    expander.output
+}
+
+/// An in-progress expansion of an alias.
+struct Expansion {
+    /// Name of the alias being expanded.
+    name: String,
+    /// The remaining tokens of the alias's value.
+    tokens: std::vec::IntoIter<Token>,
+    /// Whether the alias's value ends in a blank.
+    trailing_blank: bool,
+}
+
+/// A construct that changes how the words within it are interpreted.
+enum Frame {
+    /// A parenthesized subshell, process substitution, or function definition's parens.
+    Paren {
+        /// Whether no tokens have been seen since the opening paren.
+        empty: bool,
+        /// Whether the opening paren may start an arithmetic command (`((`).
+        arithmetic_candidate: bool,
+        /// The index just past the opening paren.
+        end_index: usize,
+    },
+    /// An arithmetic command or arithmetic for loop header, with its paren nesting depth.
+    Arithmetic(usize),
+    /// The elements of an array assignment.
+    ArrayElements,
+    /// An extended test command (`[[ ... ]]`).
+    ExtendedTest,
+    /// A case command.
+    Case(CasePhase),
+}
+
+/// The part of a case command being processed.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum CasePhase {
+    /// Expecting the word being matched.
+    Subject,
+    /// Expecting `in`.
+    In,
+    /// Within the patterns of a case item.
+    Patterns,
+    /// Within the commands of a case item.
+    Body,
+}
+
+/// Expands aliases within a stream of tokens, which may be supplied piecemeal (e.g., one
+/// line at a time).
+pub(crate) struct AliasExpander<'a> {
+    aliases: &'a HashMap<String, String>,
+    options: &'a TokenizerOptions,
+    output: Vec<Token>,
+    /// Stack of in-progress alias expansions, innermost last.
+    expansions: Vec<Expansion>,
+    /// Stack of enclosing constructs, innermost last.
+    frames: Vec<Frame>,
+    /// Whether the next word is in command position.
+    command_position: bool,
+    /// Whether the next word follows an alias whose value ended in a blank.
+    check_next_word: bool,
+    /// Number of upcoming words that are redirection targets (or here-document contents).
+    skip_words: usize,
+    /// Whether we're in the header of a for (or select) command.
+    for_header: bool,
+    /// Whether the next word is the name in a function definition using `function`.
+    function_name: bool,
+    /// Whether the previous word was the `time` reserved word.
+    after_time: bool,
+    /// The index just past the previous token, if it was a word ending in `=`.
+    assignment_end_index: Option<usize>,
+    /// The index just past the previous token, if it was a word made up of digits (which
+    /// may be the file descriptor number of a redirection), along with whether that word
+    /// was in command position.
+    io_number: Option<(usize, bool)>,
+}
+
+impl<'a> AliasExpander<'a> {
+    /// Returns a new expander, positioned at the start of a command.
+    ///
+    /// # Arguments
+    ///
+    /// * `aliases` - The aliases to expand, mapping names to their (unparsed) values.
+    /// * `options` - The options to use when tokenizing the values of aliases.
+    pub(crate) fn new(aliases: &'a HashMap<String, String>, options: &'a TokenizerOptions) -> Self {
+        Self {
+            aliases,
+            options,
+            output: vec![],
+            expansions: vec![],
+            frames: vec![],
+            command_position: true,
+            check_next_word: false,
+            skip_words: 0,
+            for_header: false,
+            function_name: false,
+            after_time: false,
+            assignment_end_index: None,
+            io_number: None,
+        }
+    }
+
+    /// Expands aliases within the given tokens, which continue the tokens previously
+    /// supplied, appending the results to the output.
+    ///
+    /// # Arguments
+    ///
+    /// * `tokens` - The tokens to expand aliases within.
+    pub(crate) fn extend(&mut self, tokens: impl IntoIterator<Item = Token>) {
+        let mut input = tokens.into_iter();
+
+        if self.aliases.is_empty() {
+            self.output.extend(input);
+            return;
+        }
+
+        while let Some(token) = self.next_token(&mut input) {
+            if let Token::Word(word, loc) = &token
+                && self.word_is_expandable()
+                && let Some(expansion) = self.expand(word, loc)
+            {
+                self.check_next_word = false;
+                self.expansions.push(expansion);
+                continue;
+            }
+
+            self.advance(&token);
+            self.output.push(token);
+        }
+    }
+
+    /// Returns the tokens output so far.
+    pub(crate) fn output(&self) -> &[Token] {
+        &self.output
+    }
+
+    /// Returns the next token to process, drawing from in-progress alias expansions before
+    /// the input.
+    fn next_token(&mut self, input: &mut impl Iterator<Item = Token>) -> Option<Token> {
+        while let Some(expansion) = self.expansions.last_mut() {
+            if let Some(token) = expansion.tokens.next() {
+                return Some(token);
+            }
+
+            if self.expansions.pop().is_some_and(|e| e.trailing_blank) {
+                self.check_next_word = true;
+            }
+        }
+
+        input.next()
+    }
+
+    fn word_is_expandable(&self) -> bool {
+        let in_commands = match self.frames.last() {
+            None | Some(Frame::Paren { .. }) => true,
+            Some(Frame::Case(phase)) => *phase == CasePhase::Body,
+            Some(Frame::Arithmetic(_) | Frame::ArrayElements | Frame::ExtendedTest) => false,
+        };
+
+        in_commands
+            && self.skip_words == 0
+            && !self.function_name
+            && (self.command_position || self.check_next_word)
+    }
+
+    /// Returns the expansion of the given word, if it names an alias that isn't already
+    /// being expanded.
+    fn expand(&self, word: &str, loc: &TokenLocation) -> Option<Expansion> {
+        let value = self.aliases.get(word)?;
+        if self.expansions.iter().any(|e| e.name == word) {
+            return None;
+        }
+
+        // If the value can't be tokenized on its own, leave the word alone.
+        let tokens = tokenizer::uncached_tokenize_str(value, self.options).ok()?;
+
+        Some(Expansion {
+            name: word.to_owned(),
+            tokens: tokens
+                .into_iter()
+                .map(|token| relocate(token, &loc.start))
+                .collect::<Vec<_>>()
+                .into_iter(),
+            trailing_blank: value.ends_with([' ', '\t']),
+        })
+    }
+
+    /// Updates the state of the expander to reflect the given token, which is about to be
+    /// output.
+    fn advance(&mut self, token: &Token) {
+        self.check_next_word = false;
+        let after_time = std::mem::take(&mut self.after_time);
+        let assignment_end_index = self.assignment_end_index.take();
+        let io_number = self.io_number.take();
+
+        match self.frames.last_mut() {
+            Some(Frame::ExtendedTest) => {
+                if is_word(token, "]]") {
+                    self.frames.pop();
+                    self.command_position = false;
+                }
+                return;
+            }
+            Some(Frame::Arithmetic(depth)) => {
+                if is_operator(token, "(") {
+                    *depth += 1;
+                } else if is_operator(token, ")") {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        self.frames.pop();
+                        self.command_position = true;
+                        self.for_header = false;
+                    }
+                }
+                return;
+            }
+            Some(Frame::ArrayElements) => {
+                if is_operator(token, ")") {
+                    self.frames.pop();
+                }
+                return;
+            }
+            Some(Frame::Case(phase)) if *phase != CasePhase::Body => {
+                match (*phase, token) {
+                    (CasePhase::Subject, Token::Word(..)) => *phase = CasePhase::In,
+                    (CasePhase::In, Token::Word(w, _)) if w == "in" => {
+                        *phase = CasePhase::Patterns;
+                    }
+                    (CasePhase::Patterns, Token::Word(w, _)) if w == "esac" => {
+                        self.frames.pop();
+                        self.command_position = false;
+                    }
+                    (CasePhase::Patterns, Token::Operator(op, _)) if op == ")" => {
+                        *phase = CasePhase::Body;
+                        self.command_position = true;
+                    }
+                    _ => (),
+                }
+                return;
+            }
+            Some(Frame::Paren { empty, .. })
+                if !is_operator(token, "(") && !is_operator(token, ")") =>
+            {
+                *empty = false;
+            }
+            _ => (),
+        }
+
+        match token {
+            Token::Operator(op, loc) => {
+                // A redirection's file descriptor number isn't the name of the command.
+                if let Some((end_index, command_position)) = io_number
+                    && end_index == loc.start.index
+                {
+                    self.command_position = command_position;
+                }
+
+                self.advance_over_operator(op, loc, assignment_end_index);
+            }
+            Token::Word(word, loc) => self.advance_over_word(word, loc, after_time),
+        }
+    }
+
+    fn advance_over_operator(
+        &mut self,
+        op: &str,
+        loc: &TokenLocation,
+        assignment_end_index: Option<usize>,
+    ) {
+        match op {
+            "\n" | ";" | "&" => {
+                self.command_position = true;
+                self.for_header = false;
+                self.skip_words = 0;
+            }
+            "&&" | "||" | "|" | "|&" => {
+                self.command_position = true;
+                self.skip_words = 0;
+            }
+            ";;" | ";&" | ";;&" => {
+                if let Some(Frame::Case(phase)) = self.frames.last_mut() {
+                    *phase = CasePhase::Patterns;
+                }
+                self.command_position = false;
+            }
+            "(" => self.open_paren(loc, assignment_end_index),
+            ")" => {
+                // Empty parens are those of a function definition, whose body follows.
+                self.command_position = matches!(
+                    self.frames.pop_if(|f| matches!(f, Frame::Paren { .. })),
+                    Some(Frame::Paren { empty: true, .. })
+                );
+            }
+            "<<" | "<<-" => {
+                // Skip the here-document's tag, contents, and end tag.
+                self.skip_words = 3;
+            }
+            "<" | ">" | ">>" | "<&" | ">&" | "<>" | ">|" | "&>" | "&>>" | "<<<" => {
+                self.skip_words = 1;
+            }
+            _ => (),
+        }
+    }
+
+    fn open_paren(&mut self, loc: &TokenLocation, assignment_end_index: Option<usize>) {
+        // An opening paren immediately following a redirection operator starts a process
+        // substitution.
+        if self.skip_words > 0 {
+            self.skip_words = 0;
+            self.push_paren(loc, false);
+            return;
+        }
+
+        // An opening paren immediately following a word ending in `=` starts the elements
+        // of an array assignment.
+        if assignment_end_index == Some(loc.start.index) {
+            self.frames.push(Frame::ArrayElements);
+            return;
+        }
+
+        // Two adjacent opening parens where a command is expected start an arithmetic
+        // command (or the header of an arithmetic for loop).
+        if let Some(Frame::Paren {
+            empty: true,
+            arithmetic_candidate: true,
+            end_index,
+            ..
+        }) = self.frames.last()
+            && *end_index == loc.start.index
+        {
+            self.frames.pop();
+            self.frames.push(Frame::Arithmetic(2));
+            return;
+        }
+
+        let arithmetic_candidate = self.command_position || self.for_header;
+        self.push_paren(loc, arithmetic_candidate);
+    }
+
+    fn push_paren(&mut self, loc: &TokenLocation, arithmetic_candidate: bool) {
+        self.frames.push(Frame::Paren {
+            empty: true,
+            arithmetic_candidate,
+            end_index: loc.end.index,
+        });
+        self.command_position = true;
+    }
+
+    fn advance_over_word(&mut self, word: &str, loc: &TokenLocation, after_time: bool) {
+        if self.skip_words > 0 {
+            self.skip_words -= 1;
+            return;
+        }
+
+        if word.ends_with('=') {
+            self.assignment_end_index = Some(loc.end.index);
+        } else if word.chars().all(|c| c.is_ascii_digit()) {
+            self.io_number = Some((loc.end.index, self.command_position));
+        }
+
+        if self.function_name {
+            self.function_name = false;
+            self.command_position = true;
+            return;
+        }
+
+        if self.for_header && word == "do" {
+            self.for_header = false;
+            self.command_position = true;
+            return;
+        }
+
+        if !self.command_position {
+            return;
+        }
+
+        match word {
+            "if" | "then" | "else" | "elif" | "do" | "while" | "until" | "!" | "{" => (),
+            "time" => self.after_time = true,
+            "-p" if after_time => (),
+            "case" => {
+                self.frames.push(Frame::Case(CasePhase::Subject));
+                self.command_position = false;
+            }
+            "esac" if matches!(self.frames.last(), Some(Frame::Case(_))) => {
+                self.frames.pop();
+                self.command_position = false;
+            }
+            "for" | "select" => {
+                self.for_header = true;
+                self.command_position = false;
+            }
+            "function" => {
+                self.function_name = true;
+                self.command_position = false;
+            }
+            "[[" => {
+                self.frames.push(Frame::ExtendedTest);
+            }
+            word if is_assignment(word) => (),
+            _ => self.command_position = false,
+        }
+    }
+}
+
+fn is_word(token: &Token, s: &str) -> bool {
+    matches!(token, Token::Word(w, _) if w == s)
+}
+
+fn is_operator(token: &Token, s: &str) -> bool {
+    matches!(token, Token::Operator(op, _) if op == s)
+}
+
+/// Returns whether the given word is a variable assignment, which may precede the name of
+/// the command in a simple command.
+fn is_assignment(word: &str) -> bool {
+    let Some((name, _)) = word.split_once('=') else {
+        return false;
+    };
+
+    let name = name.strip_suffix('+').unwrap_or(name);
+    let name = name.split_once('[').map_or(
+        name,
+        |(name, index)| if index.ends_with(']') { name } else { "" },
+    );
+
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Moves a token from an alias's value to the location of the word being expanded,
+/// preserving the relative positions of the value's tokens.
+fn relocate(token: Token, origin: &SourcePosition) -> Token {
+    let translate = |pos: &SourcePosition| SourcePosition {
+        index: origin.index + pos.index,
+        line: origin.line + pos.line - 1,
+        column: if pos.line == 1 {
+            origin.column + pos.column - 1
+        } else {
+            pos.column
+        },
+    };
+
+    let relocate_loc = |loc: &TokenLocation| TokenLocation {
+        start: Arc::new(translate(&loc.start)),
+        end: Arc::new(translate(&loc.end)),
+    };
+
+    match token {
+        Token::Operator(op, loc) => Token::Operator(op, relocate_loc(&loc)),
+        Token::Word(word, loc) => Token::Word(word, relocate_loc(&loc)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn expand(input: &str, aliases: &[(&str, &str)]) -> String {
+        let aliases = aliases
+            .iter()
+            .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+            .collect();
+        let options = TokenizerOptions::default();
+        let tokens = tokenizer::uncached_tokenize_str(input, &options).unwrap();
+
+        expand_aliases(tokens, &aliases, &options)
+            .iter()
+            .map(|token| token.to_str().replace('\n', "\\n"))
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    #[test]
+    fn expands_command_words() {
+        let aliases = [("e", "echo -n"), ("x", "unused")];
+
+        assert_eq!(expand("e x", &aliases), "echo -n x");
+        assert_eq!(
+            expand("e x; e x | e x && e", &aliases),
+            "echo -n x ; echo -n x | echo -n x && echo -n"
+        );
+        assert_eq!(expand("A=1 B[0]+=2 e x", &aliases), "A=1 B[0]+=2 echo -n x");
+        assert_eq!(expand(">out 2>&1 e x", &aliases), "> out 2 >& 1 echo -n x");
+        assert_eq!(expand("'e' \\e \"e\"", &aliases), "'e' \\e \"e\"");
+    }
+
+    #[test]
+    fn expands_within_compound_commands() {
+        let aliases = [("e", "echo"), ("x", "unused")];
+
+        assert_eq!(
+            expand("if e; then e; elif ! e; then e; else { e; }; fi", &aliases),
+            "if echo ; then echo ; elif ! echo ; then echo ; else { echo ; } ; fi"
+        );
+        assert_eq!(
+            expand("while e; do (e x); done", &aliases),
+            "while echo ; do ( echo x ) ; done"
+        );
+        assert_eq!(
+            expand("for x in x; do e; done", &aliases),
+            "for x in x ; do echo ; done"
+        );
+        assert_eq!(expand("for x do e; done", &aliases), "for x do echo ; done");
+        assert_eq!(
+            expand("for ((x = 0; x < 1; x++)); do e; done", &aliases),
+            "for ( ( x = 0 ; x < 1 ; x++ ) ) ; do echo ; done"
+        );
+        assert_eq!(
+            expand("case x in x | (e) e x;; (x) e;& x) e; esac; e", &aliases),
+            "case x in x | ( e ) echo x ;; ( x ) echo ;& x ) echo ; esac ; echo"
+        );
+        assert_eq!(
+            expand("f() { e; }; function x { e; }", &aliases),
+            "f ( ) { echo ; } ; function x { echo ; }"
+        );
+        assert_eq!(
+            expand("[[ x && e ]] && (( x )) && e", &aliases),
+            "[[ x && e ]] && ( ( x ) ) && echo"
+        );
+        assert_eq!(
+            expand("x=(e x) e <(e) x", &aliases),
+            "x= ( e x ) echo < ( echo ) x"
+        );
+        assert_eq!(expand("time -p e", &aliases), "time -p echo");
+    }
+
+    #[test]
+    fn skips_redirection_targets_and_here_documents() {
+        let aliases = [("e", "echo")];
+
+        assert_eq!(
+            expand("cat <<END >e\ne\nEND\ne x", &aliases),
+            "cat << END e\\n END > e \\n echo x"
+        );
+    }
+
+    #[test]
+    fn expands_values_with_structure() {
+        let aliases = [("kw", "if"), ("multi", "echo a; echo b |"), ("e", "echo")];
+
+        assert_eq!(
+            expand("kw e; then e; fi", &aliases),
+            "if echo ; then echo ; fi"
+        );
+        assert_eq!(expand("multi e", &aliases), "echo a ; echo b | echo");
+    }
+
+    #[test]
+    fn chains_trailing_blanks() {
+        let aliases = [("s", "sudo "), ("e", "echo "), ("w", "word"), ("n", "x")];
+
+        assert_eq!(expand("s e w n", &aliases), "sudo echo word n");
+        assert_eq!(expand("s n w", &aliases), "sudo x w");
+    }
+
+    #[test]
+    fn does_not_recurse() {
+        let aliases = [("ls", "ls -l"), ("a", "b"), ("b", "a x")];
+
+        assert_eq!(expand("ls", &aliases), "ls -l");
+        assert_eq!(expand("a", &aliases), "a x");
+        assert_eq!(expand("b", &aliases), "b x");
+    }
+
+    #[test]
+    fn preserves_adjacency_of_value_tokens() {
+        let options = TokenizerOptions::default();
+        let aliases = HashMap::from([("q".to_owned(), "cmd 2>/dev/null".to_owned())]);
+        let tokens = tokenizer::uncached_tokenize_str("x; q", &options).unwrap();
+        let expanded = expand_aliases(tokens, &aliases, &options);
+
+        let locations: Vec<_> = expanded
+            .iter()
+            .map(|t| (t.location().start.index, t.location().end.index))
+            .collect();
+        assert_eq!(locations, [(0, 1), (1, 2), (3, 6), (7, 8), (8, 9), (9, 18)]);
+    }
+}
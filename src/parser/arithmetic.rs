@@ -2,6 +2,8 @@
 
 use super::ast;
 use super::error;
+use super::tokenizer::Origin;
+use super::{SourcePosition, TokenLocation};
 
 /// Parses a shell arithmetic expression.
 ///
@@ -12,20 +14,52 @@ pub fn parse(input: &str) -> Result<ast::ArithmeticExpr, error::WordParseError>
     cacheable_parse(input.to_owned())
 }
 
+/// Parses a shell arithmetic expression that appears verbatim in a source script,
+/// recording the location of each node in that script.
+///
+/// # Arguments
+///
+/// * `input` - The arithmetic expression to parse, in string form.
+/// * `start` - The position in the source script at which `input` starts.
+pub fn parse_with_location(
+    input: &str,
+    start: &SourcePosition,
+) -> Result<ast::ArithmeticExpr, error::WordParseError> {
+    let origin = Origin { text: input, start };
+    arithmetic::full_expression(input, Some(origin))
+        .map_err(|e| error::WordParseError::ArithmeticExpression(e.into()))
+}
+
 #[cached::proc_macro::cached(size = 64, result = true)]
 fn cacheable_parse(input: String) -> Result<ast::ArithmeticExpr, error::WordParseError> {
     tracing::debug!(target: "arithmetic", "parsing arithmetic expression: '{input}'");
-    arithmetic::full_expression(input.as_str())
+    arithmetic::full_expression(input.as_str(), None)
         .map_err(|e| error::WordParseError::ArithmeticExpression(e.into()))
 }
 
+/// Records the given location on an expression that tracks its own location, unless it
+/// already has one (e.g., because it was parenthesized).
+fn with_location(mut expr: ast::ArithmeticExpr, loc: Option<TokenLocation>) -> ast::ArithmeticExpr {
+    if let ast::ArithmeticExpr::Literal(_, slot)
+    | ast::ArithmeticExpr::UnaryOp(_, _, slot)
+    | ast::ArithmeticExpr::UnaryAssignment(_, _, slot) = &mut expr
+        && slot.is_none()
+    {
+        *slot = loc;
+    }
+
+    expr
+}
+
 peg::parser! {
-    grammar arithmetic() for str {
+    grammar arithmetic(origin: Option<Origin<'_>>) for str {
         pub(crate) rule full_expression() -> ast::ArithmeticExpr =
-            ![_] { ast::ArithmeticExpr::Literal(0) } /
+            ![_] { ast::ArithmeticExpr::Literal(0, None) } /
             _ e:expression() _ { e }
 
         pub(crate) rule expression() -> ast::ArithmeticExpr = precedence!{
+            s:position!() e:@ end:position!() { with_location(e, origin.map(|o| o.location(s, end))) }
+            --
             x:(@) _ "," _ y:@ { ast::ArithmeticExpr::BinaryOp(ast::BinaryOperator::Comma, Box::new(x), Box::new(y)) }
             --
             x:lvalue() _ "*=" _ y:(@) { ast::ArithmeticExpr::BinaryAssignment(ast::BinaryOperator::Multiply, x, Box::new(y)) }
@@ -71,31 +105,36 @@ peg::parser! {
             --
             x:@ _ "**" _ y:(@) { ast::ArithmeticExpr::BinaryOp(ast::BinaryOperator::Power, Box::new(x), Box::new(y)) }
             --
-            "!" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::LogicalNot, Box::new(x)) }
-            "~" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::BitwiseNot, Box::new(x)) }
+            "!" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::LogicalNot, Box::new(x), None) }
+            "~" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::BitwiseNot, Box::new(x), None) }
             --
-            "++" x:lvalue() { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PrefixIncrement, x) }
-            "--" x:lvalue() { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PrefixDecrement, x) }
+            "++" x:lvalue() { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PrefixIncrement, x, None) }
+            "--" x:lvalue() { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PrefixDecrement, x, None) }
             --
-            x:lvalue() _ "++" { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PostfixIncrement, x) }
-            x:lvalue() _ "--" { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PostfixDecrement, x) }
+            x:lvalue() _ "++" { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PostfixIncrement, x, None) }
+            x:lvalue() _ "--" { ast::ArithmeticExpr::UnaryAssignment(ast::UnaryAssignmentOperator::PostfixDecrement, x, None) }
             --
-            "+" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::UnaryPlus, Box::new(x)) }
-            "-" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::UnaryMinus, Box::new(x)) }
+            "+" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::UnaryPlus, Box::new(x), None) }
+            "-" _ x:(@) { ast::ArithmeticExpr::UnaryOp(ast::UnaryOperator::UnaryMinus, Box::new(x), None) }
             --
-            n:literal_number() { ast::ArithmeticExpr::Literal(n) }
+            n:literal_number() { ast::ArithmeticExpr::Literal(n, None) }
             l:lvalue() { ast::ArithmeticExpr::Reference(l) }
             "(" _ expr:expression() _ ")" { expr }
         }
 
         rule lvalue() -> ast::ArithmeticTarget =
-            name:variable_name() "[" index:expression() "]" {
-                ast::ArithmeticTarget::ArrayElement(name.to_owned(), Box::new(index))
+            s:position!() name:variable_name() "[" index:expression() "]" l:loc(s) {
+                ast::ArithmeticTarget::ArrayElement(name.to_owned(), Box::new(index), l)
             } /
-            name:variable_name() {
-                ast::ArithmeticTarget::Variable(name.to_owned())
+            s:position!() name:variable_name() l:loc(s) {
+                ast::ArithmeticTarget::Variable(name.to_owned(), l)
             }
 
+        // Yields the location of the input from `start` up to the current position, if
+        // the expression is being parsed with a known origin.
+        rule loc(start: usize) -> Option<TokenLocation> =
+            end:position!() { origin.map(|o| o.location(start, end)) }
+
         rule variable_name() -> &'input str =
             $(['a'..='z' | 'A'..='Z' | '_'](['a'..='z' | 'A'..='Z' | '_' | '0'..='9']*))
 
@@ -131,3 +170,41 @@ peg::parser! {
             }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::SourceLocation;
+    use anyhow::Result;
+
+    #[test]
+    fn parse_records_locations() -> Result<()> {
+        let start = SourcePosition {
+            index: 3,
+            line: 1,
+            column: 4,
+        };
+        let input = "x = -1 + 2";
+        let expr = parse_with_location(input, &start)?;
+
+        let span = |node: &ast::ArithmeticExpr| {
+            node.location()
+                .map(|loc| (loc.start.index - start.index, loc.end.index - start.index))
+        };
+
+        assert_eq!(span(&expr), Some((0, 10)));
+        let ast::ArithmeticExpr::Assignment(_, value) = &expr else {
+            anyhow::bail!("expected an assignment: {expr:?}");
+        };
+        assert_eq!(span(value), Some((4, 10)));
+        let ast::ArithmeticExpr::BinaryOp(_, left, right) = value.as_ref() else {
+            anyhow::bail!("expected a binary operation: {value:?}");
+        };
+        assert_eq!(span(left), Some((4, 6)));
+        assert_eq!(span(right), Some((9, 10)));
+
+        assert!(parse(input)?.location().is_none());
+
+        Ok(())
+    }
+}
@@ -1,12 +1,49 @@
 //! Defines the Abstract Syntax Tree (ast) for shell programs. Includes types and utilities
 //! for manipulating the AST.
+//!
+//! # Serialization
+//!
+//! With the `serde` feature enabled, the AST types, the [`Token`](super::Token),
+//! [`TokenLocation`] and [`SourcePosition`](super::SourcePosition) types they refer to, and
+//! the word pieces produced by [`word::parse`](super::word::parse) implement
+//! `serde::Serialize` and `serde::Deserialize`. They use serde's default representation,
+//! which maps to JSON as follows:
+//!
+//! - Structs with named fields are objects keyed by field name. Unset `Option` fields are
+//!   `null`; all fields are required when deserializing.
+//! - Single-field tuple structs (such as [`CompoundList`] and [`RedirectList`]) are their
+//!   field's value; other tuple structs and tuple variants are arrays of their fields.
+//! - Enums are externally tagged: unit variants are strings (`"Sequence"`), and all other
+//!   variants are objects with a single key, the variant's name, mapped to its content
+//!   (`{"Simple": {...}}`).
+//! - Locations are objects with `start` and `end` positions, each an object with a 0-based
+//!   character `index` and 1-based `line` and `column`; `end` is exclusive.
+//!
+//! For example, `x=1 echo "$x"` serializes (with locations elided) to:
+//!
+//! ```json
+//! {"complete_commands": [[[
+//!   {"first": {"timed": null, "bang": false, "loc": {...}, "seq": [{"Simple": {
+//!     "prefix": [{"AssignmentWord": [
+//!       {"name": {"VariableName": "x"},
+//!        "value": {"Scalar": {"value": "1", "loc": {...}}},
+//!        "append": false, "loc": {...}},
+//!       {"value": "x=1", "loc": {...}}]}],
+//!     "word_or_name": {"value": "echo", "loc": {...}},
+//!     "suffix": [{"Word": {"value": "\"$x\"", "loc": {...}}}]}}]},
+//!    "additional": []},
+//!   "Sequence"]]]}
+//! ```
+//!
+//! Words hold their unexpanded source text, quoting included. Deserialized programs can
+//! be executed with [`Shell::run_program`](crate::Shell::run_program); locations are only used for diagnostics, so a
+//! transformed program may carry default (zeroed) locations for the nodes it adds.
 
 use std::fmt::{Display, Write};
 
+use super::display::{SourceWriter, WriteSource};
 use super::{TokenLocation, tokenizer};
 
-const DISPLAY_INDENT: &str = "    ";
-
 /// Provides the source location for the syntax item
 pub trait SourceLocation {
     /// The location of the syntax item, when known
@@ -27,7 +64,11 @@ pub(crate) fn maybe_location(
 /// Represents a complete shell program.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct Program {
     /// A sequence of complete shell commands.
     #[cfg_attr(test, serde(rename = "cmds"))]
@@ -50,8 +91,17 @@ impl SourceLocation for Program {
 
 impl Display for Program {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for complete_command in &self.complete_commands {
-            write!(f, "{complete_command}")?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for Program {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        for (i, complete_command) in self.complete_commands.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            f.write(complete_command)?;
         }
         Ok(())
     }
@@ -67,7 +117,11 @@ pub type CompleteCommandItem = CompoundListItem;
 /// Indicates whether the preceding command is executed synchronously or asynchronously.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum SeparatorOperator {
     /// The preceding command is executed asynchronously.
     Async,
@@ -87,13 +141,17 @@ impl Display for SeparatorOperator {
 /// Represents a sequence of command pipelines connected by boolean operators.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "AndOr"))]
 pub struct AndOrList {
     /// The first command pipeline.
     pub first: Pipeline,
     /// Any additional command pipelines, in sequence order.
-    #[cfg_attr(test, serde(skip_serializing_if = "Vec::is_empty"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Vec::is_empty"))]
     pub additional: Vec<AndOr>,
 }
 
@@ -112,9 +170,15 @@ impl SourceLocation for AndOrList {
 
 impl Display for AndOrList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.first)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for AndOrList {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.write(&self.first)?;
         for item in &self.additional {
-            write!(f, "{item}")?;
+            f.write(item)?;
         }
 
         Ok(())
@@ -203,7 +267,11 @@ impl AndOrList {
 /// succeeding pipeline.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum AndOr {
     /// Boolean AND operator; the embedded pipeline is only to be executed if the
     /// preceding command has succeeded.
@@ -213,7 +281,6 @@ pub enum AndOr {
     Or(Pipeline),
 }
 
-// TODO: add a loc
 impl SourceLocation for AndOr {
     fn location(&self) -> Option<TokenLocation> {
         match self {
@@ -225,9 +292,21 @@ impl SourceLocation for AndOr {
 
 impl Display for AndOr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for AndOr {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         match self {
-            Self::And(pipeline) => write!(f, " && {pipeline}"),
-            Self::Or(pipeline) => write!(f, " || {pipeline}"),
+            Self::And(pipeline) => {
+                write!(f, " && ")?;
+                f.write(pipeline)
+            }
+            Self::Or(pipeline) => {
+                write!(f, " || ")?;
+                f.write(pipeline)
+            }
         }
     }
 }
@@ -235,7 +314,11 @@ impl Display for AndOr {
 /// The type of timing requested for a pipeline.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum PipelineTimed {
     /// The pipeline should be timed with bash-like output.
     Timed(TokenLocation),
@@ -263,43 +346,51 @@ impl PipelineTimed {
 /// to the command that follows it.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct Pipeline {
     /// Indicates whether the pipeline's execution should be timed with reported
     /// timings in output.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
     pub timed: Option<PipelineTimed>,
     /// Indicates whether the result of the overall pipeline should be the logical
     /// negation of the result of the pipeline.
-    #[cfg_attr(test, serde(skip_serializing_if = "<&bool as std::ops::Not>::not"))]
+    #[cfg_attr(
+        test,
+        serde(default, skip_serializing_if = "<&bool as std::ops::Not>::not")
+    )]
     pub bang: bool,
     /// The sequence of commands in the pipeline.
     pub seq: Vec<Command>,
+    /// Location of the pipeline, including any `time` or `!` prefix.
+    pub loc: TokenLocation,
 }
 
 impl SourceLocation for Pipeline {
     fn location(&self) -> Option<TokenLocation> {
-        let start = self
-            .timed
-            .as_ref()
-            .and_then(SourceLocation::location)
-            .or_else(|| self.seq.first().and_then(SourceLocation::location));
-        let end = self.seq.last().and_then(SourceLocation::location);
-
-        maybe_location(start.as_ref(), end.as_ref())
+        Some(self.loc.clone())
     }
 }
 
 impl Display for Pipeline {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for Pipeline {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         if self.bang {
-            write!(f, "!")?;
+            write!(f, "! ")?;
         }
         for (i, command) in self.seq.iter().enumerate() {
             if i > 0 {
-                write!(f, " |")?;
+                write!(f, " | ")?;
             }
-            write!(f, "{command}")?;
+            f.write(command)?;
         }
 
         Ok(())
@@ -309,7 +400,11 @@ impl Display for Pipeline {
 /// Represents a shell command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum Command {
     /// A simple command, directly invoking an external command, a built-in command,
     /// a shell function, or similar.
@@ -320,6 +415,8 @@ pub enum Command {
     Function(FunctionDefinition),
     /// A command that evaluates an extended test expression.
     ExtendedTest(ExtendedTestExprCommand),
+    /// Input that could not be parsed; only produced when parsing with error recovery.
+    Error(ErrorCommand),
 }
 
 impl SourceLocation for Command {
@@ -334,33 +431,80 @@ impl SourceLocation for Command {
             }
             Self::Function(f) => f.location(),
             Self::ExtendedTest(e) => e.location(),
+            Self::Error(e) => e.location(),
         }
     }
 }
 
 impl Display for Command {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for Command {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         match self {
-            Self::Simple(simple_command) => write!(f, "{simple_command}"),
+            Self::Simple(simple_command) => f.write(simple_command),
             Self::Compound(compound_command, redirect_list) => {
-                write!(f, "{compound_command}")?;
+                f.write(compound_command)?;
                 if let Some(redirect_list) = redirect_list {
-                    write!(f, "{redirect_list}")?;
+                    write!(f, " ")?;
+                    f.write(redirect_list)?;
                 }
                 Ok(())
             }
-            Self::Function(function_definition) => write!(f, "{function_definition}"),
+            Self::Function(function_definition) => f.write(function_definition),
             Self::ExtendedTest(extended_test_expr) => {
                 write!(f, "[[ {extended_test_expr} ]]")
             }
+            Self::Error(error_command) => write!(f, "{error_command}"),
         }
     }
 }
 
+/// Input that could not be parsed as a command, retained in place of the command
+/// when parsing with error recovery.
+#[derive(Clone, Debug)]
+#[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct ErrorCommand {
+    /// The tokens that could not be parsed.
+    pub tokens: Vec<tokenizer::Token>,
+    /// Location of the unparsed input.
+    pub loc: TokenLocation,
+}
+
+impl SourceLocation for ErrorCommand {
+    fn location(&self) -> Option<TokenLocation> {
+        Some(self.loc.clone())
+    }
+}
+
+impl Display for ErrorCommand {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for (i, token) in self.tokens.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            write!(f, "{}", token.to_str())?;
+        }
+        Ok(())
+    }
+}
+
 /// Represents a compound command, potentially made up of multiple nested commands.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum CompoundCommand {
     /// An arithmetic command, evaluating an arithmetic expression.
     Arithmetic(ArithmeticCommand),
@@ -383,7 +527,6 @@ pub enum CompoundCommand {
     UntilClause(WhileOrUntilClauseCommand),
 }
 
-// TODO: complete the list
 impl SourceLocation for CompoundCommand {
     fn location(&self) -> Option<TokenLocation> {
         match self {
@@ -402,25 +545,29 @@ impl SourceLocation for CompoundCommand {
 
 impl Display for CompoundCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CompoundCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         match self {
             Self::Arithmetic(arithmetic_command) => write!(f, "{arithmetic_command}"),
             Self::ArithmeticForClause(arithmetic_for_clause_command) => {
-                write!(f, "{arithmetic_for_clause_command}")
-            }
-            Self::BraceGroup(brace_group_command) => {
-                write!(f, "{brace_group_command}")
+                f.write(arithmetic_for_clause_command)
             }
-            Self::Subshell(subshell_command) => write!(f, "{subshell_command}"),
-            Self::ForClause(for_clause_command) => write!(f, "{for_clause_command}"),
-            Self::CaseClause(case_clause_command) => {
-                write!(f, "{case_clause_command}")
-            }
-            Self::IfClause(if_clause_command) => write!(f, "{if_clause_command}"),
+            Self::BraceGroup(brace_group_command) => f.write(brace_group_command),
+            Self::Subshell(subshell_command) => f.write(subshell_command),
+            Self::ForClause(for_clause_command) => f.write(for_clause_command),
+            Self::CaseClause(case_clause_command) => f.write(case_clause_command),
+            Self::IfClause(if_clause_command) => f.write(if_clause_command),
             Self::WhileClause(while_or_until_clause_command) => {
-                write!(f, "while {while_or_until_clause_command}")
+                write!(f, "while ")?;
+                f.write(while_or_until_clause_command)
             }
             Self::UntilClause(while_or_until_clause_command) => {
-                write!(f, "until {while_or_until_clause_command}")
+                write!(f, "until ")?;
+                f.write(while_or_until_clause_command)
             }
         }
     }
@@ -429,7 +576,11 @@ impl Display for CompoundCommand {
 /// An arithmetic command, evaluating an arithmetic expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ArithmeticCommand {
     /// The raw, unparsed and unexpanded arithmetic expression.
     pub expr: UnexpandedArithmeticExpr,
@@ -452,7 +603,11 @@ impl Display for ArithmeticCommand {
 /// A subshell, which executes commands in a subshell.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct SubshellCommand {
     /// Command list in the subshell
     pub list: CompoundList,
@@ -468,8 +623,14 @@ impl SourceLocation for SubshellCommand {
 
 impl Display for SubshellCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for SubshellCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         write!(f, "( ")?;
-        write!(f, "{}", self.list)?;
+        f.write(&self.list)?;
         write!(f, " )")
     }
 }
@@ -477,7 +638,11 @@ impl Display for SubshellCommand {
 /// A for clause, which loops over a set of values.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ForClauseCommand {
     /// The name of the iterator variable.
     pub variable_name: String,
@@ -497,6 +662,12 @@ impl SourceLocation for ForClauseCommand {
 
 impl Display for ForClauseCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for ForClauseCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         write!(f, "for {} in ", self.variable_name)?;
 
         if let Some(values) = &self.values {
@@ -509,16 +680,21 @@ impl Display for ForClauseCommand {
             }
         }
 
-        writeln!(f, ";")?;
+        write!(f, ";")?;
+        f.end_line()?;
 
-        write!(f, "{}", self.body)
+        f.write(&self.body)
     }
 }
 
 /// An arithmetic for clause, which loops until an arithmetic condition is reached.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ArithmeticForClauseCommand {
     /// Optionally, the initializer expression evaluated before the first iteration of the loop.
     pub initializer: Option<UnexpandedArithmeticExpr>,
@@ -540,6 +716,12 @@ impl SourceLocation for ArithmeticForClauseCommand {
 
 impl Display for ArithmeticForClauseCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for ArithmeticForClauseCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         write!(f, "for ((")?;
 
         if let Some(initializer) = &self.initializer {
@@ -558,9 +740,10 @@ impl Display for ArithmeticForClauseCommand {
             write!(f, "{updater}")?;
         }
 
-        writeln!(f, "))")?;
+        write!(f, "))")?;
+        f.end_line()?;
 
-        write!(f, "{}", self.body)
+        f.write(&self.body)
     }
 }
 
@@ -568,30 +751,39 @@ impl Display for ArithmeticForClauseCommand {
 /// pattern-based filters.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct CaseClauseCommand {
     /// The value being matched on.
     pub value: Word,
     /// The individual case branches.
     pub cases: Vec<CaseItem>,
+    /// Location of the case clause, from `case` through `esac`.
+    pub loc: TokenLocation,
 }
 
 impl SourceLocation for CaseClauseCommand {
     fn location(&self) -> Option<TokenLocation> {
-        let start = self.value.location();
-        let end = self.cases.last().and_then(SourceLocation::location);
-
-        maybe_location(start.as_ref(), end.as_ref())
+        Some(self.loc.clone())
     }
 }
 
 impl Display for CaseClauseCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CaseClauseCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         write!(f, "case {} in", self.value)?;
         for case in &self.cases {
-            write!(indenter::indented(f).with_str(DISPLAY_INDENT), "{case}")?;
+            f.indented(|f| f.write(case))?;
         }
-        writeln!(f)?;
+        f.end_line()?;
         write!(f, "esac")
     }
 }
@@ -599,11 +791,14 @@ impl Display for CaseClauseCommand {
 /// A sequence of commands.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "List"))]
 pub struct CompoundList(pub Vec<CompoundListItem>);
 
-// TODO: doublecheck
 impl SourceLocation for CompoundList {
     fn location(&self) -> Option<TokenLocation> {
         let start = self.0.first().and_then(SourceLocation::location);
@@ -619,30 +814,42 @@ impl SourceLocation for CompoundList {
 
 impl Display for CompoundList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for (i, item) in self.0.iter().enumerate() {
-            if i > 0 {
-                writeln!(f)?;
-            }
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CompoundList {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.here_doc_scope(|f| {
+            for (i, item) in self.0.iter().enumerate() {
+                if i > 0 {
+                    f.end_line()?;
+                }
 
-            // Write the and-or list.
-            write!(f, "{}", item.0)?;
+                // Write the and-or list.
+                f.write(&item.0)?;
 
-            // Write the separator... unless we're on the list item and it's a ';'.
-            if i == self.0.len() - 1 && matches!(item.1, SeparatorOperator::Sequence) {
-                // Skip
-            } else {
-                write!(f, "{}", item.1)?;
+                // Write the separator... unless we're on the list item and it's a ';'.
+                if i == self.0.len() - 1 && matches!(item.1, SeparatorOperator::Sequence) {
+                    // Skip
+                } else {
+                    write!(f, "{}", item.1)?;
+                }
             }
-        }
 
-        Ok(())
+            Ok(())
+        })
     }
 }
 
 /// An element of a compound command list.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Item"))]
 pub struct CompoundListItem(pub AndOrList, pub SeparatorOperator);
 
@@ -654,7 +861,13 @@ impl SourceLocation for CompoundListItem {
 
 impl Display for CompoundListItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CompoundListItem {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.write(&self.0)?;
         write!(f, "{}", self.1)?;
         Ok(())
     }
@@ -663,14 +876,18 @@ impl Display for CompoundListItem {
 /// An if clause, which conditionally executes a command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct IfClauseCommand {
     /// The command whose execution result is inspected.
     pub condition: CompoundList,
     /// The command to execute if the condition is true.
     pub then: CompoundList,
     /// Optionally, `else` clauses that will be evaluated if the condition is false.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
     pub elses: Option<Vec<ElseClause>>,
     /// Location of the if clause
     pub loc: TokenLocation,
@@ -684,19 +901,24 @@ impl SourceLocation for IfClauseCommand {
 
 impl Display for IfClauseCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "if {}; then", self.condition)?;
-        write!(
-            indenter::indented(f).with_str(DISPLAY_INDENT),
-            "{}",
-            self.then
-        )?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for IfClauseCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        write!(f, "if ")?;
+        f.write(&self.condition)?;
+        write!(f, "; then")?;
+        f.end_line()?;
+        f.indented(|f| f.write(&self.then))?;
         if let Some(elses) = &self.elses {
             for else_clause in elses {
-                write!(f, "{else_clause}")?;
+                f.write(else_clause)?;
             }
         }
 
-        writeln!(f)?;
+        f.end_line()?;
         write!(f, "fi")?;
 
         Ok(())
@@ -706,36 +928,57 @@ impl Display for IfClauseCommand {
 /// Represents the `else` clause of a conditional command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ElseClause {
     /// If present, the condition that must be met for this `else` clause to be executed.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
     pub condition: Option<CompoundList>,
     /// The commands to execute if this `else` clause is selected.
     pub body: CompoundList,
+    /// Location of the clause, from its `else` or `elif` keyword through the end of its body.
+    pub loc: TokenLocation,
+}
+
+impl SourceLocation for ElseClause {
+    fn location(&self) -> Option<TokenLocation> {
+        Some(self.loc.clone())
+    }
 }
 
 impl Display for ElseClause {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for ElseClause {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.end_line()?;
         if let Some(condition) = &self.condition {
-            writeln!(f, "elif {condition}; then")?;
+            write!(f, "elif ")?;
+            f.write(condition)?;
+            write!(f, "; then")?;
         } else {
-            writeln!(f, "else")?;
+            write!(f, "else")?;
         }
+        f.end_line()?;
 
-        write!(
-            indenter::indented(f).with_str(DISPLAY_INDENT),
-            "{}",
-            self.body
-        )
+        f.indented(|f| f.write(&self.body))
     }
 }
 
 /// An individual matching case item in a case clause.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct CaseItem {
     /// The patterns that select this case branch.
     pub patterns: Vec<Word>,
@@ -755,19 +998,26 @@ impl SourceLocation for CaseItem {
 
 impl Display for CaseItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CaseItem {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.end_line()?;
         for (i, pattern) in self.patterns.iter().enumerate() {
             if i > 0 {
-                write!(f, "|")?;
+                write!(f, " | ")?;
             }
             write!(f, "{pattern}")?;
         }
-        writeln!(f, ")")?;
+        write!(f, ")")?;
+        f.end_line()?;
 
         if let Some(cmd) = &self.cmd {
-            write!(indenter::indented(f).with_str(DISPLAY_INDENT), "{cmd}")?;
+            f.indented(|f| f.write(cmd))?;
         }
-        writeln!(f)?;
+        f.end_line()?;
         write!(f, "{}", self.post_action)
     }
 }
@@ -775,7 +1025,11 @@ impl Display for CaseItem {
 /// Describes the action to take after executing the body command of a case clause.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum CaseItemPostAction {
     /// The containing case should be exited.
     ExitCase,
@@ -800,7 +1054,11 @@ impl Display for CaseItemPostAction {
 /// A while or until clause, whose looping is controlled by a condition.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct WhileOrUntilClauseCommand(pub CompoundList, pub DoGroupCommand, pub TokenLocation);
 
 impl SourceLocation for WhileOrUntilClauseCommand {
@@ -811,14 +1069,26 @@ impl SourceLocation for WhileOrUntilClauseCommand {
 
 impl Display for WhileOrUntilClauseCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}; {}", self.0, self.1)
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for WhileOrUntilClauseCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.write(&self.0)?;
+        write!(f, "; ")?;
+        f.write(&self.1)
     }
 }
 
 /// Encapsulates the definition of a shell function.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct FunctionDefinition {
     /// The name of the function.
     pub fname: Word,
@@ -826,6 +1096,8 @@ pub struct FunctionDefinition {
     pub body: FunctionBody,
     /// The source of the function definition.
     pub source: String,
+    /// Whether the definition used the non-POSIX `function` keyword.
+    pub uses_function_keyword: bool,
 }
 
 impl SourceLocation for FunctionDefinition {
@@ -843,8 +1115,14 @@ impl SourceLocation for FunctionDefinition {
 
 impl Display for FunctionDefinition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for FunctionDefinition {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         writeln!(f, "{} () ", self.fname.value)?;
-        write!(f, "{}", self.body)?;
+        f.write(&self.body)?;
         Ok(())
     }
 }
@@ -852,28 +1130,37 @@ impl Display for FunctionDefinition {
 /// Encapsulates the body of a function definition.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct FunctionBody(pub CompoundCommand, pub Option<RedirectList>);
 
 impl SourceLocation for FunctionBody {
     fn location(&self) -> Option<TokenLocation> {
         let start = self.0.location();
-
         let end = self.1.as_ref().and_then(SourceLocation::location);
 
-        if let (Some(s), Some(e)) = (start, end) {
-            Some(TokenLocation::within(&s, &e))
-        } else {
-            None
+        match (start, end) {
+            (Some(s), Some(e)) => Some(TokenLocation::within(&s, &e)),
+            (s, _) => s,
         }
     }
 }
 
 impl Display for FunctionBody {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "{}", self.0)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for FunctionBody {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        f.write(&self.0)?;
         if let Some(redirect_list) = &self.1 {
-            write!(f, "{redirect_list}")?;
+            write!(f, " ")?;
+            f.write(redirect_list)?;
         }
 
         Ok(())
@@ -883,7 +1170,11 @@ impl Display for FunctionBody {
 /// A brace group, which groups commands together.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct BraceGroupCommand {
     /// List of commands
     pub list: CompoundList,
@@ -899,13 +1190,16 @@ impl SourceLocation for BraceGroupCommand {
 
 impl Display for BraceGroupCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "{{ ")?;
-        write!(
-            indenter::indented(f).with_str(DISPLAY_INDENT),
-            "{}",
-            self.list
-        )?;
-        writeln!(f)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for BraceGroupCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        write!(f, "{{ ")?;
+        f.end_line()?;
+        f.indented(|f| f.write(&self.list))?;
+        f.end_line()?;
         write!(f, "}}")?;
 
         Ok(())
@@ -915,7 +1209,11 @@ impl Display for BraceGroupCommand {
 /// A do group, which groups commands together.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct DoGroupCommand {
     /// List of commands
     pub list: CompoundList,
@@ -923,15 +1221,24 @@ pub struct DoGroupCommand {
     pub loc: TokenLocation,
 }
 
+impl SourceLocation for DoGroupCommand {
+    fn location(&self) -> Option<TokenLocation> {
+        Some(self.loc.clone())
+    }
+}
+
 impl Display for DoGroupCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        writeln!(f, "do")?;
-        write!(
-            indenter::indented(f).with_str(DISPLAY_INDENT),
-            "{}",
-            self.list
-        )?;
-        writeln!(f)?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for DoGroupCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        write!(f, "do")?;
+        f.end_line()?;
+        f.indented(|f| f.write(&self.list))?;
+        f.end_line()?;
         write!(f, "done")
     }
 }
@@ -939,18 +1246,22 @@ impl Display for DoGroupCommand {
 /// Represents the invocation of a simple command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Simple"))]
 pub struct SimpleCommand {
     /// Optionally, a prefix to the command.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
     pub prefix: Option<CommandPrefix>,
     /// The name of the command to execute.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
     #[cfg_attr(test, serde(rename = "w"))]
     pub word_or_name: Option<Word>,
     /// Optionally, a suffix to the command.
-    #[cfg_attr(test, serde(skip_serializing_if = "Option::is_none"))]
+    #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
     pub suffix: Option<CommandSuffix>,
 }
 
@@ -964,14 +1275,20 @@ impl SourceLocation for SimpleCommand {
         let end = self.suffix.as_ref().and_then(SourceLocation::location);
 
         maybe_location(
-            start.as_ref().or(mid.as_ref()),
-            end.as_ref().or(mid.as_ref()),
+            start.as_ref().or(mid.as_ref()).or(end.as_ref()),
+            end.as_ref().or(mid.as_ref()).or(start.as_ref()),
         )
     }
 }
 
 impl Display for SimpleCommand {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for SimpleCommand {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         let mut wrote_something = false;
 
         if let Some(prefix) = &self.prefix {
@@ -979,7 +1296,7 @@ impl Display for SimpleCommand {
                 write!(f, " ")?;
             }
 
-            write!(f, "{prefix}")?;
+            f.write(prefix)?;
             wrote_something = true;
         }
 
@@ -997,7 +1314,7 @@ impl Display for SimpleCommand {
                 write!(f, " ")?;
             }
 
-            write!(f, "{suffix}")?;
+            f.write(suffix)?;
         }
 
         Ok(())
@@ -1007,7 +1324,11 @@ impl Display for SimpleCommand {
 /// Represents a prefix to a simple command.
 #[derive(Clone, Debug, Default)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Prefix"))]
 pub struct CommandPrefix(pub Vec<CommandPrefixOrSuffixItem>);
 
@@ -1022,12 +1343,18 @@ impl SourceLocation for CommandPrefix {
 
 impl Display for CommandPrefix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CommandPrefix {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         for (i, item) in self.0.iter().enumerate() {
             if i > 0 {
                 write!(f, " ")?;
             }
 
-            write!(f, "{item}")?;
+            f.write(item)?;
         }
         Ok(())
     }
@@ -1036,7 +1363,11 @@ impl Display for CommandPrefix {
 /// Represents a suffix to a simple command; a word argument, declaration, or I/O redirection.
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Suffix"))]
 pub struct CommandSuffix(pub Vec<CommandPrefixOrSuffixItem>);
 
@@ -1051,12 +1382,18 @@ impl SourceLocation for CommandSuffix {
 
 impl Display for CommandSuffix {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CommandSuffix {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         for (i, item) in self.0.iter().enumerate() {
             if i > 0 {
                 write!(f, " ")?;
             }
 
-            write!(f, "{item}")?;
+            f.write(item)?;
         }
         Ok(())
     }
@@ -1065,7 +1402,11 @@ impl Display for CommandSuffix {
 /// Represents the I/O direction of a process substitution.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ProcessSubstitutionKind {
     /// The process is read from.
     Read,
@@ -1085,7 +1426,11 @@ impl Display for ProcessSubstitutionKind {
 /// A prefix or suffix for a simple command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum CommandPrefixOrSuffixItem {
     /// An I/O redirection.
     IoRedirect(IoRedirect),
@@ -1098,24 +1443,33 @@ pub enum CommandPrefixOrSuffixItem {
     ProcessSubstitution(ProcessSubstitutionKind, SubshellCommand),
 }
 
-// TODO: complete
 impl SourceLocation for CommandPrefixOrSuffixItem {
     fn location(&self) -> Option<TokenLocation> {
         match self {
+            Self::IoRedirect(r) => r.location(),
             Self::Word(w) => w.location(),
-            _ => None,
+            Self::AssignmentWord(a, _) => a.location(),
+            Self::ProcessSubstitution(_, s) => s.location(),
         }
     }
 }
 
 impl Display for CommandPrefixOrSuffixItem {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for CommandPrefixOrSuffixItem {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         match self {
-            Self::IoRedirect(io_redirect) => write!(f, "{io_redirect}"),
+            Self::IoRedirect(io_redirect) => f.write(io_redirect),
             Self::Word(word) => write!(f, "{word}"),
             Self::AssignmentWord(_assignment, word) => write!(f, "{word}"),
             Self::ProcessSubstitution(kind, subshell_command) => {
-                write!(f, "{kind}({subshell_command})")
+                write!(f, "{kind}(")?;
+                f.write(subshell_command)?;
+                write!(f, ")")
             }
         }
     }
@@ -1124,7 +1478,11 @@ impl Display for CommandPrefixOrSuffixItem {
 /// Encapsulates an assignment declaration.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Assign"))]
 pub struct Assignment {
     /// Name being assigned to.
@@ -1132,7 +1490,10 @@ pub struct Assignment {
     /// Value being assigned.
     pub value: AssignmentValue,
     /// Whether or not to append to the preexisting value associated with the named variable.
-    #[cfg_attr(test, serde(skip_serializing_if = "<&bool as std::ops::Not>::not"))]
+    #[cfg_attr(
+        test,
+        serde(default, skip_serializing_if = "<&bool as std::ops::Not>::not")
+    )]
     pub append: bool,
     /// Location of the assignment
     pub loc: TokenLocation,
@@ -1157,7 +1518,11 @@ impl Display for Assignment {
 /// The target of an assignment.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum AssignmentName {
     /// A named variable.
     #[cfg_attr(test, serde(rename = "Var"))]
@@ -1180,7 +1545,11 @@ impl Display for AssignmentName {
 /// A value being assigned to a variable.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum AssignmentValue {
     /// A scalar (word) value.
     Scalar(Word),
@@ -1188,10 +1557,22 @@ pub enum AssignmentValue {
     Array(Vec<(Option<Word>, Word)>),
 }
 
-// TODO: complete
 impl SourceLocation for AssignmentValue {
     fn location(&self) -> Option<TokenLocation> {
-        None
+        match self {
+            Self::Scalar(w) => w.location(),
+            // N.B. The enclosing parentheses are covered by the assignment's location.
+            Self::Array(elements) => {
+                let start = elements.first().and_then(|(key, value)| {
+                    key.as_ref()
+                        .and_then(SourceLocation::location)
+                        .or_else(|| value.location())
+                });
+                let end = elements.last().and_then(|(_, value)| value.location());
+
+                maybe_location(start.as_ref(), end.as_ref())
+            }
+        }
     }
 }
 
@@ -1219,20 +1600,35 @@ impl Display for AssignmentValue {
 /// A list of I/O redirections to be applied to a command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct RedirectList(pub Vec<IoRedirect>);
 
-// TODO: complete
 impl SourceLocation for RedirectList {
     fn location(&self) -> Option<TokenLocation> {
-        None
+        let start = self.0.first().and_then(SourceLocation::location);
+        let end = self.0.last().and_then(SourceLocation::location);
+
+        maybe_location(start.as_ref(), end.as_ref())
     }
 }
 
 impl Display for RedirectList {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for item in &self.0 {
-            write!(f, "{item}")?;
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for RedirectList {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
+        for (i, item) in self.0.iter().enumerate() {
+            if i > 0 {
+                write!(f, " ")?;
+            }
+            f.write(item)?;
         }
         Ok(())
     }
@@ -1244,36 +1640,65 @@ pub type IoFd = i32;
 /// An I/O redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum IoRedirect {
     /// Redirection to a file.
-    File(Option<IoFd>, IoFileRedirectKind, IoFileRedirectTarget),
+    File(
+        Option<IoFd>,
+        IoFileRedirectKind,
+        IoFileRedirectTarget,
+        TokenLocation,
+    ),
     /// Redirection from a here-document.
-    HereDocument(Option<IoFd>, IoHereDocument),
+    HereDocument(Option<IoFd>, IoHereDocument, TokenLocation),
     /// Redirection from a here-string.
-    HereString(Option<IoFd>, Word),
+    HereString(Option<IoFd>, Word, TokenLocation),
     /// Redirection of both standard output and standard error (with optional append).
-    OutputAndError(Word, bool),
+    OutputAndError(Word, bool, TokenLocation),
 }
 
-// TODO: complete
 impl SourceLocation for IoRedirect {
     fn location(&self) -> Option<TokenLocation> {
-        None
+        match self {
+            Self::File(.., loc)
+            | Self::HereDocument(.., loc)
+            | Self::HereString(.., loc)
+            | Self::OutputAndError(.., loc) => Some(loc.clone()),
+        }
     }
 }
 
 impl Display for IoRedirect {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for IoRedirect {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         match self {
-            Self::File(fd_num, kind, target) => {
+            Self::File(fd_num, kind, target, _) => {
                 if let Some(fd_num) = fd_num {
                     write!(f, "{fd_num}")?;
                 }
 
-                write!(f, "{kind} {target}")?;
+                // Duplicated file descriptors are conventionally written without a
+                // space (e.g., `2>&1`).
+                if matches!(
+                    kind,
+                    IoFileRedirectKind::DuplicateInput | IoFileRedirectKind::DuplicateOutput
+                ) {
+                    write!(f, "{kind}")?;
+                } else {
+                    write!(f, "{kind} ")?;
+                }
+                f.write(target)?;
             }
-            Self::OutputAndError(target, append) => {
+            Self::OutputAndError(target, append, _) => {
                 write!(f, "&>")?;
                 if *append {
                     write!(f, ">")?;
@@ -1288,6 +1713,7 @@ impl Display for IoRedirect {
                     doc,
                     ..
                 },
+                _,
             ) => {
                 if let Some(fd_num) = fd_num {
                     write!(f, "{fd_num}")?;
@@ -1298,12 +1724,19 @@ impl Display for IoRedirect {
                     write!(f, "-")?;
                 }
 
-                writeln!(f, "{here_end}")?;
-
-                write!(f, "{doc}")?;
-                writeln!(f, "{here_end}")?;
+                write!(f, "{here_end}")?;
+
+                // The body follows the end of the line containing the redirection, and
+                // is terminated by the unquoted delimiter.
+                let delimiter: String = here_end
+                    .value
+                    .chars()
+                    .filter(|c| !matches!(c, '\'' | '"' | '\\'))
+                    .collect();
+                let body = std::format!("{doc}{delimiter}\n");
+                f.here_doc(body)?;
             }
-            Self::HereString(fd_num, s) => {
+            Self::HereString(fd_num, s, _) => {
                 if let Some(fd_num) = fd_num {
                     write!(f, "{fd_num}")?;
                 }
@@ -1319,7 +1752,11 @@ impl Display for IoRedirect {
 /// Kind of file I/O redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum IoFileRedirectKind {
     /// Read (`<`).
     Read,
@@ -1354,7 +1791,11 @@ impl Display for IoFileRedirectKind {
 /// Target for an I/O file redirection.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum IoFileRedirectTarget {
     /// Path to a file.
     Filename(Word),
@@ -1369,13 +1810,30 @@ pub enum IoFileRedirectTarget {
     Duplicate(Word),
 }
 
+impl SourceLocation for IoFileRedirectTarget {
+    fn location(&self) -> Option<TokenLocation> {
+        match self {
+            Self::Filename(w) | Self::Duplicate(w) => w.location(),
+            Self::Fd(_) => None,
+            Self::ProcessSubstitution(_, s) => s.location(),
+        }
+    }
+}
+
 impl Display for IoFileRedirectTarget {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        SourceWriter::new(f).write(self)
+    }
+}
+
+impl WriteSource for IoFileRedirectTarget {
+    fn write_source(&self, f: &mut SourceWriter<'_>) -> std::fmt::Result {
         match self {
             Self::Filename(word) => write!(f, "{word}"),
             Self::Fd(fd) => write!(f, "{fd}"),
             Self::ProcessSubstitution(kind, subshell_command) => {
-                write!(f, "{kind}{subshell_command}")
+                write!(f, "{kind}")?;
+                f.write(subshell_command)
             }
             Self::Duplicate(word) => write!(f, "{word}"),
         }
@@ -1385,30 +1843,45 @@ impl Display for IoFileRedirectTarget {
 /// Represents an I/O here document.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct IoHereDocument {
     /// Whether to remove leading tabs from the here document.
-    #[cfg_attr(test, serde(skip_serializing_if = "<&bool as std::ops::Not>::not"))]
+    #[cfg_attr(
+        test,
+        serde(default, skip_serializing_if = "<&bool as std::ops::Not>::not")
+    )]
     pub remove_tabs: bool,
     /// Whether to basic-expand the contents of the here document.
-    #[cfg_attr(test, serde(skip_serializing_if = "<&bool as std::ops::Not>::not"))]
+    #[cfg_attr(
+        test,
+        serde(default, skip_serializing_if = "<&bool as std::ops::Not>::not")
+    )]
     pub requires_expansion: bool,
     /// The delimiter marking the end of the here document.
     pub here_end: Word,
     /// The contents of the here document.
     pub doc: Word,
+    /// Location of the here document, from its delimiter through the closing delimiter.
+    pub loc: TokenLocation,
 }
 
-// TODO: complete
 impl SourceLocation for IoHereDocument {
     fn location(&self) -> Option<TokenLocation> {
-        None
+        Some(self.loc.clone())
     }
 }
 
 /// A (non-extended) test expression.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum TestExpr {
     /// Always evaluates to false.
     False,
@@ -1428,7 +1901,8 @@ pub enum TestExpr {
     BinaryTest(BinaryPredicate, String, String),
 }
 
-// TODO: complete
+// N.B. Test expressions are parsed from the expanded arguments of the `test` and `[`
+// builtins, so they have no location in the source script.
 impl SourceLocation for TestExpr {
     fn location(&self) -> Option<TokenLocation> {
         None
@@ -1451,24 +1925,46 @@ impl Display for TestExpr {
 }
 
 /// An extended test expression.
+///
+/// Nodes whose extent is bounded by their operands derive their location from them.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ExtendedTestExpr {
     /// Logical AND operation on two nested expressions.
     And(Box<Self>, Box<Self>),
     /// Logical OR operation on two nested expressions.
     Or(Box<Self>, Box<Self>),
     /// Logical NOT operation on a nested expression.
-    Not(Box<Self>),
+    Not(Box<Self>, TokenLocation),
     /// A parenthesized expression.
-    Parenthesized(Box<Self>),
+    Parenthesized(Box<Self>, TokenLocation),
     /// A unary test operation.
-    UnaryTest(UnaryPredicate, Word),
+    UnaryTest(UnaryPredicate, Word, TokenLocation),
     /// A binary test operation.
     BinaryTest(BinaryPredicate, Word, Word),
 }
 
+impl SourceLocation for ExtendedTestExpr {
+    fn location(&self) -> Option<TokenLocation> {
+        match self {
+            Self::And(left, right) | Self::Or(left, right) => {
+                maybe_location(left.location().as_ref(), right.location().as_ref())
+            }
+            Self::Not(_, loc) | Self::Parenthesized(_, loc) | Self::UnaryTest(_, _, loc) => {
+                Some(loc.clone())
+            }
+            Self::BinaryTest(_, left, right) => {
+                maybe_location(left.location().as_ref(), right.location().as_ref())
+            }
+        }
+    }
+}
+
 impl Display for ExtendedTestExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -1478,13 +1974,13 @@ impl Display for ExtendedTestExpr {
             Self::Or(left, right) => {
                 write!(f, "{left} || {right}")
             }
-            Self::Not(expr) => {
+            Self::Not(expr, _) => {
                 write!(f, "! {expr}")
             }
-            Self::Parenthesized(expr) => {
+            Self::Parenthesized(expr, _) => {
                 write!(f, "( {expr} )")
             }
-            Self::UnaryTest(pred, word) => {
+            Self::UnaryTest(pred, word, _) => {
                 write!(f, "{pred} {word}")
             }
             Self::BinaryTest(pred, left, right) => {
@@ -1497,7 +1993,11 @@ impl Display for ExtendedTestExpr {
 /// An extended test expression command.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct ExtendedTestExprCommand {
     /// The extended test expression
     pub expr: ExtendedTestExpr,
@@ -1520,7 +2020,11 @@ impl Display for ExtendedTestExprCommand {
 /// A unary predicate usable in an extended test expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum UnaryPredicate {
     /// Computes if the operand is a path to an existing file.
     FileExists,
@@ -1609,7 +2113,11 @@ impl Display for UnaryPredicate {
 /// A binary predicate usable in an extended test expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum BinaryPredicate {
     /// Computes if two files refer to the same device and inode numbers.
     FilesReferToSameDeviceAndInodeNumbers,
@@ -1674,7 +2182,11 @@ impl Display for BinaryPredicate {
 /// Represents a shell word.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "W"))]
 pub struct Word {
     /// Raw text of the word.
@@ -1746,10 +2258,22 @@ impl Word {
 /// Encapsulates an unparsed arithmetic expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct UnexpandedArithmeticExpr {
     /// The raw text of the expression.
     pub value: String,
+    /// Location of the expression's text, when known.
+    pub loc: Option<TokenLocation>,
+}
+
+impl SourceLocation for UnexpandedArithmeticExpr {
+    fn location(&self) -> Option<TokenLocation> {
+        self.loc.clone()
+    }
 }
 
 impl Display for UnexpandedArithmeticExpr {
@@ -1759,15 +2283,23 @@ impl Display for UnexpandedArithmeticExpr {
 }
 
 /// An arithmetic expression.
+///
+/// Locations are only known for expressions parsed with
+/// [`crate::parser::arithmetic::parse_with_location`]; nodes whose extent is
+/// bounded by their operands derive their location from them.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ArithmeticExpr {
     /// A literal integer value.
-    Literal(i64),
+    Literal(i64, Option<TokenLocation>),
     /// A dereference of a variable or array element.
     Reference(ArithmeticTarget),
     /// A unary operation on an the result of a given nested expression.
-    UnaryOp(UnaryOperator, Box<Self>),
+    UnaryOp(UnaryOperator, Box<Self>, Option<TokenLocation>),
     /// A binary operation on two nested expressions.
     BinaryOp(BinaryOperator, Box<Self>, Box<Self>),
     /// A ternary conditional expression.
@@ -1777,13 +2309,31 @@ pub enum ArithmeticExpr {
     /// A binary assignment operation.
     BinaryAssignment(BinaryOperator, ArithmeticTarget, Box<Self>),
     /// A unary assignment operation.
-    UnaryAssignment(UnaryAssignmentOperator, ArithmeticTarget),
+    UnaryAssignment(
+        UnaryAssignmentOperator,
+        ArithmeticTarget,
+        Option<TokenLocation>,
+    ),
 }
 
-// TODO: complete and add loc for literal
 impl SourceLocation for ArithmeticExpr {
     fn location(&self) -> Option<TokenLocation> {
-        None
+        match self {
+            Self::Literal(_, loc) | Self::UnaryOp(_, _, loc) | Self::UnaryAssignment(_, _, loc) => {
+                loc.clone()
+            }
+            Self::Reference(target) => target.location(),
+            Self::BinaryOp(_, left, right) => {
+                maybe_location(left.location().as_ref(), right.location().as_ref())
+            }
+            Self::Conditional(condition, _, else_branch) => maybe_location(
+                condition.location().as_ref(),
+                else_branch.location().as_ref(),
+            ),
+            Self::Assignment(target, value) | Self::BinaryAssignment(_, target, value) => {
+                maybe_location(target.location().as_ref(), value.location().as_ref())
+            }
+        }
     }
 }
 
@@ -1802,11 +2352,12 @@ impl<'a> arbitrary::Arbitrary<'a> for ArithmeticExpr {
         ])?;
 
         match *variant {
-            "Literal" => Ok(Self::Literal(i64::arbitrary(u)?)),
+            "Literal" => Ok(Self::Literal(i64::arbitrary(u)?, None)),
             "Reference" => Ok(Self::Reference(ArithmeticTarget::arbitrary(u)?)),
             "UnaryOp" => Ok(Self::UnaryOp(
                 UnaryOperator::arbitrary(u)?,
                 Box::new(Self::arbitrary(u)?),
+                None,
             )),
             "BinaryOp" => Ok(Self::BinaryOp(
                 BinaryOperator::arbitrary(u)?,
@@ -1830,6 +2381,7 @@ impl<'a> arbitrary::Arbitrary<'a> for ArithmeticExpr {
             "UnaryAssignment" => Ok(Self::UnaryAssignment(
                 UnaryAssignmentOperator::arbitrary(u)?,
                 ArithmeticTarget::arbitrary(u)?,
+                None,
             )),
             _ => unreachable!(),
         }
@@ -1839,9 +2391,9 @@ impl<'a> arbitrary::Arbitrary<'a> for ArithmeticExpr {
 impl Display for ArithmeticExpr {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Literal(literal) => write!(f, "{literal}"),
+            Self::Literal(literal, _) => write!(f, "{literal}"),
             Self::Reference(target) => write!(f, "{target}"),
-            Self::UnaryOp(op, operand) => write!(f, "{op}{operand}"),
+            Self::UnaryOp(op, operand, _) => write!(f, "{op}{operand}"),
             Self::BinaryOp(op, left, right) => {
                 if matches!(op, BinaryOperator::Comma) {
                     write!(f, "{left}{op} {right}")
@@ -1856,7 +2408,7 @@ impl Display for ArithmeticExpr {
             Self::BinaryAssignment(op, target, operand) => {
                 write!(f, "{target} {op}= {operand}")
             }
-            Self::UnaryAssignment(op, target) => match op {
+            Self::UnaryAssignment(op, target, _) => match op {
                 UnaryAssignmentOperator::PrefixIncrement
                 | UnaryAssignmentOperator::PrefixDecrement => write!(f, "{op}{target}"),
                 UnaryAssignmentOperator::PostfixIncrement
@@ -1869,7 +2421,11 @@ impl Display for ArithmeticExpr {
 /// A binary arithmetic operator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum BinaryOperator {
     /// Exponentiation (e.g., `x ** y`).
     Power,
@@ -1943,7 +2499,11 @@ impl Display for BinaryOperator {
 /// A unary arithmetic operator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum UnaryOperator {
     /// Unary plus (e.g., `+x`).
     UnaryPlus,
@@ -1969,7 +2529,11 @@ impl Display for UnaryOperator {
 /// A unary arithmetic assignment operator.
 #[derive(Clone, Copy, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum UnaryAssignmentOperator {
     /// Prefix increment (e.g., `++x`).
     PrefixIncrement,
@@ -1995,26 +2559,31 @@ impl Display for UnaryAssignmentOperator {
 /// Identifies the target of an arithmetic assignment expression.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ArithmeticTarget {
     /// A named variable.
-    Variable(String),
+    Variable(String, Option<TokenLocation>),
     /// An element in an array.
-    ArrayElement(String, Box<ArithmeticExpr>),
+    ArrayElement(String, Box<ArithmeticExpr>, Option<TokenLocation>),
 }
 
-// TODO: complete and add loc
 impl SourceLocation for ArithmeticTarget {
     fn location(&self) -> Option<TokenLocation> {
-        None
+        match self {
+            Self::Variable(_, loc) | Self::ArrayElement(_, _, loc) => loc.clone(),
+        }
     }
 }
 
 impl Display for ArithmeticTarget {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            Self::Variable(name) => write!(f, "{name}"),
-            Self::ArrayElement(name, index) => write!(f, "{name}[{index}]"),
+            Self::Variable(name, _) => write!(f, "{name}"),
+            Self::ArrayElement(name, index, _) => write!(f, "{name}[{index}]"),
         }
     }
 }
@@ -0,0 +1,165 @@
+//! Helpers for displaying AST nodes as shell source that can be parsed back.
+//!
+//! The text of a here-document follows the line containing its redirection operator,
+//! rather than the operator itself. When displaying a command list, here-document
+//! bodies are therefore deferred until the end of the current line, and are never
+//! indented (which would change their contents and hide their delimiters).
+//!
+//! Nodes that can contain commands implement [`WriteSource`], writing themselves (and
+//! their children) through a [`SourceWriter`] that carries the indentation and the
+//! pending here-documents; their [`Display`](std::fmt::Display) implementations start a
+//! new writer.
+
+use std::fmt::Write;
+
+const DISPLAY_INDENT: &str = "    ";
+
+/// An AST node that can be written as shell source through a [`SourceWriter`].
+pub(crate) trait WriteSource {
+    /// Writes the node as shell source.
+    ///
+    /// # Arguments
+    ///
+    /// * `w` - The writer to write to.
+    fn write_source(&self, w: &mut SourceWriter<'_>) -> std::fmt::Result;
+}
+
+/// A writer of shell source that indents each (non-empty) line written through it, and
+/// holds the here-document bodies waiting for the end of the current line.
+pub(crate) struct SourceWriter<'a> {
+    inner: &'a mut dyn Write,
+    /// Number of levels by which lines are indented.
+    indent: usize,
+    at_line_start: bool,
+    /// Stack of scopes (one per command list being written), each holding the
+    /// here-document bodies waiting for the end of the current line.
+    here_docs: Vec<Vec<String>>,
+}
+
+impl<'a> SourceWriter<'a> {
+    /// Returns a writer that writes to the given writer, starting at the beginning of an
+    /// unindented line.
+    ///
+    /// # Arguments
+    ///
+    /// * `inner` - The writer to write to.
+    pub fn new(inner: &'a mut dyn Write) -> Self {
+        Self {
+            inner,
+            indent: 0,
+            at_line_start: true,
+            here_docs: vec![],
+        }
+    }
+
+    /// Writes a node as shell source.
+    ///
+    /// # Arguments
+    ///
+    /// * `node` - The node to write.
+    pub fn write(&mut self, node: &(impl WriteSource + ?Sized)) -> std::fmt::Result {
+        node.write_source(self)
+    }
+
+    /// Writes through the given function with lines indented by one more level.
+    ///
+    /// # Arguments
+    ///
+    /// * `write` - Function that writes the indented text.
+    pub fn indented(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        self.indent += 1;
+        let result = write(self);
+        self.indent -= 1;
+
+        result
+    }
+
+    /// Writes a command list through the given function, collecting the here-documents it
+    /// contains so they can be written at the end of their lines. Any still pending when
+    /// the list ends are handed to the enclosing list, or written out immediately if there
+    /// is none.
+    ///
+    /// # Arguments
+    ///
+    /// * `write` - Function that writes the list.
+    pub fn here_doc_scope(
+        &mut self,
+        write: impl FnOnce(&mut Self) -> std::fmt::Result,
+    ) -> std::fmt::Result {
+        self.here_docs.push(vec![]);
+        let result = write(self);
+        let pending = self.here_docs.pop().unwrap_or_default();
+
+        result?;
+
+        if let Some(parent) = self.here_docs.last_mut() {
+            parent.extend(pending);
+        } else if !pending.is_empty() {
+            writeln!(self)?;
+            self.write_here_doc_bodies(&pending)?;
+        }
+
+        Ok(())
+    }
+
+    /// Writes a here-document body at the end of the current line: deferred until then if
+    /// a command list is being written, or otherwise immediately, after ending the line.
+    ///
+    /// # Arguments
+    ///
+    /// * `body` - The here-document's contents, followed by its delimiter line.
+    pub fn here_doc(&mut self, body: String) -> std::fmt::Result {
+        if let Some(scope) = self.here_docs.last_mut() {
+            scope.push(body);
+            Ok(())
+        } else {
+            writeln!(self)?;
+            self.write_here_doc_bodies(&[body])
+        }
+    }
+
+    /// Ends the current line, writing out any here-documents deferred until then.
+    pub fn end_line(&mut self) -> std::fmt::Result {
+        writeln!(self)?;
+
+        let pending = self
+            .here_docs
+            .last_mut()
+            .map(std::mem::take)
+            .unwrap_or_default();
+
+        self.write_here_doc_bodies(&pending)
+    }
+
+    /// Writes here-document bodies verbatim (i.e., without indentation).
+    fn write_here_doc_bodies(&mut self, bodies: &[String]) -> std::fmt::Result {
+        for body in bodies {
+            self.inner.write_str(body)?;
+            if !body.is_empty() {
+                self.at_line_start = body.ends_with('\n');
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Write for SourceWriter<'_> {
+    fn write_str(&mut self, s: &str) -> std::fmt::Result {
+        for line in s.split_inclusive('\n') {
+            if self.at_line_start && line != "\n" {
+                for _ in 0..self.indent {
+                    self.inner.write_str(DISPLAY_INDENT)?;
+                }
+            }
+
+            self.inner.write_str(line)?;
+            self.at_line_start = line.ends_with('\n');
+        }
+
+        Ok(())
+    }
+}
@@ -1,5 +1,7 @@
-use super::Token;
+use std::sync::Arc;
+
 use super::tokenizer;
+use super::{Token, TokenLocation};
 
 /// Represents an error that occurred while parsing tokens.
 #[derive(thiserror::Error, Debug)]
@@ -12,6 +14,17 @@ pub enum ParseError {
     #[error("syntax error at end of input")]
     ParsingAtEndOfInput,
 
+    /// A parsing error occurred within the given span of the input, near the given
+    /// token (or at the end of the input, if no token is present). Produced when parsing
+    /// with error recovery.
+    #[error("{}", describe_error_within_span(.token.as_ref(), .span))]
+    ParsingWithinSpan {
+        /// The token near which the error was detected, if any.
+        token: Option<Token>,
+        /// The span of input that could not be parsed.
+        span: TokenLocation,
+    },
+
     /// An error occurred while tokenizing the input stream.
     #[error("{} (detected near {})", .inner, .position.as_ref().map_or_else(|| String::from("<unknown position>"), |p| std::format!("line {} col {}", p.line, p.column)))]
     Tokenizing {
@@ -22,30 +35,59 @@ pub enum ParseError {
     },
 }
 
+fn describe_error_within_span(token: Option<&Token>, span: &TokenLocation) -> String {
+    match token {
+        Some(token) => {
+            let text = match token.to_str() {
+                "\n" => "newline",
+                text => text,
+            };
+            let start = &token.location().start;
+            std::format!(
+                "syntax error near token `{text}' (line {} col {})",
+                start.line,
+                start.column
+            )
+        }
+        None => std::format!(
+            "syntax error at end of input (line {} col {})",
+            span.end.line,
+            span.end.column
+        ),
+    }
+}
+
+impl ParseError {
+    /// Returns the location of the input that the error pertains to, if known.
+    pub fn location(&self) -> Option<TokenLocation> {
+        match self {
+            Self::ParsingNearToken(token) => Some(token.location().clone()),
+            Self::ParsingWithinSpan { span, .. } => Some(span.clone()),
+            Self::Tokenizing { position, .. } => position.as_ref().map(|p| {
+                let p = Arc::new(p.clone());
+                TokenLocation {
+                    start: p.clone(),
+                    end: p,
+                }
+            }),
+            Self::ParsingAtEndOfInput => None,
+        }
+    }
+}
+
 #[cfg(feature = "diagnostics")]
 #[allow(clippy::cast_sign_loss)]
 #[allow(unused)] // Workaround unused warnings in nightly versions of the compiler
 pub mod miette {
     use super::ParseError;
-    use miette::SourceOffset;
+    use crate::parser::TokenLocation;
+    use miette::{LabeledSpan, SourceOffset, SourceSpan};
 
     impl ParseError {
         /// Convert the original error to one miette can pretty print
         pub fn to_pretty_error(self, input: impl Into<String>) -> PrettyError {
             let input = input.into();
-            let location = match self {
-                Self::ParsingNearToken(ref token) => Some(SourceOffset::from_location(
-                    &input,
-                    token.location().start.line,
-                    token.location().start.column,
-                )),
-                Self::Tokenizing { ref position, .. } => position
-                    .as_ref()
-                    .map(|p| SourceOffset::from_location(&input, p.line, p.column)),
-                Self::ParsingAtEndOfInput => {
-                    Some(SourceOffset::from_location(&input, usize::MAX, usize::MAX))
-                }
-            };
+            let location = error_span(&self, &input);
 
             PrettyError {
                 cause: self,
@@ -53,6 +95,51 @@ pub mod miette {
                 location,
             }
         }
+
+        /// Convert a set of errors (e.g., all of those collected while parsing with
+        /// error recovery) to a single report miette can pretty print, labeling each
+        /// of them in the input.
+        ///
+        /// # Arguments
+        ///
+        /// * `errors` - The errors to report.
+        /// * `input` - The input that was parsed.
+        pub fn to_pretty_errors(
+            errors: impl IntoIterator<Item = Self>,
+            input: impl Into<String>,
+        ) -> PrettyErrors {
+            let input = input.into();
+            let labels = errors
+                .into_iter()
+                .map(|error| {
+                    let span = error_span(&error, &input)
+                        .unwrap_or_else(|| SourceOffset::from(input.len()).into());
+                    LabeledSpan::new_with_span(Some(error.to_string()), span)
+                })
+                .collect();
+
+            PrettyErrors { input, labels }
+        }
+    }
+
+    fn error_span(error: &ParseError, input: &str) -> Option<SourceSpan> {
+        match error {
+            ParseError::ParsingNearToken(token) => Some(location_span(token.location(), input)),
+            ParseError::ParsingWithinSpan { span, .. } => Some(location_span(span, input)),
+            ParseError::Tokenizing { position, .. } => position
+                .as_ref()
+                .map(|p| SourceOffset::from_location(input, p.line, p.column).into()),
+            ParseError::ParsingAtEndOfInput => {
+                Some(SourceOffset::from_location(input, usize::MAX, usize::MAX).into())
+            }
+        }
+    }
+
+    fn location_span(location: &TokenLocation, input: &str) -> SourceSpan {
+        let start = SourceOffset::from_location(input, location.start.line, location.start.column);
+        let end = SourceOffset::from_location(input, location.end.line, location.end.column);
+
+        SourceSpan::new(start, end.offset().saturating_sub(start.offset()))
     }
 
     /// Represents an error that occurred while parsing tokens.
@@ -63,7 +150,17 @@ pub mod miette {
         #[source_code]
         input: String,
         #[label("{cause}")]
-        location: Option<SourceOffset>,
+        location: Option<SourceSpan>,
+    }
+
+    /// Represents a set of errors that occurred while parsing tokens.
+    #[derive(thiserror::Error, Debug, miette::Diagnostic)]
+    #[error("Cannot parse the input script ({} syntax error(s))", .labels.len())]
+    pub struct PrettyErrors {
+        #[source_code]
+        input: String,
+        #[label(collection)]
+        labels: Vec<LabeledSpan>,
     }
 }
 
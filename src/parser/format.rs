@@ -0,0 +1,1118 @@
+//! Formats shell programs as canonical, consistently laid out source text.
+//!
+//! The layout is modeled on that of `shfmt`: each command goes on its own line and
+//! compound commands are expanded into indented blocks, while brace groups and
+//! subshells written on a single line stay on one. Words, arithmetic expressions and
+//! here-document bodies are written verbatim. Comments and line breaks following `&&`,
+//! `||` and `|` are carried over from the source, as are (single) blank lines between
+//! commands.
+//!
+//! Formatting is idempotent: formatting already formatted text leaves it unchanged.
+
+use super::ast::{self, SourceLocation};
+use super::{Comment, ParseError, Parser, ParserOptions, SourceInfo, TokenLocation};
+
+/// Unit of indentation used when formatting.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Indent {
+    /// Indent with the given number of spaces per level.
+    Spaces(usize),
+    /// Indent with one tab per level.
+    Tabs,
+}
+
+/// Options controlling how shell programs are formatted.
+#[derive(Clone, Debug)]
+pub struct FormatOptions {
+    /// Unit of indentation.
+    pub indent: Indent,
+    /// Whether lines broken at a binary operator (`&&`, `||` or `|`) should start with
+    /// the operator (continuing the previous line with a `\`), rather than end with it.
+    pub binary_next_line: bool,
+    /// Whether to indent the items of `case` commands relative to `case` and `esac`.
+    pub switch_case_indent: bool,
+    /// Whether to align the commands of consecutive single-line `case` items.
+    pub align_case_items: bool,
+    /// Whether to write a space between redirection operators and their targets.
+    pub space_redirects: bool,
+}
+
+impl Default for FormatOptions {
+    fn default() -> Self {
+        Self {
+            indent: Indent::Spaces(4),
+            binary_next_line: false,
+            switch_case_indent: false,
+            align_case_items: false,
+            space_redirects: false,
+        }
+    }
+}
+
+/// Parses the given shell script and formats it.
+///
+/// # Arguments
+///
+/// * `input` - The shell script to format.
+/// * `options` - Options controlling the formatting.
+/// * `parser_options` - Options controlling how the script is parsed.
+pub fn format_str(
+    input: &str,
+    options: &FormatOptions,
+    parser_options: &ParserOptions,
+) -> Result<String, ParseError> {
+    let mut parser = Parser::new(input.as_bytes(), parser_options, &SourceInfo::default());
+    let (program, comments) = parser.parse_program_with_comments()?;
+
+    Ok(format_program(&program, &comments, options))
+}
+
+/// Formats the given program. Comments are placed according to their locations
+/// relative to those of the program's commands.
+///
+/// # Arguments
+///
+/// * `program` - The program to format.
+/// * `comments` - The comments from the program's source, in source order.
+/// * `options` - Options controlling the formatting.
+pub fn format_program(
+    program: &ast::Program,
+    comments: &[Comment],
+    options: &FormatOptions,
+) -> String {
+    let mut formatter = Formatter::new(comments, options);
+
+    for complete_command in &program.complete_commands {
+        formatter.statements(complete_command);
+    }
+
+    formatter.leading_comments(usize::MAX);
+    formatter.out
+}
+
+struct Formatter<'a> {
+    options: &'a FormatOptions,
+    out: String,
+    indent_level: usize,
+    at_line_start: bool,
+    /// Comments not yet written.
+    comments: std::iter::Peekable<std::slice::Iter<'a, Comment>>,
+    /// Here-document bodies waiting for the end of the current line.
+    pending_here_docs: Vec<String>,
+    /// Last source line of the content written to the current line.
+    source_line: usize,
+    /// Last source line of the most recently written command or comment.
+    last_statement_line: usize,
+    /// Last source line of the most recently written here-document.
+    last_here_doc_line: usize,
+    /// Whether nothing has been written yet in the current block.
+    block_start: bool,
+}
+
+impl<'a> Formatter<'a> {
+    fn new(comments: &'a [Comment], options: &'a FormatOptions) -> Self {
+        Self {
+            options,
+            out: String::new(),
+            indent_level: 0,
+            at_line_start: true,
+            comments: comments.iter().peekable(),
+            pending_here_docs: vec![],
+            source_line: 0,
+            last_statement_line: 0,
+            last_here_doc_line: 0,
+            block_start: true,
+        }
+    }
+
+    //
+    // Output primitives.
+    //
+
+    fn write(&mut self, s: &str) {
+        if s.is_empty() {
+            return;
+        }
+
+        if self.at_line_start {
+            for _ in 0..self.indent_level {
+                match self.options.indent {
+                    Indent::Spaces(n) => self.out.extend(std::iter::repeat_n(' ', n)),
+                    Indent::Tabs => self.out.push('\t'),
+                }
+            }
+            self.at_line_start = false;
+        }
+
+        self.out.push_str(s);
+    }
+
+    /// Notes that content from the given location has been written to the current line.
+    fn mark(&mut self, loc: Option<&TokenLocation>) {
+        if let Some(loc) = loc {
+            self.source_line = self.source_line.max(last_line(loc));
+        }
+    }
+
+    /// Notes that content from the start of the given location has been written to the
+    /// current line.
+    fn mark_start(&mut self, loc: Option<&TokenLocation>) {
+        if let Some(loc) = loc {
+            self.source_line = self.source_line.max(loc.start.line);
+        }
+    }
+
+    /// Ends the current line, after any comments that trail its content in the source,
+    /// and writes out the here-document bodies deferred until then.
+    fn newline(&mut self) {
+        self.end_line(true);
+    }
+
+    fn end_line(&mut self, with_trailing_comments: bool) {
+        if with_trailing_comments && !self.at_line_start && self.source_line > 0 {
+            while let Some(comment) = self
+                .comments
+                .next_if(|c| c.loc.start.line <= self.source_line)
+            {
+                self.out.push(' ');
+                self.out.push_str(&comment.text);
+            }
+        }
+
+        self.out.push('\n');
+        self.at_line_start = true;
+        self.source_line = 0;
+
+        for body in std::mem::take(&mut self.pending_here_docs) {
+            self.out.push_str(&body);
+        }
+    }
+
+    /// Writes, on their own lines, any comments that start before the given source line.
+    fn leading_comments(&mut self, before_line: usize) {
+        while let Some(comment) = self.comments.next_if(|c| c.loc.start.line < before_line) {
+            if !self.at_line_start {
+                self.end_line(false);
+            }
+
+            self.separate(comment.loc.start.line);
+            self.write(&comment.text);
+            self.end_line(false);
+            self.last_statement_line = comment.loc.start.line;
+        }
+    }
+
+    /// Preserves a blank line from the source before content starting at the given line.
+    fn separate(&mut self, line: usize) {
+        if !self.block_start && self.last_statement_line > 0 && line > self.last_statement_line + 1
+        {
+            self.out.push('\n');
+        }
+        self.block_start = false;
+    }
+
+    /// Writes a line break after a binary operator, which continues the command.
+    fn break_at_operator(&mut self, operator: &str) {
+        if self.options.binary_next_line && self.pending_here_docs.is_empty() {
+            self.write(" \\");
+            // N.B. Comments can't follow a line continuation; they're kept for later.
+            self.end_line(false);
+            self.write(operator);
+            self.write(" ");
+        } else {
+            self.write(" ");
+            self.write(operator);
+            self.newline();
+        }
+    }
+
+    //
+    // Command lists.
+    //
+
+    /// Writes the items of a list as separate lines.
+    fn statements(&mut self, list: &ast::CompoundList) {
+        for item in &list.0 {
+            let loc = item.location();
+            let start_line = loc.as_ref().map_or(0, |loc| loc.start.line);
+
+            self.leading_comments(start_line);
+            self.separate(start_line);
+
+            self.and_or_list(&item.0);
+            if matches!(item.1, ast::SeparatorOperator::Async) {
+                self.write(" &");
+            }
+            self.newline();
+
+            if let Some(loc) = &loc {
+                self.last_statement_line = last_line(loc).max(self.last_here_doc_line);
+            }
+        }
+    }
+
+    /// Writes the items of a list as an indented block, followed by any comments that
+    /// precede the line closing the block.
+    fn block(&mut self, list: &ast::CompoundList, end_line: usize) {
+        self.indent_level += 1;
+        self.block_start = true;
+        self.statements(list);
+        self.leading_comments(end_line);
+        self.indent_level -= 1;
+    }
+
+    /// Writes the items of a list on the current line.
+    fn inline_list(&mut self, list: &ast::CompoundList) {
+        for (i, item) in list.0.iter().enumerate() {
+            if i > 0 {
+                self.write(" ");
+            }
+
+            self.and_or_list(&item.0);
+
+            match item.1 {
+                ast::SeparatorOperator::Async => self.write(" &"),
+                ast::SeparatorOperator::Sequence if i + 1 < list.0.len() => self.write(";"),
+                ast::SeparatorOperator::Sequence => (),
+            }
+        }
+    }
+
+    /// Writes a list on the current line, followed by the given keyword (e.g., `then`).
+    fn inline_list_then(&mut self, list: &ast::CompoundList, keyword: &str) {
+        self.inline_list(list);
+
+        if matches!(list.0.last(), Some(item) if matches!(item.1, ast::SeparatorOperator::Async)) {
+            self.write(" ");
+        } else {
+            self.write("; ");
+        }
+        self.write(keyword);
+    }
+
+    fn and_or_list(&mut self, list: &ast::AndOrList) {
+        self.pipeline(&list.first);
+
+        let mut previous_end_line = last_line(&list.first.loc);
+        let mut continued = false;
+
+        for and_or in &list.additional {
+            let (operator, pipeline) = match and_or {
+                ast::AndOr::And(pipeline) => ("&&", pipeline),
+                ast::AndOr::Or(pipeline) => ("||", pipeline),
+            };
+
+            if pipeline.loc.start.line > previous_end_line {
+                if !continued {
+                    self.indent_level += 1;
+                    continued = true;
+                }
+                self.break_at_operator(operator);
+            } else {
+                self.write(" ");
+                self.write(operator);
+                self.write(" ");
+            }
+
+            self.pipeline(pipeline);
+            previous_end_line = last_line(&pipeline.loc);
+        }
+
+        if continued {
+            self.indent_level -= 1;
+        }
+    }
+
+    fn pipeline(&mut self, pipeline: &ast::Pipeline) {
+        if let Some(timed) = &pipeline.timed {
+            self.write(if timed.is_posix_output() {
+                "time -p "
+            } else {
+                "time "
+            });
+            self.mark(timed.location().as_ref());
+        }
+
+        if pipeline.bang {
+            self.write("! ");
+        }
+
+        let mut previous_end_line = None;
+        let mut continued = false;
+
+        for command in &pipeline.seq {
+            let loc = command.location();
+
+            if let Some(previous_end_line) = previous_end_line {
+                if loc
+                    .as_ref()
+                    .is_some_and(|loc| loc.start.line > previous_end_line)
+                {
+                    if !continued {
+                        self.indent_level += 1;
+                        continued = true;
+                    }
+                    self.break_at_operator("|");
+                } else {
+                    self.write(" | ");
+                }
+            }
+
+            self.command(command);
+            previous_end_line = Some(loc.as_ref().map_or(0, last_line));
+        }
+
+        if continued {
+            self.indent_level -= 1;
+        }
+    }
+
+    //
+    // Commands.
+    //
+
+    fn command(&mut self, command: &ast::Command) {
+        match command {
+            ast::Command::Simple(simple_command) => self.simple_command(simple_command),
+            ast::Command::Compound(compound_command, redirects) => {
+                self.compound_command(compound_command);
+                if let Some(redirects) = redirects {
+                    self.redirect_list(redirects);
+                }
+            }
+            ast::Command::Function(function) => self.function_definition(function),
+            ast::Command::ExtendedTest(test) => {
+                self.write("[[ ");
+                self.extended_test_expr(&test.expr);
+                self.write(" ]]");
+                self.mark(Some(&test.loc));
+            }
+            ast::Command::Error(error) => {
+                self.write(&error.to_string());
+                self.mark(Some(&error.loc));
+            }
+        }
+    }
+
+    fn simple_command(&mut self, command: &ast::SimpleCommand) {
+        let prefix = command.prefix.iter().flat_map(|prefix| prefix.0.iter());
+        let suffix = command.suffix.iter().flat_map(|suffix| suffix.0.iter());
+
+        let mut first = true;
+        for item in prefix {
+            if !first {
+                self.write(" ");
+            }
+            self.prefix_or_suffix_item(item);
+            first = false;
+        }
+
+        if let Some(word) = &command.word_or_name {
+            if !first {
+                self.write(" ");
+            }
+            self.word(word);
+            first = false;
+        }
+
+        for item in suffix {
+            if !first {
+                self.write(" ");
+            }
+            self.prefix_or_suffix_item(item);
+            first = false;
+        }
+    }
+
+    fn prefix_or_suffix_item(&mut self, item: &ast::CommandPrefixOrSuffixItem) {
+        match item {
+            ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => self.redirect(redirect),
+            ast::CommandPrefixOrSuffixItem::Word(word)
+            | ast::CommandPrefixOrSuffixItem::AssignmentWord(_, word) => self.word(word),
+            ast::CommandPrefixOrSuffixItem::ProcessSubstitution(kind, subshell) => {
+                self.process_substitution(kind, subshell);
+            }
+        }
+    }
+
+    fn word(&mut self, word: &ast::Word) {
+        self.write(&word.value);
+        self.mark(word.loc.as_ref());
+    }
+
+    fn process_substitution(
+        &mut self,
+        kind: &ast::ProcessSubstitutionKind,
+        subshell: &ast::SubshellCommand,
+    ) {
+        self.write(&kind.to_string());
+        self.subshell(subshell);
+    }
+
+    fn redirect_list(&mut self, redirects: &ast::RedirectList) {
+        for redirect in &redirects.0 {
+            self.write(" ");
+            self.redirect(redirect);
+        }
+    }
+
+    fn redirect(&mut self, redirect: &ast::IoRedirect) {
+        let space = if self.options.space_redirects {
+            " "
+        } else {
+            ""
+        };
+
+        match redirect {
+            ast::IoRedirect::File(fd, kind, target, _) => {
+                if let Some(fd) = fd {
+                    self.write(&fd.to_string());
+                }
+                self.write(&kind.to_string());
+
+                match target {
+                    ast::IoFileRedirectTarget::Filename(word) => {
+                        self.write(space);
+                        self.word(word);
+                    }
+                    ast::IoFileRedirectTarget::Duplicate(word) => self.word(word),
+                    ast::IoFileRedirectTarget::Fd(fd) => self.write(&fd.to_string()),
+                    ast::IoFileRedirectTarget::ProcessSubstitution(kind, subshell) => {
+                        // N.B. The space keeps the operators apart (e.g., `< <(...)`).
+                        self.write(" ");
+                        self.process_substitution(kind, subshell);
+                    }
+                }
+            }
+            ast::IoRedirect::OutputAndError(word, append, _) => {
+                self.write(if *append { "&>>" } else { "&>" });
+                self.write(space);
+                self.word(word);
+            }
+            ast::IoRedirect::HereString(fd, word, _) => {
+                if let Some(fd) = fd {
+                    self.write(&fd.to_string());
+                }
+                self.write("<<<");
+                self.write(space);
+                self.word(word);
+            }
+            ast::IoRedirect::HereDocument(fd, here_doc, _) => {
+                if let Some(fd) = fd {
+                    self.write(&fd.to_string());
+                }
+                self.write(if here_doc.remove_tabs { "<<-" } else { "<<" });
+                self.word(&here_doc.here_end);
+
+                let delimiter: String = here_doc
+                    .here_end
+                    .value
+                    .chars()
+                    .filter(|c| !matches!(c, '\'' | '"' | '\\'))
+                    .collect();
+                self.pending_here_docs
+                    .push(std::format!("{}{delimiter}\n", here_doc.doc.value));
+                self.last_here_doc_line = last_line(&here_doc.loc);
+            }
+        }
+    }
+
+    fn function_definition(&mut self, function: &ast::FunctionDefinition) {
+        self.write(&function.fname.value);
+        self.write("() ");
+        self.mark(function.fname.loc.as_ref());
+
+        self.compound_command(&function.body.0);
+        if let Some(redirects) = &function.body.1 {
+            self.redirect_list(redirects);
+        }
+    }
+
+    fn compound_command(&mut self, command: &ast::CompoundCommand) {
+        match command {
+            ast::CompoundCommand::Arithmetic(arithmetic) => {
+                self.write("((");
+                self.write(&arithmetic.expr.value);
+                self.write("))");
+                self.mark(Some(&arithmetic.loc));
+            }
+            ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+                self.arithmetic_for_clause(for_clause);
+            }
+            ast::CompoundCommand::BraceGroup(group) => self.brace_group(group),
+            ast::CompoundCommand::Subshell(subshell) => self.subshell(subshell),
+            ast::CompoundCommand::ForClause(for_clause) => self.for_clause(for_clause),
+            ast::CompoundCommand::CaseClause(case_clause) => self.case_clause(case_clause),
+            ast::CompoundCommand::IfClause(if_clause) => self.if_clause(if_clause),
+            ast::CompoundCommand::WhileClause(clause) => self.while_or_until("while", clause),
+            ast::CompoundCommand::UntilClause(clause) => self.while_or_until("until", clause),
+        }
+    }
+
+    fn brace_group(&mut self, group: &ast::BraceGroupCommand) {
+        if fits_on_one_line(&group.loc, &group.list) {
+            self.write("{ ");
+            self.inline_list(&group.list);
+            if !ends_with_async(&group.list) {
+                self.write(";");
+            }
+            self.write(" }");
+            self.mark(Some(&group.loc));
+        } else {
+            self.write("{");
+            self.mark_start(Some(&group.loc));
+            self.newline();
+            self.block(&group.list, group.loc.end.line);
+            self.write("}");
+            self.mark(Some(&group.loc));
+        }
+    }
+
+    fn subshell(&mut self, subshell: &ast::SubshellCommand) {
+        if fits_on_one_line(&subshell.loc, &subshell.list) {
+            // N.B. A space keeps a nested subshell or arithmetic command from being
+            // read as an arithmetic command (or expansion).
+            let padding = if starts_with_paren(&subshell.list) {
+                " "
+            } else {
+                ""
+            };
+
+            self.write("(");
+            self.write(padding);
+            self.inline_list(&subshell.list);
+            self.write(padding);
+            self.write(")");
+            self.mark(Some(&subshell.loc));
+        } else {
+            self.write("(");
+            self.mark_start(Some(&subshell.loc));
+            self.newline();
+            self.block(&subshell.list, subshell.loc.end.line);
+            self.write(")");
+            self.mark(Some(&subshell.loc));
+        }
+    }
+
+    fn do_group(&mut self, group: &ast::DoGroupCommand) {
+        self.newline();
+        self.block(&group.list, group.loc.end.line);
+        self.write("done");
+        self.mark(Some(&group.loc));
+    }
+
+    fn for_clause(&mut self, for_clause: &ast::ForClauseCommand) {
+        self.write("for ");
+        self.write(&for_clause.variable_name);
+        self.mark_start(Some(&for_clause.loc));
+
+        if let Some(values) = &for_clause.values {
+            self.write(" in");
+            for value in values {
+                self.write(" ");
+                self.word(value);
+            }
+        }
+
+        self.write("; do");
+        self.do_group(&for_clause.body);
+    }
+
+    fn arithmetic_for_clause(&mut self, for_clause: &ast::ArithmeticForClauseCommand) {
+        fn expr(expr: Option<&ast::UnexpandedArithmeticExpr>) -> &str {
+            expr.map_or("", |expr| expr.value.trim())
+        }
+
+        self.write("for ((");
+        self.write(expr(for_clause.initializer.as_ref()));
+        for part in [
+            expr(for_clause.condition.as_ref()),
+            expr(for_clause.updater.as_ref()),
+        ] {
+            self.write(";");
+            if !part.is_empty() {
+                self.write(" ");
+                self.write(part);
+            }
+        }
+        self.write(")); do");
+        self.mark_start(Some(&for_clause.loc));
+
+        self.do_group(&for_clause.body);
+    }
+
+    fn while_or_until(&mut self, keyword: &str, clause: &ast::WhileOrUntilClauseCommand) {
+        self.write(keyword);
+        self.write(" ");
+        self.mark_start(Some(&clause.2));
+        self.inline_list_then(&clause.0, "do");
+        self.do_group(&clause.1);
+    }
+
+    fn if_clause(&mut self, if_clause: &ast::IfClauseCommand) {
+        let elses = if_clause.elses.as_deref().unwrap_or_default();
+        let end_line = if_clause.loc.end.line;
+        let next_line = |i: usize| elses.get(i).map_or(end_line, |e| e.loc.start.line);
+
+        self.write("if ");
+        self.mark_start(Some(&if_clause.loc));
+        self.inline_list_then(&if_clause.condition, "then");
+        self.newline();
+        self.block(&if_clause.then, next_line(0));
+
+        for (i, else_clause) in elses.iter().enumerate() {
+            if let Some(condition) = &else_clause.condition {
+                self.write("elif ");
+                self.mark_start(Some(&else_clause.loc));
+                self.inline_list_then(condition, "then");
+            } else {
+                self.write("else");
+                self.mark_start(Some(&else_clause.loc));
+            }
+
+            self.newline();
+            self.block(&else_clause.body, next_line(i + 1));
+        }
+
+        self.write("fi");
+        self.mark(Some(&if_clause.loc));
+    }
+
+    fn case_clause(&mut self, case_clause: &ast::CaseClauseCommand) {
+        self.write("case ");
+        self.word(&case_clause.value);
+        self.write(" in");
+        self.newline();
+
+        if self.options.switch_case_indent {
+            self.indent_level += 1;
+        }
+
+        let labels: Vec<String> = case_clause
+            .cases
+            .iter()
+            .map(|item| {
+                let patterns: Vec<_> = item.patterns.iter().map(|p| p.value.as_str()).collect();
+                std::format!("{})", patterns.join(" | "))
+            })
+            .collect();
+        let compact: Vec<bool> = case_clause.cases.iter().map(is_compact_case_item).collect();
+
+        self.block_start = true;
+        for (i, item) in case_clause.cases.iter().enumerate() {
+            let loc = item.location();
+            let start_line = loc.as_ref().map_or(0, |loc| loc.start.line);
+            let end_line = loc.as_ref().map_or(0, last_line);
+
+            self.leading_comments(start_line);
+            self.separate(start_line);
+
+            self.write(&labels[i]);
+            for pattern in &item.patterns {
+                self.mark(pattern.loc.as_ref());
+            }
+
+            if compact[i] {
+                if self.options.align_case_items {
+                    // Pad to the widest label among the adjacent compact items.
+                    let run_start = (0..i).rev().take_while(|&j| compact[j]).last().unwrap_or(i);
+                    let run_end = (i..compact.len())
+                        .take_while(|&j| compact[j])
+                        .last()
+                        .unwrap_or(i);
+                    let width = labels[run_start..=run_end]
+                        .iter()
+                        .map(|label| label.chars().count())
+                        .max()
+                        .unwrap_or_default();
+                    let padding = width - labels[i].chars().count();
+                    self.write(&" ".repeat(padding));
+                }
+
+                self.write(" ");
+                if let Some(cmd) = &item.cmd {
+                    self.inline_list(cmd);
+                    self.write(" ");
+                }
+                self.write(&item.post_action.to_string());
+                self.mark(loc.as_ref());
+                self.newline();
+            } else {
+                self.newline();
+                self.indent_level += 1;
+                self.block_start = true;
+                if let Some(cmd) = &item.cmd {
+                    self.statements(cmd);
+                }
+                self.leading_comments(end_line);
+                self.write(&item.post_action.to_string());
+                self.mark(loc.as_ref());
+                self.newline();
+                self.indent_level -= 1;
+            }
+
+            self.last_statement_line = end_line;
+        }
+
+        self.leading_comments(case_clause.loc.end.line);
+
+        if self.options.switch_case_indent {
+            self.indent_level -= 1;
+        }
+
+        self.write("esac");
+        self.mark(Some(&case_clause.loc));
+    }
+
+    fn extended_test_expr(&mut self, expr: &ast::ExtendedTestExpr) {
+        match expr {
+            ast::ExtendedTestExpr::And(left, right) => {
+                self.extended_test_expr(left);
+                self.write(" && ");
+                self.extended_test_expr(right);
+            }
+            ast::ExtendedTestExpr::Or(left, right) => {
+                self.extended_test_expr(left);
+                self.write(" || ");
+                self.extended_test_expr(right);
+            }
+            ast::ExtendedTestExpr::Not(expr, _) => {
+                self.write("! ");
+                self.extended_test_expr(expr);
+            }
+            ast::ExtendedTestExpr::Parenthesized(expr, _) => {
+                self.write("( ");
+                self.extended_test_expr(expr);
+                self.write(" )");
+            }
+            ast::ExtendedTestExpr::UnaryTest(predicate, word, _) => {
+                self.write(&predicate.to_string());
+                self.write(" ");
+                self.word(word);
+            }
+            ast::ExtendedTestExpr::BinaryTest(predicate, left, right) => {
+                self.word(left);
+                self.write(" ");
+                self.write(&predicate.to_string());
+                self.write(" ");
+                self.word(right);
+            }
+        }
+    }
+}
+
+/// Returns the last source line covered by the given location; a location ending
+/// with a newline (e.g., that of a here-document) doesn't cover the following line.
+fn last_line(loc: &TokenLocation) -> usize {
+    if loc.end.column == 1 && loc.end.line > loc.start.line {
+        loc.end.line - 1
+    } else {
+        loc.end.line
+    }
+}
+
+/// Returns whether a brace group or subshell with the given location and contents
+/// should be written on a single line: it must have been written on one line in the
+/// source, and only contain commands that are themselves written on one line.
+fn fits_on_one_line(loc: &TokenLocation, list: &ast::CompoundList) -> bool {
+    loc.start.line == loc.end.line && is_inline_list(list)
+}
+
+fn is_inline_list(list: &ast::CompoundList) -> bool {
+    list.0
+        .iter()
+        .flat_map(|item| item.0.iter())
+        .flat_map(|(_, pipeline)| pipeline.seq.iter())
+        .all(is_inline_command)
+}
+
+fn is_inline_command(command: &ast::Command) -> bool {
+    match command {
+        ast::Command::Simple(simple_command) => {
+            let prefix = simple_command.prefix.iter().flat_map(|p| p.0.iter());
+            let suffix = simple_command.suffix.iter().flat_map(|s| s.0.iter());
+            prefix.chain(suffix).all(|item| match item {
+                ast::CommandPrefixOrSuffixItem::ProcessSubstitution(_, subshell) => {
+                    fits_on_one_line(&subshell.loc, &subshell.list)
+                }
+                ast::CommandPrefixOrSuffixItem::IoRedirect(ast::IoRedirect::File(
+                    _,
+                    _,
+                    ast::IoFileRedirectTarget::ProcessSubstitution(_, subshell),
+                    _,
+                )) => fits_on_one_line(&subshell.loc, &subshell.list),
+                _ => true,
+            })
+        }
+        ast::Command::Compound(compound_command, _) => match compound_command {
+            ast::CompoundCommand::Arithmetic(_) => true,
+            ast::CompoundCommand::BraceGroup(group) => fits_on_one_line(&group.loc, &group.list),
+            ast::CompoundCommand::Subshell(subshell) => {
+                fits_on_one_line(&subshell.loc, &subshell.list)
+            }
+            _ => false,
+        },
+        ast::Command::ExtendedTest(_) | ast::Command::Error(_) => true,
+        ast::Command::Function(_) => false,
+    }
+}
+
+/// Returns whether a `case` item should be written on a single line.
+fn is_compact_case_item(item: &ast::CaseItem) -> bool {
+    match &item.cmd {
+        None => true,
+        Some(cmd) => {
+            let on_one_line = item
+                .loc
+                .as_ref()
+                .is_none_or(|loc| loc.start.line == loc.end.line);
+            on_one_line && cmd.0.len() == 1 && is_inline_list(cmd)
+        }
+    }
+}
+
+fn ends_with_async(list: &ast::CompoundList) -> bool {
+    list.0
+        .last()
+        .is_some_and(|item| matches!(item.1, ast::SeparatorOperator::Async))
+}
+
+/// Returns whether the formatted text of the list starts with a `(`.
+fn starts_with_paren(list: &ast::CompoundList) -> bool {
+    list.0.first().is_some_and(|item| {
+        let pipeline = &item.0.first;
+        pipeline.timed.is_none()
+            && !pipeline.bang
+            && matches!(
+                pipeline.seq.first(),
+                Some(ast::Command::Compound(
+                    ast::CompoundCommand::Subshell(_) | ast::CompoundCommand::Arithmetic(_),
+                    _
+                ))
+            )
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn format(input: &str, options: &FormatOptions) -> Result<String> {
+        Ok(format_str(input, options, &ParserOptions::default())?)
+    }
+
+    fn display(input: &str) -> Result<String> {
+        let tokens = super::super::tokenize_str(input)?;
+        let program =
+            super::super::parse_tokens(&tokens, &ParserOptions::default(), &SourceInfo::default())?;
+        Ok(program.to_string())
+    }
+
+    /// Checks that formatting the input is idempotent and preserves its meaning.
+    fn check_round_trip(input: &str, options: &FormatOptions) -> Result<()> {
+        let formatted = format(input, options)?;
+        assert_eq!(format(&formatted, options)?, formatted, "input: {input:?}");
+        assert_eq!(display(&formatted)?, display(input)?, "input: {input:?}");
+        Ok(())
+    }
+
+    const SCRIPT: &str = r#"#!/bin/bash
+# Leading comment.
+
+set -e   # trailing
+f() { echo a; }
+function g {
+  local x=1
+  if [[ -n $x && $x == 1 ]]; then echo yes
+  elif (( x > 2 )); then
+     echo big
+  else echo no; fi
+
+
+  while read -r line; do
+    echo "$line" |
+      grep x
+  done < <(ls)
+  cmd1 &&
+    cmd2 || cmd3
+  case $1 in
+    a|b) echo ab;;
+    longer) echo l ;;
+    *)
+      echo other
+      exit 1
+      ;;
+  esac
+  cat <<'EOF' | tr a b # after here-doc
+  body $x
+EOF
+  ( cd /tmp; ls ) &
+  for ((i=0;i<3;i++)); do :; done
+}
+# Final comment.
+"#;
+
+    #[test]
+    fn format_with_default_options() -> Result<()> {
+        let expected = r#"#!/bin/bash
+# Leading comment.
+
+set -e # trailing
+f() { echo a; }
+g() {
+    local x=1
+    if [[ -n $x && $x == 1 ]]; then
+        echo yes
+    elif ((x > 2)); then
+        echo big
+    else
+        echo no
+    fi
+
+    while read -r line; do
+        echo "$line" |
+            grep x
+    done < <(ls)
+    cmd1 &&
+        cmd2 || cmd3
+    case $1 in
+    a | b) echo ab ;;
+    longer) echo l ;;
+    *)
+        echo other
+        exit 1
+        ;;
+    esac
+    cat <<'EOF' | tr a b # after here-doc
+  body $x
+EOF
+    (cd /tmp; ls) &
+    for ((i=0; i<3; i++)); do
+        :
+    done
+}
+# Final comment.
+"#;
+
+        assert_eq!(format(SCRIPT, &FormatOptions::default())?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn format_with_custom_options() -> Result<()> {
+        let options = FormatOptions {
+            indent: Indent::Tabs,
+            binary_next_line: true,
+            switch_case_indent: true,
+            align_case_items: true,
+            space_redirects: true,
+        };
+
+        let input = r"case $1 in
+a|b) x >out 2>&1;;
+c) w;;
+longer) y &&
+  z ;;
+*) ;;
+esac
+";
+        let expected = "case $1 in
+\ta | b) x > out 2>&1 ;;
+\tc)     w ;;
+\tlonger)
+\t\ty \\
+\t\t\t&& z
+\t\t;;
+\t*) ;;
+esac
+";
+
+        assert_eq!(format(input, &options)?, expected);
+        check_round_trip(input, &options)?;
+        check_round_trip(SCRIPT, &options)?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_here_documents() -> Result<()> {
+        let input = "{
+cat <<-END <<\"EOF\" &&
+\tone
+\tEND
+$two
+EOF
+echo done
+}
+";
+        let expected = "{
+    cat <<-END <<\"EOF\" &&
+one
+END
+$two
+EOF
+        echo done
+}
+";
+
+        assert_eq!(format(input, &FormatOptions::default())?, expected);
+        check_round_trip(input, &FormatOptions::default())?;
+
+        Ok(())
+    }
+
+    #[test]
+    fn format_is_idempotent_over_snapshot_corpus() -> Result<()> {
+        let dir = std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("src/parser/snapshots");
+
+        let mut checked = 0;
+        for entry in std::fs::read_dir(dir)? {
+            let content = std::fs::read_to_string(entry?.path())?;
+            let Some(input) = content
+                .lines()
+                .find_map(|line| line.trim_start().strip_prefix("input: "))
+                .and_then(unescape_ron_string)
+            else {
+                continue;
+            };
+
+            // Not every snapshot's input is a complete program.
+            if display(&input).is_err() {
+                continue;
+            }
+
+            check_round_trip(&input, &FormatOptions::default())?;
+            checked += 1;
+        }
+
+        assert!(checked > 0);
+        Ok(())
+    }
+
+    /// Decodes a (quoted) string literal, as written in a RON snapshot.
+    fn unescape_ron_string(literal: &str) -> Option<String> {
+        let mut chars = literal.strip_prefix('"')?.chars();
+        let mut result = String::new();
+
+        loop {
+            match chars.next()? {
+                '"' => return Some(result),
+                '\\' => match chars.next()? {
+                    'n' => result.push('\n'),
+                    't' => result.push('\t'),
+                    'r' => result.push('\r'),
+                    '0' => result.push('\0'),
+                    'u' => {
+                        let code: String =
+                            chars.by_ref().skip(1).take_while(|c| *c != '}').collect();
+                        result.push(char::from_u32(u32::from_str_radix(&code, 16).ok()?)?);
+                    }
+                    c => result.push(c),
+                },
+                c => result.push(c),
+            }
+        }
+    }
+}
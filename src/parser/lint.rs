@@ -0,0 +1,1109 @@
+//! Static analysis of shell programs, reporting likely mistakes without executing them.
+//!
+//! The checks are modeled on a subset of those performed by `shellcheck`: expansions
+//! left open to word splitting and pathname expansion, unchecked `cd`, useless uses of
+//! `cat`, unreachable commands, variables that are referenced but never assigned,
+//! misuse of `local`, and (when parsing in POSIX or `sh` mode) bash-specific syntax.
+//! Command substitutions and here-document bodies are not analyzed.
+
+use std::collections::HashSet;
+use std::fmt::Display;
+
+use super::ast::{self, SourceLocation};
+use super::word::{self, Parameter, ParameterExpr, SpecialParameter, WordPiece};
+use super::{ParseError, Parser, ParserOptions, SourceInfo, TokenLocation, arithmetic};
+
+/// Severity of a lint finding.
+#[derive(Clone, Copy, Debug, Eq, Ord, PartialEq, PartialOrd)]
+pub enum Severity {
+    /// The script is almost certainly broken.
+    Error,
+    /// The script may not behave as intended.
+    Warning,
+    /// The script could be simplified.
+    Info,
+}
+
+impl Display for Severity {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Error => write!(f, "error"),
+            Self::Warning => write!(f, "warning"),
+            Self::Info => write!(f, "info"),
+        }
+    }
+}
+
+/// Identifies the check that produced a lint finding.
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum LintCode {
+    /// An unquoted expansion, whose result is subject to word splitting and pathname
+    /// expansion.
+    UnquotedExpansion,
+    /// A `cd` whose failure is not handled, leaving later commands to run in the wrong
+    /// directory.
+    UncheckedCd,
+    /// `cat` of a single file piped into another command, which could read the file
+    /// itself.
+    UselessCat,
+    /// An unquoted expansion used as an operand of `[` or `test`.
+    UnquotedTestOperand,
+    /// A command following an unconditional `exit` or `return`.
+    UnreachableCode,
+    /// A variable that is referenced but never assigned.
+    UnassignedVariable,
+    /// Use of `local` outside of a function.
+    LocalOutsideFunction,
+    /// Syntax or a builtin not specified by POSIX, in a program parsed in POSIX or `sh`
+    /// mode.
+    Bashism,
+}
+
+impl LintCode {
+    /// Returns the identifier used for the code in reports.
+    pub const fn id(self) -> &'static str {
+        match self {
+            Self::UnquotedExpansion => "unquoted-expansion",
+            Self::UncheckedCd => "unchecked-cd",
+            Self::UselessCat => "useless-cat",
+            Self::UnquotedTestOperand => "unquoted-test-operand",
+            Self::UnreachableCode => "unreachable-code",
+            Self::UnassignedVariable => "unassigned-variable",
+            Self::LocalOutsideFunction => "local-outside-function",
+            Self::Bashism => "bashism",
+        }
+    }
+
+    /// Returns the severity of findings with this code.
+    pub const fn severity(self) -> Severity {
+        match self {
+            Self::LocalOutsideFunction => Severity::Error,
+            Self::UselessCat => Severity::Info,
+            Self::UnquotedExpansion
+            | Self::UncheckedCd
+            | Self::UnquotedTestOperand
+            | Self::UnreachableCode
+            | Self::UnassignedVariable
+            | Self::Bashism => Severity::Warning,
+        }
+    }
+}
+
+impl Display for LintCode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.id())
+    }
+}
+
+/// A problem found in a shell program.
+#[derive(Clone, Debug)]
+pub struct Finding {
+    /// The check that produced the finding.
+    pub code: LintCode,
+    /// The severity of the finding.
+    pub severity: Severity,
+    /// Human-readable description of the problem.
+    pub message: String,
+    /// The location of the offending syntax, when known.
+    pub loc: Option<TokenLocation>,
+}
+
+impl Display for Finding {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if let Some(loc) = &self.loc {
+            write!(f, "{}:{}: ", loc.start.line, loc.start.column)?;
+        }
+        write!(f, "{}[{}]: {}", self.severity, self.code, self.message)
+    }
+}
+
+/// Parses the given shell script and checks it for problems.
+///
+/// # Arguments
+///
+/// * `input` - The shell script to check.
+/// * `options` - Options controlling how the script is parsed; bash-specific syntax is
+///   reported when POSIX or `sh` mode is enabled.
+pub fn lint_str(input: &str, options: &ParserOptions) -> Result<Vec<Finding>, ParseError> {
+    let mut parser = Parser::new(input.as_bytes(), options, &SourceInfo::default());
+    let program = parser.parse_program()?;
+
+    Ok(lint_program(&program, options))
+}
+
+/// Checks the given program for problems, returning findings in source order.
+///
+/// # Arguments
+///
+/// * `program` - The program to check.
+/// * `options` - The options the program was parsed with; bash-specific syntax is
+///   reported when POSIX or `sh` mode is enabled.
+pub fn lint_program(program: &ast::Program, options: &ParserOptions) -> Vec<Finding> {
+    let mut linter = Linter::new(options);
+
+    let mut terminated = false;
+    for complete_command in &program.complete_commands {
+        linter.items(&complete_command.0, &mut terminated);
+    }
+
+    linter.finish()
+}
+
+/// Builtins that are commonly used by scripts but not specified by POSIX.
+const NON_POSIX_BUILTINS: &[&str] = &[
+    "declare",
+    "dirs",
+    "let",
+    "local",
+    "mapfile",
+    "popd",
+    "pushd",
+    "readarray",
+    "shopt",
+    "source",
+    "typeset",
+];
+
+/// Builtins whose arguments may be variable assignments.
+const DECLARATION_BUILTINS: &[&str] = &["declare", "export", "local", "readonly", "typeset"];
+
+struct Linter<'a> {
+    options: &'a ParserOptions,
+    /// Options used to parse words. Non-POSIX expansions are always accepted here so
+    /// that words using them in `sh` mode can still be checked (and reported).
+    word_options: ParserOptions,
+    findings: Vec<Finding>,
+    /// Number of function bodies enclosing the current command.
+    function_depth: usize,
+    /// Number of `if`, `while` and `until` conditions enclosing the current command.
+    condition_depth: usize,
+    /// Names of all variables assigned anywhere in the program.
+    assigned: HashSet<String>,
+    /// Variable references, in source order.
+    references: Vec<(String, Option<TokenLocation>)>,
+}
+
+impl<'a> Linter<'a> {
+    fn new(options: &'a ParserOptions) -> Self {
+        Self {
+            options,
+            word_options: ParserOptions {
+                sh_mode: false,
+                ..options.clone()
+            },
+            findings: vec![],
+            function_depth: 0,
+            condition_depth: 0,
+            assigned: HashSet::new(),
+            references: vec![],
+        }
+    }
+
+    fn finish(mut self) -> Vec<Finding> {
+        let mut reported = HashSet::new();
+        for (name, loc) in std::mem::take(&mut self.references) {
+            // Names without lowercase letters are conventionally environment variables,
+            // which the program may legitimately expect to inherit.
+            if self.assigned.contains(&name)
+                || !name.chars().any(|c| c.is_ascii_lowercase())
+                || !reported.insert(name.clone())
+            {
+                continue;
+            }
+
+            self.report(
+                LintCode::UnassignedVariable,
+                loc,
+                format!("`{name}` is referenced but never assigned"),
+            );
+        }
+
+        self.findings
+            .sort_by_key(|f| f.loc.as_ref().map_or(usize::MAX, |loc| loc.start.index));
+        self.findings
+    }
+
+    fn report(&mut self, code: LintCode, loc: Option<TokenLocation>, message: String) {
+        self.findings.push(Finding {
+            code,
+            severity: code.severity(),
+            message,
+            loc,
+        });
+    }
+
+    fn bashism(&mut self, loc: Option<TokenLocation>, what: &str) {
+        if self.options.posix_mode || self.options.sh_mode {
+            self.report(
+                LintCode::Bashism,
+                loc,
+                format!("{what} is not specified by POSIX"),
+            );
+        }
+    }
+
+    fn compound_list(&mut self, list: &ast::CompoundList) {
+        self.items(&list.0, &mut false);
+    }
+
+    /// Checks a sequence of commands.
+    ///
+    /// # Arguments
+    ///
+    /// * `items` - The commands to check.
+    /// * `terminated` - Whether a preceding command in the same sequence unconditionally
+    ///   exits; updated for the commands checked.
+    fn items(&mut self, items: &[ast::CompoundListItem], terminated: &mut bool) {
+        for ast::CompoundListItem(and_or, separator) in items {
+            if *terminated {
+                self.report(
+                    LintCode::UnreachableCode,
+                    and_or.location(),
+                    String::from("command can never run, as it follows an unconditional exit"),
+                );
+                // Only the first unreachable command is reported.
+                *terminated = false;
+            }
+
+            self.and_or_list(and_or);
+
+            if matches!(separator, ast::SeparatorOperator::Sequence)
+                && and_or.additional.is_empty()
+                && matches!(
+                    single_simple_command(&and_or.first).and_then(command_name),
+                    Some("exit" | "return")
+                )
+            {
+                *terminated = true;
+            }
+        }
+    }
+
+    fn and_or_list(&mut self, list: &ast::AndOrList) {
+        let pipelines =
+            std::iter::once(&list.first).chain(list.additional.iter().map(|and_or| match and_or {
+                ast::AndOr::And(pipeline) | ast::AndOr::Or(pipeline) => pipeline,
+            }));
+
+        for (i, pipeline) in pipelines.enumerate() {
+            // A `cd` is considered checked if its status is tested by a condition or
+            // a following `&&` or `||`.
+            if self.condition_depth == 0
+                && i == list.additional.len()
+                && let Some(command) = single_simple_command(pipeline)
+                && command_name(command) == Some("cd")
+            {
+                self.report(
+                    LintCode::UncheckedCd,
+                    command.location(),
+                    String::from(
+                        "`cd` may fail, leaving later commands in the wrong directory; \
+                         use `cd ... || exit`",
+                    ),
+                );
+            }
+
+            self.pipeline(pipeline);
+        }
+    }
+
+    fn pipeline(&mut self, pipeline: &ast::Pipeline) {
+        if pipeline.timed.is_some() {
+            self.bashism(Some(pipeline.loc.clone()), "`time`");
+        }
+
+        if pipeline.seq.len() > 1
+            && let ast::Command::Simple(command) = &pipeline.seq[0]
+            && command.prefix.is_none()
+            && command_name(command) == Some("cat")
+            && let Some(suffix) = &command.suffix
+            && let [ast::CommandPrefixOrSuffixItem::Word(file)] = suffix.0.as_slice()
+            && !file.value.starts_with('-')
+        {
+            self.report(
+                LintCode::UselessCat,
+                command.location(),
+                format!(
+                    "useless use of `cat`; redirect `{}` into the next command instead",
+                    file.value
+                ),
+            );
+        }
+
+        for command in &pipeline.seq {
+            self.command(command);
+        }
+    }
+
+    fn command(&mut self, command: &ast::Command) {
+        match command {
+            ast::Command::Simple(command) => self.simple_command(command),
+            ast::Command::Compound(command, redirects) => {
+                self.compound_command(command);
+                if let Some(redirects) = redirects {
+                    for redirect in &redirects.0 {
+                        self.redirect(redirect);
+                    }
+                }
+            }
+            ast::Command::Function(function) => {
+                if function.uses_function_keyword {
+                    self.bashism(function.location(), "the `function` keyword");
+                }
+
+                self.function_depth += 1;
+                self.compound_command(&function.body.0);
+                self.function_depth -= 1;
+
+                if let Some(redirects) = &function.body.1 {
+                    for redirect in &redirects.0 {
+                        self.redirect(redirect);
+                    }
+                }
+            }
+            ast::Command::ExtendedTest(test) => {
+                self.bashism(Some(test.loc.clone()), "`[[ ... ]]`");
+                self.extended_test_expr(&test.expr);
+            }
+            ast::Command::Error(_) => {}
+        }
+    }
+
+    fn compound_command(&mut self, command: &ast::CompoundCommand) {
+        match command {
+            ast::CompoundCommand::Arithmetic(arithmetic) => {
+                self.bashism(Some(arithmetic.loc.clone()), "`(( ... ))`");
+                self.arithmetic(&arithmetic.expr.value);
+            }
+            ast::CompoundCommand::ArithmeticForClause(for_clause) => {
+                self.bashism(Some(for_clause.loc.clone()), "`for (( ... ))`");
+                for expr in [
+                    &for_clause.initializer,
+                    &for_clause.condition,
+                    &for_clause.updater,
+                ]
+                .into_iter()
+                .flatten()
+                {
+                    self.arithmetic(&expr.value);
+                }
+                self.compound_list(&for_clause.body.list);
+            }
+            ast::CompoundCommand::BraceGroup(group) => self.compound_list(&group.list),
+            ast::CompoundCommand::Subshell(subshell) => self.compound_list(&subshell.list),
+            ast::CompoundCommand::ForClause(for_clause) => {
+                self.assigned.insert(for_clause.variable_name.clone());
+                for value in for_clause.values.iter().flatten() {
+                    self.word(value, None);
+                }
+                self.compound_list(&for_clause.body.list);
+            }
+            ast::CompoundCommand::CaseClause(case_clause) => {
+                self.word(&case_clause.value, None);
+                for item in &case_clause.cases {
+                    for pattern in &item.patterns {
+                        self.word(pattern, None);
+                    }
+                    if let Some(cmd) = &item.cmd {
+                        self.compound_list(cmd);
+                    }
+                    if !matches!(item.post_action, ast::CaseItemPostAction::ExitCase) {
+                        self.bashism(item.location(), "`;&` and `;;&`");
+                    }
+                }
+            }
+            ast::CompoundCommand::IfClause(if_clause) => {
+                self.condition(&if_clause.condition);
+                self.compound_list(&if_clause.then);
+                for else_clause in if_clause.elses.iter().flatten() {
+                    if let Some(condition) = &else_clause.condition {
+                        self.condition(condition);
+                    }
+                    self.compound_list(&else_clause.body);
+                }
+            }
+            ast::CompoundCommand::WhileClause(clause)
+            | ast::CompoundCommand::UntilClause(clause) => {
+                self.condition(&clause.0);
+                self.compound_list(&clause.1.list);
+            }
+        }
+    }
+
+    fn condition(&mut self, list: &ast::CompoundList) {
+        self.condition_depth += 1;
+        self.compound_list(list);
+        self.condition_depth -= 1;
+    }
+
+    fn simple_command(&mut self, command: &ast::SimpleCommand) {
+        for item in command.prefix.iter().flat_map(|prefix| &prefix.0) {
+            self.prefix_or_suffix_item(item, Some(LintCode::UnquotedExpansion));
+        }
+
+        if let Some(name) = &command.word_or_name {
+            self.word(name, Some(LintCode::UnquotedExpansion));
+        }
+
+        let name = command_name(command).unwrap_or_default();
+        let args: Vec<&ast::Word> = command
+            .suffix
+            .iter()
+            .flat_map(|suffix| &suffix.0)
+            .filter_map(|item| match item {
+                ast::CommandPrefixOrSuffixItem::Word(word) => Some(word),
+                _ => None,
+            })
+            .collect();
+
+        if NON_POSIX_BUILTINS.contains(&name) {
+            self.bashism(command.location(), &format!("`{name}`"));
+        }
+
+        let split_check = match name {
+            "[" | "test" => {
+                if let Some(op) = args.iter().find(|arg| arg.value == "==") {
+                    self.bashism(op.location(), "`==` in `[`");
+                }
+                Some(LintCode::UnquotedTestOperand)
+            }
+            // Assignments passed to declaration builtins aren't subject to splitting.
+            _ if DECLARATION_BUILTINS.contains(&name) => None,
+            _ => Some(LintCode::UnquotedExpansion),
+        };
+
+        if name == "local" && self.function_depth == 0 {
+            self.report(
+                LintCode::LocalOutsideFunction,
+                command.location(),
+                String::from("`local` can only be used in a function"),
+            );
+        }
+
+        self.builtin_assignments(name, &args);
+
+        for item in command.suffix.iter().flat_map(|suffix| &suffix.0) {
+            self.prefix_or_suffix_item(item, split_check);
+        }
+    }
+
+    /// Records the variables assigned by a builtin, based on its arguments.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the command.
+    /// * `args` - The command's arguments.
+    fn builtin_assignments(&mut self, name: &str, args: &[&ast::Word]) {
+        let mut assigned: Vec<&str> = vec![];
+
+        match name {
+            _ if DECLARATION_BUILTINS.contains(&name) => {
+                for arg in args.iter().filter(|arg| !arg.value.starts_with(['-', '+'])) {
+                    let name = arg.value.split(['=', '[', '+']).next().unwrap_or_default();
+                    assigned.push(name);
+                }
+            }
+            "read" => {
+                let mut args = args.iter();
+                while let Some(arg) = args.next() {
+                    if let Some(flags) = arg.value.strip_prefix('-') {
+                        // Options that take a value are only ever last in a group.
+                        if flags.ends_with('a') {
+                            assigned.extend(args.next().map(|arg| arg.value.as_str()));
+                        } else if flags.ends_with(['d', 'i', 'n', 'N', 'p', 't', 'u']) {
+                            args.next();
+                        }
+                    } else {
+                        assigned.push(&arg.value);
+                    }
+                }
+                if assigned.is_empty() {
+                    assigned.push("REPLY");
+                }
+            }
+            "getopts" => assigned.extend(args.get(1).map(|arg| arg.value.as_str())),
+            "mapfile" | "readarray" => {
+                if let Some(arg) = args.last().filter(|arg| !arg.value.starts_with('-')) {
+                    assigned.push(&arg.value);
+                }
+            }
+            "printf" => {
+                if let [flag, var, ..] = args
+                    && flag.value == "-v"
+                {
+                    assigned.push(&var.value);
+                }
+            }
+            "let" => {
+                for arg in args {
+                    self.arithmetic(&arg.value);
+                }
+            }
+            _ => {}
+        }
+
+        for name in assigned {
+            self.assigned.insert(name.to_owned());
+        }
+    }
+
+    fn prefix_or_suffix_item(
+        &mut self,
+        item: &ast::CommandPrefixOrSuffixItem,
+        split_check: Option<LintCode>,
+    ) {
+        match item {
+            ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => self.redirect(redirect),
+            ast::CommandPrefixOrSuffixItem::Word(word) => self.word(word, split_check),
+            ast::CommandPrefixOrSuffixItem::AssignmentWord(assignment, _) => {
+                self.assignment(assignment);
+            }
+            ast::CommandPrefixOrSuffixItem::ProcessSubstitution(_, subshell) => {
+                self.bashism(Some(subshell.loc.clone()), "process substitution");
+                self.compound_list(&subshell.list);
+            }
+        }
+    }
+
+    fn assignment(&mut self, assignment: &ast::Assignment) {
+        match &assignment.name {
+            ast::AssignmentName::VariableName(name) => {
+                self.assigned.insert(name.clone());
+            }
+            ast::AssignmentName::ArrayElementName(name, _) => {
+                self.bashism(Some(assignment.loc.clone()), "array assignment");
+                self.assigned.insert(name.clone());
+            }
+        }
+
+        if assignment.append {
+            self.bashism(Some(assignment.loc.clone()), "`+=`");
+        }
+
+        match &assignment.value {
+            ast::AssignmentValue::Scalar(value) => self.word(value, None),
+            ast::AssignmentValue::Array(elements) => {
+                self.bashism(Some(assignment.loc.clone()), "array assignment");
+                for (index, value) in elements {
+                    if let Some(index) = index {
+                        self.word(index, None);
+                    }
+                    self.word(value, None);
+                }
+            }
+        }
+    }
+
+    fn redirect(&mut self, redirect: &ast::IoRedirect) {
+        match redirect {
+            ast::IoRedirect::File(_, _, target, _) => match target {
+                ast::IoFileRedirectTarget::Filename(word) => {
+                    self.word(word, Some(LintCode::UnquotedExpansion));
+                }
+                ast::IoFileRedirectTarget::Duplicate(word) => self.word(word, None),
+                ast::IoFileRedirectTarget::ProcessSubstitution(_, subshell) => {
+                    self.bashism(Some(subshell.loc.clone()), "process substitution");
+                    self.compound_list(&subshell.list);
+                }
+                ast::IoFileRedirectTarget::Fd(_) => {}
+            },
+            ast::IoRedirect::HereDocument(..) => {}
+            ast::IoRedirect::HereString(_, word, loc) => {
+                self.bashism(Some(loc.clone()), "`<<<`");
+                self.word(word, None);
+            }
+            ast::IoRedirect::OutputAndError(word, _, loc) => {
+                self.bashism(Some(loc.clone()), "`&>`");
+                self.word(word, Some(LintCode::UnquotedExpansion));
+            }
+        }
+    }
+
+    fn extended_test_expr(&mut self, expr: &ast::ExtendedTestExpr) {
+        match expr {
+            ast::ExtendedTestExpr::And(left, right) | ast::ExtendedTestExpr::Or(left, right) => {
+                self.extended_test_expr(left);
+                self.extended_test_expr(right);
+            }
+            ast::ExtendedTestExpr::Not(expr, _) | ast::ExtendedTestExpr::Parenthesized(expr, _) => {
+                self.extended_test_expr(expr);
+            }
+            ast::ExtendedTestExpr::UnaryTest(_, operand, _) => self.word(operand, None),
+            ast::ExtendedTestExpr::BinaryTest(_, left, right) => {
+                self.word(left, None);
+                self.word(right, None);
+            }
+        }
+    }
+
+    /// Records the variables assigned by an arithmetic expression.
+    fn arithmetic(&mut self, expr: &str) {
+        fn visit(expr: &ast::ArithmeticExpr, assigned: &mut HashSet<String>) {
+            match expr {
+                ast::ArithmeticExpr::Literal(..) | ast::ArithmeticExpr::Reference(_) => {}
+                ast::ArithmeticExpr::UnaryOp(_, operand, _) => visit(operand, assigned),
+                ast::ArithmeticExpr::BinaryOp(_, left, right) => {
+                    visit(left, assigned);
+                    visit(right, assigned);
+                }
+                ast::ArithmeticExpr::Conditional(condition, then, otherwise) => {
+                    visit(condition, assigned);
+                    visit(then, assigned);
+                    visit(otherwise, assigned);
+                }
+                ast::ArithmeticExpr::Assignment(target, value)
+                | ast::ArithmeticExpr::BinaryAssignment(_, target, value) => {
+                    target_assigned(target, assigned);
+                    visit(value, assigned);
+                }
+                ast::ArithmeticExpr::UnaryAssignment(_, target, _) => {
+                    target_assigned(target, assigned);
+                }
+            }
+        }
+
+        fn target_assigned(target: &ast::ArithmeticTarget, assigned: &mut HashSet<String>) {
+            match target {
+                ast::ArithmeticTarget::Variable(name, _)
+                | ast::ArithmeticTarget::ArrayElement(name, _, _) => {
+                    assigned.insert(name.clone());
+                }
+            }
+        }
+
+        if let Ok(expr) = arithmetic::parse(expr) {
+            visit(&expr, &mut self.assigned);
+        }
+    }
+
+    /// Checks the expansions in a word.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - The word to check.
+    /// * `split_check` - The code to report unquoted expansions with, if the word is
+    ///   subject to word splitting.
+    fn word(&mut self, word: &ast::Word, split_check: Option<LintCode>) {
+        // Words that fail to parse are reported when the program runs.
+        if let Ok(pieces) = word::parse(&word.value, &self.word_options) {
+            self.word_pieces(word, &pieces, split_check);
+        }
+    }
+
+    fn word_pieces(
+        &mut self,
+        word: &ast::Word,
+        pieces: &[word::WordPieceWithSource],
+        split_check: Option<LintCode>,
+    ) {
+        for piece in pieces {
+            let loc = piece.location_in(word);
+            let splits = match &piece.piece {
+                WordPiece::ParameterExpansion(expr) => {
+                    self.parameter_expr(expr, loc.clone());
+                    expansion_may_split(expr)
+                }
+                WordPiece::CommandSubstitution(_) | WordPiece::BackquotedCommandSubstitution(_) => {
+                    true
+                }
+                WordPiece::DoubleQuotedSequence(pieces) => {
+                    self.word_pieces(word, pieces, None);
+                    false
+                }
+                WordPiece::GettextDoubleQuotedSequence(pieces) => {
+                    self.bashism(loc.clone(), "`$\"...\"`");
+                    self.word_pieces(word, pieces, None);
+                    false
+                }
+                WordPiece::AnsiCQuotedText(_) => {
+                    self.bashism(loc.clone(), "`$'...'`");
+                    false
+                }
+                WordPiece::ArithmeticExpression(expr) => {
+                    self.arithmetic(&expr.value);
+                    false
+                }
+                WordPiece::Text(_)
+                | WordPiece::SingleQuotedText(_)
+                | WordPiece::TildePrefix(_)
+                | WordPiece::EscapeSequence(_) => false,
+            };
+
+            if splits && let Some(code) = split_check {
+                let text = &word.value[piece.start_index..piece.end_index];
+                let message = if matches!(code, LintCode::UnquotedTestOperand) {
+                    format!(
+                        "unquoted operand `{text}` may expand to several words or none at all; \
+                         double-quote it"
+                    )
+                } else {
+                    format!(
+                        "unquoted `{text}` is subject to word splitting and pathname expansion; \
+                         double-quote it"
+                    )
+                };
+                self.report(code, loc, message);
+            }
+        }
+    }
+
+    fn parameter_expr(&mut self, expr: &ParameterExpr, loc: Option<TokenLocation>) {
+        let (parameter, indirect) = match expr {
+            // These explicitly handle the parameter being unset.
+            ParameterExpr::UseDefaultValues {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::IndicateErrorIfNullOrUnset {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::UseAlternativeValue {
+                parameter,
+                indirect,
+                ..
+            } => (None, Some((parameter, *indirect))),
+            ParameterExpr::AssignDefaultValues {
+                parameter,
+                indirect,
+                ..
+            } => {
+                if let Some(name) = parameter_name(parameter) {
+                    self.assigned.insert(name.to_owned());
+                }
+                (None, Some((parameter, *indirect)))
+            }
+            ParameterExpr::Parameter {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::ParameterLength {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::RemoveSmallestSuffixPattern {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::RemoveLargestSuffixPattern {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::RemoveSmallestPrefixPattern {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::RemoveLargestPrefixPattern {
+                parameter,
+                indirect,
+                ..
+            } => (Some(parameter), Some((parameter, *indirect))),
+            ParameterExpr::Substring {
+                parameter,
+                indirect,
+                ..
+            } => {
+                self.bashism(loc.clone(), "substring expansion");
+                (Some(parameter), Some((parameter, *indirect)))
+            }
+            ParameterExpr::ReplaceSubstring {
+                parameter,
+                indirect,
+                ..
+            } => {
+                self.bashism(loc.clone(), "pattern substitution");
+                (Some(parameter), Some((parameter, *indirect)))
+            }
+            ParameterExpr::Transform {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::UppercaseFirstChar {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::UppercasePattern {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::LowercaseFirstChar {
+                parameter,
+                indirect,
+                ..
+            }
+            | ParameterExpr::LowercasePattern {
+                parameter,
+                indirect,
+                ..
+            } => {
+                self.bashism(loc.clone(), "case modification and transformation");
+                (Some(parameter), Some((parameter, *indirect)))
+            }
+            ParameterExpr::VariableNames { .. } => {
+                self.bashism(loc.clone(), "`${!prefix*}`");
+                (None, None)
+            }
+            ParameterExpr::MemberKeys { .. } => {
+                self.bashism(loc.clone(), "`${!array[@]}`");
+                (None, None)
+            }
+        };
+
+        if let Some((parameter, indirect)) = indirect {
+            if indirect {
+                self.bashism(loc.clone(), "indirect expansion");
+            }
+            if matches!(
+                parameter,
+                Parameter::NamedWithIndex { .. } | Parameter::NamedWithAllIndices { .. }
+            ) {
+                self.bashism(loc.clone(), "array expansion");
+            }
+        }
+
+        if let Some(name) = parameter.and_then(parameter_name) {
+            self.references.push((name.to_owned(), loc));
+        }
+    }
+}
+
+/// Returns the sole command of the pipeline, if it is a simple command.
+fn single_simple_command(pipeline: &ast::Pipeline) -> Option<&ast::SimpleCommand> {
+    match pipeline.seq.as_slice() {
+        [ast::Command::Simple(command)] => Some(command),
+        _ => None,
+    }
+}
+
+/// Returns the name of the command invoked by a simple command, if it is literal.
+fn command_name(command: &ast::SimpleCommand) -> Option<&str> {
+    command
+        .word_or_name
+        .as_ref()
+        .map(|name| name.value.as_str())
+}
+
+fn parameter_name(parameter: &Parameter) -> Option<&str> {
+    match parameter {
+        Parameter::Named(name)
+        | Parameter::NamedWithIndex { name, .. }
+        | Parameter::NamedWithAllIndices { name, .. } => Some(name),
+        Parameter::Positional(_) | Parameter::Special(_) => None,
+    }
+}
+
+/// Returns whether the result of an unquoted expansion may be split into multiple words
+/// (or none); expansions that always yield a single number are exempt.
+fn expansion_may_split(expr: &ParameterExpr) -> bool {
+    match expr {
+        ParameterExpr::ParameterLength { .. } => false,
+        ParameterExpr::Parameter {
+            parameter: Parameter::Special(special),
+            indirect: false,
+            ..
+        } => matches!(
+            special,
+            SpecialParameter::AllPositionalParameters { .. }
+                | SpecialParameter::CurrentOptionFlags
+                | SpecialParameter::ShellName
+        ),
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn lint(input: &str) -> Result<Vec<String>> {
+        Ok(lint_str(input, &ParserOptions::default())?
+            .iter()
+            .map(ToString::to_string)
+            .collect())
+    }
+
+    fn lint_posix(input: &str) -> Result<Vec<(LintCode, String)>> {
+        let options = ParserOptions {
+            posix_mode: true,
+            ..ParserOptions::default()
+        };
+        lint_with_options(input, &options)
+    }
+
+    fn lint_with_options(input: &str, options: &ParserOptions) -> Result<Vec<(LintCode, String)>> {
+        Ok(lint_str(input, options)?
+            .into_iter()
+            .map(|f| (f.code, f.message))
+            .collect())
+    }
+
+    #[test]
+    fn lint_clean_script() -> Result<()> {
+        let input = r#"#!/bin/bash
+set -e
+dir=${1:-.}
+cd "$dir" || exit 1
+for f in *.txt; do
+    count=$(wc -l < "$f")
+    echo "$f: $count $# $?"
+done
+read -r name rest
+if [ -n "$name" ]; then echo "${name}" "$rest"; fi
+f() { local x=1; echo "$x"; return 0; }
+(( total = 1 ))
+echo "$total $HOME"
+"#;
+
+        assert_eq!(lint(input)?, Vec::<String>::new());
+        Ok(())
+    }
+
+    #[test]
+    fn lint_unquoted_expansions() -> Result<()> {
+        let input = r#"x=1
+echo $x "$x" ${#x} $(date) >$x
+[ $x = y ]
+export y=$x
+"#;
+
+        assert_eq!(
+            lint(input)?,
+            [
+                "2:6: warning[unquoted-expansion]: unquoted `$x` is subject to word splitting \
+                 and pathname expansion; double-quote it",
+                "2:20: warning[unquoted-expansion]: unquoted `$(date)` is subject to word \
+                 splitting and pathname expansion; double-quote it",
+                "2:29: warning[unquoted-expansion]: unquoted `$x` is subject to word splitting \
+                 and pathname expansion; double-quote it",
+                "3:3: warning[unquoted-test-operand]: unquoted operand `$x` may expand to \
+                 several words or none at all; double-quote it",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_command_usage() -> Result<()> {
+        let input = r#"cd /tmp
+cd /tmp && ls
+if cd /tmp; then :; fi
+cat file | grep x
+cat -n file | grep x
+local v=1
+exit 0
+echo never
+echo "$undefined ${undefined} ${maybe:-x}"
+"#;
+
+        assert_eq!(
+            lint(input)?,
+            [
+                "1:1: warning[unchecked-cd]: `cd` may fail, leaving later commands in the wrong \
+                 directory; use `cd ... || exit`",
+                "4:1: info[useless-cat]: useless use of `cat`; redirect `file` into the next \
+                 command instead",
+                "6:1: error[local-outside-function]: `local` can only be used in a function",
+                "8:1: warning[unreachable-code]: command can never run, as it follows an \
+                 unconditional exit",
+                "9:7: warning[unassigned-variable]: `undefined` is referenced but never \
+                 assigned",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_bashisms() -> Result<()> {
+        let input = r#"a=(1 2)
+[[ -n "${a[0]}" ]] && echo "${a/y/z}" <<< $'x'
+[ "$a" == b ]
+source ./f
+"#;
+
+        assert_eq!(lint(input)?, Vec::<String>::new());
+        assert_eq!(
+            lint_posix(input)?
+                .into_iter()
+                .map(|(code, message)| {
+                    assert_eq!(code, LintCode::Bashism);
+                    message
+                })
+                .collect::<Vec<_>>(),
+            [
+                "array assignment is not specified by POSIX",
+                "`[[ ... ]]` is not specified by POSIX",
+                "array expansion is not specified by POSIX",
+                "pattern substitution is not specified by POSIX",
+                "`<<<` is not specified by POSIX",
+                "`$'...'` is not specified by POSIX",
+                "`==` in `[` is not specified by POSIX",
+                "`source` is not specified by POSIX",
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_function_keyword() -> Result<()> {
+        let options = ParserOptions {
+            posix_mode: true,
+            ..ParserOptions::default()
+        };
+
+        assert_eq!(
+            lint_with_options(
+                "function f { :; }\nfunction g() { :; }\nh() { :; }\n",
+                &options
+            )?,
+            [
+                (
+                    LintCode::Bashism,
+                    String::from("the `function` keyword is not specified by POSIX")
+                ),
+                (
+                    LintCode::Bashism,
+                    String::from("the `function` keyword is not specified by POSIX")
+                ),
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn lint_sh_mode_checks_non_posix_words() -> Result<()> {
+        let options = ParserOptions {
+            sh_mode: true,
+            ..ParserOptions::default()
+        };
+
+        assert_eq!(
+            lint_with_options("x=a\necho \"${x/a/b}\" $x\n", &options)?,
+            [
+                (
+                    LintCode::Bashism,
+                    String::from("pattern substitution is not specified by POSIX")
+                ),
+                (
+                    LintCode::UnquotedExpansion,
+                    String::from(
+                        "unquoted `$x` is subject to word splitting and pathname expansion; \
+                         double-quote it"
+                    )
+                ),
+            ]
+        );
+        Ok(())
+    }
+}
@@ -2,14 +2,21 @@
 
 pub mod arithmetic;
 pub mod ast;
+pub mod format;
+pub mod lint;
 pub mod pattern;
 pub mod prompt;
 pub mod readline_binding;
 pub mod test_command;
+pub mod visit;
+pub mod visit_mut;
 pub mod word;
 
+mod alias;
+mod display;
 mod error;
 mod parse;
+mod recovery;
 mod tokenizer;
 
 #[cfg(test)]
@@ -20,10 +27,11 @@ pub use error::{
 };
 
 #[cfg(feature = "diagnostics")]
-pub use error::miette::PrettyError;
+pub use error::miette::{PrettyError, PrettyErrors};
 
 pub use parse::{Parser, ParserBuilder, ParserOptions, SourceInfo, parse_tokens};
+pub use recovery::parse_tokens_with_recovery;
 pub use tokenizer::{
-    SourcePosition, Token, TokenLocation, TokenizerError, TokenizerOptions, tokenize_str,
+    Comment, SourcePosition, Token, TokenLocation, TokenizerError, TokenizerOptions, tokenize_str,
     tokenize_str_with_options, uncached_tokenize_str, unquote_str,
 };
@@ -1,6 +1,8 @@
+use std::collections::{HashMap, VecDeque};
+
 use super::ast::{self, SeparatorOperator, SourceLocation, maybe_location};
-use super::tokenizer::{Token, TokenEndReason, Tokenizer, TokenizerOptions, Tokens};
-use super::{TokenLocation, error};
+use super::tokenizer::{Comment, Token, TokenEndReason, Tokenizer, TokenizerOptions, Tokens};
+use super::{TokenLocation, alias, error, recovery};
 
 use bon::Builder;
 
@@ -50,6 +52,159 @@ pub struct Parser<R: std::io::BufRead> {
     /// Information about the source of the tokens
     #[builder(default)]
     source_info: SourceInfo,
+    /// Aliases to expand, mapping names to their values
+    #[builder(default)]
+    aliases: HashMap<String, String>,
+    /// Input that has been tokenized but not yet parsed, when parsing line by line
+    #[builder(skip)]
+    pending_input: Option<PendingInput>,
+}
+
+/// Tokenized input awaiting parsing by [`Parser::parse_next_line`].
+struct PendingInput {
+    /// The tokens of the input.
+    tokens: Vec<Token>,
+    /// The index of the first token not yet parsed.
+    next: usize,
+    /// The error that stopped tokenization of the input, if any.
+    error: Option<error::ParseError>,
+    /// Complete commands parsed ahead of `next` (without alias expansion), along with the
+    /// index of the token just past each.
+    parsed_ahead: VecDeque<(ast::CompleteCommand, usize)>,
+    /// Whether parsing the remaining input all at once failed.
+    parse_ahead_failed: bool,
+}
+
+impl PendingInput {
+    /// Parses all remaining input at once, without expanding aliases, queueing up the
+    /// complete commands it contains. If the input can't be parsed in full (e.g., due to a
+    /// syntax error partway through), nothing is queued, so the commands preceding the
+    /// error can still be parsed (and executed) line by line.
+    fn parse_ahead(&mut self, options: &ParserOptions, source_info: &SourceInfo) {
+        let tokens = &self.tokens[self.next..];
+        let result = match self.error {
+            Some(_) => None,
+            None => token_parser::program_lines(&Tokens { tokens }, options, source_info).ok(),
+        };
+
+        match result {
+            Some(commands) => self.parsed_ahead.extend(
+                commands
+                    .into_iter()
+                    .map(|(command, end)| (command, self.next + end)),
+            ),
+            None => self.parse_ahead_failed = true,
+        }
+    }
+
+    /// Parses the next line of the input, expanding the given aliases within it.
+    ///
+    /// Lines are expanded and added to the candidate input as needed, doubling its size
+    /// each time the first command in it turns out to be incomplete, so a command spanning
+    /// many lines is parsed in time proportional to its length.
+    fn parse_line(
+        &mut self,
+        aliases: &HashMap<String, String>,
+        options: &ParserOptions,
+        source_info: &SourceInfo,
+    ) -> Result<Option<ast::Program>, error::ParseError> {
+        let tokenizer_options = options.tokenizer_options();
+        let mut expander = alias::AliasExpander::new(aliases, &tokenizer_options);
+
+        let tokens = &self.tokens;
+        let is_newline = |token: &Token| matches!(token, Token::Operator(op, _) if op == "\n");
+
+        let start = self.next
+            + tokens[self.next..]
+                .iter()
+                .take_while(|token| is_newline(token))
+                .count();
+
+        // The index of the token just past each line expanded so far, along with the
+        // number of tokens its expansion brought the output to.
+        let mut line_ends: Vec<(usize, usize)> = vec![];
+        let mut lines_wanted = 1;
+        // The line through which to parse next, once the first command's extent is known.
+        let mut candidate_line = None;
+
+        loop {
+            while line_ends.len() < lines_wanted {
+                let line_start = line_ends.last().map_or(start, |(end, _)| *end);
+                if line_start == tokens.len() {
+                    break;
+                }
+
+                let line_end = tokens[line_start..]
+                    .iter()
+                    .position(is_newline)
+                    .map_or(tokens.len(), |i| line_start + i + 1);
+
+                expander.extend(tokens[line_start..line_end].iter().cloned());
+                line_ends.push((line_end, expander.output().len()));
+            }
+
+            let Some(&(input_end, _)) = line_ends.last() else {
+                self.next = tokens.len();
+                return self.error.take().map_or(Ok(None), Err);
+            };
+
+            let expanded = expander.output();
+            let at_end = input_end == tokens.len();
+
+            let line = match candidate_line {
+                Some(line) => line,
+                None => match token_parser::leading_complete_command(
+                    &Tokens { tokens: expanded },
+                    options,
+                    source_info,
+                ) {
+                    // The command ends on the line containing the token following it.
+                    Ok(end) => line_ends
+                        .partition_point(|(_, len)| *len <= end)
+                        .min(line_ends.len() - 1),
+                    // Parsing failed at the end of the input; the command continues on
+                    // the lines that follow.
+                    Err(err) if err.location >= expanded.len() && !at_end => {
+                        lines_wanted = line_ends.len() * 2;
+                        continue;
+                    }
+                    Err(_) if at_end && self.error.is_some() => line_ends.len() - 1,
+                    Err(err) => {
+                        self.next = tokens.len();
+                        return Err(error::convert_peg_parse_error(&err, expanded));
+                    }
+                },
+            };
+
+            // If tokenization failed partway through this line, report the failure rather
+            // than the (incomplete) commands preceding it.
+            let (line_end, line_len) = line_ends[line];
+            if line_end == tokens.len()
+                && let Some(err) = self.error.take()
+            {
+                self.next = tokens.len();
+                return Err(err);
+            }
+
+            match parse_program_tokens(&expanded[..line_len], options, source_info) {
+                Ok(program) => {
+                    self.next = line_end;
+                    return Ok(Some(program));
+                }
+                // The first command is complete, but the line it ends on continues with
+                // another command (which may happen if an alias's value contains a
+                // newline); add lines one at a time until that command is complete too.
+                Err(err) if err.location >= line_len && line_end < tokens.len() => {
+                    candidate_line = Some(line + 1);
+                    lines_wanted = lines_wanted.max(line + 2);
+                }
+                Err(err) => {
+                    self.next = tokens.len();
+                    return Err(error::convert_peg_parse_error(&err, &expanded[..line_len]));
+                }
+            }
+        }
+    }
 }
 
 impl<R: std::io::BufRead> Parser<R> {
@@ -65,9 +220,22 @@ impl<R: std::io::BufRead> Parser<R> {
             reader,
             options: options.clone(),
             source_info: source_info.clone(),
+            aliases: HashMap::new(),
+            pending_input: None,
         }
     }
 
+    /// Sets the aliases to expand in subsequently parsed input. Aliases are expanded as
+    /// bash does: in the first word of each simple command (and in the word following an
+    /// alias whose value ends in a blank).
+    ///
+    /// # Arguments
+    ///
+    /// * `aliases` - The aliases to expand, mapping names to their values.
+    pub fn set_aliases(&mut self, aliases: HashMap<String, String>) {
+        self.aliases = aliases;
+    }
+
     /// Parses the input into an abstract syntax tree (AST) of a shell program.
     pub fn parse_program(&mut self) -> Result<ast::Program, error::ParseError> {
         //
@@ -82,6 +250,85 @@ impl<R: std::io::BufRead> Parser<R> {
         parse_tokens(&tokens, &self.options, &self.source_info)
     }
 
+    /// Parses the input into an abstract syntax tree (AST) of a shell program, also
+    /// returning the comments it contains (which aren't otherwise represented in the
+    /// AST), in source order.
+    pub fn parse_program_with_comments(
+        &mut self,
+    ) -> Result<(ast::Program, Vec<Comment>), error::ParseError> {
+        let mut comments = vec![];
+        let tokens = match self.tokenize_until_error(Some(&mut comments)) {
+            (tokens, None) => tokens,
+            (_, Some(err)) => return Err(err),
+        };
+
+        let program = parse_tokens(&tokens, &self.options, &self.source_info)?;
+        Ok((program, comments))
+    }
+
+    /// Parses the input into an abstract syntax tree (AST) of a shell program, recovering
+    /// from syntax errors rather than stopping at the first one. Returns a best-effort
+    /// program, in which each command that could not be parsed is represented by an
+    /// [`ast::Command::Error`] node, along with all errors encountered.
+    pub fn parse_program_with_recovery(&mut self) -> (ast::Program, Vec<error::ParseError>) {
+        let (tokens, tokenizer_error) = self.tokenize_until_error(None);
+
+        if let Some(tokenizer_error) = tokenizer_error {
+            recovery::parse_partial_tokens_with_recovery(
+                &tokens,
+                tokenizer_error,
+                &self.options,
+                &self.source_info,
+            )
+        } else {
+            recovery::parse_tokens_with_recovery(&tokens, &self.options, &self.source_info)
+        }
+    }
+
+    /// Parses the next line of the input into an abstract syntax tree (AST) of a shell
+    /// program, returning `None` once the input is exhausted. A line extends as far as
+    /// needed to complete the commands it contains, e.g. through the end of a compound
+    /// command that spans multiple lines.
+    ///
+    /// Aliases are expanded using the definitions set at the time of the call, so a caller
+    /// that executes each line before parsing the next follows bash's rules: an alias
+    /// defined on a line takes effect on the following line.
+    pub fn parse_next_line(&mut self) -> Result<Option<ast::Program>, error::ParseError> {
+        if self.pending_input.is_none() {
+            let (tokens, error) = self.tokenize_until_error(None);
+            self.pending_input = Some(PendingInput {
+                tokens,
+                next: 0,
+                error,
+                parsed_ahead: VecDeque::new(),
+                parse_ahead_failed: false,
+            });
+        }
+
+        let Some(input) = self.pending_input.as_mut() else {
+            return Ok(None);
+        };
+
+        // Without aliases to expand, the remaining lines parse the same way regardless of
+        // what's executed in between; parse them all at once.
+        if self.aliases.is_empty() {
+            if input.parsed_ahead.is_empty() && !input.parse_ahead_failed {
+                input.parse_ahead(&self.options, &self.source_info);
+            }
+
+            if let Some((command, end)) = input.parsed_ahead.pop_front() {
+                input.next = end;
+                return Ok(Some(ast::Program {
+                    complete_commands: vec![command],
+                }));
+            }
+        } else {
+            input.parsed_ahead.clear();
+        }
+
+        input.parse_line(&self.aliases, &self.options, &self.source_info)
+    }
+
     /// Parses a function definition body from the input. The body is expected to be
     /// preceded by "()", but no function name.
     pub fn parse_function_parens_and_body(
@@ -97,9 +344,42 @@ impl<R: std::io::BufRead> Parser<R> {
     }
 
     fn tokenize(&mut self) -> Result<Vec<Token>, error::ParseError> {
+        match self.tokenize_until_error(None) {
+            (tokens, None) => Ok(alias::expand_aliases(
+                tokens,
+                &self.aliases,
+                &self.options.tokenizer_options(),
+            )),
+            (_, Some(err)) => Err(err),
+        }
+    }
+
+    /// Tokenizes the input, returning the tokens produced before any error occurred,
+    /// along with the error (if any).
+    ///
+    /// # Arguments
+    ///
+    /// * `comments` - If provided, receives the comments encountered in the input.
+    fn tokenize_until_error(
+        &mut self,
+        comments: Option<&mut Vec<Comment>>,
+    ) -> (Vec<Token>, Option<error::ParseError>) {
         // First we tokenize the input, according to the policy implied by provided options.
         let mut tokenizer = Tokenizer::new(&mut self.reader, &self.options.tokenizer_options());
+        if comments.is_some() {
+            tokenizer.retain_comments();
+        }
+
+        let result = Self::tokenize_with(&mut tokenizer);
+
+        if let Some(comments) = comments {
+            comments.extend(tokenizer.take_comments());
+        }
 
+        result
+    }
+
+    fn tokenize_with(tokenizer: &mut Tokenizer<'_, R>) -> (Vec<Token>, Option<error::ParseError>) {
         tracing::debug!(target: "tokenize", "Tokenizing...");
 
         let mut tokens = vec![];
@@ -107,10 +387,11 @@ impl<R: std::io::BufRead> Parser<R> {
             let result = match tokenizer.next_token() {
                 Ok(result) => result,
                 Err(e) => {
-                    return Err(error::ParseError::Tokenizing {
+                    let err = error::ParseError::Tokenizing {
                         inner: e,
                         position: tokenizer.current_location(),
-                    });
+                    };
+                    return (tokens, Some(err));
                 }
             };
 
@@ -127,7 +408,7 @@ impl<R: std::io::BufRead> Parser<R> {
 
         tracing::debug!(target: "tokenize", "  => {} token(s)", tokens.len());
 
-        Ok(tokens)
+        (tokens, None)
     }
 }
 
@@ -147,6 +428,16 @@ pub fn parse_tokens(
     parse_result_to_error(parse_result, tokens)
 }
 
+/// Parses a sequence of tokens into a shell program, returning the raw parse error
+/// (identifying the index of the token at which parsing failed) on failure.
+pub(crate) fn parse_program_tokens(
+    tokens: &[Token],
+    options: &ParserOptions,
+    source_info: &SourceInfo,
+) -> Result<ast::Program, peg::error::ParseError<usize>> {
+    token_parser::program(&Tokens { tokens }, options, source_info)
+}
+
 fn parse_result_to_error<R>(
     parse_result: Result<R, peg::error::ParseError<usize>>,
     tokens: &Vec<Token>,
@@ -205,24 +496,27 @@ impl<'a> peg::ParseSlice<'a> for Tokens<'a> {
 
     fn parse_slice(&'a self, start: usize, end: usize) -> Self::Slice {
         let mut result = String::new();
-        let mut last_token_was_word = false;
+        let mut last_token: Option<&Token> = None;
 
         for token in &self.tokens[start..end] {
-            match token {
-                Token::Operator(s, _) => {
-                    result.push_str(s);
-                    last_token_was_word = false;
-                }
-                Token::Word(s, _) => {
-                    // Place spaces between adjacent words.
-                    if last_token_was_word {
-                        result.push(' ');
-                    }
-
-                    result.push_str(s);
-                    last_token_was_word = true;
+            // Preserve the spacing between tokens on the same line, so that offsets into
+            // the slice can be mapped back to the source. Otherwise, place spaces between
+            // adjacent words.
+            match last_token {
+                Some(last) if last.location().end.line == token.location().start.line => {
+                    let gap = token
+                        .location()
+                        .start
+                        .column
+                        .saturating_sub(last.location().end.column);
+                    result.extend(std::iter::repeat_n(' ', gap));
                 }
+                Some(Token::Word(..)) if matches!(token, Token::Word(..)) => result.push(' '),
+                _ => (),
             }
+
+            result.push_str(token.to_str());
+            last_token = Some(token);
         }
 
         result
@@ -251,6 +545,16 @@ peg::parser! {
         rule complete_commands() -> Vec<ast::CompleteCommand> =
             c:complete_command() ++ newline_list()
 
+        // Parses a program, also returning the index of the token just past each of its
+        // complete commands.
+        pub(crate) rule program_lines() -> Vec<(ast::CompleteCommand, usize)> =
+            linebreak() c:(c:complete_command() p:position!() { (c, p) }) ** newline_list() linebreak() { c }
+
+        // Parses the first complete command of the input, returning the index of the token
+        // just past it; the input following that command's line is ignored.
+        pub(crate) rule leading_complete_command() -> usize =
+            linebreak() complete_command() p:position!() (newline() / ![_]) [_]* { p }
+
         rule complete_command() -> ast::CompleteCommand =
             first:and_or() remainder:(s:separator_op() l:and_or() { (s, l) })* last_sep:separator_op()? {
                 let mut and_ors = vec![first];
@@ -284,11 +588,23 @@ peg::parser! {
 
         rule pipeline() -> ast::Pipeline =
             timed:pipeline_timed()? bang:bang()* seq:pipe_sequence() {?
+                let start = timed
+                    .as_ref()
+                    .and_then(SourceLocation::location)
+                    .or_else(|| bang.first().map(|b| b.location().clone()))
+                    .or_else(|| seq.first().and_then(SourceLocation::location));
+                let end = seq
+                    .last()
+                    .and_then(SourceLocation::location)
+                    .or_else(|| bang.last().map(|b| b.location().clone()))
+                    .or_else(|| timed.as_ref().and_then(SourceLocation::location));
+
                 if timed.is_none() && bang.is_empty() && seq.is_empty() {
                     Err("empty pipeline")
                 } else {
                     let invert = bang.len() % 2 == 1;
-                    Ok(ast::Pipeline { timed, bang: invert, seq })
+                    let loc = maybe_location(start.as_ref(), end.as_ref()).unwrap_or_default();
+                    Ok(ast::Pipeline { timed, bang: invert, seq, loc })
                 }
             }
 
@@ -302,13 +618,13 @@ peg::parser! {
                 }
             }
 
-        rule bang() -> bool = specific_word("!") { true }
+        rule bang() -> &'input Token = specific_word("!")
 
         pub(crate) rule pipe_sequence() -> Vec<ast::Command> =
             c:(c:command() r:&pipe_extension_redirection()? {? // check for `|&` without consuming the stream.
                 let mut c = c;
-                if r.is_some() {
-                    add_pipe_extension_redirection(&mut c)?;
+                if let Some(r) = r {
+                    add_pipe_extension_redirection(&mut c, r.location())?;
                 }
                 Ok(c)
             }) ** (pipe_operator() linebreak()) {
@@ -355,16 +671,21 @@ peg::parser! {
             }
 
         pub(crate) rule arithmetic_expression() -> ast::UnexpandedArithmeticExpr =
-            raw_expr:$(arithmetic_expression_piece()*) { ast::UnexpandedArithmeticExpr { value: raw_expr } }
+            pieces:&(arithmetic_expression_piece()*) raw_expr:$(arithmetic_expression_piece()*) {
+                let start = pieces.first().map(|(first, _)| first.location());
+                let end = pieces.last().map(|(_, last)| last.location());
+                ast::UnexpandedArithmeticExpr { value: raw_expr, loc: maybe_location(start, end) }
+            }
 
-        rule arithmetic_expression_piece() =
+        // N.B. Returns the first and last tokens of the piece.
+        rule arithmetic_expression_piece() -> (&'input Token, &'input Token) =
             // Allow a parenthesized expression (with matching opening and closing parens).
-            specific_operator("(") (!specific_operator(")") arithmetic_expression_piece())* specific_operator(")") {} /
+            s:specific_operator("(") (!specific_operator(")") arithmetic_expression_piece())* e:specific_operator(")") { (s, e) } /
             // Otherwise consume any token that's neither the normal end of the entire arithmetic expression, nor an
             // unexpected mismatched closing parenthesis. In the latter case, it may be that this really was never an
             // arithmetic expression in the first place and we need to backtrack and instead try parsing as a subshell
             // command instead.
-            !arithmetic_end() !specific_operator(")") [_] {}
+            !arithmetic_end() !specific_operator(")") t:[_] { (t, t) }
 
         // TODO: evaluate arithmetic end; the semicolon is used in arithmetic for loops.
         rule arithmetic_end() -> () =
@@ -446,9 +767,15 @@ peg::parser! {
             --
             left:(@) linebreak() specific_operator("&&") linebreak() right:@ { ast::ExtendedTestExpr::And(Box::from(left), Box::from(right)) }
             --
-            specific_word("!") e:@ { ast::ExtendedTestExpr::Not(Box::from(e)) }
+            s:specific_word("!") e:@ {
+                let loc = location_through(s.location(), &e);
+                ast::ExtendedTestExpr::Not(Box::from(e), loc)
+            }
             --
-            specific_operator("(") e:extended_test_expression() specific_operator(")") { ast::ExtendedTestExpr::Parenthesized(Box::from(e)) }
+            s:specific_operator("(") e:extended_test_expression() end:specific_operator(")") {
+                let loc = TokenLocation::within(s.location(), end.location());
+                ast::ExtendedTestExpr::Parenthesized(Box::from(e), loc)
+            }
             --
             // Arithmetic operators
             left:word() specific_word("-eq") right:word() { ast::ExtendedTestExpr::BinaryTest(ast::BinaryPredicate::ArithmeticEqualTo, ast::Word::from(left), ast::Word::from(right)) }
@@ -474,9 +801,12 @@ peg::parser! {
             left:word() specific_operator("<") right:word()   { ast::ExtendedTestExpr::BinaryTest(ast::BinaryPredicate::LeftSortsBeforeRight, ast::Word::from(left), ast::Word::from(right)) }
             left:word() specific_operator(">") right:word()   { ast::ExtendedTestExpr::BinaryTest(ast::BinaryPredicate::LeftSortsAfterRight, ast::Word::from(left), ast::Word::from(right)) }
             --
-            p:extended_unary_predicate() f:word() { ast::ExtendedTestExpr::UnaryTest(p, ast::Word::from(f)) }
+            s:&word() p:extended_unary_predicate() f:word() {
+                let loc = TokenLocation::within(s.location(), f.location());
+                ast::ExtendedTestExpr::UnaryTest(p, ast::Word::from(f), loc)
+            }
             --
-            w:word() { ast::ExtendedTestExpr::UnaryTest(ast::UnaryPredicate::StringHasNonZeroLength, ast::Word::from(w)) }
+            w:word() { ast::ExtendedTestExpr::UnaryTest(ast::UnaryPredicate::StringHasNonZeroLength, ast::Word::from(w), w.location().clone()) }
         }
 
         rule extended_unary_predicate() -> ast::UnaryPredicate =
@@ -510,14 +840,17 @@ peg::parser! {
         // N.B. For some reason we seem to need to allow a select subset
         // of unescaped operators in regex words.
         rule regex_word() -> ast::Word =
-            value:$((!specific_word("]]") regex_word_piece())+) {
-                ast::Word::from(value)
+            pieces:&((!specific_word("]]") p:regex_word_piece() { p })+) value:$((!specific_word("]]") regex_word_piece())+) {
+                let start = pieces.first().map(|(first, _)| first.location());
+                let end = pieces.last().map(|(_, last)| last.location());
+                ast::Word { value, loc: maybe_location(start, end) }
             }
 
-        rule regex_word_piece() =
-            word() {} /
-            specific_operator("|") {} /
-            specific_operator("(") parenthesized_regex_word()* specific_operator(")") {}
+        // N.B. Returns the first and last tokens of the piece.
+        rule regex_word_piece() -> (&'input Token, &'input Token) =
+            w:word() { (w, w) } /
+            p:specific_operator("|") { (p, p) } /
+            s:specific_operator("(") parenthesized_regex_word()* e:specific_operator(")") { (s, e) }
 
         rule parenthesized_regex_word() =
             regex_word_piece() /
@@ -533,14 +866,15 @@ peg::parser! {
             (w:word() { ast::Word::from(w) })+
 
         pub(crate) rule case_clause() -> ast::CaseClauseCommand =
-            specific_word("case") w:word() linebreak() _in() linebreak() first_items:case_item()* last_item:case_item_ns()? specific_word("esac") {
+            s:specific_word("case") w:word() linebreak() _in() linebreak() first_items:case_item()* last_item:case_item_ns()? e:specific_word("esac") {
                 let mut cases = first_items;
 
                 if let Some(last_item) = last_item {
                     cases.push(last_item);
                 }
 
-                ast::CaseClauseCommand { value: ast::Word::from(w), cases }
+                let loc = TokenLocation::within(s.location(), e.location());
+                ast::CaseClauseCommand { value: ast::Word::from(w), cases, loc }
             }
 
         pub(crate) rule case_item_ns() -> ast::CaseItem =
@@ -591,7 +925,7 @@ peg::parser! {
         rule if_clause() -> ast::IfClauseCommand =
             s:specific_word("if") condition:compound_list() specific_word("then") then:compound_list() elses:else_part()? e:specific_word("fi") {
                 let start = s.location();
-                let end = s.location();
+                let end = e.location();
                 let loc = TokenLocation::within(start, end);
 
                 ast::IfClauseCommand {
@@ -618,14 +952,16 @@ peg::parser! {
             e:_unconditional_else_part() { vec![e] }
 
         rule _conditional_else_part() -> ast::ElseClause =
-            specific_word("elif") condition:compound_list() specific_word("then") body:compound_list() {
-                ast::ElseClause { condition: Some(condition), body }
+            s:specific_word("elif") condition:compound_list() specific_word("then") body:compound_list() {
+                let loc = location_through(s.location(), &body);
+                ast::ElseClause { condition: Some(condition), body, loc }
             }
 
         rule _unconditional_else_part() -> ast::ElseClause =
-            specific_word("else") body:compound_list() {
-                ast::ElseClause { condition: None, body }
-             }
+            s:specific_word("else") body:compound_list() {
+                let loc = location_through(s.location(), &body);
+                ast::ElseClause { condition: None, body, loc }
+            }
 
         rule while_clause() -> ast::WhileOrUntilClauseCommand =
             s:specific_word("while") c:compound_list() d:do_group() {
@@ -647,11 +983,21 @@ peg::parser! {
 
         // N.B. Non-sh extensions allows use of the 'function' word to indicate a function definition.
         rule function_definition() -> ast::FunctionDefinition =
-            specific_word("function")? fname:fname() body:function_parens_and_body() {
-                ast::FunctionDefinition { fname, body, source: source_info.source.clone() }
+            k:specific_word("function")? fname:fname() body:function_parens_and_body() {
+                ast::FunctionDefinition {
+                    fname,
+                    body,
+                    source: source_info.source.clone(),
+                    uses_function_keyword: k.is_some(),
+                }
             } /
             specific_word("function") fname:fname() linebreak() body:function_body() {
-                ast::FunctionDefinition { fname, body, source: source_info.source.clone() }
+                ast::FunctionDefinition {
+                    fname,
+                    body,
+                    source: source_info.source.clone(),
+                    uses_function_keyword: true,
+                }
             } /
             expected!("function definition")
 
@@ -733,14 +1079,27 @@ peg::parser! {
 
         // N.B. here strings are extensions to the POSIX standard.
         rule io_redirect() -> ast::IoRedirect =
-            n:io_number()? f:io_file() {
+            s:&[_] n:io_number()? f:io_file() {
                     let (kind, target) = f;
-                    ast::IoRedirect::File(n, kind, target)
+                    let loc = location_through(s.location(), &target);
+                    ast::IoRedirect::File(n, kind, target, loc)
                 } /
-            non_posix_extensions_enabled() specific_operator("&>>") target:filename() { ast::IoRedirect::OutputAndError(ast::Word::from(target), true) } /
-            non_posix_extensions_enabled() specific_operator("&>") target:filename() { ast::IoRedirect::OutputAndError(ast::Word::from(target), false) } /
-            non_posix_extensions_enabled() n:io_number()? specific_operator("<<<") w:word() { ast::IoRedirect::HereString(n, ast::Word::from(w)) } /
-            n:io_number()? h:io_here() { ast::IoRedirect::HereDocument(n, h) } /
+            non_posix_extensions_enabled() s:specific_operator("&>>") target:filename() {
+                let loc = TokenLocation::within(s.location(), target.location());
+                ast::IoRedirect::OutputAndError(ast::Word::from(target), true, loc)
+            } /
+            non_posix_extensions_enabled() s:specific_operator("&>") target:filename() {
+                let loc = TokenLocation::within(s.location(), target.location());
+                ast::IoRedirect::OutputAndError(ast::Word::from(target), false, loc)
+            } /
+            non_posix_extensions_enabled() s:&[_] n:io_number()? specific_operator("<<<") w:word() {
+                let loc = TokenLocation::within(s.location(), w.location());
+                ast::IoRedirect::HereString(n, ast::Word::from(w), loc)
+            } /
+            s:&[_] n:io_number()? h:io_here() {
+                let loc = TokenLocation::within(s.location(), &h.loc);
+                ast::IoRedirect::HereDocument(n, h, loc)
+            } /
             expected!("I/O redirect")
 
         // N.B. Process substitution forms are extensions to the POSIX standard.
@@ -776,7 +1135,8 @@ peg::parser! {
                     remove_tabs: true,
                     requires_expansion,
                     here_end: ast::Word::from(here_tag),
-                    doc: ast::Word::from(doc)
+                    doc: ast::Word::from(doc),
+                    loc: TokenLocation::within(here_tag.location(), closing_tag.location()),
                 }
             } /
             specific_operator("<<") here_tag:here_tag() doc:[_] closing_tag:here_tag() {
@@ -785,7 +1145,8 @@ peg::parser! {
                     remove_tabs: false,
                     requires_expansion,
                     here_end: ast::Word::from(here_tag),
-                    doc: ast::Word::from(doc)
+                    doc: ast::Word::from(doc),
+                    loc: TokenLocation::within(here_tag.location(), closing_tag.location()),
                 }
             }
 
@@ -869,7 +1230,7 @@ peg::parser! {
                     if i > 0 {
                         all_as_word.push(' ');
                     }
-                    all_as_word.push_str(e);
+                    all_as_word.push_str(e.to_str());
                 }
                 all_as_word.push(')');
 
@@ -879,15 +1240,18 @@ peg::parser! {
             } /
             [Token::Word(w, l)] {?
                 let mut parsed = parse_assignment_word(w.as_str())?;
+                if let ast::AssignmentValue::Scalar(value) = &mut parsed.value {
+                    value.loc = Some(TokenLocation::within_text(&l.start, w, w.len() - value.value.len()..w.len()));
+                }
                 parsed.loc = l.clone();
                 Ok((parsed, ast::Word::with_location(w, l)))
             }
 
-        rule array_elements() -> Vec<&'input String> =
+        rule array_elements() -> Vec<&'input Token> =
              linebreak() e:array_element()* { e }
 
-        rule array_element() -> &'input String =
-            linebreak() [Token::Word(e, _)] linebreak() { e }
+        rule array_element() -> &'input Token =
+            linebreak() e:word() linebreak() { e }
 
         // N.B. An I/O number must be a string of only digits, and it must be
         // followed by a '<' or '>' character (but not consume them). We also
@@ -972,8 +1336,12 @@ fn parse_assignment_word(word: &str) -> Result<ast::Assignment, &'static str> {
     parse_result.map_err(|_| "not assignment word")
 }
 
-// add `2>&1` to the command if the pipeline is `|&`
-fn add_pipe_extension_redirection(c: &mut ast::Command) -> Result<(), &'static str> {
+// add `2>&1` to the command if the pipeline is `|&`; the redirect is attributed to the
+// location of the `|&` operator.
+fn add_pipe_extension_redirection(
+    c: &mut ast::Command,
+    loc: &TokenLocation,
+) -> Result<(), &'static str> {
     fn add_to_redirect_list(l: &mut Option<ast::RedirectList>, r: ast::IoRedirect) {
         if let Some(l) = l {
             l.0.push(r);
@@ -987,6 +1355,7 @@ fn add_pipe_extension_redirection(c: &mut ast::Command) -> Result<(), &'static s
         Some(2),
         ast::IoFileRedirectKind::DuplicateOutput,
         ast::IoFileRedirectTarget::Fd(1),
+        loc.clone(),
     );
 
     match c {
@@ -1001,11 +1370,19 @@ fn add_pipe_extension_redirection(c: &mut ast::Command) -> Result<(), &'static s
         ast::Command::Compound(_, l) => add_to_redirect_list(l, r),
         ast::Command::Function(f) => add_to_redirect_list(&mut f.body.1, r),
         ast::Command::ExtendedTest(_) => return Err("|& unimplemented for extended tests"),
+        ast::Command::Error(_) => return Err("|& unsupported for unparsed input"),
     }
 
     Ok(())
 }
 
+/// Returns the location spanning from `start` through the end of `node`, or just `start`
+/// if `node` has no location.
+fn location_through(start: &TokenLocation, node: &impl SourceLocation) -> TokenLocation {
+    node.location()
+        .map_or_else(|| start.clone(), |end| TokenLocation::within(start, &end))
+}
+
 #[inline]
 fn locations_are_contiguous(
     loc_left: &crate::TokenLocation,
@@ -1016,26 +1393,30 @@ fn locations_are_contiguous(
 
 fn parse_array_assignment(
     word: &str,
-    elements: &[&String],
+    elements: &[&Token],
 ) -> Result<ast::Assignment, &'static str> {
     let (assignment_name, append) =
         assignments::name_and_equals(word).map_err(|_| "not array assignment word")?;
 
-    let elements = elements
+    let elements_as_words = elements
         .iter()
-        .map(|element| assignments::literal_array_element(element))
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(|_| "invalid array element in literal")?;
+        .map(|element| -> Result<_, &'static str> {
+            let text = element.to_str();
+            let start = &element.location().start;
+            let (key, value) = assignments::literal_array_element(text)
+                .map_err(|_| "invalid array element in literal")?;
+
+            // N.B. The key follows the opening bracket, and the value ends the element.
+            let key = key.map(|k| {
+                let loc = TokenLocation::within_text(start, text, 1..1 + k.len());
+                ast::Word::with_location(k.as_str(), &loc)
+            });
+            let loc = TokenLocation::within_text(start, text, text.len() - value.len()..text.len());
+            let value = ast::Word::with_location(value.as_str(), &loc);
 
-    let elements_as_words = elements
-        .into_iter()
-        .map(|(key, value)| {
-            (
-                key.map(|k| ast::Word::new(k.as_str())),
-                ast::Word::new(value.as_str()),
-            )
+            Ok((key, value))
         })
-        .collect();
+        .collect::<Result<Vec<_>, _>>()?;
 
     Ok(ast::Assignment {
         name: assignment_name,
@@ -1059,6 +1440,41 @@ mod tests {
         result: &'a T,
     }
 
+    #[test]
+    fn function_display_round_trips() -> Result<()> {
+        let input = r#"f() {
+    if cat <<'EOF' | grep -q x; then
+body $x
+EOF
+        ! true && { echo a; } 2>&1 >/dev/null
+    fi
+    case $1 in a|b) cat <<-END;;
+	tabbed
+	END
+    esac
+}
+"#;
+
+        let display = |s: &str| -> Result<String> {
+            let tokens = tokenize_str(s)?;
+            let program = super::token_parser::program(
+                &Tokens {
+                    tokens: tokens.as_slice(),
+                },
+                &ParserOptions::default(),
+                &SourceInfo::default(),
+            )?;
+            Ok(program.to_string())
+        };
+
+        // Here-document bodies must follow their line, without indentation.
+        let displayed = display(input)?;
+        assert!(displayed.contains("; then\nbody $x\nEOF\n"));
+        assert_eq!(display(&displayed)?, displayed);
+
+        Ok(())
+    }
+
     #[test]
     fn parse_case() -> Result<()> {
         let input = r"\
@@ -1230,4 +1646,189 @@ for f in A B C; do
 
         Ok(())
     }
+
+    #[test]
+    fn nodes_report_source_locations() -> Result<()> {
+        // Returns the source text spanned by a node's location.
+        fn text<'a>(input: &'a str, node: &impl SourceLocation) -> Option<&'a str> {
+            let loc = node.location()?;
+            input.get(loc.start.index..loc.end.index)
+        }
+
+        let input = r"! x=1 y=(a [k]=v) cat <<EOF 2>&1 |& wc
+body
+EOF
+if [[ ! -n $a ]]; then :; elif (( 1 +  2 )); then :
+else :
+fi
+case $x in a) ;; esac
+";
+
+        let tokens = tokenize_str(input)?;
+        let program = super::token_parser::program(
+            &Tokens {
+                tokens: tokens.as_slice(),
+            },
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        )?;
+
+        let pipeline = &program.complete_commands[0].0[0].0.first;
+        assert_eq!(
+            text(input, pipeline),
+            Some("! x=1 y=(a [k]=v) cat <<EOF 2>&1 |& wc")
+        );
+
+        let ast::Command::Simple(cat) = &pipeline.seq[0] else {
+            panic!("expected simple command");
+        };
+        let prefix = &cat.prefix.as_ref().unwrap().0;
+        let ast::CommandPrefixOrSuffixItem::AssignmentWord(x, _) = &prefix[0] else {
+            panic!("expected assignment");
+        };
+        assert_eq!(text(input, &x.value), Some("1"));
+        let ast::CommandPrefixOrSuffixItem::AssignmentWord(y, _) = &prefix[1] else {
+            panic!("expected assignment");
+        };
+        assert_eq!(text(input, y), Some("y=(a [k]=v)"));
+        let ast::AssignmentValue::Array(elements) = &y.value else {
+            panic!("expected array");
+        };
+        assert_eq!(text(input, elements[1].0.as_ref().unwrap()), Some("k"));
+        assert_eq!(text(input, &elements[1].1), Some("v"));
+
+        let suffix = &cat.suffix.as_ref().unwrap().0;
+        assert_eq!(
+            text(input, &suffix[0]),
+            Some("<<EOF 2>&1 |& wc\nbody\nEOF\n")
+        );
+        assert_eq!(text(input, &suffix[1]), Some("2>&1"));
+        assert_eq!(text(input, &suffix[2]), Some("|&"));
+
+        let ast::Command::Compound(ast::CompoundCommand::IfClause(if_clause), _) =
+            &program.complete_commands[1].0[0].0.first.seq[0]
+        else {
+            panic!("expected if clause");
+        };
+        assert!(text(input, if_clause).is_some_and(|t| t.starts_with("if") && t.ends_with("fi")));
+        let ast::Command::ExtendedTest(test) = &if_clause.condition.0[0].0.first.seq[0] else {
+            panic!("expected extended test");
+        };
+        assert_eq!(text(input, &test.expr), Some("! -n $a"));
+        let elses = if_clause.elses.as_ref().unwrap();
+        assert_eq!(text(input, &elses[0]), Some("elif (( 1 +  2 )); then :"));
+        assert_eq!(text(input, &elses[1]), Some("else :"));
+        let ast::Command::Compound(ast::CompoundCommand::Arithmetic(arithmetic), _) =
+            &elses[0].condition.as_ref().unwrap().0[0].0.first.seq[0]
+        else {
+            panic!("expected arithmetic command");
+        };
+        assert_eq!(text(input, &arithmetic.expr), Some("1 +  2"));
+        assert_eq!(arithmetic.expr.value, "1 +  2");
+
+        let ast::Command::Compound(ast::CompoundCommand::CaseClause(case), _) =
+            &program.complete_commands[2].0[0].0.first.seq[0]
+        else {
+            panic!("expected case clause");
+        };
+        assert_eq!(text(input, case), Some("case $x in a) ;; esac"));
+        assert_eq!(text(input, &case.cases[0]), Some("a) ;;"));
+
+        Ok(())
+    }
+
+    fn line_parser<'a>(input: &'a str, aliases: &[(&str, &str)]) -> Parser<&'a [u8]> {
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        parser.set_aliases(
+            aliases
+                .iter()
+                .map(|(name, value)| ((*name).to_owned(), (*value).to_owned()))
+                .collect(),
+        );
+        parser
+    }
+
+    fn parse_lines(input: &str, aliases: &[(&str, &str)]) -> Result<Vec<String>> {
+        let mut parser = line_parser(input, aliases);
+
+        let mut lines = vec![];
+        while let Some(program) = parser.parse_next_line()? {
+            lines.push(program.to_string().trim_end().to_owned());
+        }
+        Ok(lines)
+    }
+
+    #[test]
+    fn parse_next_line_splits_input_into_lines() -> Result<()> {
+        let input = "\n\necho a; echo b\nif true; then\n  echo c\nfi\n\necho d |\n  cat\n";
+
+        for aliases in [&[][..], &[("x", "echo x")][..]] {
+            let lines = parse_lines(input, aliases)?;
+            assert_eq!(lines.len(), 3);
+            assert!(lines[0].starts_with("echo a"));
+            assert!(lines[1].starts_with("if true"));
+            assert!(lines[2].starts_with("echo d"));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_next_line_expands_aliases_per_line() -> Result<()> {
+        // The second command of the alias's value is incomplete until the next line.
+        let lines = parse_lines(
+            "x\necho b\nfi\ny\n",
+            &[("x", "echo a\nif true; then"), ("y", "echo y")],
+        )?;
+
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("echo a"));
+        assert!(lines[0].contains("echo b"));
+        assert!(lines[1].starts_with("echo y"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_next_line_reports_errors_after_preceding_lines() -> Result<()> {
+        for aliases in [&[][..], &[("x", "echo x")][..]] {
+            let mut parser = line_parser("echo a\necho b )\necho c\n", aliases);
+            assert!(parser.parse_next_line()?.is_some());
+            assert!(parser.parse_next_line().is_err());
+            assert!(parser.parse_next_line()?.is_none());
+
+            let mut parser = line_parser("echo a\necho 'b\n", aliases);
+            assert!(parser.parse_next_line()?.is_some());
+            assert!(matches!(
+                parser.parse_next_line(),
+                Err(error::ParseError::Tokenizing { .. })
+            ));
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_next_line_scales_with_command_length() -> Result<()> {
+        // A long multi-line command must not be re-parsed from its start on each line.
+        let body: String = (0..5000).map(|i| std::format!("  echo {i}\n")).collect();
+        let input = std::format!("f() {{\n{body}}}\necho done\n");
+
+        for aliases in [&[][..], &[("echo", "echo")][..]] {
+            let started = std::time::Instant::now();
+            let lines = parse_lines(&input, aliases)?;
+            assert_eq!(lines.len(), 2);
+            assert!(
+                started.elapsed() < std::time::Duration::from_secs(10),
+                "took {:?}",
+                started.elapsed()
+            );
+        }
+
+        Ok(())
+    }
 }
@@ -87,6 +87,17 @@ pub fn parse_key_sequence_readline_binding(
         .map_err(|_err| error::BindingParseError::Unknown(input.to_owned()))
 }
 
+/// Parses a key sequence on its own, e.g. as given to `bind -r` or as the body of a
+/// readline macro. The sequence may optionally be enclosed in double quotes.
+///
+/// # Arguments
+///
+/// * `input` - The input string to parse
+pub fn parse_key_sequence(input: &str) -> Result<KeySequence, error::BindingParseError> {
+    readline_binding::standalone_key_sequence(input)
+        .map_err(|_err| error::BindingParseError::Unknown(input.to_owned()))
+}
+
 /// Converts a `KeySequence` to a vector of `KeyStroke`.
 ///
 /// # Arguments
@@ -124,21 +135,75 @@ pub fn key_sequence_to_strokes(
     Ok(strokes)
 }
 
+/// Translates a symbolic key name (as used in inputrc files) to its key code.
+fn symbolic_key_name(name: &str) -> Option<u8> {
+    match name.to_ascii_lowercase().as_str() {
+        "del" | "rubout" => Some(b'\x7f'),
+        "esc" | "escape" => Some(b'\x1b'),
+        "lfd" | "newline" => Some(b'\n'),
+        "ret" | "return" => Some(b'\r'),
+        "spc" | "space" => Some(b' '),
+        "tab" => Some(b'\t'),
+        _ => None,
+    }
+}
+
 peg::parser! {
     grammar readline_binding() for str {
         rule _() = [' ' | '\t' | '\n']*
 
         pub rule key_sequence_shell_cmd_binding() -> KeySequenceShellCommandBinding =
-            _ "\"" seq:key_sequence() "\"" _ ":" _ cmd:shell_cmd() _ { KeySequenceShellCommandBinding { seq, shell_cmd: cmd } }
+            _ seq:binding_key() _ ":" _ cmd:shell_cmd() _ { KeySequenceShellCommandBinding { seq, shell_cmd: cmd } }
 
         pub rule key_sequence_readline_binding() -> KeySequenceReadlineBinding =
-            _ "\"" seq:key_sequence() "\"" _ ":" _ "\"" cmd:readline_cmd() "\"" _ {
+            _ seq:binding_key() _ ":" _ "\"" cmd:readline_cmd() "\"" _ {
                 KeySequenceReadlineBinding { seq, target: ReadlineTarget::Command(cmd) }
             } /
-            _ "\"" seq:key_sequence() "\"" _ ":" _ func:readline_function() _ {
+            _ seq:binding_key() _ ":" _ func:readline_function() _ {
                 KeySequenceReadlineBinding { seq, target: ReadlineTarget::Function(func) }
             }
 
+        pub rule standalone_key_sequence() -> KeySequence =
+            _ "\"" seq:key_sequence() "\"" _ { seq } /
+            _ seq:unquoted_key_sequence() _ { seq }
+
+        // The key in a binding may be given either as a quoted key sequence or as a
+        // key name (e.g., `Control-u`).
+        rule binding_key() -> KeySequence =
+            "\"" seq:key_sequence() "\"" { seq } /
+            key_name()
+
+        rule unquoted_key_sequence() -> KeySequence =
+            items:unquoted_key_sequence_item()+ { KeySequence(items) }
+
+        rule unquoted_key_sequence_item() -> KeySequenceItem =
+            escaped_key_sequence_item() /
+            [c if c != '"' && !c.is_whitespace()] { KeySequenceItem::Byte(c as u8) }
+
+        // Key names, as used in inputrc files: zero or more modifier prefixes
+        // followed by a symbolic key name or a single character.
+        rule key_name() -> KeySequence =
+            modifiers:key_name_modifier()* key:key_name_key() {
+                let mut items = modifiers;
+                items.push(key);
+                KeySequence(items)
+            }
+
+        rule key_name_modifier() -> KeySequenceItem =
+            i("control-") { KeySequenceItem::Control } /
+            i("c-") { KeySequenceItem::Control } /
+            i("meta-") { KeySequenceItem::Meta } /
+            i("m-") { KeySequenceItem::Meta }
+
+        rule key_name_key() -> KeySequenceItem =
+            name:$(['a'..='z' | 'A'..='Z']*<2,>) {?
+                symbolic_key_name(name).map(KeySequenceItem::Byte).ok_or("key name")
+            } /
+            [c if c != ':' && c != '"' && !c.is_whitespace()] { KeySequenceItem::Byte(c as u8) }
+
+        rule i(literal: &'static str) =
+            input:$([_]*<{literal.len()}>) {? if input.eq_ignore_ascii_case(literal) { Ok(()) } else { Err(literal) } }
+
         rule readline_cmd() -> String = s:$([^'"']*) { s.to_string() }
         rule shell_cmd() -> String = s:$([_]*) { s.to_string() }
         rule readline_function() -> String = s:$([_]*) { s.to_string() }
@@ -148,6 +213,10 @@ peg::parser! {
             items:key_sequence_item()* { KeySequence(items) }
 
         rule key_sequence_item() -> KeySequenceItem =
+            escaped_key_sequence_item() /
+            [c if c != '"'] { KeySequenceItem::Byte(c as u8) }
+
+        rule escaped_key_sequence_item() -> KeySequenceItem =
             "\\C-" { KeySequenceItem::Control } /
             "\\M-" { KeySequenceItem::Meta } /
             "\\e" { KeySequenceItem::Byte(b'\x1b') } /
@@ -163,8 +232,7 @@ peg::parser! {
             "\\t" { KeySequenceItem::Byte(b'\t') } /
             "\\v" { KeySequenceItem::Byte(b'\x0b') } /
             "\\" n:octal_number() { KeySequenceItem::Byte(n) } /
-            "\\" n:hex_number() { KeySequenceItem::Byte(n) } /
-            [c if c != '"'] { KeySequenceItem::Byte(c as u8) }
+            "\\" n:hex_number() { KeySequenceItem::Byte(n) }
 
         rule octal_number() -> u8 =
             s:$(['0'..='7']*<1,3>) {? u8::from_str_radix(s, 8).or(Err("invalid octal number")) }
@@ -218,4 +286,40 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_key_name_binding_parse() -> Result<()> {
+        let binding = parse_key_sequence_readline_binding("Control-u: universal-argument")?;
+        assert_eq!(
+            binding.seq.0,
+            [KeySequenceItem::Control, KeySequenceItem::Byte(b'u')]
+        );
+        assert_eq!(
+            binding.target,
+            ReadlineTarget::Function("universal-argument".to_string())
+        );
+
+        let binding = parse_key_sequence_readline_binding("Meta-Rubout: backward-kill-word")?;
+        assert_eq!(
+            binding.seq.0,
+            [KeySequenceItem::Meta, KeySequenceItem::Byte(0x7f)]
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_standalone_key_sequence_parse() -> Result<()> {
+        let seq = parse_key_sequence(r#""\C-xa""#)?;
+        assert_eq!(
+            seq.0,
+            [
+                KeySequenceItem::Control,
+                KeySequenceItem::Byte(b'x'),
+                KeySequenceItem::Byte(b'a')
+            ]
+        );
+
+        Ok(())
+    }
 }
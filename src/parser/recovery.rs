@@ -0,0 +1,701 @@
+//! Implements parsing with error recovery.
+//!
+//! When the parser fails, the input is split into the commands that precede the error
+//! (which are parsed as usual) and the command containing the error, which ends at the
+//! next command separator outside of any compound command. The latter is retained in
+//! the AST as an error node, and parsing resumes after it. To report as many errors as
+//! possible, the failing command is then re-parsed repeatedly, each time replacing the
+//! innermost command that failed to parse with a placeholder.
+
+use std::sync::Arc;
+
+use super::ast;
+use super::error::ParseError;
+use super::parse::{ParserOptions, SourceInfo, parse_program_tokens};
+use super::{SourcePosition, Token, TokenLocation};
+
+/// Parses a sequence of tokens into the abstract syntax tree (AST) of a shell program,
+/// recovering from syntax errors. Returns a best-effort program, in which each command
+/// that could not be parsed is represented by an [`ast::Command::Error`] node, along
+/// with all syntax errors encountered.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens to parse.
+/// * `options` - The options to use when parsing.
+/// * `source_info` - Information about the source of the tokens.
+pub fn parse_tokens_with_recovery(
+    tokens: &[Token],
+    options: &ParserOptions,
+    source_info: &SourceInfo,
+) -> (ast::Program, Vec<ParseError>) {
+    let mut recovery = Recovery::new(options, source_info);
+    recovery.parse(tokens);
+    recovery.finish()
+}
+
+/// Parses the tokens produced before tokenizing failed, recovering from syntax errors.
+/// The command during which tokenizing failed is retained as an error node, with the
+/// tokenizer error reported after any syntax errors in the preceding commands.
+///
+/// # Arguments
+///
+/// * `tokens` - The tokens produced before the failure.
+/// * `tokenizer_error` - The error that stopped tokenizing.
+/// * `options` - The options to use when parsing.
+/// * `source_info` - Information about the source of the tokens.
+pub(crate) fn parse_partial_tokens_with_recovery(
+    tokens: &[Token],
+    tokenizer_error: ParseError,
+    options: &ParserOptions,
+    source_info: &SourceInfo,
+) -> (ast::Program, Vec<ParseError>) {
+    let mut recovery = Recovery::new(options, source_info);
+
+    // The last command is incomplete, so only parse the commands preceding it.
+    let mut scanner = NestingScanner::new();
+    let complete_len = tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| scanner.scan(token) && scanner.depth() == 0)
+        .map(|(i, _)| i + 1)
+        .last()
+        .unwrap_or(0);
+
+    recovery.parse(&tokens[..complete_len]);
+
+    let incomplete = skip_newlines(&tokens[complete_len..]);
+    if let Some(first) = incomplete.first() {
+        let end = match &tokenizer_error {
+            ParseError::Tokenizing {
+                position: Some(position),
+                ..
+            } => Arc::new(position.clone()),
+            _ => incomplete[incomplete.len() - 1].location().end.clone(),
+        };
+
+        recovery.push_error_command(
+            incomplete,
+            TokenLocation {
+                start: first.location().start.clone(),
+                end,
+            },
+            ast::SeparatorOperator::Sequence,
+        );
+    }
+
+    recovery.errors.push(tokenizer_error);
+    recovery.finish()
+}
+
+struct Recovery<'a> {
+    options: &'a ParserOptions,
+    source_info: &'a SourceInfo,
+    complete_commands: Vec<ast::CompleteCommand>,
+    errors: Vec<ParseError>,
+}
+
+impl<'a> Recovery<'a> {
+    const fn new(options: &'a ParserOptions, source_info: &'a SourceInfo) -> Self {
+        Self {
+            options,
+            source_info,
+            complete_commands: vec![],
+            errors: vec![],
+        }
+    }
+
+    /// Parses the tokens as a program, returning the index of the token at which parsing
+    /// failed on error.
+    fn parse_tokens(&self, tokens: &[Token]) -> Result<ast::Program, usize> {
+        parse_program_tokens(tokens, self.options, self.source_info).map_err(|err| {
+            // The parser may have backtracked past the actual error.
+            first_misplaced_parenthesis(tokens).map_or(err.location, |i| i.min(err.location))
+        })
+    }
+
+    fn finish(self) -> (ast::Program, Vec<ParseError>) {
+        (
+            ast::Program {
+                complete_commands: self.complete_commands,
+            },
+            self.errors,
+        )
+    }
+
+    fn parse(&mut self, mut tokens: &[Token]) {
+        while !tokens.is_empty() {
+            let failed_at = match self.parse_tokens(tokens) {
+                Ok(program) => {
+                    self.complete_commands.extend(program.complete_commands);
+                    return;
+                }
+                Err(failed_at) => failed_at,
+            };
+
+            // Parse as many commands as possible before the failing one.
+            let boundaries = command_boundaries(tokens);
+            let resume_at = boundaries
+                .iter()
+                .rev()
+                .filter(|boundary| **boundary <= failed_at)
+                .find_map(|boundary| {
+                    self.parse_tokens(&tokens[..*boundary])
+                        .ok()
+                        .map(|program| (*boundary, program))
+                })
+                .map_or(0, |(boundary, program)| {
+                    self.complete_commands.extend(program.complete_commands);
+                    boundary
+                });
+
+            // The failing command extends to the next separator after the error.
+            let end = boundaries
+                .iter()
+                .copied()
+                .find(|boundary| *boundary > failed_at.max(resume_at))
+                .unwrap_or(tokens.len());
+
+            let failed = &tokens[resume_at..end];
+            self.record_errors(failed, failed_at - resume_at);
+
+            let (failed, separator) = split_separator(failed);
+            let failed = skip_newlines(failed);
+            if let (Some(first), Some(last)) = (failed.first(), failed.last()) {
+                let loc = TokenLocation::within(first.location(), last.location());
+                self.push_error_command(failed, loc, separator);
+            }
+
+            tokens = &tokens[end..];
+        }
+    }
+
+    /// Records the errors in a command that failed to parse, by repeatedly replacing the
+    /// innermost failing command with a placeholder and parsing it again.
+    fn record_errors(&mut self, tokens: &[Token], mut failed_at: usize) {
+        let mut tokens = tokens.to_vec();
+
+        // Each placeholder replaces at least one token, bounding the number of attempts.
+        for _ in 0..tokens.len() {
+            let Some((start, end)) = innermost_command(&tokens, failed_at) else {
+                // The input ended without any command being left open; report all of it.
+                self.push_parse_error(&tokens, failed_at, 0, tokens.len());
+                return;
+            };
+
+            self.push_parse_error(&tokens, failed_at, start, end.max(failed_at + 1));
+
+            // Replace the failing command with a placeholder, taking care that it is
+            // still separated from the token that followed it.
+            let loc = TokenLocation::within(
+                tokens[start].location(),
+                tokens[end.max(start + 1) - 1].location(),
+            );
+            let mut placeholder = vec![Token::Word(String::from(":"), loc.clone())];
+            if tokens.get(end).is_some_and(|token| !is_separator(token)) {
+                placeholder.push(Token::Operator(String::from(";"), loc));
+            }
+            let placeholder_end = start + placeholder.len();
+            tokens.splice(start..end, placeholder);
+
+            match self.parse_tokens(&tokens) {
+                Ok(_) => return,
+                // Stop if the placeholder didn't let parsing get any further.
+                Err(location) if location < placeholder_end => return,
+                Err(location) => failed_at = location,
+            }
+        }
+    }
+
+    fn push_parse_error(&mut self, tokens: &[Token], failed_at: usize, start: usize, end: usize) {
+        let span = if start < end.min(tokens.len()) {
+            TokenLocation::within(
+                tokens[start].location(),
+                tokens[end.min(tokens.len()) - 1].location(),
+            )
+        } else if let Some(last) = tokens.last() {
+            TokenLocation {
+                start: last.location().end.clone(),
+                end: last.location().end.clone(),
+            }
+        } else {
+            let position = Arc::new(SourcePosition::default());
+            TokenLocation {
+                start: position.clone(),
+                end: position,
+            }
+        };
+
+        self.errors.push(ParseError::ParsingWithinSpan {
+            token: tokens.get(failed_at).cloned(),
+            span,
+        });
+    }
+
+    fn push_error_command(
+        &mut self,
+        tokens: &[Token],
+        loc: TokenLocation,
+        separator: ast::SeparatorOperator,
+    ) {
+        let command = ast::Command::Error(ast::ErrorCommand {
+            tokens: tokens.to_vec(),
+            loc: loc.clone(),
+        });
+
+        let and_or = ast::AndOrList {
+            first: ast::Pipeline {
+                timed: None,
+                bang: false,
+                seq: vec![command],
+                loc,
+            },
+            additional: vec![],
+        };
+
+        self.complete_commands
+            .push(ast::CompoundList(vec![ast::CompoundListItem(
+                and_or, separator,
+            )]));
+    }
+}
+
+/// Returns the indices just past each separator that ends a command outside of any
+/// compound command.
+fn command_boundaries(tokens: &[Token]) -> Vec<usize> {
+    let mut scanner = NestingScanner::new();
+
+    tokens
+        .iter()
+        .enumerate()
+        .filter(|(_, token)| scanner.scan(token) && scanner.depth() == 0)
+        .map(|(i, _)| i + 1)
+        .collect()
+}
+
+/// Finds the innermost command containing the given token, returning the range of
+/// tokens from its start up to (but not including) the separator, keyword or closing
+/// delimiter that ends it. If the token itself doesn't belong in any command (e.g., an
+/// unexpected separator), the range covers just that token.
+fn innermost_command(tokens: &[Token], failed_at: usize) -> Option<(usize, usize)> {
+    let mut scanner = NestingScanner::new();
+
+    // Track the start of the current command at each level of nesting.
+    let mut starts: Vec<Option<usize>> = vec![None];
+
+    for (i, token) in tokens.iter().enumerate().take(failed_at) {
+        let depth = scanner.depth();
+        let was_at_command_start = scanner.at_command_start;
+        let ends_command = scanner.scan(token);
+
+        // A compound command is a command within the enclosing level.
+        if scanner.depth() > depth {
+            starts[depth].get_or_insert(i);
+        }
+
+        starts.resize(scanner.depth() + 1, None);
+        if ends_command {
+            starts[scanner.depth()] = None;
+        } else if was_at_command_start && !scanner.at_command_start && !scanner.started_keyword {
+            starts[scanner.depth()] = Some(i);
+        }
+    }
+
+    // Look for the innermost command that's still open.
+    let open_command = starts
+        .iter()
+        .enumerate()
+        .rev()
+        .find_map(|(depth, start)| start.map(|start| (start, depth)));
+
+    let (start, depth) = match open_command {
+        Some(open_command) => open_command,
+        None if failed_at < tokens.len() => {
+            let depth = scanner.depth();
+            if scanner.scan(&tokens[failed_at]) || scanner.depth() < depth {
+                return Some((failed_at, failed_at + 1));
+            }
+            (failed_at, depth)
+        }
+        None => return None,
+    };
+
+    // Find where the command ends.
+    let mut scanner = NestingScanner::new();
+    for (i, token) in tokens.iter().enumerate() {
+        let depth_before = scanner.depth();
+        let was_at_command_start = scanner.at_command_start;
+        let ends_command = scanner.scan(token);
+
+        if i <= start || i < failed_at {
+            continue;
+        }
+
+        if (ends_command && scanner.depth() == depth)
+            || scanner.depth() < depth
+            || (depth_before == depth && was_at_command_start && scanner.started_keyword)
+        {
+            return Some((start, i));
+        }
+    }
+
+    Some((start, tokens.len()))
+}
+
+/// Returns the index of the first opening parenthesis that can't be valid where it
+/// appears: following a command word, without being part of a function definition
+/// or array assignment.
+fn first_misplaced_parenthesis(tokens: &[Token]) -> Option<usize> {
+    let mut scanner = NestingScanner::new();
+
+    for (i, token) in tokens.iter().enumerate() {
+        if matches!(token, Token::Operator(op, _) if op == "(")
+            && !scanner.at_command_start
+            && !scanner.in_case_patterns
+            && !scanner.in_extended_test()
+            && i > 0
+            && matches!(&tokens[i - 1], Token::Word(w, _) if !w.ends_with('='))
+            && !matches!(tokens.get(i + 1), Some(Token::Operator(op, _)) if op == ")")
+        {
+            return Some(i);
+        }
+
+        scanner.scan(token);
+    }
+
+    None
+}
+
+fn is_separator(token: &Token) -> bool {
+    matches!(token, Token::Operator(op, _) if matches!(op.as_str(), ";" | "&" | "\n"))
+}
+
+fn split_separator(tokens: &[Token]) -> (&[Token], ast::SeparatorOperator) {
+    match tokens.split_last() {
+        Some((Token::Operator(op, _), rest)) if op == "&" => (rest, ast::SeparatorOperator::Async),
+        Some((last, rest)) if is_separator(last) => (rest, ast::SeparatorOperator::Sequence),
+        _ => (tokens, ast::SeparatorOperator::Sequence),
+    }
+}
+
+fn skip_newlines(tokens: &[Token]) -> &[Token] {
+    let start = tokens
+        .iter()
+        .position(|token| !matches!(token, Token::Operator(op, _) if op == "\n"))
+        .unwrap_or(tokens.len());
+
+    &tokens[start..]
+}
+
+/// Tracks the nesting of compound commands while scanning tokens. This approximates
+/// the grammar closely enough to find where commands start and end, even in input
+/// that the parser rejects.
+struct NestingScanner {
+    /// The keyword or delimiter expected to close each compound command still open.
+    expected_closers: Vec<&'static str>,
+    /// Whether the next word is in a position where reserved words are recognized.
+    at_command_start: bool,
+    /// Whether the last word scanned was a reserved word.
+    started_keyword: bool,
+    /// Whether the last operator scanned requires the command to continue.
+    after_continuation: bool,
+    /// Whether the next word is the name of a function being defined.
+    expecting_function_name: bool,
+    /// Whether the `in` of a case command is expected.
+    expecting_case_in: bool,
+    /// Whether case patterns are being scanned.
+    in_case_patterns: bool,
+}
+
+impl NestingScanner {
+    const fn new() -> Self {
+        Self {
+            expected_closers: vec![],
+            at_command_start: true,
+            started_keyword: false,
+            after_continuation: false,
+            expecting_function_name: false,
+            expecting_case_in: false,
+            in_case_patterns: false,
+        }
+    }
+
+    fn depth(&self) -> usize {
+        self.expected_closers.len()
+    }
+
+    fn in_extended_test(&self) -> bool {
+        self.expected_closers.last() == Some(&"]]")
+    }
+
+    /// Scans the given token, returning whether it's a separator ending a command.
+    fn scan(&mut self, token: &Token) -> bool {
+        let after_continuation = std::mem::take(&mut self.after_continuation);
+        self.started_keyword = false;
+
+        // Extended test expressions may contain operators with other meanings.
+        if self.in_extended_test() {
+            if matches!(token, Token::Word(w, _) if w == "]]") {
+                self.expected_closers.pop();
+                self.at_command_start = false;
+            }
+            return false;
+        }
+
+        match token {
+            Token::Operator(op, _) => self.scan_operator(op, after_continuation),
+            Token::Word(w, _) => {
+                self.scan_word(w);
+                false
+            }
+        }
+    }
+
+    fn scan_operator(&mut self, op: &str, after_continuation: bool) -> bool {
+        match op {
+            ";" | "&" | "\n" => {
+                self.at_command_start = true;
+                return !(op == "\n" && after_continuation);
+            }
+            ";;" | ";&" | ";;&" => {
+                // Ends the current case item, including anything left open within it.
+                if let Some(index) = self.expected_closers.iter().rposition(|c| *c == "esac") {
+                    self.expected_closers.truncate(index + 1);
+                    self.in_case_patterns = true;
+                    self.at_command_start = false;
+                }
+                return true;
+            }
+            "&&" | "||" | "|" | "|&" => {
+                self.at_command_start = true;
+                self.after_continuation = true;
+            }
+            "(" => {
+                // Only a subshell (or arithmetic command) opens a compound command; a
+                // parenthesis elsewhere belongs to a function definition, an array or a
+                // case pattern.
+                if self.at_command_start && !self.in_case_patterns {
+                    self.started_keyword = true;
+                    self.expected_closers.push(")");
+                }
+                self.at_command_start = true;
+            }
+            ")" => {
+                if self.in_case_patterns {
+                    self.in_case_patterns = false;
+                } else {
+                    self.close(")");
+                }
+                self.at_command_start = true;
+            }
+            // Redirection operators are followed by a filename (or here-document tag).
+            _ => self.at_command_start = false,
+        }
+
+        false
+    }
+
+    fn scan_word(&mut self, word: &str) {
+        if self.in_case_patterns {
+            if word == "esac" {
+                self.in_case_patterns = false;
+                self.close("esac");
+            }
+            self.at_command_start = false;
+            return;
+        }
+
+        if !self.at_command_start {
+            if std::mem::take(&mut self.expecting_case_in) && word == "in" {
+                self.in_case_patterns = true;
+            }
+            if std::mem::take(&mut self.expecting_function_name) {
+                self.at_command_start = true;
+            }
+            return;
+        }
+
+        self.started_keyword = true;
+        self.at_command_start = match word {
+            "if" => self.open("fi"),
+            "while" | "until" => self.open("done"),
+            "{" => self.open("}"),
+            "[[" => self.open("]]"),
+            "for" | "select" => {
+                self.open("done");
+                false
+            }
+            "case" => {
+                self.open("esac");
+                self.expecting_case_in = true;
+                false
+            }
+            "function" => {
+                self.expecting_function_name = true;
+                false
+            }
+            "then" | "do" | "else" | "elif" | "!" | "time" => true,
+            "fi" | "done" | "esac" | "}" => {
+                // An unmatched closing keyword is treated like any other word.
+                self.started_keyword = self.close(word);
+                false
+            }
+            _ => {
+                self.started_keyword = false;
+                false
+            }
+        };
+    }
+
+    /// Opens a compound command closed by the given keyword or delimiter. Returns true,
+    /// as a command may follow.
+    fn open(&mut self, closer: &'static str) -> bool {
+        self.expected_closers.push(closer);
+        true
+    }
+
+    /// Closes the innermost compound command closed by the given keyword or delimiter,
+    /// along with any left open within it. Returns whether there was one to close.
+    fn close(&mut self, closer: &str) -> bool {
+        if let Some(index) = self.expected_closers.iter().rposition(|c| *c == closer) {
+            self.expected_closers.truncate(index);
+            true
+        } else {
+            false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::Parser;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn recover(input: &str) -> (ast::Program, Vec<ParseError>) {
+        let mut parser = Parser::new(
+            std::io::BufReader::new(input.as_bytes()),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        parser.parse_program_with_recovery()
+    }
+
+    fn error_lines(errors: &[ParseError]) -> Vec<(usize, usize)> {
+        errors
+            .iter()
+            .filter_map(ParseError::location)
+            .map(|loc| (loc.start.line, loc.end.line))
+            .collect()
+    }
+
+    fn is_error_command(command: &ast::CompleteCommand) -> bool {
+        matches!(
+            command.0.as_slice(),
+            [ast::CompoundListItem(ast::AndOrList { first, .. }, _)]
+                if matches!(first.seq.as_slice(), [ast::Command::Error(_)])
+        )
+    }
+
+    #[test]
+    fn valid_input_has_no_errors() -> Result<()> {
+        let input = "for f in a b; do\n  echo \"$f\" | (cat)\ndone\nx=(1 2)\n";
+
+        let (program, errors) = recover(input);
+        assert!(errors.is_empty());
+
+        let expected = Parser::new(
+            std::io::BufReader::new(input.as_bytes()),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        )
+        .parse_program()?;
+        assert_eq!(program.to_string(), expected.to_string());
+
+        Ok(())
+    }
+
+    #[test]
+    fn recovers_at_command_separators() {
+        let (program, errors) = recover("echo a\nif true; then echo (; fi\necho ok\n");
+
+        assert_eq!(program.complete_commands.len(), 3);
+        assert!(!is_error_command(&program.complete_commands[0]));
+        assert!(is_error_command(&program.complete_commands[1]));
+        assert_eq!(program.complete_commands[2].to_string(), "echo ok");
+
+        assert_eq!(errors.len(), 1);
+        let span = errors[0].location().unwrap();
+        assert_eq!((span.start.line, span.start.column), (2, 15));
+        assert!(matches!(
+            &errors[0],
+            ParseError::ParsingWithinSpan { token: Some(token), .. } if token.to_str() == "("
+        ));
+    }
+
+    #[test]
+    fn reports_all_errors() {
+        let input = r#"f() {
+  echo (
+}
+g() {
+  cat )
+}
+h() {
+  if; then :; fi
+  echo ok; done
+}
+case x in a) echo (;; b) echo ) ;; esac
+echo end
+"#;
+
+        let (program, errors) = recover(input);
+
+        assert_eq!(
+            error_lines(&errors),
+            vec![(2, 2), (5, 5), (8, 8), (9, 10), (11, 11), (11, 11)]
+        );
+        assert_eq!(
+            program.complete_commands.last().map(ToString::to_string),
+            Some(String::from("echo end"))
+        );
+    }
+
+    #[test]
+    fn reports_unterminated_compound_commands() {
+        let (program, errors) = recover("echo a\nwhile true; do\n  echo b\n");
+
+        assert_eq!(program.complete_commands.len(), 2);
+        assert!(is_error_command(&program.complete_commands[1]));
+        assert_eq!(error_lines(&errors), vec![(2, 4)]);
+        assert!(matches!(
+            &errors[0],
+            ParseError::ParsingWithinSpan { token: None, .. }
+        ));
+    }
+
+    #[test]
+    fn reports_tokenizer_errors() {
+        let (program, errors) = recover("echo a; echo (\necho \"b");
+
+        assert_eq!(program.complete_commands.len(), 3);
+        assert!(is_error_command(&program.complete_commands[2]));
+
+        assert_eq!(errors.len(), 2);
+        assert!(matches!(&errors[0], ParseError::ParsingWithinSpan { .. }));
+        assert!(matches!(&errors[1], ParseError::Tokenizing { .. }));
+    }
+
+    #[cfg(feature = "diagnostics")]
+    #[test]
+    fn renders_all_errors() {
+        use miette::Diagnostic;
+
+        let input = "echo (\ncat )\n";
+        let (_, errors) = recover(input);
+
+        let report = ParseError::to_pretty_errors(errors, input);
+        assert_eq!(report.labels().map(Iterator::count), Some(2));
+    }
+}
@@ -19,9 +19,8 @@ struct TestCase {
 }
 
 #[test]
-#[ignore = "not yet ready for default-enablement"]
 fn test_parser_using_yaml_test_cases() {
-    insta::glob!("../../brush-shell", "tests/cases/**/*.yaml", |path| {
+    insta::glob!("../../tests", "cases/**/*.yaml", |path| {
         test_parser_using_yaml(path).unwrap();
     });
 }
@@ -90,7 +89,9 @@ struct ParseSummary<'a> {
 
 #[cfg_attr(test, derive(serde::Serialize))]
 enum ParseResult {
-    Success(super::ast::Program),
+    /// The lines of the parsed program, as canonically formatted.
+    Success(Vec<String>),
+    /// The error that parsing failed with.
     Failure(String),
 }
 
@@ -121,8 +122,16 @@ fn parse_script_content(s: &str) -> ParseSummary<'_> {
         }
     };
 
+    // The formatted program keeps the snapshots compact, while still reflecting how the
+    // input was parsed.
+    let formatted = super::format::format_program(
+        &parsed_program,
+        &[],
+        &super::format::FormatOptions::default(),
+    );
+
     ParseSummary {
         input: input_lines,
-        result: ParseResult::Success(parsed_program),
+        result: ParseResult::Success(formatted.lines().map(String::from).collect()),
     }
 }
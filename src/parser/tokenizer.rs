@@ -30,7 +30,11 @@ pub(crate) enum TokenEndReason {
 /// Represents a position in a source shell script.
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Pos"))]
 pub struct SourcePosition {
     /// The 0-based index of the character in the input stream.
@@ -43,6 +47,29 @@ pub struct SourcePosition {
     pub column: usize,
 }
 
+impl SourcePosition {
+    /// Returns the position reached by advancing over the given text, which is assumed
+    /// to start at this position.
+    ///
+    /// # Arguments
+    ///
+    /// * `text` - The text to advance over.
+    pub fn advanced_by(&self, text: &str) -> Self {
+        let mut position = self.clone();
+        for c in text.chars() {
+            if c == '\n' {
+                position.line += 1;
+                position.column = 1;
+            } else {
+                position.column += 1;
+            }
+            position.index += 1;
+        }
+
+        position
+    }
+}
+
 impl Display for SourcePosition {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         f.write_fmt(format_args!("line {} col {}", self.line, self.column))
@@ -60,7 +87,11 @@ impl From<&SourcePosition> for miette::SourceOffset {
 /// Represents the location of a token in its source shell script.
 #[derive(Clone, Default, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 #[cfg_attr(test, serde(rename = "Loc"))]
 pub struct TokenLocation {
     /// The start position of the token.
@@ -80,12 +111,49 @@ impl TokenLocation {
             end: end.end.clone(),
         }
     }
+
+    /// Returns the location of a byte range within a fragment of source text, such as
+    /// the text of a word or an arithmetic expression.
+    ///
+    /// # Arguments
+    ///
+    /// * `origin` - The position in the enclosing source at which `text` starts.
+    /// * `text` - The fragment of source text.
+    /// * `range` - The byte range within `text`.
+    pub fn within_text(origin: &SourcePosition, text: &str, range: std::ops::Range<usize>) -> Self {
+        let start = origin.advanced_by(&text[..range.start]);
+        let end = start.advanced_by(&text[range]);
+
+        Self {
+            start: Arc::new(start),
+            end: Arc::new(end),
+        }
+    }
+}
+
+/// The text of a fragment of source parsed by a sub-parser (e.g., a word or an arithmetic
+/// expression) and where it starts in its source script.
+#[derive(Clone, Copy)]
+pub(crate) struct Origin<'a> {
+    pub text: &'a str,
+    pub start: &'a SourcePosition,
+}
+
+impl Origin<'_> {
+    /// Returns the location of the given byte range of the text.
+    pub fn location(&self, start: usize, end: usize) -> TokenLocation {
+        TokenLocation::within_text(self.start, self.text, start..end)
+    }
 }
 
 /// Represents a token extracted from a shell script.
 #[derive(Clone, Debug)]
 #[cfg_attr(feature = "fuzz-testing", derive(arbitrary::Arbitrary))]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum Token {
     /// An operator token.
     #[cfg_attr(test, serde(rename = "Op"))]
@@ -113,6 +181,21 @@ impl Token {
     }
 }
 
+/// Represents a comment in a shell script. Comments aren't tokens, but can optionally
+/// be retained alongside them (e.g., for reformatting the script).
+#[derive(Clone, Debug)]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
+pub struct Comment {
+    /// The text of the comment, including its leading `#`.
+    pub text: String,
+    /// The location of the comment in the source script.
+    pub loc: TokenLocation,
+}
+
 #[cfg(feature = "diagnostics")]
 impl From<&Token> for miette::SourceSpan {
     fn from(token: &Token) -> Self {
@@ -263,6 +346,10 @@ struct CrossTokenParseState {
     queued_tokens: Vec<TokenizeResult>,
     /// Are we in an arithmetic expansion?
     arithmetic_expansion: bool,
+    /// Position of the start of the current line within a here-document body.
+    here_line_start: SourcePosition,
+    /// Comments encountered so far, if they're being retained.
+    comments: Option<Vec<Comment>>,
 }
 
 /// Options controlling how the tokenizer operates.
@@ -470,10 +557,12 @@ impl TokenParseState {
                     token: None,
                 });
 
-                // Then queue the body document we just finished.
+                // Then queue the body document we just finished; it ends where the line
+                // holding the (end) here-tag starts.
+                let body_end = std::mem::take(&mut cross_token_state.here_line_start);
                 cross_token_state.queued_tokens.push(TokenizeResult {
                     reason,
-                    token: Some(self.pop(&cross_token_state.cursor)),
+                    token: Some(self.pop(&body_end)),
                 });
 
                 // Then queue up the (end) here-tag.
@@ -585,10 +674,27 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                 current_here_tags: vec![],
                 queued_tokens: vec![],
                 arithmetic_expansion: false,
+                here_line_start: SourcePosition::default(),
+                comments: None,
             },
         }
     }
 
+    /// Starts retaining the (top-level) comments encountered in the input, so they can
+    /// later be retrieved with [`Self::take_comments`].
+    pub fn retain_comments(&mut self) {
+        self.cross_state.comments.get_or_insert_default();
+    }
+
+    /// Returns the comments retained so far, in source order.
+    pub fn take_comments(&mut self) -> Vec<Comment> {
+        self.cross_state
+            .comments
+            .as_mut()
+            .map(std::mem::take)
+            .unwrap_or_default()
+    }
+
     #[expect(clippy::unnecessary_wraps)]
     pub fn current_location(&self) -> Option<SourcePosition> {
         Some(self.cross_state.cursor.clone())
@@ -732,9 +838,18 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                 // For now, just include the character in the current token. We also check
                 // if there are leading tabs to be removed.
                 //
+                let at_line_start = !state.started_token() || state.current_token().ends_with('\n');
+                if at_line_start
+                    && self.cross_state.here_line_start.line != self.cross_state.cursor.line
+                {
+                    self.cross_state
+                        .cursor
+                        .clone_into(&mut self.cross_state.here_line_start);
+                }
+
                 if !self.cross_state.current_here_tags.is_empty()
                     && self.cross_state.current_here_tags[0].remove_tabs
-                    && (!state.started_token() || state.current_token().ends_with('\n'))
+                    && at_line_start
                     && c == '\t'
                 {
                     // Consume it but don't include it.
@@ -1151,15 +1266,26 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                         TokenEndReason::NonNewLineBlank,
                         &mut self.cross_state,
                     )?;
-                } else if include_space {
-                    state.append_char(c);
+                    self.consume_char()?;
+
+                    // If the token was held back (e.g., a here-tag), then we keep going with
+                    // the same state; make sure the next token doesn't start at this char.
+                    if result.is_none() {
+                        self.cross_state
+                            .cursor
+                            .clone_into(&mut state.start_position);
+                    }
                 } else {
-                    // Make sure we don't include this char in the token range.
-                    state.start_position.column += 1;
-                    state.start_position.index += 1;
-                }
+                    if include_space {
+                        state.append_char(c);
+                    } else {
+                        // Make sure we don't include this char in the token range.
+                        state.start_position.column += 1;
+                        state.start_position.index += 1;
+                    }
 
-                self.consume_char()?;
+                    self.consume_char()?;
+                }
             }
             //
             // N.B. We need to remember if we were recursively called in a variable
@@ -1171,21 +1297,41 @@ impl<'a, R: ?Sized + std::io::BufRead> Tokenizer<'a, R> {
                 self.consume_char()?;
                 state.append_char(c);
             } else if c == '#' {
+                let comment_start = self.cross_state.cursor.clone();
+                let mut comment_text = String::new();
+
                 // Consume the '#'.
                 self.consume_char()?;
+                comment_text.push(c);
 
                 let mut done = false;
                 while !done {
                     done = match self.peek_char()? {
                         Some('\n') => true,
                         None => true,
-                        _ => {
+                        Some(comment_char) => {
                             // Consume the peeked char; it's part of the comment.
                             self.consume_char()?;
+                            comment_text.push(comment_char);
                             false
                         }
                     };
                 }
+
+                // Only retain comments at the top level; any others are part of the
+                // text of an enclosing token.
+                if terminating_char.is_none()
+                    && let Some(comments) = &mut self.cross_state.comments
+                {
+                    comments.push(Comment {
+                        text: comment_text,
+                        loc: TokenLocation {
+                            start: Arc::new(comment_start),
+                            end: Arc::new(self.cross_state.cursor.clone()),
+                        },
+                    });
+                }
+
                 // Re-start loop as if the comment never happened.
             } else if state.started_token() {
                 // In all other cases where we have an in-progress token, we delimit here.
@@ -1419,6 +1565,26 @@ bc"
         Ok(())
     }
 
+    #[test]
+    fn tokenize_retaining_comments() -> Result<()> {
+        let input = "#!/bin/sh\na # one\nb $(c # nested\n)\n";
+        let mut reader = std::io::BufReader::new(input.as_bytes());
+        let mut tokenizer = Tokenizer::new(&mut reader, &TokenizerOptions::default());
+        tokenizer.retain_comments();
+
+        while !matches!(tokenizer.next_token()?.reason, TokenEndReason::EndOfInput) {}
+
+        let comments = tokenizer.take_comments();
+        let texts: Vec<_> = comments.iter().map(|c| c.text.as_str()).collect();
+        assert_eq!(texts, ["#!/bin/sh", "# one"]);
+
+        let loc = &comments[1].loc;
+        assert_eq!((loc.start.line, loc.start.column), (2, 3));
+        assert_eq!((loc.start.index, loc.end.index), (12, 17));
+
+        Ok(())
+    }
+
     #[test]
     fn tokenize_empty_here_doc() -> Result<()> {
         assert_ron_snapshot!(test_tokenizer(
@@ -0,0 +1,1086 @@
+//! Traversal of shell program ASTs.
+//!
+//! The [`Visit`] trait (and its counterpart [`VisitMut`](super::visit_mut::VisitMut), for
+//! rewriting programs in place) has a method for each type of node in [`ast`], which by
+//! default walks the node's children by calling the corresponding free `walk_*` function.
+//! Implementors override the methods for the nodes they are interested in, calling the
+//! `walk_*` function from the override to continue into the node's children.
+//!
+//! Words, arithmetic expressions and command substitutions are stored in the AST as
+//! unexpanded source text; visitors additionally parse them (with the [`ParserOptions`]
+//! returned by [`Visit::parser_options`], which default to [`ParserOptions::default`]) and
+//! descend into their word pieces, parameter expressions, arithmetic expressions and nested
+//! programs. Text that fails to parse isn't descended into.
+
+use super::ast;
+use super::word::{self, WordPiece, WordPieceWithSource};
+use std::sync::LazyLock;
+
+use super::{Parser, ParserOptions, SourceInfo, arithmetic};
+
+/// The options with which visitors parse text by default.
+pub(super) static DEFAULT_PARSER_OPTIONS: LazyLock<ParserOptions> =
+    LazyLock::new(ParserOptions::default);
+
+/// Defines a visitor trait over the AST, along with its `walk_*` functions.
+///
+/// # Arguments
+///
+/// * `trait` - The name of the trait to define, with its attributes.
+/// * `ref` - The reference type through which nodes are visited (`&` or `&mut`).
+/// * The remaining tokens are additional trait items; they must include the methods for
+///   visiting words and the expressions parsed from them.
+macro_rules! visitor {
+    (
+        $(#[$attr:meta])*
+        trait $trait:ident;
+        ref [$($r:tt)+];
+        $($extra:tt)*
+    ) => {
+        $(#[$attr])*
+        pub trait $trait {
+            /// Returns the options with which to parse words, arithmetic expressions and
+            /// command substitutions.
+            fn parser_options(&self) -> &ParserOptions {
+                &super::visit::DEFAULT_PARSER_OPTIONS
+            }
+
+            /// Visits a program.
+            fn visit_program(&mut self, node: $($r)+ ast::Program) {
+                walk_program(self, node);
+            }
+
+            /// Visits a list of commands.
+            fn visit_compound_list(&mut self, node: $($r)+ ast::CompoundList) {
+                walk_compound_list(self, node);
+            }
+
+            /// Visits an item in a list of commands.
+            fn visit_compound_list_item(&mut self, node: $($r)+ ast::CompoundListItem) {
+                walk_compound_list_item(self, node);
+            }
+
+            /// Visits a sequence of pipelines joined by `&&` and `||`.
+            fn visit_and_or_list(&mut self, node: $($r)+ ast::AndOrList) {
+                walk_and_or_list(self, node);
+            }
+
+            /// Visits a pipeline joined to the preceding one by `&&` or `||`.
+            fn visit_and_or(&mut self, node: $($r)+ ast::AndOr) {
+                walk_and_or(self, node);
+            }
+
+            /// Visits a pipeline.
+            fn visit_pipeline(&mut self, node: $($r)+ ast::Pipeline) {
+                walk_pipeline(self, node);
+            }
+
+            /// Visits a command.
+            fn visit_command(&mut self, node: $($r)+ ast::Command) {
+                walk_command(self, node);
+            }
+
+            /// Visits a compound command.
+            fn visit_compound_command(&mut self, node: $($r)+ ast::CompoundCommand) {
+                walk_compound_command(self, node);
+            }
+
+            /// Visits an arithmetic command (`(( ... ))`).
+            fn visit_arithmetic_command(&mut self, node: $($r)+ ast::ArithmeticCommand) {
+                walk_arithmetic_command(self, node);
+            }
+
+            /// Visits an arithmetic `for` clause.
+            fn visit_arithmetic_for_clause_command(
+                &mut self,
+                node: $($r)+ ast::ArithmeticForClauseCommand,
+            ) {
+                walk_arithmetic_for_clause_command(self, node);
+            }
+
+            /// Visits a brace group.
+            fn visit_brace_group_command(&mut self, node: $($r)+ ast::BraceGroupCommand) {
+                walk_brace_group_command(self, node);
+            }
+
+            /// Visits a subshell, including those of process substitutions.
+            fn visit_subshell_command(&mut self, node: $($r)+ ast::SubshellCommand) {
+                walk_subshell_command(self, node);
+            }
+
+            /// Visits a `for` clause.
+            fn visit_for_clause_command(&mut self, node: $($r)+ ast::ForClauseCommand) {
+                walk_for_clause_command(self, node);
+            }
+
+            /// Visits a `case` clause.
+            fn visit_case_clause_command(&mut self, node: $($r)+ ast::CaseClauseCommand) {
+                walk_case_clause_command(self, node);
+            }
+
+            /// Visits an item in a `case` clause.
+            fn visit_case_item(&mut self, node: $($r)+ ast::CaseItem) {
+                walk_case_item(self, node);
+            }
+
+            /// Visits an `if` clause.
+            fn visit_if_clause_command(&mut self, node: $($r)+ ast::IfClauseCommand) {
+                walk_if_clause_command(self, node);
+            }
+
+            /// Visits an `elif` or `else` clause.
+            fn visit_else_clause(&mut self, node: $($r)+ ast::ElseClause) {
+                walk_else_clause(self, node);
+            }
+
+            /// Visits a `while` or `until` clause.
+            fn visit_while_or_until_clause_command(
+                &mut self,
+                node: $($r)+ ast::WhileOrUntilClauseCommand,
+            ) {
+                walk_while_or_until_clause_command(self, node);
+            }
+
+            /// Visits the `do` group of a loop.
+            fn visit_do_group_command(&mut self, node: $($r)+ ast::DoGroupCommand) {
+                walk_do_group_command(self, node);
+            }
+
+            /// Visits a function definition.
+            fn visit_function_definition(&mut self, node: $($r)+ ast::FunctionDefinition) {
+                walk_function_definition(self, node);
+            }
+
+            /// Visits the body of a function definition.
+            fn visit_function_body(&mut self, node: $($r)+ ast::FunctionBody) {
+                walk_function_body(self, node);
+            }
+
+            /// Visits a simple command.
+            fn visit_simple_command(&mut self, node: $($r)+ ast::SimpleCommand) {
+                walk_simple_command(self, node);
+            }
+
+            /// Visits the prefix of a simple command.
+            fn visit_command_prefix(&mut self, node: $($r)+ ast::CommandPrefix) {
+                walk_command_prefix(self, node);
+            }
+
+            /// Visits the suffix of a simple command.
+            fn visit_command_suffix(&mut self, node: $($r)+ ast::CommandSuffix) {
+                walk_command_suffix(self, node);
+            }
+
+            /// Visits an item in the prefix or suffix of a simple command.
+            fn visit_command_prefix_or_suffix_item(
+                &mut self,
+                node: $($r)+ ast::CommandPrefixOrSuffixItem,
+            ) {
+                walk_command_prefix_or_suffix_item(self, node);
+            }
+
+            /// Visits a variable assignment.
+            fn visit_assignment(&mut self, node: $($r)+ ast::Assignment) {
+                walk_assignment(self, node);
+            }
+
+            /// Visits a list of redirections.
+            fn visit_redirect_list(&mut self, node: $($r)+ ast::RedirectList) {
+                walk_redirect_list(self, node);
+            }
+
+            /// Visits a redirection.
+            fn visit_io_redirect(&mut self, node: $($r)+ ast::IoRedirect) {
+                walk_io_redirect(self, node);
+            }
+
+            /// Visits a here-document. Its body is only visited if it is subject to
+            /// expansion.
+            fn visit_io_here_document(&mut self, node: $($r)+ ast::IoHereDocument) {
+                walk_io_here_document(self, node);
+            }
+
+            /// Visits an extended test command (`[[ ... ]]`).
+            fn visit_extended_test_expr_command(
+                &mut self,
+                node: $($r)+ ast::ExtendedTestExprCommand,
+            ) {
+                walk_extended_test_expr_command(self, node);
+            }
+
+            /// Visits an extended test expression.
+            fn visit_extended_test_expr(&mut self, node: $($r)+ ast::ExtendedTestExpr) {
+                walk_extended_test_expr(self, node);
+            }
+
+            /// Visits a test expression, as parsed from the arguments of `test` or `[`.
+            fn visit_test_expr(&mut self, node: $($r)+ ast::TestExpr) {
+                walk_test_expr(self, node);
+            }
+
+            /// Visits a parsed arithmetic expression.
+            fn visit_arithmetic_expr(&mut self, node: $($r)+ ast::ArithmeticExpr) {
+                walk_arithmetic_expr(self, node);
+            }
+
+            /// Visits the target of an arithmetic assignment or reference.
+            fn visit_arithmetic_target(&mut self, node: $($r)+ ast::ArithmeticTarget) {
+                walk_arithmetic_target(self, node);
+            }
+
+            $($extra)*
+        }
+
+        /// Walks the commands of a program.
+        pub fn walk_program<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::Program) {
+            for list in $($r)+ node.complete_commands {
+                v.visit_compound_list(list);
+            }
+        }
+
+        /// Walks the items of a list of commands.
+        pub fn walk_compound_list<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::CompoundList) {
+            for item in $($r)+ node.0 {
+                v.visit_compound_list_item(item);
+            }
+        }
+
+        /// Walks the and-or list of an item in a list of commands.
+        pub fn walk_compound_list_item<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::CompoundListItem,
+        ) {
+            v.visit_and_or_list($($r)+ node.0);
+        }
+
+        /// Walks the pipelines of an and-or list.
+        pub fn walk_and_or_list<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::AndOrList) {
+            v.visit_pipeline($($r)+ node.first);
+            for and_or in $($r)+ node.additional {
+                v.visit_and_or(and_or);
+            }
+        }
+
+        /// Walks the pipeline of an `&&` or `||`.
+        pub fn walk_and_or<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::AndOr) {
+            match node {
+                ast::AndOr::And(pipeline) | ast::AndOr::Or(pipeline) => v.visit_pipeline(pipeline),
+            }
+        }
+
+        /// Walks the commands of a pipeline.
+        pub fn walk_pipeline<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::Pipeline) {
+            for command in $($r)+ node.seq {
+                v.visit_command(command);
+            }
+        }
+
+        /// Walks a command.
+        pub fn walk_command<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::Command) {
+            match node {
+                ast::Command::Simple(command) => v.visit_simple_command(command),
+                ast::Command::Compound(command, redirects) => {
+                    v.visit_compound_command(command);
+                    if let Some(redirects) = redirects {
+                        v.visit_redirect_list(redirects);
+                    }
+                }
+                ast::Command::Function(function) => v.visit_function_definition(function),
+                ast::Command::ExtendedTest(test) => v.visit_extended_test_expr_command(test),
+                ast::Command::Error(_) => {}
+            }
+        }
+
+        /// Walks a compound command.
+        pub fn walk_compound_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::CompoundCommand,
+        ) {
+            match node {
+                ast::CompoundCommand::Arithmetic(command) => v.visit_arithmetic_command(command),
+                ast::CompoundCommand::ArithmeticForClause(command) => {
+                    v.visit_arithmetic_for_clause_command(command);
+                }
+                ast::CompoundCommand::BraceGroup(command) => v.visit_brace_group_command(command),
+                ast::CompoundCommand::Subshell(command) => v.visit_subshell_command(command),
+                ast::CompoundCommand::ForClause(command) => v.visit_for_clause_command(command),
+                ast::CompoundCommand::CaseClause(command) => v.visit_case_clause_command(command),
+                ast::CompoundCommand::IfClause(command) => v.visit_if_clause_command(command),
+                ast::CompoundCommand::WhileClause(command)
+                | ast::CompoundCommand::UntilClause(command) => {
+                    v.visit_while_or_until_clause_command(command);
+                }
+            }
+        }
+
+        /// Walks the expression of an arithmetic command.
+        pub fn walk_arithmetic_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ArithmeticCommand,
+        ) {
+            v.visit_unexpanded_arithmetic_expr($($r)+ node.expr);
+        }
+
+        /// Walks the expressions and body of an arithmetic `for` clause.
+        pub fn walk_arithmetic_for_clause_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ArithmeticForClauseCommand,
+        ) {
+            if let Some(initializer) = $($r)+ node.initializer {
+                v.visit_unexpanded_arithmetic_expr(initializer);
+            }
+            if let Some(condition) = $($r)+ node.condition {
+                v.visit_unexpanded_arithmetic_expr(condition);
+            }
+            if let Some(updater) = $($r)+ node.updater {
+                v.visit_unexpanded_arithmetic_expr(updater);
+            }
+            v.visit_do_group_command($($r)+ node.body);
+        }
+
+        /// Walks the commands of a brace group.
+        pub fn walk_brace_group_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::BraceGroupCommand,
+        ) {
+            v.visit_compound_list($($r)+ node.list);
+        }
+
+        /// Walks the commands of a subshell.
+        pub fn walk_subshell_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::SubshellCommand,
+        ) {
+            v.visit_compound_list($($r)+ node.list);
+        }
+
+        /// Walks the values and body of a `for` clause.
+        pub fn walk_for_clause_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ForClauseCommand,
+        ) {
+            if let Some(values) = $($r)+ node.values {
+                for value in values {
+                    v.visit_word(value);
+                }
+            }
+            v.visit_do_group_command($($r)+ node.body);
+        }
+
+        /// Walks the value and items of a `case` clause.
+        pub fn walk_case_clause_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::CaseClauseCommand,
+        ) {
+            v.visit_word($($r)+ node.value);
+            for item in $($r)+ node.cases {
+                v.visit_case_item(item);
+            }
+        }
+
+        /// Walks the patterns and commands of a `case` item.
+        pub fn walk_case_item<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::CaseItem) {
+            for pattern in $($r)+ node.patterns {
+                v.visit_word(pattern);
+            }
+            if let Some(cmd) = $($r)+ node.cmd {
+                v.visit_compound_list(cmd);
+            }
+        }
+
+        /// Walks the conditions and branches of an `if` clause.
+        pub fn walk_if_clause_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::IfClauseCommand,
+        ) {
+            v.visit_compound_list($($r)+ node.condition);
+            v.visit_compound_list($($r)+ node.then);
+            if let Some(elses) = $($r)+ node.elses {
+                for else_clause in elses {
+                    v.visit_else_clause(else_clause);
+                }
+            }
+        }
+
+        /// Walks the condition and body of an `elif` or `else` clause.
+        pub fn walk_else_clause<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::ElseClause) {
+            if let Some(condition) = $($r)+ node.condition {
+                v.visit_compound_list(condition);
+            }
+            v.visit_compound_list($($r)+ node.body);
+        }
+
+        /// Walks the condition and body of a `while` or `until` clause.
+        pub fn walk_while_or_until_clause_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::WhileOrUntilClauseCommand,
+        ) {
+            v.visit_compound_list($($r)+ node.0);
+            v.visit_do_group_command($($r)+ node.1);
+        }
+
+        /// Walks the commands of a `do` group.
+        pub fn walk_do_group_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::DoGroupCommand,
+        ) {
+            v.visit_compound_list($($r)+ node.list);
+        }
+
+        /// Walks the name and body of a function definition.
+        pub fn walk_function_definition<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::FunctionDefinition,
+        ) {
+            v.visit_word($($r)+ node.fname);
+            v.visit_function_body($($r)+ node.body);
+        }
+
+        /// Walks the command and redirections of a function body.
+        pub fn walk_function_body<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::FunctionBody) {
+            v.visit_compound_command($($r)+ node.0);
+            if let Some(redirects) = $($r)+ node.1 {
+                v.visit_redirect_list(redirects);
+            }
+        }
+
+        /// Walks the prefix, name and suffix of a simple command.
+        pub fn walk_simple_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::SimpleCommand,
+        ) {
+            if let Some(prefix) = $($r)+ node.prefix {
+                v.visit_command_prefix(prefix);
+            }
+            if let Some(name) = $($r)+ node.word_or_name {
+                v.visit_word(name);
+            }
+            if let Some(suffix) = $($r)+ node.suffix {
+                v.visit_command_suffix(suffix);
+            }
+        }
+
+        /// Walks the items of a simple command's prefix.
+        pub fn walk_command_prefix<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::CommandPrefix) {
+            for item in $($r)+ node.0 {
+                v.visit_command_prefix_or_suffix_item(item);
+            }
+        }
+
+        /// Walks the items of a simple command's suffix.
+        pub fn walk_command_suffix<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::CommandSuffix) {
+            for item in $($r)+ node.0 {
+                v.visit_command_prefix_or_suffix_item(item);
+            }
+        }
+
+        /// Walks an item in the prefix or suffix of a simple command. Only the parsed form
+        /// of an assignment is visited, not its original word.
+        pub fn walk_command_prefix_or_suffix_item<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::CommandPrefixOrSuffixItem,
+        ) {
+            match node {
+                ast::CommandPrefixOrSuffixItem::IoRedirect(redirect) => {
+                    v.visit_io_redirect(redirect);
+                }
+                ast::CommandPrefixOrSuffixItem::Word(word) => v.visit_word(word),
+                ast::CommandPrefixOrSuffixItem::AssignmentWord(assignment, _) => {
+                    v.visit_assignment(assignment);
+                }
+                ast::CommandPrefixOrSuffixItem::ProcessSubstitution(_, subshell) => {
+                    v.visit_subshell_command(subshell);
+                }
+            }
+        }
+
+        /// Walks the value of an assignment.
+        pub fn walk_assignment<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::Assignment) {
+            match $($r)+ node.value {
+                ast::AssignmentValue::Scalar(value) => v.visit_word(value),
+                ast::AssignmentValue::Array(elements) => {
+                    for (index, value) in elements {
+                        if let Some(index) = index {
+                            v.visit_word(index);
+                        }
+                        v.visit_word(value);
+                    }
+                }
+            }
+        }
+
+        /// Walks the redirections of a list.
+        pub fn walk_redirect_list<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::RedirectList) {
+            for redirect in $($r)+ node.0 {
+                v.visit_io_redirect(redirect);
+            }
+        }
+
+        /// Walks the target of a redirection.
+        pub fn walk_io_redirect<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::IoRedirect) {
+            match node {
+                ast::IoRedirect::File(_, _, target, _) => match target {
+                    ast::IoFileRedirectTarget::Filename(word)
+                    | ast::IoFileRedirectTarget::Duplicate(word) => v.visit_word(word),
+                    ast::IoFileRedirectTarget::ProcessSubstitution(_, subshell) => {
+                        v.visit_subshell_command(subshell);
+                    }
+                    ast::IoFileRedirectTarget::Fd(_) => {}
+                },
+                ast::IoRedirect::HereDocument(_, here_doc, _) => v.visit_io_here_document(here_doc),
+                ast::IoRedirect::HereString(_, word, _)
+                | ast::IoRedirect::OutputAndError(word, _, _) => v.visit_word(word),
+            }
+        }
+
+        /// Walks the body of a here-document, if it is subject to expansion.
+        pub fn walk_io_here_document<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::IoHereDocument,
+        ) {
+            if node.requires_expansion {
+                v.visit_word($($r)+ node.doc);
+            }
+        }
+
+        /// Walks the expression of an extended test command.
+        pub fn walk_extended_test_expr_command<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ExtendedTestExprCommand,
+        ) {
+            v.visit_extended_test_expr($($r)+ node.expr);
+        }
+
+        /// Walks the operands of an extended test expression.
+        pub fn walk_extended_test_expr<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ExtendedTestExpr,
+        ) {
+            match node {
+                ast::ExtendedTestExpr::And(left, right)
+                | ast::ExtendedTestExpr::Or(left, right) => {
+                    v.visit_extended_test_expr(left);
+                    v.visit_extended_test_expr(right);
+                }
+                ast::ExtendedTestExpr::Not(expr, _)
+                | ast::ExtendedTestExpr::Parenthesized(expr, _) => {
+                    v.visit_extended_test_expr(expr);
+                }
+                ast::ExtendedTestExpr::UnaryTest(_, operand, _) => v.visit_word(operand),
+                ast::ExtendedTestExpr::BinaryTest(_, left, right) => {
+                    v.visit_word(left);
+                    v.visit_word(right);
+                }
+            }
+        }
+
+        /// Walks the subexpressions of a test expression.
+        pub fn walk_test_expr<V: $trait + ?Sized>(v: &mut V, node: $($r)+ ast::TestExpr) {
+            match node {
+                ast::TestExpr::And(left, right) | ast::TestExpr::Or(left, right) => {
+                    v.visit_test_expr(left);
+                    v.visit_test_expr(right);
+                }
+                ast::TestExpr::Not(expr) | ast::TestExpr::Parenthesized(expr) => {
+                    v.visit_test_expr(expr);
+                }
+                ast::TestExpr::False
+                | ast::TestExpr::Literal(_)
+                | ast::TestExpr::UnaryTest(..)
+                | ast::TestExpr::BinaryTest(..) => {}
+            }
+        }
+
+        /// Walks the subexpressions and targets of an arithmetic expression.
+        pub fn walk_arithmetic_expr<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ArithmeticExpr,
+        ) {
+            match node {
+                ast::ArithmeticExpr::Literal(..) => {}
+                ast::ArithmeticExpr::Reference(target)
+                | ast::ArithmeticExpr::UnaryAssignment(_, target, _) => {
+                    v.visit_arithmetic_target(target);
+                }
+                ast::ArithmeticExpr::UnaryOp(_, operand, _) => v.visit_arithmetic_expr(operand),
+                ast::ArithmeticExpr::BinaryOp(_, left, right) => {
+                    v.visit_arithmetic_expr(left);
+                    v.visit_arithmetic_expr(right);
+                }
+                ast::ArithmeticExpr::Conditional(condition, then, otherwise) => {
+                    v.visit_arithmetic_expr(condition);
+                    v.visit_arithmetic_expr(then);
+                    v.visit_arithmetic_expr(otherwise);
+                }
+                ast::ArithmeticExpr::Assignment(target, value)
+                | ast::ArithmeticExpr::BinaryAssignment(_, target, value) => {
+                    v.visit_arithmetic_target(target);
+                    v.visit_arithmetic_expr(value);
+                }
+            }
+        }
+
+        /// Walks the index of an arithmetic target.
+        pub fn walk_arithmetic_target<V: $trait + ?Sized>(
+            v: &mut V,
+            node: $($r)+ ast::ArithmeticTarget,
+        ) {
+            match node {
+                ast::ArithmeticTarget::Variable(..) => {}
+                ast::ArithmeticTarget::ArrayElement(_, index, _) => v.visit_arithmetic_expr(index),
+            }
+        }
+    };
+}
+
+pub(crate) use visitor;
+
+visitor! {
+    /// Visits the nodes of a shell program AST, along with the parsed structure of its
+    /// words and arithmetic expressions.
+    trait Visit;
+    ref [&];
+
+    /// Visits a word.
+    fn visit_word(&mut self, node: &ast::Word) {
+        walk_word(self, node);
+    }
+
+    /// Visits a piece of a parsed word.
+    fn visit_word_piece(&mut self, node: &WordPieceWithSource) {
+        walk_word_piece(self, node);
+    }
+
+    /// Visits the expression of a parameter expansion.
+    fn visit_parameter_expr(&mut self, node: &word::ParameterExpr) {
+        walk_parameter_expr(self, node);
+    }
+
+    /// Visits an expanded parameter.
+    fn visit_parameter(&mut self, _node: &word::Parameter) {}
+
+    /// Visits the text of a command substitution.
+    fn visit_command_substitution(&mut self, text: &str) {
+        walk_command_substitution(self, text);
+    }
+
+    /// Visits an unparsed arithmetic expression.
+    fn visit_unexpanded_arithmetic_expr(&mut self, node: &ast::UnexpandedArithmeticExpr) {
+        walk_unexpanded_arithmetic_expr(self, node);
+    }
+}
+
+/// Walks the pieces of a word. If the word's location is known, so are those of the
+/// expansions within it.
+pub fn walk_word<V: Visit + ?Sized>(v: &mut V, node: &ast::Word) {
+    if let Some(loc) = &node.loc {
+        if let Ok(pieces) = word::parse_with_location(&node.value, &loc.start, v.parser_options()) {
+            for piece in &pieces {
+                v.visit_word_piece(piece);
+            }
+        }
+    } else {
+        walk_word_text(v, &node.value);
+    }
+}
+
+/// Walks the pieces of text parsed as a word.
+fn walk_word_text<V: Visit + ?Sized>(v: &mut V, text: &str) {
+    if let Ok(pieces) = word::parse(text, v.parser_options()) {
+        for piece in &pieces {
+            v.visit_word_piece(piece);
+        }
+    }
+}
+
+/// Walks the expansions within a piece of a word.
+pub fn walk_word_piece<V: Visit + ?Sized>(v: &mut V, node: &WordPieceWithSource) {
+    match &node.piece {
+        WordPiece::DoubleQuotedSequence(pieces)
+        | WordPiece::GettextDoubleQuotedSequence(pieces) => {
+            for piece in pieces {
+                v.visit_word_piece(piece);
+            }
+        }
+        WordPiece::ParameterExpansion(expr) => v.visit_parameter_expr(expr),
+        WordPiece::CommandSubstitution(text) | WordPiece::BackquotedCommandSubstitution(text) => {
+            v.visit_command_substitution(text);
+        }
+        WordPiece::ArithmeticExpression(expr) => v.visit_unexpanded_arithmetic_expr(expr),
+        WordPiece::Text(_)
+        | WordPiece::SingleQuotedText(_)
+        | WordPiece::AnsiCQuotedText(_)
+        | WordPiece::TildePrefix(_)
+        | WordPiece::EscapeSequence(_) => {}
+    }
+}
+
+/// Walks the parameter and operands of a parameter expression. Operands that are words
+/// (such as default values and patterns) are walked as words.
+pub fn walk_parameter_expr<V: Visit + ?Sized>(v: &mut V, node: &word::ParameterExpr) {
+    match node {
+        word::ParameterExpr::Parameter { parameter, .. }
+        | word::ParameterExpr::ParameterLength { parameter, .. }
+        | word::ParameterExpr::Transform { parameter, .. } => v.visit_parameter(parameter),
+        word::ParameterExpr::UseDefaultValues {
+            parameter,
+            default_value: operand,
+            ..
+        }
+        | word::ParameterExpr::AssignDefaultValues {
+            parameter,
+            default_value: operand,
+            ..
+        }
+        | word::ParameterExpr::IndicateErrorIfNullOrUnset {
+            parameter,
+            error_message: operand,
+            ..
+        }
+        | word::ParameterExpr::UseAlternativeValue {
+            parameter,
+            alternative_value: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveSmallestSuffixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveLargestSuffixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveSmallestPrefixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveLargestPrefixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::UppercaseFirstChar {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::UppercasePattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::LowercaseFirstChar {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::LowercasePattern {
+            parameter,
+            pattern: operand,
+            ..
+        } => {
+            v.visit_parameter(parameter);
+            if let Some(operand) = operand {
+                walk_word_text(v, operand);
+            }
+        }
+        word::ParameterExpr::Substring {
+            parameter,
+            offset,
+            length,
+            ..
+        } => {
+            v.visit_parameter(parameter);
+            v.visit_unexpanded_arithmetic_expr(offset);
+            if let Some(length) = length {
+                v.visit_unexpanded_arithmetic_expr(length);
+            }
+        }
+        word::ParameterExpr::ReplaceSubstring {
+            parameter,
+            pattern,
+            replacement,
+            ..
+        } => {
+            v.visit_parameter(parameter);
+            walk_word_text(v, pattern);
+            if let Some(replacement) = replacement {
+                walk_word_text(v, replacement);
+            }
+        }
+        word::ParameterExpr::VariableNames { .. } | word::ParameterExpr::MemberKeys { .. } => {}
+    }
+}
+
+/// Walks the program of a command substitution.
+pub fn walk_command_substitution<V: Visit + ?Sized>(v: &mut V, text: &str) {
+    let mut parser = Parser::new(text.as_bytes(), v.parser_options(), &SourceInfo::default());
+    if let Ok(program) = parser.parse_program() {
+        v.visit_program(&program);
+    }
+}
+
+/// Walks the parsed form of an arithmetic expression, followed by the expansions in its
+/// text. As expansions are performed before the expression is evaluated, each is parsed as
+/// a placeholder `0`, padded to keep the locations of what follows it.
+pub fn walk_unexpanded_arithmetic_expr<V: Visit + ?Sized>(
+    v: &mut V,
+    node: &ast::UnexpandedArithmeticExpr,
+) {
+    let pieces = if let Some(loc) = &node.loc {
+        word::parse_with_location(&node.value, &loc.start, v.parser_options())
+    } else {
+        word::parse(&node.value, v.parser_options())
+    };
+    let Ok(pieces) = pieces else {
+        return;
+    };
+
+    let mut text = String::new();
+    for piece in &pieces {
+        let piece_text = &node.value[piece.start_index..piece.end_index];
+        match &piece.piece {
+            WordPiece::Text(_) | WordPiece::EscapeSequence(_) => text.push_str(piece_text),
+            _ => {
+                text.push('0');
+                text.extend(
+                    piece_text
+                        .chars()
+                        .skip(1)
+                        .map(|c| if c == '\n' { '\n' } else { ' ' }),
+                );
+            }
+        }
+    }
+
+    let expr = if let Some(loc) = &node.loc {
+        arithmetic::parse_with_location(&text, &loc.start)
+    } else {
+        arithmetic::parse(&text)
+    };
+    if let Ok(expr) = expr {
+        v.visit_arithmetic_expr(&expr);
+    }
+
+    for piece in &pieces {
+        v.visit_word_piece(piece);
+    }
+}
+
+/// Returns the names of the commands invoked by the simple commands of a program,
+/// including those in command and process substitutions, each once, in the order they
+/// are visited.
+/// Names are returned as written, so names that aren't literal (e.g. `$cmd`) are included
+/// unexpanded.
+///
+/// # Arguments
+///
+/// * `program` - The program to inspect.
+/// * `options` - The options with which to parse the program's words.
+pub fn command_names(program: &ast::Program, options: &ParserOptions) -> Vec<String> {
+    struct CommandNames<'a>(indexmap::IndexSet<String>, &'a ParserOptions);
+
+    impl Visit for CommandNames<'_> {
+        fn parser_options(&self) -> &ParserOptions {
+            self.1
+        }
+
+        fn visit_simple_command(&mut self, node: &ast::SimpleCommand) {
+            if let Some(name) = &node.word_or_name {
+                self.0.insert(name.value.clone());
+            }
+            walk_simple_command(self, node);
+        }
+    }
+
+    let mut visitor = CommandNames(indexmap::IndexSet::new(), options);
+    visitor.visit_program(program);
+    visitor.0.into_iter().collect()
+}
+
+/// Returns the names of the variables referenced by a program's parameter expansions and
+/// arithmetic expressions (including assignments to them), each once, in the order they
+/// are visited.
+///
+/// # Arguments
+///
+/// * `program` - The program to inspect.
+/// * `options` - The options with which to parse the program's words.
+pub fn variable_references(program: &ast::Program, options: &ParserOptions) -> Vec<String> {
+    struct VariableReferences<'a>(indexmap::IndexSet<String>, &'a ParserOptions);
+
+    impl Visit for VariableReferences<'_> {
+        fn parser_options(&self) -> &ParserOptions {
+            self.1
+        }
+
+        fn visit_parameter(&mut self, node: &word::Parameter) {
+            match node {
+                word::Parameter::Named(name)
+                | word::Parameter::NamedWithIndex { name, .. }
+                | word::Parameter::NamedWithAllIndices { name, .. } => {
+                    self.0.insert(name.clone());
+                }
+                word::Parameter::Positional(_) | word::Parameter::Special(_) => {}
+            }
+        }
+
+        fn visit_parameter_expr(&mut self, node: &word::ParameterExpr) {
+            if let word::ParameterExpr::MemberKeys { variable_name, .. } = node {
+                self.0.insert(variable_name.clone());
+            }
+            walk_parameter_expr(self, node);
+        }
+
+        fn visit_arithmetic_target(&mut self, node: &ast::ArithmeticTarget) {
+            match node {
+                ast::ArithmeticTarget::Variable(name, _)
+                | ast::ArithmeticTarget::ArrayElement(name, _, _) => {
+                    self.0.insert(name.clone());
+                }
+            }
+            walk_arithmetic_target(self, node);
+        }
+    }
+
+    let mut visitor = VariableReferences(indexmap::IndexSet::new(), options);
+    visitor.visit_program(program);
+    visitor.0.into_iter().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::ast::SourceLocation;
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    fn parse(input: &str) -> Result<ast::Program> {
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        Ok(parser.parse_program()?)
+    }
+
+    const SCRIPT: &str = r#"f() { grep "${pattern:-$default}" "$@"; }
+x=$(ls "$dir" | f) y=(`uname` "${arr[@]}")
+if [[ -n ${!keys[@]} ]]; then (( count += ${#items} )); fi
+cat >"${out%/}" <<EOF 2> >(logger)
+$((total * 2)) ${msg/$from/$to}
+EOF
+cat <<'EOF'
+$ignored
+EOF
+"#;
+
+    #[test]
+    fn collect_command_names() -> Result<()> {
+        assert_eq!(
+            command_names(&parse(SCRIPT)?, &ParserOptions::default()),
+            ["grep", "ls", "f", "uname", "cat", "logger"]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn collect_variable_references() -> Result<()> {
+        assert_eq!(
+            variable_references(&parse(SCRIPT)?, &ParserOptions::default()),
+            [
+                "pattern", "default", "dir", "arr", "keys", "count", "items", "out", "total",
+                "msg", "from", "to"
+            ]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn visit_with_parser_options() -> Result<()> {
+        let program = parse("echo ${arr[0]} $x")?;
+        let sh_options = ParserOptions {
+            sh_mode: true,
+            ..ParserOptions::default()
+        };
+
+        assert_eq!(
+            variable_references(&program, &ParserOptions::default()),
+            ["arr", "x"]
+        );
+        assert_eq!(variable_references(&program, &sh_options), ["x"]);
+        Ok(())
+    }
+
+    #[test]
+    fn visit_nested_arithmetic() -> Result<()> {
+        #[derive(Default)]
+        struct Literals(Vec<i64>);
+
+        impl Visit for Literals {
+            fn visit_arithmetic_expr(&mut self, node: &ast::ArithmeticExpr) {
+                if let ast::ArithmeticExpr::Literal(value, _) = node {
+                    self.0.push(*value);
+                }
+                walk_arithmetic_expr(self, node);
+            }
+        }
+
+        let mut visitor = Literals::default();
+        visitor.visit_program(&parse(
+            "for ((i = 1; i < 2; i++)); do echo ${s:3:4} $(( a[5] ? 6 : 7 )); done",
+        )?);
+
+        assert_eq!(visitor.0, [1, 2, 3, 4, 5, 6, 7]);
+        Ok(())
+    }
+
+    #[test]
+    fn visit_locates_expansions() -> Result<()> {
+        #[derive(Default)]
+        struct Locations(Vec<String>);
+
+        impl Locations {
+            fn push(&mut self, what: String, loc: Option<crate::parser::TokenLocation>) {
+                let loc = loc.map_or_else(String::new, |loc| {
+                    std::format!("{}:{}", loc.start.line, loc.start.column)
+                });
+                self.0.push(std::format!("{what}@{loc}"));
+            }
+        }
+
+        impl Visit for Locations {
+            fn visit_parameter_expr(&mut self, node: &word::ParameterExpr) {
+                self.push("param".into(), node.location());
+                walk_parameter_expr(self, node);
+            }
+
+            fn visit_arithmetic_expr(&mut self, node: &ast::ArithmeticExpr) {
+                if let ast::ArithmeticExpr::Literal(value, _) = node {
+                    self.push(value.to_string(), node.location());
+                }
+                walk_arithmetic_expr(self, node);
+            }
+        }
+
+        let mut visitor = Locations::default();
+        visitor.visit_program(&parse("echo a${b:1:2} \"$((3 + $c))\"\n(( 4 ))")?);
+
+        assert_eq!(
+            visitor.0,
+            [
+                "param@1:7",
+                "1@1:11",
+                "2@1:13",
+                "3@1:20",
+                "0@1:24",
+                "param@1:24",
+                "4@2:4"
+            ]
+        );
+        Ok(())
+    }
+}
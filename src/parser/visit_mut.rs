@@ -0,0 +1,481 @@
+//! In-place rewriting of shell program ASTs.
+//!
+//! [`VisitMut`] mirrors [`Visit`](super::visit::Visit), visiting nodes through mutable
+//! references so that they may be rewritten. Words, arithmetic expressions and command
+//! substitutions are parsed as they are for [`Visit`](super::visit::Visit), and the text
+//! of any that are rewritten is regenerated from their parsed form. Pieces of a word that
+//! weren't rewritten keep their original text.
+//!
+//! The parsed form of an arithmetic expression is only visited if it contains no
+//! expansions, as the placeholders that stand in for them could not be written back; the
+//! expansions themselves are always visited.
+
+use super::ast;
+use super::visit::visitor;
+use super::word::{self, WordPiece, WordPieceWithSource};
+use super::{Parser, ParserOptions, SourceInfo, SourcePosition, arithmetic};
+
+visitor! {
+    /// Visits the nodes of a shell program AST through mutable references, allowing them
+    /// to be rewritten in place.
+    trait VisitMut;
+    ref [&mut];
+
+    /// Visits a word.
+    fn visit_word(&mut self, node: &mut ast::Word) {
+        walk_word(self, node);
+    }
+
+    /// Visits a piece of a parsed word.
+    fn visit_word_piece(&mut self, node: &mut WordPieceWithSource) {
+        walk_word_piece(self, node);
+    }
+
+    /// Visits the expression of a parameter expansion.
+    fn visit_parameter_expr(&mut self, node: &mut word::ParameterExpr) {
+        walk_parameter_expr(self, node);
+    }
+
+    /// Visits an expanded parameter.
+    fn visit_parameter(&mut self, _node: &mut word::Parameter) {}
+
+    /// Visits the text of a command substitution.
+    fn visit_command_substitution(&mut self, text: &mut String) {
+        walk_command_substitution(self, text);
+    }
+
+    /// Visits an unparsed arithmetic expression.
+    fn visit_unexpanded_arithmetic_expr(&mut self, node: &mut ast::UnexpandedArithmeticExpr) {
+        walk_unexpanded_arithmetic_expr(self, node);
+    }
+}
+
+/// Walks the pieces of a word, regenerating its text if any are rewritten. If the word's
+/// location is known, so are those of the expansions within it.
+pub fn walk_word<V: VisitMut + ?Sized>(v: &mut V, node: &mut ast::Word) {
+    let start = node.loc.as_ref().map(|loc| loc.start.clone());
+    walk_text(v, &mut node.value, start.as_deref());
+}
+
+/// Walks the pieces of text parsed as a word, regenerating it if any are rewritten.
+fn walk_text<V: VisitMut + ?Sized>(v: &mut V, text: &mut String, start: Option<&SourcePosition>) {
+    let pieces = if let Some(start) = start {
+        word::parse_with_location(text, start, v.parser_options())
+    } else {
+        word::parse(text, v.parser_options())
+    };
+    if let Ok(pieces) = pieces {
+        walk_pieces(v, text, pieces);
+    }
+}
+
+/// Walks the given pieces of a text, regenerating it if any are rewritten.
+fn walk_pieces<V: VisitMut + ?Sized>(
+    v: &mut V,
+    text: &mut String,
+    mut pieces: Vec<WordPieceWithSource>,
+) {
+    let original = pieces.clone();
+    for piece in &mut pieces {
+        v.visit_word_piece(piece);
+    }
+
+    let mut rewritten = String::new();
+    write_pieces(&mut rewritten, text, &original, &pieces);
+    if rewritten != *text {
+        *text = rewritten;
+    }
+}
+
+/// Writes out the given pieces of a text, keeping the original text of those that weren't
+/// rewritten.
+fn write_pieces(
+    out: &mut String,
+    text: &str,
+    original: &[WordPieceWithSource],
+    pieces: &[WordPieceWithSource],
+) {
+    for (original, piece) in original.iter().zip(pieces) {
+        match (&original.piece, &piece.piece) {
+            (
+                WordPiece::DoubleQuotedSequence(original_inner),
+                WordPiece::DoubleQuotedSequence(inner),
+            )
+            | (
+                WordPiece::GettextDoubleQuotedSequence(original_inner),
+                WordPiece::GettextDoubleQuotedSequence(inner),
+            ) if original_inner.len() == inner.len() => {
+                if matches!(piece.piece, WordPiece::GettextDoubleQuotedSequence(_)) {
+                    out.push('$');
+                }
+                out.push('"');
+                write_pieces(out, text, original_inner, inner);
+                out.push('"');
+            }
+            _ if original.piece.to_string() == piece.piece.to_string() => {
+                out.push_str(&text[original.start_index..original.end_index]);
+            }
+            _ => out.push_str(&piece.piece.to_string()),
+        }
+    }
+}
+
+/// Walks the expansions within a piece of a word.
+pub fn walk_word_piece<V: VisitMut + ?Sized>(v: &mut V, node: &mut WordPieceWithSource) {
+    match &mut node.piece {
+        WordPiece::DoubleQuotedSequence(pieces)
+        | WordPiece::GettextDoubleQuotedSequence(pieces) => {
+            for piece in pieces {
+                v.visit_word_piece(piece);
+            }
+        }
+        WordPiece::ParameterExpansion(expr) => v.visit_parameter_expr(expr),
+        WordPiece::CommandSubstitution(text) | WordPiece::BackquotedCommandSubstitution(text) => {
+            v.visit_command_substitution(text);
+        }
+        WordPiece::ArithmeticExpression(expr) => v.visit_unexpanded_arithmetic_expr(expr),
+        WordPiece::Text(_)
+        | WordPiece::SingleQuotedText(_)
+        | WordPiece::AnsiCQuotedText(_)
+        | WordPiece::TildePrefix(_)
+        | WordPiece::EscapeSequence(_) => {}
+    }
+}
+
+/// Walks the parameter and operands of a parameter expression. Operands that are words
+/// (such as default values and patterns) are walked as words.
+pub fn walk_parameter_expr<V: VisitMut + ?Sized>(v: &mut V, node: &mut word::ParameterExpr) {
+    match node {
+        word::ParameterExpr::Parameter { parameter, .. }
+        | word::ParameterExpr::ParameterLength { parameter, .. }
+        | word::ParameterExpr::Transform { parameter, .. } => v.visit_parameter(parameter),
+        word::ParameterExpr::UseDefaultValues {
+            parameter,
+            default_value: operand,
+            ..
+        }
+        | word::ParameterExpr::AssignDefaultValues {
+            parameter,
+            default_value: operand,
+            ..
+        }
+        | word::ParameterExpr::IndicateErrorIfNullOrUnset {
+            parameter,
+            error_message: operand,
+            ..
+        }
+        | word::ParameterExpr::UseAlternativeValue {
+            parameter,
+            alternative_value: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveSmallestSuffixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveLargestSuffixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveSmallestPrefixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::RemoveLargestPrefixPattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::UppercaseFirstChar {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::UppercasePattern {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::LowercaseFirstChar {
+            parameter,
+            pattern: operand,
+            ..
+        }
+        | word::ParameterExpr::LowercasePattern {
+            parameter,
+            pattern: operand,
+            ..
+        } => {
+            v.visit_parameter(parameter);
+            if let Some(operand) = operand {
+                walk_text(v, operand, None);
+            }
+        }
+        word::ParameterExpr::Substring {
+            parameter,
+            offset,
+            length,
+            ..
+        } => {
+            v.visit_parameter(parameter);
+            v.visit_unexpanded_arithmetic_expr(offset);
+            if let Some(length) = length {
+                v.visit_unexpanded_arithmetic_expr(length);
+            }
+        }
+        word::ParameterExpr::ReplaceSubstring {
+            parameter,
+            pattern,
+            replacement,
+            ..
+        } => {
+            v.visit_parameter(parameter);
+            walk_text(v, pattern, None);
+            if let Some(replacement) = replacement {
+                walk_text(v, replacement, None);
+            }
+        }
+        word::ParameterExpr::VariableNames { .. } | word::ParameterExpr::MemberKeys { .. } => {}
+    }
+}
+
+/// Walks the program of a command substitution, regenerating its text if it's rewritten.
+pub fn walk_command_substitution<V: VisitMut + ?Sized>(v: &mut V, text: &mut String) {
+    let mut parser = Parser::new(text.as_bytes(), v.parser_options(), &SourceInfo::default());
+    let Ok(mut program) = parser.parse_program() else {
+        return;
+    };
+
+    let original = program.to_string();
+    v.visit_program(&mut program);
+    let rewritten = program.to_string();
+    if rewritten != original {
+        *text = rewritten;
+    }
+}
+
+/// Walks the expansions in the text of an arithmetic expression, or, if it has none, its
+/// parsed form, regenerating the text if either is rewritten.
+pub fn walk_unexpanded_arithmetic_expr<V: VisitMut + ?Sized>(
+    v: &mut V,
+    node: &mut ast::UnexpandedArithmeticExpr,
+) {
+    let pieces = if let Some(loc) = &node.loc {
+        word::parse_with_location(&node.value, &loc.start, v.parser_options())
+    } else {
+        word::parse(&node.value, v.parser_options())
+    };
+    let Ok(pieces) = pieces else {
+        return;
+    };
+
+    if pieces.iter().any(|piece| {
+        !matches!(
+            piece.piece,
+            WordPiece::Text(_) | WordPiece::EscapeSequence(_)
+        )
+    }) {
+        walk_pieces(v, &mut node.value, pieces);
+        return;
+    }
+
+    let expr = if let Some(loc) = &node.loc {
+        arithmetic::parse_with_location(&node.value, &loc.start)
+    } else {
+        arithmetic::parse(&node.value)
+    };
+    let Ok(mut expr) = expr else {
+        return;
+    };
+
+    let original = expr.to_string();
+    v.visit_arithmetic_expr(&mut expr);
+    if expr.to_string() != original {
+        node.value = String::new();
+        write_arithmetic_expr(&mut node.value, &expr);
+    }
+}
+
+/// Writes out an arithmetic expression, parenthesizing each operand that isn't a literal
+/// or reference so the text parses back into the same expression.
+fn write_arithmetic_expr(out: &mut String, expr: &ast::ArithmeticExpr) {
+    match expr {
+        ast::ArithmeticExpr::Literal(..) => out.push_str(&expr.to_string()),
+        ast::ArithmeticExpr::Reference(target) => write_arithmetic_target(out, target),
+        ast::ArithmeticExpr::UnaryOp(op, operand, _) => {
+            out.push_str(&op.to_string());
+            write_arithmetic_operand(out, operand);
+        }
+        ast::ArithmeticExpr::BinaryOp(op, left, right) => {
+            write_arithmetic_operand(out, left);
+            if matches!(op, ast::BinaryOperator::Comma) {
+                out.push_str(", ");
+            } else {
+                out.push_str(&format!(" {op} "));
+            }
+            write_arithmetic_operand(out, right);
+        }
+        ast::ArithmeticExpr::Conditional(condition, then, otherwise) => {
+            write_arithmetic_operand(out, condition);
+            out.push_str(" ? ");
+            write_arithmetic_operand(out, then);
+            out.push_str(" : ");
+            write_arithmetic_operand(out, otherwise);
+        }
+        ast::ArithmeticExpr::Assignment(target, value) => {
+            write_arithmetic_target(out, target);
+            out.push_str(" = ");
+            write_arithmetic_operand(out, value);
+        }
+        ast::ArithmeticExpr::BinaryAssignment(op, target, value) => {
+            write_arithmetic_target(out, target);
+            out.push_str(&format!(" {op}= "));
+            write_arithmetic_operand(out, value);
+        }
+        ast::ArithmeticExpr::UnaryAssignment(op, target, _) => match op {
+            ast::UnaryAssignmentOperator::PrefixIncrement
+            | ast::UnaryAssignmentOperator::PrefixDecrement => {
+                out.push_str(&op.to_string());
+                write_arithmetic_target(out, target);
+            }
+            ast::UnaryAssignmentOperator::PostfixIncrement
+            | ast::UnaryAssignmentOperator::PostfixDecrement => {
+                write_arithmetic_target(out, target);
+                out.push_str(&op.to_string());
+            }
+        },
+    }
+}
+
+/// Writes out an operand of an arithmetic expression, parenthesized unless it's a
+/// non-negative literal or a reference.
+fn write_arithmetic_operand(out: &mut String, operand: &ast::ArithmeticExpr) {
+    match operand {
+        ast::ArithmeticExpr::Literal(value, _) if *value >= 0 => {
+            write_arithmetic_expr(out, operand);
+        }
+        ast::ArithmeticExpr::Reference(_) => write_arithmetic_expr(out, operand),
+        _ => {
+            out.push('(');
+            write_arithmetic_expr(out, operand);
+            out.push(')');
+        }
+    }
+}
+
+/// Writes out the target of an arithmetic assignment or reference.
+fn write_arithmetic_target(out: &mut String, target: &ast::ArithmeticTarget) {
+    match target {
+        ast::ArithmeticTarget::Variable(name, _) => out.push_str(name),
+        ast::ArithmeticTarget::ArrayElement(name, index, _) => {
+            out.push_str(name);
+            out.push('[');
+            write_arithmetic_expr(out, index);
+            out.push(']');
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::parser::{Parser, ParserOptions, SourceInfo};
+    use anyhow::Result;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn rewrite_program() -> Result<()> {
+        /// Replaces `rm` with `echo rm` and renames a variable in arithmetic expressions.
+        struct Rewriter;
+
+        impl VisitMut for Rewriter {
+            fn visit_simple_command(&mut self, node: &mut ast::SimpleCommand) {
+                if let Some(name) = &mut node.word_or_name
+                    && name.value == "rm"
+                {
+                    name.value = String::from("echo");
+                    let suffix = node
+                        .suffix
+                        .get_or_insert_with(|| ast::CommandSuffix(vec![]));
+                    suffix.0.insert(
+                        0,
+                        ast::CommandPrefixOrSuffixItem::Word(ast::Word::from(String::from("rm"))),
+                    );
+                }
+                walk_simple_command(self, node);
+            }
+
+            fn visit_unexpanded_arithmetic_expr(
+                &mut self,
+                node: &mut ast::UnexpandedArithmeticExpr,
+            ) {
+                node.value = node.value.replace("old", "new");
+            }
+        }
+
+        let input = "if true; then rm -rf /tmp/x | cat; fi; (( old += 1 ))";
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        let mut program = parser.parse_program()?;
+
+        Rewriter.visit_program(&mut program);
+
+        assert_eq!(
+            program.to_string(),
+            "if true; then\n    echo rm -rf /tmp/x | cat\nfi;\n((new += 1))"
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn rewrite_expansions() -> Result<()> {
+        /// Renames a variable wherever it's referenced.
+        struct Renamer;
+
+        impl VisitMut for Renamer {
+            fn visit_parameter(&mut self, node: &mut word::Parameter) {
+                if let word::Parameter::Named(name) = node
+                    && name == "old"
+                {
+                    *name = String::from("new");
+                }
+            }
+
+            fn visit_arithmetic_target(&mut self, node: &mut ast::ArithmeticTarget) {
+                if let ast::ArithmeticTarget::Variable(name, _) = node
+                    && name == "old"
+                {
+                    *name = String::from("new");
+                }
+                walk_arithmetic_target(self, node);
+            }
+        }
+
+        let input = r#"echo "a ${old:-$old} $HOME" $(cd /; echo $old
+pwd) '$old' $((old * (2 + 1))) ${#old}
+[[ $old == `echo $x` ]]"#;
+        let mut parser = Parser::new(
+            input.as_bytes(),
+            &ParserOptions::default(),
+            &SourceInfo::default(),
+        );
+        let mut program = parser.parse_program()?;
+
+        Renamer.visit_program(&mut program);
+
+        assert_eq!(
+            program.to_string(),
+            r#"echo "a ${new:-${new}} $HOME" $(cd /;
+echo ${new}
+pwd) '$old' $((new * (2 + 1))) ${#new}
+[[ ${new} == `echo $x` ]]"#
+        );
+        Ok(())
+    }
+}
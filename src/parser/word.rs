@@ -13,12 +13,19 @@ use std::fmt::Debug;
 use std::fmt::Display;
 
 use super::ParserOptions;
+use super::SourcePosition;
+use super::TokenLocation;
 use super::ast;
 use super::error;
+use super::tokenizer::Origin;
 
 /// Encapsulates a `WordPiece` together with its position in the string it came from.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub struct WordPieceWithSource {
     /// The word piece.
     pub piece: WordPiece,
@@ -28,9 +35,30 @@ pub struct WordPieceWithSource {
     pub end_index: usize,
 }
 
+impl WordPieceWithSource {
+    /// Returns the location of the piece in the source script, if the word it was
+    /// parsed from has a known location.
+    ///
+    /// # Arguments
+    ///
+    /// * `word` - The word whose text the piece was parsed from.
+    pub fn location_in(&self, word: &ast::Word) -> Option<TokenLocation> {
+        let loc = word.loc.as_ref()?;
+        Some(TokenLocation::within_text(
+            &loc.start,
+            &word.value,
+            self.start_index..self.end_index,
+        ))
+    }
+}
+
 /// Represents a piece of a word.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum WordPiece {
     /// A simple unquoted, unescaped string.
     Text(String),
@@ -56,9 +84,40 @@ pub enum WordPiece {
     ArithmeticExpression(ast::UnexpandedArithmeticExpr),
 }
 
+impl Display for WordPiece {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Text(text) | Self::EscapeSequence(text) => write!(f, "{text}"),
+            Self::SingleQuotedText(text) => write!(f, "'{text}'"),
+            Self::AnsiCQuotedText(text) => write!(f, "$'{text}'"),
+            Self::DoubleQuotedSequence(pieces) | Self::GettextDoubleQuotedSequence(pieces) => {
+                if matches!(self, Self::GettextDoubleQuotedSequence(_)) {
+                    write!(f, "$")?;
+                }
+                write!(f, "\"")?;
+                for piece in pieces {
+                    write!(f, "{}", piece.piece)?;
+                }
+                write!(f, "\"")
+            }
+            Self::TildePrefix(prefix) => write!(f, "~{prefix}"),
+            Self::ParameterExpansion(expr) => write!(f, "{expr}"),
+            Self::CommandSubstitution(command) => write!(f, "$({command})"),
+            Self::BackquotedCommandSubstitution(command) => {
+                write!(f, "`{}`", command.replace('`', "\\`"))
+            }
+            Self::ArithmeticExpression(expr) => write!(f, "$(({expr}))"),
+        }
+    }
+}
+
 /// Type of a parameter test.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ParameterTestType {
     /// Check for unset or null.
     UnsetOrNull,
@@ -66,9 +125,22 @@ pub enum ParameterTestType {
     Unset,
 }
 
+impl Display for ParameterTestType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::UnsetOrNull => write!(f, ":"),
+            Self::Unset => Ok(()),
+        }
+    }
+}
+
 /// A parameter, used in a parameter expansion.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum Parameter {
     /// A 0-indexed positional parameter.
     Positional(u32),
@@ -112,9 +184,32 @@ impl Display for Parameter {
     }
 }
 
+impl Parameter {
+    /// Writes the parameter as it appears within the braces of a parameter expansion.
+    fn fmt_name(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Positional(n) => write!(f, "{n}"),
+            Self::Special(s) => write!(f, "{s}"),
+            Self::Named(name) => write!(f, "{name}"),
+            Self::NamedWithIndex { name, index } => write!(f, "{name}[{index}]"),
+            Self::NamedWithAllIndices { name, concatenate } => {
+                if *concatenate {
+                    write!(f, "{name}[*]")
+                } else {
+                    write!(f, "{name}[@]")
+                }
+            }
+        }
+    }
+}
+
 /// A special parameter, used in a parameter expansion.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum SpecialParameter {
     /// All positional parameters.
     AllPositionalParameters {
@@ -157,7 +252,11 @@ impl Display for SpecialParameter {
 
 /// A parameter expression, used in a parameter expansion.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ParameterExpr {
     /// A parameter, with optional indirection.
     Parameter {
@@ -167,6 +266,9 @@ pub enum ParameterExpr {
         /// reference, which should be subsequently dereferenced
         /// for the expansion.
         indirect: bool,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Conditionally use default values.
     UseDefaultValues {
@@ -180,6 +282,9 @@ pub enum ParameterExpr {
         test_type: ParameterTestType,
         /// Default value to conditionally use.
         default_value: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Conditionally assign default values.
     AssignDefaultValues {
@@ -193,6 +298,9 @@ pub enum ParameterExpr {
         test_type: ParameterTestType,
         /// Default value to conditionally assign.
         default_value: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Indicate error if null or unset.
     IndicateErrorIfNullOrUnset {
@@ -206,6 +314,9 @@ pub enum ParameterExpr {
         test_type: ParameterTestType,
         /// Error message to conditionally yield.
         error_message: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Conditionally use an alternative value.
     UseAlternativeValue {
@@ -219,6 +330,9 @@ pub enum ParameterExpr {
         test_type: ParameterTestType,
         /// Alternative value to conditionally use.
         alternative_value: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Compute the length of the given parameter.
     ParameterLength {
@@ -228,6 +342,9 @@ pub enum ParameterExpr {
         /// reference, which should be subsequently dereferenced
         /// for the expansion.
         indirect: bool,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Remove the smallest suffix from the given string matching the given pattern.
     RemoveSmallestSuffixPattern {
@@ -239,6 +356,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Remove the largest suffix from the given string matching the given pattern.
     RemoveLargestSuffixPattern {
@@ -250,6 +370,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Remove the smallest prefix from the given string matching the given pattern.
     RemoveSmallestPrefixPattern {
@@ -261,6 +384,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Remove the largest prefix from the given string matching the given pattern.
     RemoveLargestPrefixPattern {
@@ -272,6 +398,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Extract a substring from the given parameter.
     Substring {
@@ -288,6 +417,9 @@ pub enum ParameterExpr {
         /// to compute the length of substring to be extracted; if left
         /// unspecified, the remainder of the string will be extracted.
         length: Option<ast::UnexpandedArithmeticExpr>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Transform the given parameter.
     Transform {
@@ -299,6 +431,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Type of transformation to apply.
         op: ParameterTransformOp,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Uppercase the first character of the given parameter.
     UppercaseFirstChar {
@@ -310,6 +445,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Uppercase the portion of the given parameter matching the given pattern.
     UppercasePattern {
@@ -321,6 +459,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Lowercase the first character of the given parameter.
     LowercaseFirstChar {
@@ -332,6 +473,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Lowercase the portion of the given parameter matching the given pattern.
     LowercasePattern {
@@ -343,6 +487,9 @@ pub enum ParameterExpr {
         indirect: bool,
         /// Optionally provides a pattern to match.
         pattern: Option<String>,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Replace occurrences of the given pattern in the given parameter.
     ReplaceSubstring {
@@ -358,6 +505,9 @@ pub enum ParameterExpr {
         replacement: Option<String>,
         /// Kind of match to perform.
         match_kind: SubstringMatchKind,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Select variable names from the environment with a given prefix.
     VariableNames {
@@ -365,6 +515,9 @@ pub enum ParameterExpr {
         prefix: String,
         /// Whether to concatenate the results.
         concatenate: bool,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
     /// Select member keys from the named array.
     MemberKeys {
@@ -372,12 +525,260 @@ pub enum ParameterExpr {
         variable_name: String,
         /// Whether to concatenate the results.
         concatenate: bool,
+        /// Location of the expression, when known.
+        #[cfg_attr(test, serde(default, skip_serializing_if = "Option::is_none"))]
+        loc: Option<TokenLocation>,
     },
 }
 
+impl ParameterExpr {
+    fn loc_mut(&mut self) -> &mut Option<TokenLocation> {
+        match self {
+            Self::Parameter { loc, .. }
+            | Self::UseDefaultValues { loc, .. }
+            | Self::AssignDefaultValues { loc, .. }
+            | Self::IndicateErrorIfNullOrUnset { loc, .. }
+            | Self::UseAlternativeValue { loc, .. }
+            | Self::ParameterLength { loc, .. }
+            | Self::RemoveSmallestSuffixPattern { loc, .. }
+            | Self::RemoveLargestSuffixPattern { loc, .. }
+            | Self::RemoveSmallestPrefixPattern { loc, .. }
+            | Self::RemoveLargestPrefixPattern { loc, .. }
+            | Self::Substring { loc, .. }
+            | Self::Transform { loc, .. }
+            | Self::UppercaseFirstChar { loc, .. }
+            | Self::UppercasePattern { loc, .. }
+            | Self::LowercaseFirstChar { loc, .. }
+            | Self::LowercasePattern { loc, .. }
+            | Self::ReplaceSubstring { loc, .. }
+            | Self::VariableNames { loc, .. }
+            | Self::MemberKeys { loc, .. } => loc,
+        }
+    }
+
+    /// Returns the expression with the given location recorded on it.
+    fn with_location(mut self, loc: Option<TokenLocation>) -> Self {
+        *self.loc_mut() = loc;
+        self
+    }
+}
+
+impl ast::SourceLocation for ParameterExpr {
+    fn location(&self) -> Option<TokenLocation> {
+        match self {
+            Self::Parameter { loc, .. }
+            | Self::UseDefaultValues { loc, .. }
+            | Self::AssignDefaultValues { loc, .. }
+            | Self::IndicateErrorIfNullOrUnset { loc, .. }
+            | Self::UseAlternativeValue { loc, .. }
+            | Self::ParameterLength { loc, .. }
+            | Self::RemoveSmallestSuffixPattern { loc, .. }
+            | Self::RemoveLargestSuffixPattern { loc, .. }
+            | Self::RemoveSmallestPrefixPattern { loc, .. }
+            | Self::RemoveLargestPrefixPattern { loc, .. }
+            | Self::Substring { loc, .. }
+            | Self::Transform { loc, .. }
+            | Self::UppercaseFirstChar { loc, .. }
+            | Self::UppercasePattern { loc, .. }
+            | Self::LowercaseFirstChar { loc, .. }
+            | Self::LowercasePattern { loc, .. }
+            | Self::ReplaceSubstring { loc, .. }
+            | Self::VariableNames { loc, .. }
+            | Self::MemberKeys { loc, .. } => loc.clone(),
+        }
+    }
+}
+
+impl Display for ParameterExpr {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "${{")?;
+        match self {
+            Self::Parameter {
+                parameter,
+                indirect,
+                ..
+            } => fmt_parameter(f, parameter, *indirect)?,
+            Self::UseDefaultValues {
+                parameter,
+                indirect,
+                test_type,
+                default_value: operand,
+                ..
+            }
+            | Self::AssignDefaultValues {
+                parameter,
+                indirect,
+                test_type,
+                default_value: operand,
+                ..
+            }
+            | Self::IndicateErrorIfNullOrUnset {
+                parameter,
+                indirect,
+                test_type,
+                error_message: operand,
+                ..
+            }
+            | Self::UseAlternativeValue {
+                parameter,
+                indirect,
+                test_type,
+                alternative_value: operand,
+                ..
+            } => {
+                let op = match self {
+                    Self::UseDefaultValues { .. } => "-",
+                    Self::AssignDefaultValues { .. } => "=",
+                    Self::IndicateErrorIfNullOrUnset { .. } => "?",
+                    _ => "+",
+                };
+                fmt_parameter(f, parameter, *indirect)?;
+                write!(
+                    f,
+                    "{test_type}{op}{}",
+                    operand.as_deref().unwrap_or_default()
+                )?;
+            }
+            Self::ParameterLength { parameter, .. } => {
+                write!(f, "#")?;
+                parameter.fmt_name(f)?;
+            }
+            Self::RemoveSmallestSuffixPattern {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::RemoveLargestSuffixPattern {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::RemoveSmallestPrefixPattern {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::RemoveLargestPrefixPattern {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::UppercaseFirstChar {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::UppercasePattern {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::LowercaseFirstChar {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            }
+            | Self::LowercasePattern {
+                parameter,
+                indirect,
+                pattern,
+                ..
+            } => {
+                let op = match self {
+                    Self::RemoveSmallestSuffixPattern { .. } => "%",
+                    Self::RemoveLargestSuffixPattern { .. } => "%%",
+                    Self::RemoveSmallestPrefixPattern { .. } => "#",
+                    Self::RemoveLargestPrefixPattern { .. } => "##",
+                    Self::UppercaseFirstChar { .. } => "^",
+                    Self::UppercasePattern { .. } => "^^",
+                    Self::LowercaseFirstChar { .. } => ",",
+                    _ => ",,",
+                };
+                fmt_parameter(f, parameter, *indirect)?;
+                write!(f, "{op}{}", pattern.as_deref().unwrap_or_default())?;
+            }
+            Self::Substring {
+                parameter,
+                indirect,
+                offset,
+                length,
+                ..
+            } => {
+                fmt_parameter(f, parameter, *indirect)?;
+                write!(f, ":{offset}")?;
+                if let Some(length) = length {
+                    write!(f, ":{length}")?;
+                }
+            }
+            Self::Transform {
+                parameter,
+                indirect,
+                op,
+                ..
+            } => {
+                fmt_parameter(f, parameter, *indirect)?;
+                write!(f, "@{op}")?;
+            }
+            Self::ReplaceSubstring {
+                parameter,
+                indirect,
+                pattern,
+                replacement,
+                match_kind,
+                ..
+            } => {
+                fmt_parameter(f, parameter, *indirect)?;
+                write!(f, "/{match_kind}{pattern}")?;
+                if let Some(replacement) = replacement {
+                    write!(f, "/{replacement}")?;
+                }
+            }
+            Self::VariableNames {
+                prefix,
+                concatenate,
+                ..
+            } => write!(f, "!{prefix}{}", if *concatenate { "*" } else { "@" })?,
+            Self::MemberKeys {
+                variable_name,
+                concatenate,
+                ..
+            } => write!(
+                f,
+                "!{variable_name}[{}]",
+                if *concatenate { "*" } else { "@" }
+            )?,
+        }
+        write!(f, "}}")
+    }
+}
+
+/// Writes a parameter as it appears within the braces of a parameter expansion, preceded by
+/// `!` if it's to be dereferenced.
+fn fmt_parameter(
+    f: &mut std::fmt::Formatter<'_>,
+    parameter: &Parameter,
+    indirect: bool,
+) -> std::fmt::Result {
+    if indirect {
+        write!(f, "!")?;
+    }
+    parameter.fmt_name(f)
+}
+
 /// Kind of substring match.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum SubstringMatchKind {
     /// Match the prefix of the string.
     Prefix,
@@ -389,9 +790,24 @@ pub enum SubstringMatchKind {
     Anywhere,
 }
 
+impl Display for SubstringMatchKind {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Prefix => write!(f, "#"),
+            Self::Suffix => write!(f, "%"),
+            Self::FirstOccurrence => Ok(()),
+            Self::Anywhere => write!(f, "/"),
+        }
+    }
+}
+
 /// Kind of operation to apply to a parameter.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum ParameterTransformOp {
     /// Capitalizate initials.
     CapitalizeInitial,
@@ -416,9 +832,35 @@ pub enum ParameterTransformOp {
     ToUpperCase,
 }
 
+impl Display for ParameterTransformOp {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::CapitalizeInitial => write!(f, "u"),
+            Self::ExpandEscapeSequences => write!(f, "E"),
+            Self::PossiblyQuoteWithArraysExpanded { separate_words } => {
+                if *separate_words {
+                    write!(f, "k")
+                } else {
+                    write!(f, "K")
+                }
+            }
+            Self::PromptExpand => write!(f, "P"),
+            Self::Quoted => write!(f, "Q"),
+            Self::ToAssignmentLogic => write!(f, "A"),
+            Self::ToAttributeFlags => write!(f, "a"),
+            Self::ToLowerCase => write!(f, "L"),
+            Self::ToUpperCase => write!(f, "U"),
+        }
+    }
+}
+
 /// Represents a sub-word that is either a brace expression or some other word text.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum BraceExpressionOrText {
     /// A brace expression.
     Expr(BraceExpression),
@@ -431,7 +873,11 @@ pub type BraceExpression = Vec<BraceExpressionMember>;
 
 /// Member of a brace expression.
 #[derive(Clone, Debug)]
-#[cfg_attr(test, derive(PartialEq, Eq, serde::Serialize))]
+#[cfg_attr(test, derive(PartialEq, Eq))]
+#[cfg_attr(
+    any(test, feature = "serde"),
+    derive(serde::Serialize, serde::Deserialize)
+)]
 pub enum BraceExpressionMember {
     /// An inclusive numerical sequence.
     NumberSequence {
@@ -468,6 +914,27 @@ pub fn parse(
     cacheable_parse(word.to_owned(), options.to_owned())
 }
 
+/// Parse a word that appears verbatim in a source script into its constituent pieces,
+/// recording the location in that script of the expansions within it.
+///
+/// # Arguments
+///
+/// * `word` - The word to parse.
+/// * `start` - The position in the source script at which `word` starts.
+/// * `options` - The parser options to use.
+pub fn parse_with_location(
+    word: &str,
+    start: &SourcePosition,
+    options: &ParserOptions,
+) -> Result<Vec<WordPieceWithSource>, error::WordParseError> {
+    let context = GrammarContext {
+        options,
+        origin: Some(Origin { text: word, start }),
+    };
+    expansion_parser::unexpanded_word(word, &context)
+        .map_err(|err| error::WordParseError::Word(word.to_owned(), err.into()))
+}
+
 #[cached::proc_macro::cached(size = 64, result = true)]
 fn cacheable_parse(
     word: String,
@@ -475,7 +942,7 @@ fn cacheable_parse(
 ) -> Result<Vec<WordPieceWithSource>, error::WordParseError> {
     tracing::debug!(target: "expansion", "Parsing word '{}'", word);
 
-    let pieces = expansion_parser::unexpanded_word(word.as_str(), &options)
+    let pieces = expansion_parser::unexpanded_word(word.as_str(), &GrammarContext::new(&options))
         .map_err(|err| error::WordParseError::Word(word.clone(), err.into()))?;
 
     tracing::debug!(target: "expansion", "Parsed word '{}' => {{{:?}}}", word, pieces);
@@ -493,7 +960,7 @@ pub fn parse_parameter(
     word: &str,
     options: &ParserOptions,
 ) -> Result<Parameter, error::WordParseError> {
-    expansion_parser::parameter(word, options)
+    expansion_parser::parameter(word, &GrammarContext::new(options))
         .map_err(|err| error::WordParseError::Parameter(word.to_owned(), err.into()))
 }
 
@@ -507,12 +974,34 @@ pub fn parse_brace_expansions(
     word: &str,
     options: &ParserOptions,
 ) -> Result<Option<Vec<BraceExpressionOrText>>, error::WordParseError> {
-    expansion_parser::brace_expansions(word, options)
+    expansion_parser::brace_expansions(word, &GrammarContext::new(options))
         .map_err(|err| error::WordParseError::BraceExpansion(word.to_owned(), err.into()))
 }
 
+/// State shared by the rules of the expansion grammar.
+struct GrammarContext<'a> {
+    /// The parser options to use.
+    options: &'a ParserOptions,
+    /// The word being parsed and where it starts in its source script, if known.
+    origin: Option<Origin<'a>>,
+}
+
+impl<'a> GrammarContext<'a> {
+    const fn new(options: &'a ParserOptions) -> Self {
+        Self {
+            options,
+            origin: None,
+        }
+    }
+
+    /// Returns the location of the given byte range of the word being parsed, if known.
+    fn location(&self, start: usize, end: usize) -> Option<TokenLocation> {
+        self.origin.map(|origin| origin.location(start, end))
+    }
+}
+
 peg::parser! {
-    grammar expansion_parser(parser_options: &ParserOptions) for str {
+    grammar expansion_parser(context: &GrammarContext<'_>) for str {
         // Helper rule that enables pegviz to be used to visualize debug peg traces.
         rule traced<T>(e: rule<T>) -> T =
             &(input:$([_]*) {
@@ -734,11 +1223,11 @@ peg::parser! {
         // TODO: Deal with fact that there may be a quoted word or escaped closing brace chars.
         // TODO: Improve on how we handle a '$' not followed by a valid variable name or parameter.
         rule parameter_expansion() -> WordPiece =
-            "${" e:parameter_expression() "}" {
-                WordPiece::ParameterExpansion(e)
+            s:position!() "${" e:parameter_expression() "}" end:position!() {
+                WordPiece::ParameterExpansion(e.with_location(context.location(s, end)))
             } /
-            "$" parameter:unbraced_parameter() {
-                WordPiece::ParameterExpansion(ParameterExpr::Parameter { parameter, indirect: false })
+            s:position!() "$" parameter:unbraced_parameter() end:position!() {
+                WordPiece::ParameterExpansion(ParameterExpr::Parameter { parameter, indirect: false, loc: context.location(s, end) })
             } /
             "$" !['\''] {
                 WordPiece::Text("$".to_owned())
@@ -746,36 +1235,36 @@ peg::parser! {
 
         rule parameter_expression() -> ParameterExpr =
             indirect:parameter_indirection() parameter:parameter() test_type:parameter_test_type() "-" default_value:parameter_expression_word()? {
-                ParameterExpr::UseDefaultValues { parameter, indirect, test_type, default_value }
+                ParameterExpr::UseDefaultValues { parameter, indirect, test_type, default_value, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() test_type:parameter_test_type() "=" default_value:parameter_expression_word()? {
-                ParameterExpr::AssignDefaultValues { parameter, indirect, test_type, default_value }
+                ParameterExpr::AssignDefaultValues { parameter, indirect, test_type, default_value, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() test_type:parameter_test_type() "?" error_message:parameter_expression_word()? {
-                ParameterExpr::IndicateErrorIfNullOrUnset { parameter, indirect, test_type, error_message }
+                ParameterExpr::IndicateErrorIfNullOrUnset { parameter, indirect, test_type, error_message, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() test_type:parameter_test_type() "+" alternative_value:parameter_expression_word()? {
-                ParameterExpr::UseAlternativeValue { parameter, indirect, test_type, alternative_value }
+                ParameterExpr::UseAlternativeValue { parameter, indirect, test_type, alternative_value, loc: None }
             } /
             "#" parameter:parameter() {
-                ParameterExpr::ParameterLength { parameter, indirect: false }
+                ParameterExpr::ParameterLength { parameter, indirect: false, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "%%" pattern:parameter_expression_word()? {
-                ParameterExpr::RemoveLargestSuffixPattern { parameter, indirect, pattern }
+                ParameterExpr::RemoveLargestSuffixPattern { parameter, indirect, pattern, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "%" pattern:parameter_expression_word()? {
-                ParameterExpr::RemoveSmallestSuffixPattern { parameter, indirect, pattern }
+                ParameterExpr::RemoveSmallestSuffixPattern { parameter, indirect, pattern, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "##" pattern:parameter_expression_word()? {
-                ParameterExpr::RemoveLargestPrefixPattern { parameter, indirect, pattern }
+                ParameterExpr::RemoveLargestPrefixPattern { parameter, indirect, pattern, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "#" pattern:parameter_expression_word()? {
-                ParameterExpr::RemoveSmallestPrefixPattern { parameter, indirect, pattern }
+                ParameterExpr::RemoveSmallestPrefixPattern { parameter, indirect, pattern, loc: None }
             } /
             // N.B. The following case is for non-sh extensions.
             non_posix_extensions_enabled() e:non_posix_parameter_expression() { e } /
             indirect:parameter_indirection() parameter:parameter() {
-                ParameterExpr::Parameter { parameter, indirect }
+                ParameterExpr::Parameter { parameter, indirect, loc: None }
             }
 
         rule parameter_test_type() -> ParameterTestType =
@@ -789,46 +1278,46 @@ peg::parser! {
 
         rule non_posix_parameter_expression() -> ParameterExpr =
             "!" variable_name:variable_name() "[*]" {
-                ParameterExpr::MemberKeys { variable_name: variable_name.to_owned(), concatenate: true }
+                ParameterExpr::MemberKeys { variable_name: variable_name.to_owned(), concatenate: true, loc: None }
             } /
             "!" variable_name:variable_name() "[@]" {
-                ParameterExpr::MemberKeys { variable_name: variable_name.to_owned(), concatenate: false }
+                ParameterExpr::MemberKeys { variable_name: variable_name.to_owned(), concatenate: false, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() ":" offset:substring_offset() length:(":" l:substring_length() { l })? {
-                ParameterExpr::Substring { parameter, indirect, offset, length }
+                ParameterExpr::Substring { parameter, indirect, offset, length, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "@" op:non_posix_parameter_transformation_op() {
-                ParameterExpr::Transform { parameter, indirect, op }
+                ParameterExpr::Transform { parameter, indirect, op, loc: None }
             } /
             "!" prefix:variable_name() "*" {
-                ParameterExpr::VariableNames { prefix: prefix.to_owned(), concatenate: true }
+                ParameterExpr::VariableNames { prefix: prefix.to_owned(), concatenate: true, loc: None }
             } /
             "!" prefix:variable_name() "@" {
-                ParameterExpr::VariableNames { prefix: prefix.to_owned(), concatenate: false }
+                ParameterExpr::VariableNames { prefix: prefix.to_owned(), concatenate: false, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "/#" pattern:parameter_search_pattern() replacement:parameter_replacement_str()? {
-                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::Prefix }
+                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::Prefix, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "/%" pattern:parameter_search_pattern() replacement:parameter_replacement_str()? {
-                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::Suffix }
+                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::Suffix, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "//" pattern:parameter_search_pattern() replacement:parameter_replacement_str()? {
-                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::Anywhere }
+                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::Anywhere, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "/" pattern:parameter_search_pattern() replacement:parameter_replacement_str()? {
-                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::FirstOccurrence }
+                ParameterExpr::ReplaceSubstring { parameter, indirect, pattern, replacement, match_kind: SubstringMatchKind::FirstOccurrence, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "^^" pattern:parameter_expression_word()? {
-                ParameterExpr::UppercasePattern { parameter, indirect, pattern }
+                ParameterExpr::UppercasePattern { parameter, indirect, pattern, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "^" pattern:parameter_expression_word()? {
-                ParameterExpr::UppercaseFirstChar { parameter, indirect, pattern }
+                ParameterExpr::UppercaseFirstChar { parameter, indirect, pattern, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() ",," pattern:parameter_expression_word()? {
-                ParameterExpr::LowercasePattern { parameter, indirect, pattern }
+                ParameterExpr::LowercasePattern { parameter, indirect, pattern, loc: None }
             } /
             indirect:parameter_indirection() parameter:parameter() "," pattern:parameter_expression_word()? {
-                ParameterExpr::LowercaseFirstChar { parameter, indirect, pattern }
+                ParameterExpr::LowercaseFirstChar { parameter, indirect, pattern, loc: None }
             }
 
         rule parameter_indirection() -> bool =
@@ -902,13 +1391,18 @@ peg::parser! {
             s:$([^'`']) { s }
 
         rule arithmetic_expansion() -> WordPiece =
-            "$((" e:$(arithmetic_word(<"))">)) "))" { WordPiece::ArithmeticExpression(ast::UnexpandedArithmeticExpr { value: e.to_owned() } ) }
+            "$((" e:located_arithmetic_word(<"))">) "))" { WordPiece::ArithmeticExpression(e) }
 
         rule substring_offset() -> ast::UnexpandedArithmeticExpr =
-            s:$(arithmetic_word(<[':' | '}']>)) { ast::UnexpandedArithmeticExpr { value: s.to_owned() } }
+            located_arithmetic_word(<[':' | '}']>)
 
         rule substring_length() -> ast::UnexpandedArithmeticExpr =
-            s:$(arithmetic_word(<[':' | '}']>)) { ast::UnexpandedArithmeticExpr { value: s.to_owned() } }
+            located_arithmetic_word(<[':' | '}']>)
+
+        rule located_arithmetic_word<T>(stop_condition: rule<T>) -> ast::UnexpandedArithmeticExpr =
+            start:position!() s:$(arithmetic_word(<stop_condition()>)) end:position!() {
+                ast::UnexpandedArithmeticExpr { value: s.to_owned(), loc: context.location(start, end) }
+            }
 
         rule parameter_replacement_str() -> String =
             "/" s:$(word(<['}']>)) { s.to_owned() }
@@ -920,19 +1414,20 @@ peg::parser! {
             s:$(word(<['}']>)) { s.to_owned() }
 
         rule extglob_enabled() -> () =
-            &[_] {? if parser_options.enable_extended_globbing { Ok(()) } else { Err("no extglob") } }
+            &[_] {? if context.options.enable_extended_globbing { Ok(()) } else { Err("no extglob") } }
 
         rule non_posix_extensions_enabled() -> () =
-            &[_] {? if !parser_options.sh_mode { Ok(()) } else { Err("posix") } }
+            &[_] {? if !context.options.sh_mode { Ok(()) } else { Err("posix") } }
 
         rule tilde_parsing_enabled() -> () =
-            &[_] {? if parser_options.tilde_expansion { Ok(()) } else { Err("no tilde expansion") } }
+            &[_] {? if context.options.tilde_expansion { Ok(()) } else { Err("no tilde expansion") } }
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::parser::ast::SourceLocation;
     use anyhow::Result;
     use insta::assert_ron_snapshot;
 
@@ -970,10 +1465,22 @@ mod tests {
 
     #[test]
     fn parse_command_substitution() -> Result<()> {
-        super::expansion_parser::command_piece("echo", &ParserOptions::default())?;
-        super::expansion_parser::command_piece("hi", &ParserOptions::default())?;
-        super::expansion_parser::command("echo hi", &ParserOptions::default())?;
-        super::expansion_parser::command_substitution("$(echo hi)", &ParserOptions::default())?;
+        super::expansion_parser::command_piece(
+            "echo",
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
+        super::expansion_parser::command_piece(
+            "hi",
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
+        super::expansion_parser::command(
+            "echo hi",
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
+        super::expansion_parser::command_substitution(
+            "$(echo hi)",
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
 
         assert_ron_snapshot!(test_parse("$(echo hi)")?);
 
@@ -982,12 +1489,21 @@ mod tests {
 
     #[test]
     fn parse_command_substitution_with_embedded_quotes() -> Result<()> {
-        super::expansion_parser::command_piece("echo", &ParserOptions::default())?;
-        super::expansion_parser::command_piece(r#""hi""#, &ParserOptions::default())?;
-        super::expansion_parser::command(r#"echo "hi""#, &ParserOptions::default())?;
+        super::expansion_parser::command_piece(
+            "echo",
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
+        super::expansion_parser::command_piece(
+            r#""hi""#,
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
+        super::expansion_parser::command(
+            r#"echo "hi""#,
+            &GrammarContext::new(&ParserOptions::default()),
+        )?;
         super::expansion_parser::command_substitution(
             r#"$(echo "hi")"#,
-            &ParserOptions::default(),
+            &GrammarContext::new(&ParserOptions::default()),
         )?;
 
         assert_ron_snapshot!(test_parse(r#"$(echo "hi")"#)?);
@@ -1033,34 +1549,121 @@ mod tests {
     #[test]
     fn test_arithmetic_word_parsing() {
         let options = ParserOptions::default();
-
-        assert!(super::expansion_parser::is_arithmetic_word("a", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("b", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word(" a + b ", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("(a)", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("((a))", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("(((a)))", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("(1+2)", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("(1+2)*3", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word("((1+2)*3)", &options).is_ok());
+        let context = GrammarContext::new(&options);
+
+        assert!(super::expansion_parser::is_arithmetic_word("a", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("b", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word(" a + b ", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("(a)", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("((a))", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("(((a)))", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("(1+2)", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("(1+2)*3", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word("((1+2)*3)", &context).is_ok());
     }
 
     #[test]
     fn test_arithmetic_word_piece_parsing() {
         let options = ParserOptions::default();
+        let context = GrammarContext::new(&options);
+
+        assert!(super::expansion_parser::is_arithmetic_word_piece("a", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("b", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece(" a + b ", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("(a)", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("((a))", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("(((a)))", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("(1+2)", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("((1+2))", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("((1+2)*3)", &context).is_ok());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("(a", &context).is_err());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("(a))", &context).is_err());
+        assert!(super::expansion_parser::is_arithmetic_word_piece("((a)", &context).is_err());
+    }
+
+    #[test]
+    fn piece_location_in_word() -> Result<()> {
+        let start = crate::parser::SourcePosition {
+            index: 10,
+            line: 2,
+            column: 5,
+        };
+        let word = ast::Word::with_location(
+            "a${b}c",
+            &TokenLocation::within_text(&start, "a${b}c", 0..6),
+        );
+
+        let pieces = super::parse(&word.value, &ParserOptions::default())?;
+        let loc = pieces[1]
+            .location_in(&word)
+            .ok_or_else(|| anyhow::anyhow!("expected a location"))?;
+
+        assert_eq!((loc.start.index, loc.end.index), (11, 15));
+        assert_eq!((loc.start.line, loc.start.column), (2, 6));
+        let unlocated = ast::Word::from(String::from("a${b}c"));
+        assert!(pieces[1].location_in(&unlocated).is_none());
+
+        Ok(())
+    }
 
-        assert!(super::expansion_parser::is_arithmetic_word_piece("a", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("b", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece(" a + b ", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("(a)", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("((a))", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("(((a)))", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("(1+2)", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("((1+2))", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("((1+2)*3)", &options).is_ok());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("(a", &options).is_err());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("(a))", &options).is_err());
-        assert!(super::expansion_parser::is_arithmetic_word_piece("((a)", &options).is_err());
+    #[test]
+    fn display_pieces() -> Result<()> {
+        for word in [
+            "a'b'$'c\\n'\\ d",
+            r#""x ${y} \$z"$"w""#,
+            "~user/dir",
+            "${!x:-a b}${y=}${z?err}${w:+alt}${#v}",
+            "${a%x}${a%%x}${a#x}${a##x}${a^x}${a^^}${a,x}${a,,}",
+            "${s: -1:2}${s@Q}${s@k}${s/#a/b}${s/%a}${s//a/b}${s/a/b}",
+            "${!pre*}${!pre@}${!arr[*]}${!arr[@]}${arr[i + 1]}${arr[@]}${1}${10}${?}",
+            "$(echo hi)`echo \\`hi\\``$((1 + 2))",
+        ] {
+            let pieces = super::parse(word, &ParserOptions::default())?;
+            let displayed: String = pieces.iter().map(|p| p.piece.to_string()).collect();
+            assert_eq!(displayed, word);
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn parse_records_locations() -> Result<()> {
+        let start = crate::parser::SourcePosition {
+            index: 10,
+            line: 2,
+            column: 5,
+        };
+        let span = |loc: Option<TokenLocation>| {
+            loc.map(|loc| (loc.start.index - start.index, loc.end.index - start.index))
+        };
+
+        let pieces =
+            super::parse_with_location("a$((x + 1))${b:1:n}", &start, &ParserOptions::default())?;
+        let WordPiece::ArithmeticExpression(expr) = &pieces[1].piece else {
+            anyhow::bail!("expected an arithmetic expansion: {pieces:?}");
+        };
+        assert_eq!(span(expr.loc.clone()), Some((4, 9)));
+
+        let WordPiece::ParameterExpansion(expr) = &pieces[2].piece else {
+            anyhow::bail!("expected a parameter expansion: {pieces:?}");
+        };
+        assert_eq!(span(expr.location()), Some((11, 19)));
+        let ParameterExpr::Substring { offset, length, .. } = expr else {
+            anyhow::bail!("expected a substring expression: {expr:?}");
+        };
+        assert_eq!(span(offset.loc.clone()), Some((15, 16)));
+        assert_eq!(
+            span(length.as_ref().and_then(|l| l.loc.clone())),
+            Some((17, 18))
+        );
+
+        let pieces = super::parse("$b", &ParserOptions::default())?;
+        let WordPiece::ParameterExpansion(expr) = &pieces[0].piece else {
+            anyhow::bail!("expected a parameter expansion: {pieces:?}");
+        };
+        assert!(expr.location().is_none());
+
+        Ok(())
     }
 
     #[test]
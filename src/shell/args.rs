@@ -28,6 +28,8 @@ const HEADING_STANDARD_OPTIONS: &str = "Standard shell options";
 
 const HEADING_UI_OPTIONS: &str = "User interface options";
 
+const HEADING_FORMAT_OPTIONS: &str = "Formatting options";
+
 /// Identifies the input backend to use for the shell.
 #[derive(Clone, Copy, clap::ValueEnum)]
 pub enum InputBackend {
@@ -178,6 +180,30 @@ pub struct CommandLineArgs {
     )]
     pub disabled_events: Vec<events::TraceEvent>,
 
+    /// Print the input script in canonical form instead of executing it.
+    #[clap(long = "format", help_heading = HEADING_FORMAT_OPTIONS)]
+    pub format: bool,
+
+    /// Number of spaces to indent by when formatting (0 indents with tabs).
+    #[clap(long = "format-indent", value_name = "N", default_value_t = 4, help_heading = HEADING_FORMAT_OPTIONS)]
+    pub format_indent: usize,
+
+    /// Place binary operators (`&&`, `||`, `|`) at the start of continuation lines.
+    #[clap(long = "format-binary-next-line", help_heading = HEADING_FORMAT_OPTIONS)]
+    pub format_binary_next_line: bool,
+
+    /// Indent case items within `case` statements.
+    #[clap(long = "format-case-indent", help_heading = HEADING_FORMAT_OPTIONS)]
+    pub format_case_indent: bool,
+
+    /// Align the bodies of consecutive single-line case items.
+    #[clap(long = "format-align-case", help_heading = HEADING_FORMAT_OPTIONS)]
+    pub format_align_case: bool,
+
+    /// Place a space between redirection operators and their targets.
+    #[clap(long = "format-space-redirects", help_heading = HEADING_FORMAT_OPTIONS)]
+    pub format_space_redirects: bool,
+
     /// Path and arguments for script to execute (optional).
     #[clap(
         trailing_var_arg = true,
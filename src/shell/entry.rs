@@ -72,6 +72,10 @@ pub fn run() {
         }
     };
 
+    if parsed_args.format {
+        std::process::exit(format_input(&parsed_args));
+    }
+
     //
     // Run.
     //
@@ -97,6 +101,58 @@ pub fn run() {
     std::process::exit(i32::from(exit_code));
 }
 
+/// Formats the shell's input script to stdout instead of executing it. Returns the exit code.
+///
+/// # Arguments
+///
+/// * `args` - The parsed command-line arguments.
+fn format_input(args: &CommandLineArgs) -> i32 {
+    let input = if let Some(command) = &args.command {
+        Ok(command.clone())
+    } else if let Some(script_path) = args.script_args.first() {
+        std::fs::read_to_string(script_path)
+    } else {
+        std::io::read_to_string(std::io::stdin())
+    };
+
+    let input = match input {
+        Ok(input) => input,
+        Err(e) => {
+            eprintln!("error: {e}");
+            return 1;
+        }
+    };
+
+    let options = crate::parser::format::FormatOptions {
+        indent: if args.format_indent == 0 {
+            crate::parser::format::Indent::Tabs
+        } else {
+            crate::parser::format::Indent::Spaces(args.format_indent)
+        },
+        binary_next_line: args.format_binary_next_line,
+        switch_case_indent: args.format_case_indent,
+        align_case_items: args.format_align_case,
+        space_redirects: args.format_space_redirects,
+    };
+
+    let parser_options = crate::parser::ParserOptions {
+        posix_mode: args.posix || args.sh_mode,
+        sh_mode: args.sh_mode,
+        ..Default::default()
+    };
+
+    match crate::parser::format::format_str(&input, &options, &parser_options) {
+        Ok(formatted) => {
+            print!("{formatted}");
+            0
+        }
+        Err(e) => {
+            eprintln!("error: {e}");
+            1
+        }
+    }
+}
+
 /// Installs panic handlers to report our panic and cleanly exit on panic.
 fn install_panic_handlers() {
     //
@@ -315,6 +371,8 @@ async fn instantiate_shell(
             verbose: args.verbose,
             max_function_call_depth: None,
             key_bindings: None,
+            line_editor: None,
+            command_not_found_handler: None,
             error_formatter: Some(new_error_formatter(args)),
             shell_version: Some(env!("CARGO_PKG_VERSION").to_string()),
             builtins,
@@ -443,4 +501,27 @@ mod tests {
             assert!(result.is_err());
         }
     }
+
+    #[test]
+    fn parse_format_args() -> Result<()> {
+        let args = vec![
+            "brush",
+            "--format",
+            "--format-indent",
+            "0",
+            "--format-binary-next-line",
+            "-c",
+            "a&&b",
+        ];
+        let args = args.into_iter().map(|s| s.to_string()).collect::<Vec<_>>();
+
+        let parsed_args = CommandLineArgs::try_parse_from(args)?;
+        assert!(parsed_args.format);
+        assert_eq!(parsed_args.format_indent, 0);
+        assert!(parsed_args.format_binary_next_line);
+        assert!(!parsed_args.format_case_indent);
+        assert_eq!(format_input(&parsed_args), 0);
+
+        Ok(())
+    }
 }
@@ -0,0 +1,368 @@
+//! Differential compatibility tests.
+//!
+//! Runs each case of the YAML corpus in `tests/cases` with this crate's shell and with the
+//! system's bash, and compares their stdout, stderr, exit status and the files they leave
+//! behind. Cases where the shells are known to behave differently are annotated with a
+//! `known_divergence` reason; such cases are expected to differ, and are reported if they
+//! start matching (so the annotation can be removed).
+//!
+//! Set `COMPAT_CASES` to a substring of `<set name>/<case name>` to only run matching cases.
+//! The tests are skipped if bash isn't available.
+
+use std::collections::{BTreeMap, HashMap};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+
+use anyhow::{Context, Result};
+use kodegen_bash_shell::openfiles::{self, OpenFiles};
+use kodegen_bash_shell::{Shell, ShellValue, ShellVariable};
+use serde::Deserialize;
+
+/// The path to the bash used as the reference implementation.
+const BASH_PATH: &str = "/bin/bash";
+
+/// How long a case may run in either shell.
+const CASE_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// A set of test cases, as stored in one YAML file.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestCaseSet {
+    /// Name of the test case set.
+    name: String,
+    /// Test cases in the set.
+    cases: Vec<TestCase>,
+}
+
+/// A single test case.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestCase {
+    /// Name of the test case.
+    name: String,
+    /// The script to run.
+    stdin: String,
+    /// Additional environment variables to run the script with.
+    #[serde(default)]
+    env: BTreeMap<String, String>,
+    /// Files to create in the working directory before running the script.
+    #[serde(default)]
+    test_files: Vec<TestFile>,
+    /// Whether to skip comparing stderr (e.g., because error messages are worded differently).
+    #[serde(default)]
+    ignore_stderr: bool,
+    /// Whether to skip comparing the exit status.
+    #[serde(default)]
+    ignore_exit_status: bool,
+    /// If set, the reason the shells are known to behave differently for this case.
+    #[serde(default)]
+    known_divergence: Option<String>,
+}
+
+/// A file to create before running a test case.
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TestFile {
+    /// Path of the file, relative to the working directory.
+    path: String,
+    /// Contents of the file.
+    contents: String,
+}
+
+/// The observable behavior of a shell running a test case.
+#[derive(Debug, PartialEq, Eq)]
+struct Outcome {
+    stdout: String,
+    stderr: Option<String>,
+    exit_status: Option<u8>,
+    files: BTreeMap<String, String>,
+}
+
+impl Outcome {
+    fn describe_differences(&self, expected: &Self) -> String {
+        let mut description = String::new();
+
+        let mut compare = |what: &str, ours: &dyn std::fmt::Debug, bash: &dyn std::fmt::Debug| {
+            let (ours, bash) = (std::format!("{ours:#?}"), std::format!("{bash:#?}"));
+            if ours != bash {
+                description.push_str(&std::format!(
+                    "  {what}:\n    bash: {bash}\n    ours: {ours}\n"
+                ));
+            }
+        };
+
+        compare("stdout", &self.stdout, &expected.stdout);
+        compare("stderr", &self.stderr, &expected.stderr);
+        compare("exit status", &self.exit_status, &expected.exit_status);
+        compare("files", &self.files, &expected.files);
+
+        description
+    }
+}
+
+/// A working directory for running a test case in, removed when dropped.
+struct TestDir(PathBuf);
+
+impl TestDir {
+    fn new(label: &str, test_case: &TestCase) -> Result<Self> {
+        static COUNTER: std::sync::atomic::AtomicUsize = std::sync::atomic::AtomicUsize::new(0);
+
+        let dir = std::env::temp_dir().join(std::format!(
+            "brush-compat-{}-{}-{label}",
+            std::process::id(),
+            COUNTER.fetch_add(1, std::sync::atomic::Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir)?;
+
+        for file in &test_case.test_files {
+            let path = dir.join(&file.path);
+            if let Some(parent) = path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            std::fs::write(path, &file.contents)?;
+        }
+
+        Ok(Self(dir))
+    }
+
+    /// Returns the files in the directory, keyed by relative path; directories map to `/`.
+    fn files(&self) -> Result<BTreeMap<String, String>> {
+        fn visit(root: &Path, dir: &Path, files: &mut BTreeMap<String, String>) -> Result<()> {
+            for entry in std::fs::read_dir(dir)? {
+                let path = entry?.path();
+                let relative = path.strip_prefix(root)?.to_string_lossy().into_owned();
+                if path.is_dir() {
+                    files.insert(relative, "/".into());
+                    visit(root, &path, files)?;
+                } else {
+                    files.insert(
+                        relative,
+                        String::from_utf8_lossy(&std::fs::read(&path)?).into(),
+                    );
+                }
+            }
+            Ok(())
+        }
+
+        let mut files = BTreeMap::new();
+        visit(&self.0, &self.0, &mut files)?;
+        Ok(files)
+    }
+}
+
+impl Drop for TestDir {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.0);
+    }
+}
+
+/// Returns the environment to run test cases with.
+fn case_env(test_case: &TestCase, dir: &TestDir) -> HashMap<String, String> {
+    let mut env = HashMap::from([
+        ("PATH".into(), std::env::var("PATH").unwrap_or_default()),
+        ("HOME".into(), dir.0.to_string_lossy().into_owned()),
+        ("LC_ALL".into(), "C".into()),
+    ]);
+    env.extend(test_case.env.clone());
+    env
+}
+
+/// Normalizes text produced by a shell, so output that only differs in the path of the
+/// working directory or the name of the shell compares equal.
+fn normalize(text: &str, dir: &TestDir, shell_name: &str) -> String {
+    text.replace(dir.0.to_string_lossy().as_ref(), "$TESTDIR")
+        .replace(&std::format!("{shell_name}: "), "$SHELL: ")
+}
+
+fn outcome(
+    test_case: &TestCase,
+    dir: &TestDir,
+    shell_name: &str,
+    stdout: &[u8],
+    stderr: &[u8],
+    exit_status: u8,
+) -> Result<Outcome> {
+    Ok(Outcome {
+        stdout: normalize(&String::from_utf8_lossy(stdout), dir, shell_name),
+        stderr: (!test_case.ignore_stderr)
+            .then(|| normalize(&String::from_utf8_lossy(stderr), dir, shell_name)),
+        exit_status: (!test_case.ignore_exit_status).then_some(exit_status),
+        files: dir.files()?,
+    })
+}
+
+fn run_with_bash(test_case: &TestCase) -> Result<Outcome> {
+    let dir = TestDir::new("bash", test_case)?;
+
+    // The script is passed as a command string rather than on stdin, so that both shells
+    // run it with the same (empty) standard input.
+    let mut child = std::process::Command::new(BASH_PATH)
+        .args(["--norc", "--noprofile", "-c", test_case.stdin.as_str()])
+        .current_dir(&dir.0)
+        .env_clear()
+        .envs(case_env(test_case, &dir))
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()?;
+
+    let stdout_thread = read_in_thread(child.stdout.take().context("bash stdout")?);
+    let stderr_thread = read_in_thread(child.stderr.take().context("bash stderr")?);
+
+    // Wait for bash to exit, killing it if it runs for too long.
+    let deadline = Instant::now() + CASE_TIMEOUT;
+    let status = loop {
+        if let Some(status) = child.try_wait()? {
+            break status;
+        }
+        if Instant::now() >= deadline {
+            child.kill()?;
+            child.wait()?;
+            anyhow::bail!("timed out");
+        }
+        std::thread::sleep(Duration::from_millis(10));
+    };
+
+    #[expect(clippy::cast_possible_truncation, clippy::cast_sign_loss)]
+    let exit_status = status.code().map_or(128, |code| code as u8);
+
+    let stdout = stdout_thread.join().expect("stdout reader panicked")?;
+    let stderr = stderr_thread.join().expect("stderr reader panicked")?;
+
+    outcome(test_case, &dir, "bash", &stdout, &stderr, exit_status)
+}
+
+async fn run_with_brush(test_case: &TestCase) -> Result<Outcome> {
+    let dir = TestDir::new("brush", test_case)?;
+
+    // Drain the output pipes on separate threads, so the script can't block on them.
+    let (stdout_reader, stdout_writer) = std::io::pipe()?;
+    let (stderr_reader, stderr_writer) = std::io::pipe()?;
+    let (stdout_thread, stderr_thread) =
+        (read_in_thread(stdout_reader), read_in_thread(stderr_reader));
+
+    let fds = HashMap::from([
+        (OpenFiles::STDIN_FD, openfiles::null()?),
+        (OpenFiles::STDOUT_FD, stdout_writer.into()),
+        (OpenFiles::STDERR_FD, stderr_writer.into()),
+    ]);
+
+    let mut shell = Shell::builder()
+        .working_dir(dir.0.clone())
+        .do_not_inherit_env(true)
+        .no_profile(true)
+        .no_rc(true)
+        .shell_name("brush".into())
+        .fds(fds)
+        .build()
+        .await?;
+
+    for (name, value) in case_env(test_case, &dir) {
+        let mut var = ShellVariable::new(ShellValue::String(value));
+        var.export();
+        shell.set_env_global(&name, var)?;
+    }
+
+    let params = shell.default_exec_params();
+    let result = tokio::time::timeout(CASE_TIMEOUT, shell.exec(test_case.stdin.as_str(), &params))
+        .await
+        .context("timed out")??;
+    let exit_status: u8 = result.exit_code.into();
+
+    // Close our ends of the output pipes.
+    drop(params);
+    drop(shell);
+
+    let stdout = stdout_thread.join().expect("stdout reader panicked")?;
+    let stderr = stderr_thread.join().expect("stderr reader panicked")?;
+
+    outcome(test_case, &dir, "brush", &stdout, &stderr, exit_status)
+}
+
+/// Reads the given stream to its end on a separate thread, so the writer can't block on it.
+fn read_in_thread(
+    mut reader: impl Read + Send + 'static,
+) -> std::thread::JoinHandle<std::io::Result<Vec<u8>>> {
+    std::thread::spawn(move || {
+        let mut output = vec![];
+        reader.read_to_end(&mut output).map(|_| output)
+    })
+}
+
+fn load_test_case_sets() -> Result<Vec<(PathBuf, TestCaseSet)>> {
+    let cases_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/cases");
+
+    let mut paths: Vec<_> = std::fs::read_dir(&cases_dir)?
+        .map(|entry| entry.map(|e| e.path()))
+        .collect::<Result<_, _>>()?;
+    paths.retain(|path| path.extension().is_some_and(|ext| ext == "yaml"));
+    paths.sort();
+
+    paths
+        .into_iter()
+        .map(|path| {
+            let file = std::fs::File::open(&path)?;
+            let set = serde_yaml::from_reader(file)
+                .with_context(|| std::format!("parsing {}", path.display()))?;
+            Ok((path, set))
+        })
+        .collect()
+}
+
+#[tokio::test]
+async fn compat_with_bash() -> Result<()> {
+    if !Path::new(BASH_PATH).exists() {
+        eprintln!("skipping: {BASH_PATH} not found");
+        return Ok(());
+    }
+
+    let filter = std::env::var("COMPAT_CASES").ok();
+
+    let mut failures = vec![];
+    let mut case_count = 0;
+
+    for (path, set) in load_test_case_sets()? {
+        for test_case in &set.cases {
+            let full_name = std::format!("{}/{}", set.name, test_case.name);
+            if filter
+                .as_ref()
+                .is_some_and(|f| !full_name.contains(f.as_str()))
+            {
+                continue;
+            }
+            case_count += 1;
+
+            let bash = run_with_bash(test_case).with_context(|| full_name.clone())?;
+            let ours = run_with_brush(test_case).await;
+
+            let differences = match &ours {
+                Ok(ours) => ours.describe_differences(&bash),
+                Err(err) => std::format!("  error: {err:#}\n"),
+            };
+
+            match (&test_case.known_divergence, differences.is_empty()) {
+                (None, false) => failures.push(std::format!(
+                    "{full_name} ({}):\n{differences}",
+                    path.display()
+                )),
+                (Some(reason), true) => failures.push(std::format!(
+                    "{full_name} ({}): now matches bash; remove its known divergence ({reason})\n",
+                    path.display()
+                )),
+                _ => (),
+            }
+        }
+    }
+
+    assert!(case_count > 0 || filter.is_some(), "no test cases found");
+    assert!(
+        failures.is_empty(),
+        "{} of {case_count} cases failed:\n\n{}",
+        failures.len(),
+        failures.join("\n")
+    );
+
+    Ok(())
+}
@@ -0,0 +1,75 @@
+//! Tests loading the `loadable-builtin` example library into a shell via `enable -f`.
+//!
+//! The library is built by `cargo test` along with the crate's other examples; these tests
+//! locate it in the target directory rather than building it themselves.
+
+#![cfg(any(unix, windows))]
+
+use std::path::PathBuf;
+
+use anyhow::{Context, Result};
+use kodegen_bash_shell::Shell;
+
+/// Returns the path of the built `loadable-builtin` example library.
+fn example_library_path() -> Result<PathBuf> {
+    let file_name = format!(
+        "{}loadable_builtin{}",
+        std::env::consts::DLL_PREFIX,
+        std::env::consts::DLL_SUFFIX
+    );
+
+    // Examples are built into `<target>/<profile>/examples`; the test executable lives
+    // somewhere under `<target>/<profile>`, with the exact layout depending on cargo's
+    // version and configuration.
+    let exe = std::env::current_exe()?;
+    exe.ancestors()
+        .map(|dir| dir.join("examples").join(&file_name))
+        .find(|path| path.exists())
+        .with_context(|| {
+            format!(
+                "{file_name} not found near {}; build it with `cargo build --example loadable-builtin`",
+                exe.display()
+            )
+        })
+}
+
+#[tokio::test]
+async fn enable_example_library() -> Result<()> {
+    let library = example_library_path()?;
+
+    let mut shell = Shell::builder().build().await?;
+    let params = shell.default_exec_params();
+
+    let result = shell
+        .exec(format!("enable -f '{}' hello", library.display()), &params)
+        .await?;
+    assert!(result.is_success());
+    assert!(shell.loaded_builtin("hello").is_some());
+
+    shell.exec("greeting=$(hello there)", &params).await?;
+    assert_eq!(shell.env_str("greeting").as_deref(), Some("Hello, there!"));
+
+    shell.exec("usage=$(help hello)", &params).await?;
+    let usage = shell.env_str("usage").unwrap_or_default();
+    assert!(usage.contains("hello [name ...]"));
+    assert!(usage.contains("Print a greeting."));
+
+    shell.exec("kind=$(type -t hello)", &params).await?;
+    assert_eq!(shell.env_str("kind").as_deref(), Some("builtin"));
+
+    // Dry runs record loaded builtins instead of running them.
+    let trace = shell.dry_run("hello dry run", &params).await?;
+    let commands: Vec<_> = trace.commands().collect();
+    assert_eq!(commands.len(), 1);
+    assert_eq!(commands[0].argv, ["hello", "dry", "run"]);
+    assert!(commands[0].stubbed);
+
+    let result = shell.exec("enable -d hello", &params).await?;
+    assert!(result.is_success());
+    assert!(shell.loaded_builtin("hello").is_none());
+
+    let result = shell.exec("type -t hello", &params).await?;
+    assert!(!result.is_success());
+
+    Ok(())
+}
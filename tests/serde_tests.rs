@@ -0,0 +1,105 @@
+//! Tests of the JSON form of the AST, as produced with the `serde` feature.
+//!
+//! These are integration tests so that they exercise the serialization shipped with the
+//! feature, rather than the compacted form the crate's own snapshot tests use.
+
+use anyhow::Result;
+use kodegen_bash_shell::Shell;
+use kodegen_bash_shell::parser::{
+    ParserOptions, SourceInfo, Token, ast, parse_tokens, tokenize_str,
+};
+use pretty_assertions::assert_eq;
+
+fn parse(input: &str) -> Result<ast::Program> {
+    let tokens = tokenize_str(input)?;
+    Ok(parse_tokens(
+        &tokens,
+        &ParserOptions::default(),
+        &SourceInfo::default(),
+    )?)
+}
+
+/// Replaces all locations in the given serialized AST with null.
+fn elide_locations(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (key, value) in map {
+                if key == "loc" {
+                    *value = serde_json::Value::Null;
+                } else {
+                    elide_locations(value);
+                }
+            }
+        }
+        serde_json::Value::Array(values) => values.iter_mut().for_each(elide_locations),
+        _ => (),
+    }
+}
+
+#[test]
+fn program_json_matches_documented_example() -> Result<()> {
+    // Extract the example from the module docs, treating elided locations as null.
+    let documented = include_str!("../src/parser/ast.rs")
+        .lines()
+        .skip_while(|line| *line != "//! ```json")
+        .skip(1)
+        .take_while(|line| *line != "//! ```")
+        .map(|line| line.trim_start_matches("//!"))
+        .collect::<Vec<_>>()
+        .join("\n")
+        .replace("{...}", "null");
+    let documented: serde_json::Value = serde_json::from_str(&documented)?;
+
+    let mut serialized = serde_json::to_value(parse(r#"x=1 echo "$x""#)?)?;
+    elide_locations(&mut serialized);
+
+    assert_eq!(serialized, documented);
+
+    Ok(())
+}
+
+#[test]
+fn program_json_round_trip() -> Result<()> {
+    let input = r#"#!/bin/bash
+f() { local -a arr=(1 [3]=x); arr+=("$@"); }
+if [[ -n ${1:-} && $1 =~ ^a ]]; then time ! echo a |& cat; fi
+case $x in a|b) echo ab ;& *) ;; esac
+for ((i = 0; i < 3; i++)); do (( n += i )); done 2>/dev/null
+while read -r line; do echo "$line"; done < <(ls) &
+cat <<-EOF >&2 {fd}>out
+	$HOME
+	EOF
+"#;
+
+    // The AST types only implement equality in the crate's own tests, so compare what
+    // the deserialized values serialize back to.
+    let program = parse(input)?;
+    let json = serde_json::to_value(&program)?;
+    let deserialized: ast::Program = serde_json::from_value(json.clone())?;
+
+    assert_eq!(serde_json::to_value(&deserialized)?, json);
+    assert_eq!(deserialized.to_string(), program.to_string());
+
+    let tokens = tokenize_str(input)?;
+    let json = serde_json::to_value(&tokens)?;
+    let deserialized: Vec<Token> = serde_json::from_value(json.clone())?;
+
+    assert_eq!(serde_json::to_value(&deserialized)?, json);
+
+    Ok(())
+}
+
+#[tokio::test]
+async fn run_deserialized_program() -> Result<()> {
+    let mut shell = Shell::builder().build().await?;
+    let params = shell.default_exec_params();
+
+    let json = serde_json::to_string(&parse("x=$((1 + 2)); y=\"$x!\"")?)?;
+    let program: ast::Program = serde_json::from_str(&json)?;
+
+    let result = shell.run_program(program, &params).await?;
+    assert!(result.is_success());
+    assert_eq!(shell.env_str("y").as_deref(), Some("3!"));
+
+    Ok(())
+}